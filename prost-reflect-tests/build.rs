@@ -9,23 +9,23 @@ fn main() -> io::Result<()> {
         .type_attribute(".test.WellKnownTypes", "#[cfg_attr(test, derive(::proptest_derive::Arbitrary))]")
         .field_attribute(
             ".test.WellKnownTypes.timestamp",
-            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::arbitrary::timestamp())\"))]",
+            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::strategy::arbitrary_message::<::prost_types::Timestamp>())\"))]",
         )
         .field_attribute(
             ".test.WellKnownTypes.duration",
-            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::arbitrary::duration())\"))]",
+            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::strategy::arbitrary_message::<::prost_types::Duration>())\"))]",
         )
         .field_attribute(
             ".test.WellKnownTypes.struct",
-            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::arbitrary::struct_())\"))]",
+            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::strategy::arbitrary_message::<::prost_types::Struct>())\"))]",
         )
         .field_attribute(
             ".test.WellKnownTypes.list",
-            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::arbitrary::list())\"))]",
+            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::strategy::arbitrary_message::<::prost_types::ListValue>())\"))]",
         )
         .field_attribute(
             ".test.WellKnownTypes.mask",
-            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::arbitrary::mask())\"))]",
+            "#[cfg_attr(test, proptest(strategy = \"::proptest::option::of(crate::strategy::arbitrary_message::<::prost_types::FieldMask>())\"))]",
         )
         .field_attribute(
             ".test.WellKnownTypes.empty",
@@ -46,6 +46,7 @@ fn main() -> io::Result<()> {
                 "src/imports.proto",
                 "src/ext.proto",
                 "src/options.proto",
+                "src/validate.proto",
             ],
             &["src/"],
         )?;
@@ -0,0 +1,236 @@
+use prost::Message;
+use prost_reflect::{
+    any::{TypeResolver, UnpackAnyError},
+    text_format::{FormatOptions, ParseOptions},
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, MessageDeserializer,
+    ReflectMessage, SerializeOptions,
+};
+use serde_json::json;
+
+use crate::{proto::Point, test_file_descriptor};
+
+/// A pool containing only `google.protobuf.Any`, so the types of its values must be found elsewhere.
+fn any_only_pool() -> DescriptorPool {
+    let any_file = DescriptorPool::global()
+        .get_file_by_name("google/protobuf/any.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .clone();
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_proto(any_file).unwrap();
+    pool
+}
+
+fn any_message(type_url: &str, value: Vec<u8>) -> DynamicMessage {
+    let desc = any_only_pool()
+        .get_message_by_name("google.protobuf.Any")
+        .unwrap();
+    let mut message = DynamicMessage::new(desc);
+    message
+        .transcode_from(&prost_types::Any {
+            type_url: type_url.to_owned(),
+            value,
+        })
+        .unwrap();
+    message
+}
+
+fn point() -> DynamicMessage {
+    Point {
+        latitude: 1,
+        longitude: 2,
+    }
+    .transcode_to_dynamic()
+}
+
+struct PrefixResolver;
+
+impl TypeResolver for PrefixResolver {
+    fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+        let message_name = type_url.strip_prefix("example.com/")?;
+        test_file_descriptor().get_message_by_name(message_name)
+    }
+}
+
+#[test]
+fn pack_unpack() {
+    let any = point().pack_any();
+    assert_eq!(any.descriptor().full_name(), "google.protobuf.Any");
+    assert_eq!(
+        any.transcode_to::<prost_types::Any>().unwrap(),
+        prost_types::Any {
+            type_url: "type.googleapis.com/test.Point".to_owned(),
+            value: point().encode_to_vec(),
+        }
+    );
+    assert_eq!(any.unpack_any().unwrap(), point());
+}
+
+#[test]
+fn unpack_not_any() {
+    assert_eq!(
+        point().unpack_any().unwrap_err(),
+        UnpackAnyError::NotAny {
+            message_name: "test.Point".to_owned()
+        }
+    );
+}
+
+#[test]
+fn unpack_type_outside_pool() {
+    let any = any_message("type.googleapis.com/test.Point", point().encode_to_vec());
+    assert_eq!(
+        any.unpack_any().unwrap_err(),
+        UnpackAnyError::TypeNotFound {
+            type_url: "type.googleapis.com/test.Point".to_owned()
+        }
+    );
+    assert_eq!(
+        any.unpack_any_with_resolver(&test_file_descriptor())
+            .unwrap(),
+        point()
+    );
+}
+
+#[test]
+fn unpack_invalid_value() {
+    let any = any_message("type.googleapis.com/test.Point", b"\x08".to_vec());
+    assert!(matches!(
+        any.unpack_any_with_resolver(&test_file_descriptor()),
+        Err(UnpackAnyError::Decode(_))
+    ));
+}
+
+#[test]
+fn descriptor_pool_resolver() {
+    let pool = test_file_descriptor();
+    for type_url in [
+        "type.googleapis.com/test.Point",
+        "example.com/test.Point",
+        "/test.Point",
+    ] {
+        assert_eq!(
+            pool.resolve(type_url).unwrap().full_name(),
+            "test.Point",
+            "{}",
+            type_url
+        );
+    }
+    assert!(pool.resolve("test.Point").is_none());
+    assert!(pool.resolve("type.googleapis.com/test.NotFound").is_none());
+
+    let pools = vec![any_only_pool(), pool];
+    assert_eq!(
+        pools
+            .resolve("type.googleapis.com/test.Point")
+            .unwrap()
+            .full_name(),
+        "test.Point"
+    );
+}
+
+#[test]
+fn json_resolver() {
+    let any = any_message("example.com/test.Point", point().encode_to_vec());
+    let expected = json!({
+        "@type": "example.com/test.Point",
+        "latitude": 1,
+        "longitude": 2,
+    });
+
+    assert!(serde_json::to_value(&any).is_err());
+    let options = SerializeOptions::new().type_resolver(PrefixResolver);
+    let json = any
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap();
+    assert_eq!(json, expected);
+
+    assert!(DynamicMessage::deserialize(any.descriptor(), expected.clone()).is_err());
+    let options = DeserializeOptions::new().type_resolver(PrefixResolver);
+    let message =
+        DynamicMessage::deserialize_with_options(any.descriptor(), expected, &options).unwrap();
+    assert_eq!(message, any);
+}
+
+#[test]
+fn json_resolver_well_known_type() {
+    let any = any_message(
+        "type.googleapis.com/google.protobuf.Int32Value",
+        5i32.encode_to_vec(),
+    );
+    let expected = json!({
+        "@type": "type.googleapis.com/google.protobuf.Int32Value",
+        "value": 5,
+    });
+
+    let options = SerializeOptions::new().type_resolver(DescriptorPool::global());
+    let json = any
+        .serialize_with_options(serde_json::value::Serializer, &options)
+        .unwrap();
+    assert_eq!(json, expected);
+
+    let options = DeserializeOptions::new().type_resolver(DescriptorPool::global());
+    let message =
+        DynamicMessage::deserialize_with_options(any.descriptor(), expected, &options).unwrap();
+    assert_eq!(message, any);
+}
+
+#[test]
+fn deserializer_resolver() {
+    use serde::Deserialize;
+
+    let any = any_message("example.com/test.Point", point().encode_to_vec());
+
+    assert!(serde_json::Value::deserialize(MessageDeserializer::new(&any)).is_err());
+    let deserializer = MessageDeserializer::new(&any).type_resolver(PrefixResolver);
+    assert_eq!(
+        serde_json::Value::deserialize(deserializer).unwrap(),
+        json!({
+            "@type": "example.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        })
+    );
+}
+
+#[test]
+fn text_format_resolver() {
+    let any = any_message("type.googleapis.com/test.Point", point().encode_to_vec());
+
+    assert_eq!(
+        any.to_text_format(),
+        r#"type_url:"type.googleapis.com/test.Point",value:"\010\001\020\002""#
+    );
+    let options = FormatOptions::new().type_resolver(test_file_descriptor());
+    assert_eq!(
+        any.to_text_format_with_options(&options),
+        "[type.googleapis.com/test.Point]{latitude:1,longitude:2}"
+    );
+
+    let input = "[type.googleapis.com/test.Point] { latitude: 1 longitude: 2 }";
+    let err = DynamicMessage::parse_text_format(any.descriptor(), input).unwrap_err();
+    assert_eq!(err.to_string(), "message type 'test.Point' not found");
+    let options = ParseOptions::new().type_resolver(test_file_descriptor());
+    assert_eq!(
+        DynamicMessage::parse_text_format_with_options(any.descriptor(), input, &options).unwrap(),
+        any
+    );
+}
+
+#[test]
+fn text_format_resolver_custom_domain() {
+    let any = any_message("example.com/test.Point", point().encode_to_vec());
+    let input = "[example.com/test.Point] { latitude: 1 longitude: 2 }";
+
+    assert!(DynamicMessage::parse_text_format(any.descriptor(), input).is_err());
+    let options = ParseOptions::new().type_resolver(PrefixResolver);
+    let message =
+        DynamicMessage::parse_text_format_with_options(any.descriptor(), input, &options).unwrap();
+    assert_eq!(message, any);
+
+    let options = FormatOptions::new().type_resolver(PrefixResolver);
+    assert_eq!(
+        message.to_text_format_with_options(&options),
+        "[example.com/test.Point]{latitude:1,longitude:2}"
+    );
+}
@@ -0,0 +1,161 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    iter::FromIterator,
+};
+
+use prost_reflect::{
+    CanonicalDynamicMessage, CanonicalOptions, CanonicalValue, DynamicMessage, MapKey,
+    ReflectMessage, Value,
+};
+
+use crate::proto::{ComplexType, ScalarArrays, Scalars};
+
+fn complex_type() -> DynamicMessage {
+    ComplexType {
+        string_map: HashMap::from_iter((0..20).map(|i| {
+            (
+                i.to_string(),
+                Scalars {
+                    int32: i,
+                    ..Default::default()
+                },
+            )
+        })),
+        int_map: HashMap::from_iter((0..20).map(|i| (i, Scalars::default()))),
+        nested: Some(Scalars {
+            double: 1.5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn canonical_map_order() {
+    let message = complex_type();
+
+    // Rebuild the map fields, so that entries are inserted in a different order.
+    let mut reordered = message.clone();
+    for field in ["string_map", "int_map"] {
+        let map = reordered
+            .get_field_by_name_mut(field)
+            .unwrap()
+            .as_map_mut()
+            .unwrap();
+        let mut entries: Vec<(MapKey, Value)> = map.drain().collect();
+        entries.reverse();
+        map.extend(entries);
+    }
+
+    let options = CanonicalOptions::new();
+    assert_eq!(
+        CanonicalDynamicMessage::new(message.clone()),
+        CanonicalDynamicMessage::new(reordered.clone())
+    );
+    assert_eq!(
+        message.canonical_hash(&options),
+        reordered.canonical_hash(&options)
+    );
+
+    let set = HashSet::<_>::from_iter([
+        CanonicalDynamicMessage::new(message),
+        CanonicalDynamicMessage::new(reordered),
+    ]);
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn canonical_floats() {
+    let message = |double: Vec<f64>| {
+        ScalarArrays {
+            double,
+            ..Default::default()
+        }
+        .transcode_to_dynamic()
+    };
+
+    let a = CanonicalDynamicMessage::new(message(vec![f64::NAN, 0.0]));
+    let b = CanonicalDynamicMessage::new(message(vec![-f64::NAN, -0.0]));
+    assert_eq!(a, b);
+    assert_eq!(
+        a.canonical_hash(&a.options()),
+        b.canonical_hash(&b.options())
+    );
+
+    assert!(
+        CanonicalValue::new(Value::F64(f64::NAN)) > CanonicalValue::new(Value::F64(f64::INFINITY))
+    );
+    assert!(CanonicalValue::new(Value::F32(-1.0)) < CanonicalValue::new(Value::F32(-0.0)));
+    assert_eq!(
+        CanonicalValue::new(Value::F32(-0.0)),
+        CanonicalValue::new(Value::F32(0.0))
+    );
+}
+
+#[test]
+fn canonical_ordering() {
+    let message = |int32: i32, string: &str| {
+        CanonicalDynamicMessage::new(
+            Scalars {
+                int32,
+                string: string.to_owned(),
+                ..Default::default()
+            }
+            .transcode_to_dynamic(),
+        )
+    };
+
+    let sorted = BTreeSet::from_iter([
+        message(2, "a"),
+        message(1, "b"),
+        message(1, "a"),
+        message(0, ""),
+        message(1, "a"),
+    ]);
+    assert_eq!(
+        sorted
+            .into_iter()
+            .map(|m| m.into_inner().transcode_to::<Scalars>().unwrap())
+            .map(|m| (m.int32, m.string))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, "".to_owned()),
+            (1, "a".to_owned()),
+            (1, "b".to_owned()),
+            (2, "a".to_owned()),
+        ]
+    );
+
+    // Messages of different types are ordered by name.
+    assert!(
+        CanonicalDynamicMessage::new(ComplexType::default().transcode_to_dynamic())
+            < CanonicalDynamicMessage::new(Scalars::default().transcode_to_dynamic())
+    );
+}
+
+#[test]
+fn canonical_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    let a = DynamicMessage::decode(desc.clone(), b"\x18\x01".as_ref()).unwrap();
+    let b = DynamicMessage::decode(desc, b"\x18\x01\xa0\x06\x05".as_ref()).unwrap();
+
+    assert_ne!(
+        CanonicalDynamicMessage::new(a.clone()),
+        CanonicalDynamicMessage::new(b.clone())
+    );
+
+    let options = CanonicalOptions::new().ignore_unknown_fields(true);
+    assert_eq!(
+        CanonicalDynamicMessage::with_options(a.clone(), options),
+        CanonicalDynamicMessage::with_options(b.clone(), options)
+    );
+    assert_eq!(a.canonical_hash(&options), b.canonical_hash(&options));
+
+    // Wrappers with different options are never equal.
+    assert_ne!(
+        CanonicalDynamicMessage::new(a.clone()),
+        CanonicalDynamicMessage::with_options(a, options)
+    );
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+
+use prost_reflect::{
+    cel::{Activation, CelType, CelValue, Env, EvalError},
+    MapKey, ReflectMessage, TimeError,
+};
+use serde_json::json;
+
+use crate::{message, test_file_descriptor};
+
+fn env() -> Env {
+    let pool = test_file_descriptor();
+    Env::new(pool.clone())
+        .variable("scalars", pool.get_message_by_name("test.Scalars").unwrap())
+        .variable(
+            "complex",
+            pool.get_message_by_name("test.ComplexType").unwrap(),
+        )
+        .variable(
+            "wkt",
+            pool.get_message_by_name("test.WellKnownTypes").unwrap(),
+        )
+        .variable("name", CelType::String)
+}
+
+fn activation() -> Activation {
+    Activation::new()
+        .bind(
+            "scalars",
+            message(
+                "test.Scalars",
+                json!({ "int32": 5, "uint64": "7", "double": 1.5, "string": "hello", "bytes": "AQI=" }),
+            ),
+        )
+        .bind(
+            "complex",
+            message(
+                "test.ComplexType",
+                json!({
+                    "stringMap": { "a": { "int32": 1 }, "b": { "int32": 2 } },
+                    "intMap": { "3": { "string": "three" } },
+                    "nested": { "bool": true },
+                    "myEnum": ["FOO", "BAR"],
+                }),
+            ),
+        )
+        .bind(
+            "wkt",
+            message(
+                "test.WellKnownTypes",
+                json!({
+                    "timestamp": "2023-03-15T12:30:45.250Z",
+                    "duration": "90.5s",
+                    "struct": { "role": "admin", "level": 3, "tags": ["a", "b"] },
+                    "int32": 42,
+                    "string": "wrapped",
+                    "list": [1, "two", null],
+                }),
+            ),
+        )
+        .bind("name", "alice")
+}
+
+fn eval(source: &str) -> Result<CelValue, EvalError> {
+    let program = env()
+        .compile(source)
+        .unwrap_or_else(|err| panic!("{}: {}", source, err));
+    program.evaluate(&activation())
+}
+
+fn assert_true(source: &str) {
+    assert_eq!(eval(source).unwrap(), CelValue::Bool(true), "{}", source);
+}
+
+fn compile_error(source: &str) -> String {
+    env().compile(source).unwrap_err().to_string()
+}
+
+#[test]
+fn literals_and_operators() {
+    assert_eq!(eval("1 + 2 * 3").unwrap(), CelValue::Int(7));
+    assert_eq!(eval("7u / 2u").unwrap(), CelValue::Uint(3));
+    assert_eq!(eval("-7 % 3").unwrap(), CelValue::Int(-1));
+    assert_eq!(eval("1.5 * 2.0").unwrap(), CelValue::Double(3.0));
+    assert_eq!(
+        eval("'foo' + \"bar\"").unwrap(),
+        CelValue::String("foobar".to_owned())
+    );
+    assert_eq!(
+        eval("b'a' + b'\\x00'").unwrap(),
+        CelValue::from(vec![b'a', 0])
+    );
+    assert_eq!(
+        eval("[1, 2] + [3]").unwrap(),
+        CelValue::List(vec![CelValue::Int(1), CelValue::Int(2), CelValue::Int(3)])
+    );
+    assert_true("1 < 2 && 2u >= 2u && 'a' < 'b' && !(1.0 > 2.0)");
+    assert_true("1 == 1.0 && 2u == 2 && 1 < 1.5 && 3u > -1");
+    assert_true("true ? name == 'alice' : false");
+    assert_true("[1, 2, 3][1] == 2 && {'a': 1}['a'] == 1");
+    assert_true("2 in [1, 2] && 'a' in {'a': 1} && !(3 in [1, 2])");
+    assert_true("size('héllo') == 5 && size(b'ab') == 2 && size([1]) == 1");
+    assert_true("name.startsWith('al') && name.endsWith('ce') && name.contains('lic')");
+    assert_true("name.matches('^a.*e$') && matches(name, 'l')");
+}
+
+#[test]
+fn logical_operators_absorb_errors() {
+    assert_true("false && 1 / 0 == 1 || true");
+    assert_true("1 / 0 == 1 || true");
+    assert_eq!(eval("1 / 0 == 1 || false"), Err(EvalError::DivisionByZero));
+    assert_eq!(eval("false && [1][5] == 1").unwrap(), CelValue::Bool(false));
+}
+
+#[test]
+fn arithmetic_errors() {
+    assert_eq!(eval("1 / 0"), Err(EvalError::DivisionByZero));
+    assert_eq!(eval("5u % 0u"), Err(EvalError::DivisionByZero));
+    assert!(matches!(
+        eval("9223372036854775807 + 1"),
+        Err(EvalError::Overflow { .. })
+    ));
+    assert!(matches!(eval("0u - 1u"), Err(EvalError::Overflow { .. })));
+    assert!(matches!(
+        eval("-9223372036854775808"),
+        Ok(CelValue::Int(i64::MIN))
+    ));
+    assert_eq!(
+        eval("[1, 2][2]"),
+        Err(EvalError::IndexOutOfRange { index: 2, len: 2 })
+    );
+    assert!(matches!(
+        eval("{'a': 1}['b']"),
+        Err(EvalError::NoSuchKey { .. })
+    ));
+}
+
+#[test]
+fn field_selection() {
+    assert_eq!(eval("scalars.int32").unwrap(), CelValue::Int(5));
+    assert_eq!(eval("scalars.uint64").unwrap(), CelValue::Uint(7));
+    assert_eq!(eval("scalars.float").unwrap(), CelValue::Double(0.0));
+    assert_eq!(eval("scalars.bytes").unwrap(), CelValue::from(vec![1u8, 2]));
+    assert_true("scalars.string == 'hello' && scalars.double == 1.5");
+    assert_true("complex.nested.bool && complex.nested.int32 == 0");
+    assert_true("complex.my_enum == [1, 3] && complex.optional_enum == 0");
+    assert_true("complex.my_enum[1] == test.ComplexType.MyEnum.BAR");
+
+    assert_eq!(
+        env().compile("scalars.int32").unwrap().result_type(),
+        &CelType::Int
+    );
+    assert_eq!(
+        env().compile("complex.string_map").unwrap().result_type(),
+        &CelType::Map(
+            Box::new(CelType::String),
+            Box::new(CelType::Message(
+                test_file_descriptor()
+                    .get_message_by_name("test.Scalars")
+                    .unwrap()
+            ))
+        )
+    );
+}
+
+#[test]
+fn nested_field_selection() {
+    assert_eq!(
+        eval("complex.string_map['b'].int32").unwrap(),
+        CelValue::Int(2)
+    );
+    assert_eq!(
+        eval("complex.nested").unwrap(),
+        CelValue::from(message("test.Scalars", json!({ "bool": true })))
+    );
+    assert_eq!(eval("complex.nested.string").unwrap(), CelValue::from(""));
+    assert_eq!(eval("wkt.struct.role").unwrap(), CelValue::from("admin"));
+    assert!(matches!(
+        eval("wkt.timestamp").unwrap(),
+        CelValue::Timestamp(_)
+    ));
+    assert_true("[complex.nested][0].bool && {'a': complex.nested}.a.bool");
+    assert_true("has(complex.nested.bool) && !has(complex.nested.int32)");
+}
+
+#[test]
+fn has_macro() {
+    assert_true("has(scalars.int32) && !has(scalars.int64)");
+    assert_true("has(complex.nested) && has(complex.string_map) && has(complex.my_enum)");
+    assert_true("has(wkt.timestamp) && !has(wkt.float) && has(wkt.int32)");
+    assert_true("has(wkt.struct.role) && !has(wkt.struct.missing)");
+}
+
+#[test]
+fn map_and_list_indexing() {
+    assert_true("complex.string_map['b'].int32 == 2");
+    assert_true("complex.int_map[3].string == 'three'");
+    assert_true("complex.int_map[3u].string == 'three'");
+    assert_true("'a' in complex.string_map && !('z' in complex.string_map)");
+    assert_true("size(complex.string_map) == 2 && size(complex.my_enum) == 2");
+    assert!(matches!(
+        eval("complex.string_map['z']"),
+        Err(EvalError::NoSuchKey { .. })
+    ));
+}
+
+#[test]
+fn macros() {
+    assert_true("complex.my_enum.all(e, e > 0)");
+    assert_true("complex.my_enum.exists(e, e == 3)");
+    assert_true("complex.my_enum.exists_one(e, e == 1)");
+    assert_true("complex.string_map.all(k, complex.string_map[k].int32 > 0)");
+    assert_true("[1, 2, 3].map(x, x * 2) == [2, 4, 6]");
+    assert_true("[1, 2, 3].map(x, x > 1, x * 10) == [20, 30]");
+    assert_true("[1, 2, 3].filter(x, x % 2 == 1) == [1, 3]");
+    assert_true("complex.string_map.map(k, k) == ['a', 'b']");
+    assert_true("[0, 1].exists(x, 1 / x == 1)");
+    assert_eq!(
+        eval("[0, 1].all(x, 1 / x == 1)"),
+        Err(EvalError::DivisionByZero)
+    );
+}
+
+#[test]
+fn well_known_types() {
+    assert_eq!(
+        eval("wkt.timestamp").unwrap().cel_type(),
+        CelType::Timestamp
+    );
+    assert_true("wkt.timestamp == timestamp('2023-03-15T12:30:45.250Z')");
+    assert_true("wkt.duration == duration('1m30.5s')");
+    assert_true("wkt.duration.getSeconds() == 90 && wkt.duration.getMilliseconds() == 90500");
+    assert_true("wkt.timestamp.getFullYear() == 2023 && wkt.timestamp.getMonth() == 2");
+    assert_true("wkt.timestamp.getDate() == 15 && wkt.timestamp.getDayOfMonth() == 14");
+    assert_true("wkt.timestamp.getDayOfWeek() == 3 && wkt.timestamp.getDayOfYear() == 73");
+    assert_true("wkt.timestamp.getHours() == 12 && wkt.timestamp.getHours('+05:30') == 18");
+    assert_true(
+        "wkt.timestamp.getMinutes('-00:45') == 45 && wkt.timestamp.getMilliseconds() == 250",
+    );
+    assert_true("wkt.timestamp + duration('12h') > timestamp('2023-03-16T00:00:00Z')");
+    assert_true("timestamp('2023-03-16T00:00:00Z') - wkt.timestamp == duration('11h29m14.75s')");
+    assert_true("string(wkt.duration) == '90.5s' && int(timestamp('1970-01-01T00:01:00Z')) == 60");
+
+    assert_true("wkt.int32 == 42 && wkt.string == 'wrapped'");
+    assert_true("wkt.float == null && wkt.int64 == null");
+    assert_eq!(
+        env().compile("wkt.int32").unwrap().result_type(),
+        &CelType::Wrapper(Box::new(CelType::Int))
+    );
+
+    assert_true("wkt.struct.role == 'admin' && wkt.struct['level'] == 3.0");
+    assert_true("wkt.struct.tags == ['a', 'b'] && 'admin' in [wkt.struct.role]");
+    assert_true("wkt.list[0] == 1.0 && wkt.list[1] == 'two' && wkt.list[2] == null");
+    assert!(matches!(
+        eval("duration('1x')"),
+        Err(EvalError::InvalidArgument { .. })
+    ));
+    assert!(matches!(
+        eval("timestamp('9999-12-31T23:59:59Z') + duration('1s')"),
+        Err(EvalError::Overflow { .. })
+    ));
+}
+
+#[test]
+fn out_of_range_time() {
+    let timestamp = prost_types::Timestamp {
+        seconds: i64::MAX,
+        nanos: 0,
+    };
+    assert_eq!(
+        CelValue::try_from(timestamp),
+        Err(TimeError::OutOfRange {
+            message_name: "google.protobuf.Timestamp"
+        })
+    );
+    assert_eq!(
+        CelValue::try_from(prost_types::Duration {
+            seconds: i64::MIN,
+            nanos: 0,
+        }),
+        Err(TimeError::OutOfRange {
+            message_name: "google.protobuf.Duration"
+        })
+    );
+    assert!(matches!(
+        CelValue::from(timestamp.transcode_to_dynamic()),
+        CelValue::Message(_)
+    ));
+
+    let program = Env::new(test_file_descriptor())
+        .variable("t", CelType::Timestamp)
+        .compile("t.getHours('+01:00')")
+        .unwrap();
+    let activation = Activation::new().bind("t", CelValue::Timestamp(timestamp));
+    assert_eq!(
+        program.evaluate(&activation),
+        Err(EvalError::Overflow {
+            function: "getHours".to_owned()
+        })
+    );
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(eval("int('42')").unwrap(), CelValue::Int(42));
+    assert_eq!(eval("int(2.9)").unwrap(), CelValue::Int(2));
+    assert_eq!(eval("uint(3)").unwrap(), CelValue::Uint(3));
+    assert_eq!(eval("double(3u)").unwrap(), CelValue::Double(3.0));
+    assert_eq!(eval("string(1.5)").unwrap(), CelValue::from("1.5"));
+    assert_eq!(eval("string(b'hi')").unwrap(), CelValue::from("hi"));
+    assert_eq!(eval("bytes('hi')").unwrap(), CelValue::from(b"hi".to_vec()));
+    assert_eq!(eval("bool('true')").unwrap(), CelValue::Bool(true));
+    assert!(matches!(eval("uint(-1)"), Err(EvalError::Overflow { .. })));
+    assert!(matches!(eval("int(1e19)"), Err(EvalError::Overflow { .. })));
+    assert!(matches!(
+        eval("int('x')"),
+        Err(EvalError::InvalidArgument { .. })
+    ));
+
+    assert_true("type(1) == int && type('a') == string && type(scalars) == test.Scalars");
+    assert_true("type(wkt.timestamp) == google.protobuf.Timestamp");
+    assert_true("dyn(1) == 1.0");
+}
+
+#[test]
+fn message_construction() {
+    let value = eval("test.Point{latitude: 1, longitude: -2}").unwrap();
+    let expected = message("test.Point", json!({ "latitude": 1, "longitude": -2 }));
+    assert_eq!(value, CelValue::Message(expected));
+
+    assert_true("test.Scalars{string: 'x', bytes: b'y'}.string == 'x'");
+    assert_true(
+        "test.ComplexType{string_map: {'k': test.Scalars{int32: 1}}}.string_map.k.int32 == 1",
+    );
+    assert_true("test.ComplexType{my_enum: [1, 3]}.my_enum == complex.my_enum");
+    assert_true(
+        "test.WellKnownTypes{int32: 5, timestamp: timestamp('2000-01-01T00:00:00Z')}.int32 == 5",
+    );
+    assert_true("test.WellKnownTypes{struct: {'a': 1}}.struct.a == 1.0");
+    assert_true("!has(test.WellKnownTypes{int32: null}.int32)");
+
+    assert!(matches!(
+        eval("test.Scalars{int32: 3000000000}"),
+        Err(EvalError::InvalidArgument { .. })
+    ));
+}
+
+#[test]
+fn bind_values() {
+    let env = Env::new(test_file_descriptor())
+        .variable("tags", CelType::List(Box::new(CelType::String)))
+        .variable(
+            "limits",
+            CelType::Map(Box::new(CelType::String), Box::new(CelType::Int)),
+        )
+        .variable(
+            "point",
+            test_file_descriptor()
+                .get_message_by_name("test.Point")
+                .unwrap(),
+        );
+    let program = env
+        .compile("'admin' in tags && limits['rps'] > 10 && point.latitude == 1")
+        .unwrap();
+
+    let activation = Activation::new()
+        .bind("tags", vec![CelValue::from("admin")])
+        .bind(
+            "limits",
+            HashMap::from([(MapKey::String("rps".to_owned()), CelValue::Int(20))]),
+        )
+        .bind("point", message("test.Point", json!({ "latitude": 1 })));
+    assert_eq!(program.evaluate(&activation).unwrap(), CelValue::Bool(true));
+
+    assert_eq!(
+        program.evaluate(&Activation::new()),
+        Err(EvalError::UnboundVariable {
+            name: "tags".to_owned()
+        })
+    );
+
+    let point = message("test.Point", json!({ "latitude": 1 }));
+    assert_eq!(
+        CelValue::from(point.clone())
+            .as_message()
+            .unwrap()
+            .descriptor(),
+        point.descriptor()
+    );
+}
+
+#[test]
+fn type_errors() {
+    assert_eq!(
+        compile_error("scalars.nope"),
+        "undefined field 'nope' for type 'test.Scalars'"
+    );
+    assert_eq!(compile_error("nope + 1"), "undeclared reference to 'nope'");
+    assert!(compile_error("scalars.int32 + 'a'").contains("no matching overload"));
+    assert!(compile_error("scalars.string.startsWith(1)").contains("no matching overload"));
+    assert!(compile_error("test.Point{latitude: 'a'}").contains("expected"));
+    assert!(compile_error("test.Point{nope: 1}").contains("undefined field 'nope'"));
+    assert!(compile_error("test.Nope{}").contains("test.Nope"));
+    assert!(compile_error("1 +").contains("reached end of input"));
+    assert!(compile_error("'abc").contains("unterminated string"));
+    assert!(compile_error("scalars.int32 ? 1 : 2").contains("no matching overload for '_?_:_'"));
+    assert!(compile_error("[1].all(x, x)").contains("bool"));
+    assert!(compile_error("has(scalars)").contains("has"));
+    assert!(compile_error(&"(".repeat(300)).contains("nested too deeply"));
+    assert!(compile_error(&format!("{}1", "1 + ".repeat(150))).contains("nested too deeply"));
+    assert!(compile_error(&format!(
+        "{}1{}",
+        "(".repeat(30),
+        " + 1 + 1 + 1 + 1)".repeat(30)
+    ))
+    .contains("nested too deeply"));
+}
@@ -0,0 +1,351 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+use prost::{bytes::Bytes, Message};
+use prost_reflect::{
+    convert::{ConversionOptions, DropReason},
+    DescriptorPool, DynamicMessage, MapKey, ReflectMessage, Value,
+};
+use prost_types::{
+    descriptor_proto::ExtensionRange, field_descriptor_proto::Type, DescriptorProto,
+    FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+};
+
+use crate::proto::{schema_v1, schema_v2, ScalarArrays, Scalars, SchemaV1, SchemaV2};
+
+fn schema_v1() -> SchemaV1 {
+    SchemaV1 {
+        id: 5,
+        name: "foo".to_owned(),
+        status: schema_v1::Status::Active as i32,
+        scores: vec![1, -2],
+        items: HashMap::from_iter([(3, schema_v1::Nested { count: 4 })]),
+        nested: Some(schema_v1::Nested { count: 6 }),
+        ratio: 1.5,
+        legacy: "bar".to_owned(),
+    }
+}
+
+#[test]
+fn convert_by_name() {
+    let source = schema_v1().transcode_to_dynamic();
+    let conversion = source.convert_to(
+        &SchemaV2::default().descriptor(),
+        &ConversionOptions::new().match_by_name(true),
+    );
+
+    assert_eq!(conversion.dropped_fields().len(), 1);
+    let dropped = &conversion.dropped_fields()[0];
+    assert_eq!(dropped.path(), "legacy");
+    assert_eq!(dropped.value(), &Value::String("bar".to_owned()));
+    assert_eq!(dropped.reason(), DropReason::Incompatible);
+    assert_eq!(
+        dropped.to_string(),
+        "incompatible value for legacy: \"bar\""
+    );
+    assert!(!conversion.is_lossless());
+
+    assert_eq!(
+        conversion
+            .into_message()
+            .transcode_to::<SchemaV2>()
+            .unwrap(),
+        SchemaV2 {
+            id: 5,
+            name: b"foo".to_vec(),
+            status: schema_v2::Status::Active as i32,
+            scores: vec![1, -2],
+            items: HashMap::from_iter([(3, schema_v2::Nested { count: 4 })]),
+            nested: Some(schema_v2::Nested { count: 6 }),
+            ratio: 1.5,
+            legacy: 0,
+        }
+    );
+}
+
+#[test]
+fn convert_by_number() {
+    let source = schema_v1().transcode_to_dynamic();
+    let conversion =
+        source.convert_to(&SchemaV2::default().descriptor(), &ConversionOptions::new());
+
+    let dropped: Vec<_> = conversion
+        .dropped_fields()
+        .iter()
+        .map(|field| (field.path(), field.reason()))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            ("id", DropReason::NotFound),
+            ("name", DropReason::NotFound),
+            ("status", DropReason::NotFound),
+            ("scores", DropReason::NotFound),
+            ("items", DropReason::NotFound),
+            ("nested", DropReason::NotFound),
+            ("ratio", DropReason::NotFound),
+            ("legacy", DropReason::NotFound),
+        ]
+    );
+    assert_eq!(conversion.message().fields().count(), 0);
+}
+
+#[test]
+fn convert_enum_by_number() {
+    let mut source = DynamicMessage::new(SchemaV1::default().descriptor());
+    source.set_field_by_name("status", Value::EnumNumber(1));
+
+    // Matching by name maps the value by name, matching by number keeps the number.
+    let by_name = source.convert_to(
+        &SchemaV2::default().descriptor(),
+        &ConversionOptions::new().match_by_name(true),
+    );
+    assert_eq!(
+        by_name
+            .message()
+            .get_field_by_name("status")
+            .unwrap()
+            .as_ref(),
+        &Value::EnumNumber(2)
+    );
+
+    let by_number = source.convert_to(&SchemaV1::default().descriptor(), &ConversionOptions::new());
+    assert_eq!(
+        by_number
+            .message()
+            .get_field_by_name("status")
+            .unwrap()
+            .as_ref(),
+        &Value::EnumNumber(1)
+    );
+
+    // Values which are not defined in the source enum cannot be mapped by name.
+    source.set_field_by_name("status", Value::EnumNumber(7));
+    let by_name = source.convert_to(
+        &SchemaV2::default().descriptor(),
+        &ConversionOptions::new().match_by_name(true),
+    );
+    assert_eq!(by_name.dropped_fields()[0].path(), "status");
+}
+
+#[test]
+fn convert_singular_to_repeated() {
+    let source = Scalars {
+        int32: 1,
+        string: "foo".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let conversion = source.convert_to(
+        &ScalarArrays::default().descriptor(),
+        &ConversionOptions::new(),
+    );
+    assert!(conversion.is_lossless());
+    assert_eq!(
+        conversion.message().transcode_to::<ScalarArrays>().unwrap(),
+        ScalarArrays {
+            int32: vec![1],
+            string: vec!["foo".to_owned()],
+            ..Default::default()
+        }
+    );
+
+    // Repeated fields cannot be converted to singular fields.
+    let source = ScalarArrays {
+        int32: vec![1, 2],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let conversion = source.convert_to(&Scalars::default().descriptor(), &ConversionOptions::new());
+    assert_eq!(conversion.dropped_fields().len(), 1);
+    assert_eq!(conversion.dropped_fields()[0].path(), "int32");
+    assert_eq!(
+        conversion.dropped_fields()[0].value(),
+        &Value::List(vec![Value::I32(1), Value::I32(2)])
+    );
+}
+
+#[test]
+fn convert_narrowing() {
+    let source = SchemaV2 {
+        id: 5,
+        name: b"foo".to_vec(),
+        status: schema_v2::Status::Deleted as i32,
+        scores: vec![1, 2],
+        items: HashMap::from_iter([(3, schema_v2::Nested { count: 4 })]),
+        nested: Some(schema_v2::Nested { count: 6 }),
+        ratio: 1.5,
+        legacy: 7,
+    }
+    .transcode_to_dynamic();
+
+    let conversion = source.convert_to(
+        &SchemaV1::default().descriptor(),
+        &ConversionOptions::new().match_by_name(true),
+    );
+    let dropped: Vec<_> = conversion
+        .dropped_fields()
+        .iter()
+        .map(|field| (field.path(), field.value().clone(), field.reason()))
+        .collect();
+    assert_eq!(
+        dropped,
+        vec![
+            ("id", Value::I64(5), DropReason::Incompatible),
+            (
+                "name",
+                Value::Bytes(Bytes::from_static(b"foo")),
+                DropReason::Incompatible
+            ),
+            ("scores[0]", Value::I64(1), DropReason::Incompatible),
+            ("scores[1]", Value::I64(2), DropReason::Incompatible),
+            (
+                "items[3]",
+                source.get_field_by_name("items").unwrap().as_map().unwrap()[&MapKey::I64(3)]
+                    .clone(),
+                DropReason::Incompatible
+            ),
+            ("nested.count", Value::U64(6), DropReason::Incompatible),
+            ("ratio", Value::F64(1.5), DropReason::Incompatible),
+            ("legacy", Value::I32(7), DropReason::Incompatible),
+        ]
+    );
+
+    // Compatible values are still converted.
+    let message = conversion
+        .into_message()
+        .transcode_to::<SchemaV1>()
+        .unwrap();
+    assert_eq!(
+        message,
+        SchemaV1 {
+            status: schema_v1::Status::Deleted as i32,
+            nested: Some(schema_v1::Nested { count: 0 }),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn convert_unknown_fields() {
+    // Field 3 is unknown to `google.protobuf.Empty`, but is `int32` in `test.Scalars`.
+    let source = DynamicMessage::decode(
+        crate::test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+        b"\x18\x05\xa0\x06\x01".as_ref(),
+    )
+    .unwrap();
+
+    let conversion = source.convert_to(&Scalars::default().descriptor(), &ConversionOptions::new());
+    assert!(conversion.is_lossless());
+    assert_eq!(
+        conversion
+            .message()
+            .get_field_by_name("int32")
+            .unwrap()
+            .as_ref(),
+        &Value::I32(5)
+    );
+    assert_eq!(conversion.message().unknown_fields().count(), 1);
+    assert_eq!(
+        conversion.message().encode_to_vec(),
+        b"\x18\x05\xa0\x06\x01"
+    );
+
+    let conversion = source.convert_to(
+        &Scalars::default().descriptor(),
+        &ConversionOptions::new().preserve_unknown_fields(false),
+    );
+    assert!(!conversion.is_lossless());
+    assert_eq!(conversion.message().encode_to_vec(), b"");
+    let dropped = conversion.dropped_fields();
+    assert_eq!(dropped.len(), 2);
+    assert_eq!(dropped[0].path(), "3");
+    assert_eq!(dropped[0].reason(), DropReason::NotPreserved);
+    assert_eq!(
+        dropped[0].value(),
+        &Value::Bytes(Bytes::from_static(b"\x18\x05"))
+    );
+    assert_eq!(dropped[1].path(), "100");
+    assert_eq!(
+        dropped[1].to_string(),
+        "unknown field not preserved for 100: \"\\240\\006\\001\""
+    );
+
+    let conversion = source.convert_to(
+        &Scalars::default().descriptor(),
+        &ConversionOptions::new().match_by_name(true),
+    );
+    let dropped = conversion.dropped_fields();
+    assert_eq!(dropped.len(), 2);
+    assert_eq!(dropped[0].path(), "3");
+    assert_eq!(
+        dropped[0].value(),
+        &Value::Bytes(Bytes::from_static(b"\x18\x05"))
+    );
+
+    // Unknown fields which cannot be decoded as the target field are incompatible.
+    let conversion =
+        source.convert_to(&SchemaV1::default().descriptor(), &ConversionOptions::new());
+    assert_eq!(conversion.dropped_fields().len(), 0);
+    let source = DynamicMessage::decode(
+        crate::test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+        b"\x10\x05".as_ref(),
+    )
+    .unwrap();
+    let conversion =
+        source.convert_to(&SchemaV1::default().descriptor(), &ConversionOptions::new());
+    assert_eq!(
+        conversion.dropped_fields()[0].reason(),
+        DropReason::Incompatible
+    );
+}
+
+#[test]
+fn convert_extension_by_name_across_packages() {
+    let file = |package: &str, number: i32| FileDescriptorProto {
+        name: Some(format!("{}.proto", package)),
+        package: Some(package.to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("Message".to_owned()),
+            extension_range: vec![ExtensionRange {
+                start: Some(100),
+                end: Some(200),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        extension: vec![FieldDescriptorProto {
+            name: Some("value".to_owned()),
+            number: Some(number),
+            r#type: Some(Type::Int32 as i32),
+            extendee: Some(format!(".{}.Message", package)),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+        file: vec![file("v1", 100), file("v2", 150)],
+    })
+    .unwrap();
+    let source_desc = pool.get_message_by_name("v1.Message").unwrap();
+    let target_desc = pool.get_message_by_name("v2.Message").unwrap();
+
+    let mut source = DynamicMessage::new(source_desc);
+    source.set_extension(
+        &pool.get_extension_by_name("v1.value").unwrap(),
+        Value::I32(5),
+    );
+
+    let conversion = source.convert_to(&target_desc, &ConversionOptions::new().match_by_name(true));
+    assert!(conversion.is_lossless());
+    assert_eq!(
+        conversion
+            .message()
+            .get_extension(&pool.get_extension_by_name("v2.value").unwrap())
+            .as_ref(),
+        &Value::I32(5)
+    );
+}
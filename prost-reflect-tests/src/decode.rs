@@ -900,6 +900,77 @@ fn roundtrip_group() {
     .unwrap();
 }
 
+#[test]
+fn encode_deterministic_map_order() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([
+            ("b".to_owned(), Scalars::default()),
+            ("a".to_owned(), Scalars::default()),
+        ]),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        message.encode_deterministic_to_vec(),
+        b"\x0a\x05\x0a\x01a\x12\x00\x0a\x05\x0a\x01b\x12\x00"
+    );
+
+    let message = ComplexType {
+        string_map: HashMap::from_iter((0..50).map(|i| {
+            (
+                i.to_string(),
+                Scalars {
+                    int32: i,
+                    ..Default::default()
+                },
+            )
+        })),
+        int_map: HashMap::from_iter((0..50).map(|i| (i, Scalars::default()))),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let mut reordered = message.clone();
+    for field in ["string_map", "int_map"] {
+        let map = reordered
+            .get_field_by_name_mut(field)
+            .unwrap()
+            .as_map_mut()
+            .unwrap();
+        let mut entries: Vec<_> = map.drain().collect();
+        entries.reverse();
+        map.extend(entries);
+    }
+
+    let bytes = message.encode_deterministic_to_vec();
+    assert_eq!(bytes, reordered.encode_deterministic_to_vec());
+    assert_eq!(bytes.len(), message.encoded_len());
+    assert_eq!(
+        DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap(),
+        message
+    );
+}
+
+#[test]
+fn encode_deterministic_field_order() {
+    const BYTES: &[u8] = b"\x90\x07\x01\x89\x07\x00\x00\x00\x00\x00\x00\x45\x40\x08\x01";
+
+    let message_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    let message = DynamicMessage::decode(message_desc, BYTES).unwrap();
+
+    assert_eq!(
+        message.encode_deterministic_to_vec(),
+        b"\x08\x01\x89\x07\x00\x00\x00\x00\x00\x00\x45\x40\x90\x07\x01"
+    );
+
+    let mut buf = [0u8; 4];
+    assert!(message.encode_deterministic(&mut buf.as_mut()).is_err());
+    assert_eq!(buf, [0; 4]);
+}
+
 fn roundtrip<T>(message: &T) -> Result<(), TestCaseError>
 where
     T: PartialEq + Debug + ReflectMessage + Default,
@@ -0,0 +1,338 @@
+use std::{collections::HashMap, iter::FromIterator, mem};
+
+use proptest::prelude::*;
+use prost::{bytes::Buf, Message};
+use prost_reflect::{DecodeOptions, DynamicMessage, ReflectMessage, Value};
+use prost_types::DescriptorProto;
+
+use crate::{
+    proto::{contains_group, ComplexType, ContainsGroup, ScalarArrays, Scalars, WellKnownTypes},
+    test_file_descriptor,
+};
+
+fn nested_descriptor_proto(depth: usize) -> Vec<u8> {
+    let mut message = DescriptorProto::default();
+    for _ in 1..depth {
+        message = DescriptorProto {
+            nested_type: vec![message],
+            ..Default::default()
+        };
+    }
+    message.encode_to_vec()
+}
+
+#[test]
+fn decode_options_default() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                string: "foo".to_owned(),
+                ..Default::default()
+            },
+        )]),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        nested: Some(Scalars {
+            bytes: b"bar".to_vec(),
+            ..Default::default()
+        }),
+        my_enum: vec![0, 1, 2],
+        optional_enum: 1,
+    };
+    let bytes = message.encode_to_vec();
+
+    let decoded = DynamicMessage::decode_with_options(
+        message.descriptor(),
+        bytes.as_slice(),
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(decoded.transcode_to::<ComplexType>().unwrap(), message);
+
+    let group = ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "bar".to_owned(),
+            b: Some(1),
+        }),
+        optionalgroup: None,
+        repeatedgroup: vec![contains_group::RepeatedGroup {
+            e: "hello".to_owned(),
+            f: None,
+        }],
+    };
+    let bytes = group.encode_to_vec();
+    let decoded = DynamicMessage::decode_with_options(
+        group.descriptor(),
+        bytes.as_slice(),
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(decoded.transcode_to::<ContainsGroup>().unwrap(), group);
+}
+
+#[test]
+fn decode_options_recursion_limit() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.DescriptorProto")
+        .unwrap();
+    let bytes = nested_descriptor_proto(10);
+
+    let options = DecodeOptions::new().recursion_limit(9);
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes.as_slice(), &options).is_ok());
+
+    let options = DecodeOptions::new().recursion_limit(8);
+    let err = DynamicMessage::decode_with_options(desc, bytes.as_slice(), &options).unwrap_err();
+    assert!(err.to_string().contains("recursion limit reached"));
+}
+
+#[test]
+fn decode_options_recursion_limit_unknown_group() {
+    let desc = Scalars::default().descriptor();
+    // Unknown field 100 containing a group nested three levels deep.
+    let bytes = b"\xa3\x06\xa3\x06\xa3\x06\xa4\x06\xa4\x06\xa4\x06";
+
+    let options = DecodeOptions::new().recursion_limit(3);
+    let message =
+        DynamicMessage::decode_with_options(desc.clone(), bytes.as_ref(), &options).unwrap();
+    assert_eq!(message.unknown_fields().count(), 1);
+    assert_eq!(message.encode_to_vec(), bytes);
+
+    let options = DecodeOptions::new().recursion_limit(2);
+    assert!(DynamicMessage::decode_with_options(desc.clone(), bytes.as_ref(), &options).is_err());
+
+    let options = options.discard_unknown_fields(true);
+    assert!(DynamicMessage::decode_with_options(desc, bytes.as_ref(), &options).is_err());
+}
+
+#[test]
+fn decode_options_allocation_limit() {
+    let message = ScalarArrays {
+        string: vec!["a".repeat(100); 10],
+        ..Default::default()
+    };
+    let bytes = message.encode_to_vec();
+
+    let options = DecodeOptions::new().allocation_limit(10_000);
+    let decoded =
+        DynamicMessage::decode_with_options(message.descriptor(), bytes.as_slice(), &options)
+            .unwrap();
+    assert_eq!(decoded.transcode_to::<ScalarArrays>().unwrap(), message);
+
+    let options = DecodeOptions::new().allocation_limit(500);
+    let err = DynamicMessage::decode_with_options(message.descriptor(), bytes.as_slice(), &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("allocation limit exceeded"));
+
+    // The limit also applies to nested messages and map entries.
+    let message = ComplexType {
+        string_map: HashMap::from_iter([(
+            "a".to_owned(),
+            Scalars {
+                bytes: vec![0; 1000],
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    };
+    let bytes = message.encode_to_vec();
+    assert!(
+        DynamicMessage::decode_with_options(message.descriptor(), bytes.as_slice(), &options)
+            .is_err()
+    );
+
+    // Unknown fields count towards the limit.
+    let bytes = [b"\xa2\x06\xe8\x07".as_ref(), &[0; 1000]].concat();
+    assert!(DynamicMessage::decode_with_options(
+        Scalars::default().descriptor(),
+        bytes.as_slice(),
+        &options
+    )
+    .is_err());
+}
+
+#[test]
+fn decode_options_allocation_limit_packed() {
+    let message = ScalarArrays {
+        int32: vec![1; 100],
+        double: vec![1.0; 100],
+        ..Default::default()
+    };
+    let bytes = message.encode_to_vec();
+
+    let options = DecodeOptions::new().allocation_limit(200 * mem::size_of::<Value>());
+    let decoded =
+        DynamicMessage::decode_with_options(message.descriptor(), bytes.as_slice(), &options)
+            .unwrap();
+    assert_eq!(decoded.transcode_to::<ScalarArrays>().unwrap(), message);
+
+    let options = DecodeOptions::new().allocation_limit(150 * mem::size_of::<Value>());
+    let err = DynamicMessage::decode_with_options(message.descriptor(), bytes.as_slice(), &options)
+        .unwrap_err();
+    assert!(err.to_string().contains("allocation limit exceeded"));
+}
+
+#[test]
+fn decode_options_allocation_limit_packed_chained() {
+    let message = ScalarArrays {
+        int32: vec![1000; 100],
+        ..Default::default()
+    };
+    let bytes = message.encode_to_vec();
+    let (first, second) = bytes.split_at(bytes.len() / 2);
+
+    let options = DecodeOptions::new().allocation_limit(100 * mem::size_of::<Value>());
+    let decoded =
+        DynamicMessage::decode_with_options(message.descriptor(), first.chain(second), &options)
+            .unwrap();
+    assert_eq!(decoded.transcode_to::<ScalarArrays>().unwrap(), message);
+
+    let options = DecodeOptions::new().allocation_limit(99 * mem::size_of::<Value>());
+    let err =
+        DynamicMessage::decode_with_options(message.descriptor(), first.chain(second), &options)
+            .unwrap_err();
+    assert!(err.to_string().contains("allocation limit exceeded"));
+}
+
+#[test]
+fn decode_options_allocation_limit_checked_before_allocating() {
+    // string = 14, with a length of 1GB but only a few bytes of data.
+    let bytes = b"\x72\x80\x80\x80\x80\x04abc";
+
+    let options = DecodeOptions::new().allocation_limit(1000);
+    let err = DynamicMessage::decode_with_options(
+        ScalarArrays::default().descriptor(),
+        bytes.as_ref(),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("buffer underflow"));
+
+    // string = 14, with a length of 2000.
+    let bytes = [b"\x72\xd0\x0f".as_ref(), &[b'a'; 2000]].concat();
+    let err = DynamicMessage::decode_with_options(
+        ScalarArrays::default().descriptor(),
+        bytes.as_slice(),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("allocation limit exceeded"));
+}
+
+#[test]
+fn decode_default_recursion_limit() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.DescriptorProto")
+        .unwrap();
+
+    let bytes = nested_descriptor_proto(101);
+    assert!(DynamicMessage::decode(desc.clone(), bytes.as_slice()).is_ok());
+    let mut message = DynamicMessage::new(desc.clone());
+    assert!(message.merge(bytes.as_slice()).is_ok());
+
+    let bytes = nested_descriptor_proto(102);
+    assert!(DynamicMessage::decode(desc.clone(), bytes.as_slice()).is_err());
+    let mut message = DynamicMessage::new(desc);
+    assert!(message.merge(bytes.as_slice()).is_err());
+}
+
+#[test]
+fn decode_options_unknown_fields() {
+    let desc = Scalars::default().descriptor();
+    // int32 = 1, unknown field 100 = 5
+    let bytes = b"\x18\x01\xa0\x06\x05";
+
+    let message =
+        DynamicMessage::decode_with_options(desc.clone(), bytes.as_ref(), &DecodeOptions::new())
+            .unwrap();
+    assert_eq!(message.unknown_fields().count(), 1);
+
+    let options = DecodeOptions::new().discard_unknown_fields(true);
+    let message =
+        DynamicMessage::decode_with_options(desc.clone(), bytes.as_ref(), &options).unwrap();
+    assert_eq!(message.unknown_fields().count(), 0);
+    assert_eq!(
+        message.get_field_by_name("int32").unwrap().as_ref(),
+        &Value::I32(1)
+    );
+
+    let options = options.deny_unknown_fields(true);
+    let err = DynamicMessage::decode_with_options(desc, bytes.as_ref(), &options).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("unknown field 100 in message 'test.Scalars'"));
+
+    // Unknown fields in nested messages are also rejected.
+    let nested = ComplexType::default().descriptor();
+    let bytes = b"\x1a\x05\x18\x01\xa0\x06\x05";
+    assert!(DynamicMessage::decode_with_options(nested.clone(), bytes.as_ref(), &options).is_err());
+
+    let options = DecodeOptions::new().discard_unknown_fields(true);
+    let message = DynamicMessage::decode_with_options(nested, bytes.as_ref(), &options).unwrap();
+    let nested_message = message.get_field_by_name("nested").unwrap();
+    assert_eq!(
+        nested_message
+            .as_message()
+            .unwrap()
+            .unknown_fields()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn merge_with_options() {
+    let desc = Scalars::default().descriptor();
+    let mut message = DynamicMessage::new(desc);
+    message
+        .merge_with_options(b"\x18\x01".as_ref(), &DecodeOptions::new())
+        .unwrap();
+    message
+        .merge_with_options(b"\x20\x02".as_ref(), &DecodeOptions::new())
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<Scalars>().unwrap(),
+        Scalars {
+            int32: 1,
+            int64: 2,
+            ..Default::default()
+        }
+    );
+}
+
+fn decode_matches<T>(message: &T) -> Result<(), TestCaseError>
+where
+    T: ReflectMessage,
+{
+    let bytes = message.encode_to_vec();
+    let expected = DynamicMessage::decode(message.descriptor(), bytes.as_slice()).unwrap();
+    let actual = DynamicMessage::decode_with_options(
+        message.descriptor(),
+        bytes.as_slice(),
+        &Default::default(),
+    )
+    .unwrap();
+    prop_assert_eq!(actual, expected);
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn decode_options_arb_scalar_arrays(message: ScalarArrays) {
+        decode_matches(&message)?;
+    }
+
+    #[test]
+    fn decode_options_arb_complex_type(message: ComplexType) {
+        decode_matches(&message)?;
+    }
+
+    #[test]
+    fn decode_options_arb_well_known_types(message: WellKnownTypes) {
+        decode_matches(&message)?;
+    }
+}
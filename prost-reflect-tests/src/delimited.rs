@@ -0,0 +1,150 @@
+use std::io::{BufReader, Cursor, ErrorKind};
+
+use prost::{bytes::BytesMut, Message};
+use prost_reflect::{
+    delimited::{DelimitedCodec, DelimitedError, DelimitedReader, DelimitedWriter},
+    DynamicMessage, ReflectMessage,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::proto::Scalars;
+
+fn messages() -> Vec<DynamicMessage> {
+    (0..5)
+        .map(|i| {
+            Scalars {
+                int32: i,
+                string: "x".repeat(i as usize * 100),
+                ..Default::default()
+            }
+            .transcode_to_dynamic()
+        })
+        .collect()
+}
+
+#[test]
+fn delimited_roundtrip() {
+    let messages = messages();
+
+    let mut writer = DelimitedWriter::new(Vec::new());
+    for message in &messages {
+        writer.write(message).unwrap();
+    }
+    writer.flush().unwrap();
+    let bytes = writer.into_inner();
+
+    let mut expected = Vec::new();
+    for message in &messages {
+        message.encode_length_delimited(&mut expected).unwrap();
+    }
+    assert_eq!(bytes, expected);
+
+    let reader = DelimitedReader::new(
+        BufReader::new(Cursor::new(bytes)),
+        Scalars::default().descriptor(),
+    );
+    let read = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read, messages);
+}
+
+#[test]
+fn delimited_reader_errors() {
+    let desc = Scalars::default().descriptor();
+
+    let mut bytes = Vec::new();
+    let mut writer = DelimitedWriter::new(&mut bytes);
+    for message in messages() {
+        writer.write(&message).unwrap();
+    }
+
+    // The stream ends in the middle of a message.
+    let mut reader = DelimitedReader::new(&bytes[..bytes.len() - 1], desc.clone());
+    assert_eq!(reader.by_ref().take(4).filter(Result::is_ok).count(), 4);
+    match reader.next() {
+        Some(Err(DelimitedError::Io(err))) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(reader.next().is_none());
+
+    // The stream ends in the middle of a length prefix.
+    let mut reader = DelimitedReader::new(b"\x96".as_ref(), desc.clone());
+    assert!(matches!(reader.next(), Some(Err(DelimitedError::Io(_)))));
+
+    // The length prefix exceeds the maximum size.
+    let mut reader = DelimitedReader::new(bytes.as_slice(), desc.clone()).max_message_size(200);
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(DelimitedError::MessageTooLarge {
+            size: 205,
+            max_size: 200
+        }))
+    ));
+
+    // The length prefix is not a valid varint.
+    let mut reader = DelimitedReader::new([0xff; 11].as_ref(), desc.clone());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(DelimitedError::InvalidLength))
+    ));
+
+    // The message is not valid.
+    let mut reader = DelimitedReader::new(b"\x02\x18\xff".as_ref(), desc);
+    assert!(matches!(
+        reader.next(),
+        Some(Err(DelimitedError::Decode(_)))
+    ));
+}
+
+#[test]
+fn delimited_writer_max_size() {
+    let mut writer = DelimitedWriter::new(Vec::new()).max_message_size(100);
+
+    let messages = messages();
+    writer.write(&messages[0]).unwrap();
+    assert!(matches!(
+        writer.write(&messages[1]),
+        Err(DelimitedError::MessageTooLarge {
+            size: 104,
+            max_size: 100
+        })
+    ));
+    assert_eq!(writer.get_ref().as_slice(), b"\x00");
+}
+
+#[test]
+fn delimited_codec() {
+    let messages = messages();
+    let mut codec = DelimitedCodec::new(Scalars::default().descriptor());
+
+    let mut buf = BytesMut::new();
+    for message in &messages {
+        codec.encode(message, &mut buf).unwrap();
+    }
+    let bytes = buf.freeze();
+
+    // Feed the bytes to the decoder in small chunks.
+    let mut decoded = Vec::new();
+    let mut src = BytesMut::new();
+    for chunk in bytes.chunks(7) {
+        src.extend_from_slice(chunk);
+        while let Some(message) = codec.decode(&mut src).unwrap() {
+            decoded.push(message);
+        }
+    }
+    assert!(src.is_empty());
+    assert_eq!(decoded, messages);
+
+    let mut codec = codec.max_message_size(100);
+    let mut src = BytesMut::from(&bytes[..]);
+    assert_eq!(codec.decode(&mut src).unwrap().as_ref(), Some(&messages[0]));
+    assert!(matches!(
+        codec.decode(&mut src),
+        Err(DelimitedError::MessageTooLarge { .. })
+    ));
+    assert!(matches!(
+        codec.encode(messages[1].clone(), &mut BytesMut::new()),
+        Err(DelimitedError::MessageTooLarge { .. })
+    ));
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use prost_reflect::{DynamicMessage, MessageDeserializer, ReflectMessage};
+use prost_types::{value::Kind, ListValue, Struct, Timestamp};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::proto::{ComplexType, Point, Scalars, WellKnownTypes};
+
+fn deserialize<'de, T: Deserialize<'de>>(message: &impl ReflectMessage) -> T {
+    T::deserialize(message.transcode_to_dynamic().into_deserializer()).unwrap()
+}
+
+#[test]
+fn deserialize_struct() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Scalars {
+        double: f64,
+        int32: i32,
+        int64: i64,
+        uint64: u64,
+        #[serde(rename = "bool")]
+        boolean: bool,
+        string: String,
+        #[serde(with = "serde_bytes_vec")]
+        bytes: Vec<u8>,
+    }
+
+    let value: Scalars = deserialize(&crate::proto::Scalars {
+        double: 1.5,
+        int32: -3,
+        int64: i64::MAX,
+        uint64: u64::MAX,
+        r#bool: true,
+        string: "hello".to_owned(),
+        bytes: b"\x00\xff".to_vec(),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        value,
+        Scalars {
+            double: 1.5,
+            int32: -3,
+            int64: i64::MAX,
+            uint64: u64::MAX,
+            boolean: true,
+            string: "hello".to_owned(),
+            bytes: b"\x00\xff".to_vec(),
+        }
+    );
+}
+
+mod serde_bytes_vec {
+    use serde::{de::Visitor, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct BytesVisitor;
+
+        impl Visitor<'_> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+#[test]
+fn deserialize_default_fields() {
+    let value: HashMap<String, serde_json::Value> = deserialize(&Scalars::default());
+    assert_eq!(value.len(), 15);
+    assert_eq!(value["int64"], json!(0));
+    assert_eq!(value["string"], json!(""));
+}
+
+#[test]
+fn deserialize_self_describing() {
+    let value: serde_json::Value = deserialize(&Scalars {
+        int64: -9_007_199_254_740_993,
+        uint64: 18_446_744_073_709_551_615,
+        ..Default::default()
+    });
+
+    assert_eq!(value["int64"], json!(-9_007_199_254_740_993i64));
+    assert_eq!(value["uint64"], json!(18_446_744_073_709_551_615u64));
+}
+
+#[test]
+fn deserialize_bytes_as_string_or_seq() {
+    #[derive(Debug, Deserialize)]
+    struct AsString {
+        bytes: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct AsSeq {
+        bytes: Vec<u8>,
+    }
+
+    let message = Scalars {
+        bytes: b"i\xa6\xbem\xb6\xffX".to_vec(),
+        ..Default::default()
+    };
+    assert_eq!(deserialize::<AsString>(&message).bytes, "aaa+bbb/WA==");
+    assert_eq!(
+        deserialize::<AsSeq>(&message).bytes,
+        b"i\xa6\xbem\xb6\xffX".to_vec()
+    );
+}
+
+#[test]
+fn deserialize_field_names() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct ProtoNames {
+        string_map: HashMap<String, Point>,
+        optional_enum: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct JsonNames {
+        string_map: HashMap<String, Point>,
+        optional_enum: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Point {
+        int32: i32,
+    }
+
+    let message = ComplexType {
+        string_map: HashMap::from([(
+            "a".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        optional_enum: 3,
+        ..Default::default()
+    };
+
+    let proto_names: ProtoNames = deserialize(&message);
+    assert_eq!(proto_names.string_map["a"], Point { int32: 5 });
+    assert_eq!(proto_names.optional_enum, "BAR");
+
+    let json_names: JsonNames = deserialize(&message);
+    assert_eq!(json_names.string_map["a"], Point { int32: 5 });
+    assert_eq!(json_names.optional_enum, "BAR");
+
+    let dynamic = message.transcode_to_dynamic();
+    let map: BTreeMap<String, serde_json::Value> =
+        Deserialize::deserialize(dynamic.clone().into_deserializer()).unwrap();
+    assert!(map.contains_key("stringMap"));
+    let map: BTreeMap<String, serde_json::Value> =
+        Deserialize::deserialize(MessageDeserializer::new(&dynamic).use_proto_field_name(true))
+            .unwrap();
+    assert!(map.contains_key("string_map"));
+}
+
+#[test]
+fn deserialize_enums() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum MyEnum {
+        Default,
+        Foo,
+        Bar,
+        Neg,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct ComplexType {
+        my_enum: Vec<MyEnum>,
+        optional_enum: i32,
+    }
+
+    let value: ComplexType = deserialize(&crate::proto::ComplexType {
+        my_enum: vec![1, 3, -4],
+        optional_enum: 3,
+        ..Default::default()
+    });
+    assert_eq!(
+        value,
+        ComplexType {
+            my_enum: vec![MyEnum::Foo, MyEnum::Bar, MyEnum::Neg],
+            optional_enum: 3,
+        }
+    );
+
+    #[derive(Debug, Deserialize)]
+    struct UnknownEnum {
+        #[allow(dead_code)]
+        optional_enum: MyEnum,
+    }
+
+    let message = crate::proto::ComplexType {
+        optional_enum: 42,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let err = UnknownEnum::deserialize(message.into_deserializer()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown value 42 for enum 'test.ComplexType.MyEnum'"
+    );
+}
+
+#[test]
+fn deserialize_int_map_keys() {
+    #[derive(Debug, Deserialize)]
+    struct ComplexType {
+        int_map: BTreeMap<i32, HashMap<String, serde_json::Value>>,
+    }
+
+    let value: ComplexType = deserialize(&crate::proto::ComplexType {
+        int_map: HashMap::from([(-1, Scalars::default()), (2, Scalars::default())]),
+        ..Default::default()
+    });
+    assert_eq!(value.int_map.keys().copied().collect::<Vec<_>>(), [-1, 2]);
+}
+
+#[test]
+fn deserialize_well_known_types() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct WellKnownTypes {
+        timestamp: String,
+        duration: Option<String>,
+        #[serde(rename = "struct")]
+        structure: HashMap<String, serde_json::Value>,
+        int32: Option<i32>,
+        int64: Option<i64>,
+        string: Option<String>,
+        mask: String,
+        list: Vec<serde_json::Value>,
+        null: (),
+    }
+
+    let value: WellKnownTypes = deserialize(&crate::proto::WellKnownTypes {
+        timestamp: Some(Timestamp {
+            seconds: 63_108_020,
+            nanos: 21_000_000,
+        }),
+        r#struct: Some(Struct {
+            fields: BTreeMap::from([(
+                "number".to_owned(),
+                prost_types::Value {
+                    kind: Some(Kind::NumberValue(42.0)),
+                },
+            )]),
+        }),
+        int64: Some(i64::MIN),
+        string: Some(String::new()),
+        mask: Some(prost_types::FieldMask {
+            paths: vec!["field_one".to_owned(), "field_two.b".to_owned()],
+        }),
+        list: Some(ListValue {
+            values: vec![
+                prost_types::Value {
+                    kind: Some(Kind::NullValue(0)),
+                },
+                prost_types::Value {
+                    kind: Some(Kind::BoolValue(true)),
+                },
+            ],
+        }),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        value,
+        WellKnownTypes {
+            timestamp: "1972-01-01T10:00:20.021Z".to_owned(),
+            duration: None,
+            structure: HashMap::from([("number".to_owned(), json!(42.0))]),
+            int32: None,
+            int64: Some(i64::MIN),
+            string: Some(String::new()),
+            mask: "fieldOne,fieldTwo.b".to_owned(),
+            list: vec![json!(null), json!(true)],
+            null: (),
+        }
+    );
+}
+
+#[test]
+fn deserialize_std_time_types() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct WellKnownTypes {
+        timestamp: SystemTime,
+        duration: Duration,
+    }
+
+    let value: WellKnownTypes = deserialize(&crate::proto::WellKnownTypes {
+        timestamp: Some(Timestamp {
+            seconds: 1_000,
+            nanos: 5,
+        }),
+        duration: Some(prost_types::Duration {
+            seconds: 3,
+            nanos: 500_000_000,
+        }),
+        ..Default::default()
+    });
+
+    assert_eq!(
+        value,
+        WellKnownTypes {
+            timestamp: UNIX_EPOCH + Duration::new(1_000, 5),
+            duration: Duration::from_millis(3_500),
+        }
+    );
+}
+
+#[test]
+fn deserialize_std_time_types_out_of_range() {
+    #[derive(Debug, Deserialize)]
+    struct Timestamps {
+        #[allow(dead_code)]
+        timestamp: SystemTime,
+    }
+
+    let message = crate::proto::WellKnownTypes {
+        timestamp: Some(Timestamp {
+            seconds: 253_402_300_800,
+            nanos: 0,
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let err = Timestamps::deserialize(message.into_deserializer()).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+
+    #[derive(Debug, Deserialize)]
+    struct Durations {
+        #[allow(dead_code)]
+        duration: Duration,
+    }
+
+    let message = crate::proto::WellKnownTypes {
+        duration: Some(prost_types::Duration {
+            seconds: 315_576_000_001,
+            nanos: 0,
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let err = Durations::deserialize(message.into_deserializer()).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+}
+
+#[test]
+fn deserialize_any() {
+    let message = prost_types::Any {
+        type_url: "type.googleapis.com/test.Point".to_owned(),
+        value: prost::Message::encode_to_vec(&Point {
+            latitude: 1,
+            longitude: 2,
+        }),
+    };
+    let dynamic = DynamicMessage::decode(
+        crate::test_file_descriptor()
+            .get_message_by_name("google.protobuf.Any")
+            .unwrap(),
+        prost::Message::encode_to_vec(&message).as_slice(),
+    )
+    .unwrap();
+
+    let value = serde_json::Value::deserialize(dynamic.into_deserializer()).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "@type": "type.googleapis.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        })
+    );
+}
+
+#[test]
+fn deserialize_borrowed() {
+    let message = WellKnownTypes::default().transcode_to_dynamic();
+    let value = serde_json::Value::deserialize(MessageDeserializer::new(&message)).unwrap();
+    assert_eq!(value, json!({ "null": null }));
+}
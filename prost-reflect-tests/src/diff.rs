@@ -0,0 +1,270 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+use prost::Message;
+use prost_reflect::{
+    diff::{DiffOptions, Difference, RepeatedFieldComparison},
+    DynamicMessage, ReflectMessage, Value,
+};
+use prost_types::{DescriptorProto, FieldDescriptorProto};
+
+use crate::{
+    proto::{ComplexType, ScalarArrays, Scalars},
+    test_file_descriptor,
+};
+
+fn describe(differences: Vec<Difference>) -> Vec<String> {
+    differences.iter().map(|d| d.to_string()).collect()
+}
+
+#[test]
+fn diff_equal() {
+    let message = ComplexType {
+        string_map: HashMap::from_iter([("a".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            double: 1.5,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(message.diff(&message, &DiffOptions::new()), vec![]);
+}
+
+#[test]
+fn diff_scalars_and_messages() {
+    let old = ComplexType {
+        nested: Some(Scalars {
+            int32: 1,
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        optional_enum: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let new = ComplexType {
+        nested: Some(Scalars {
+            int32: 2,
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        old.diff(&new, &DiffOptions::new()),
+        vec![
+            Difference::Changed {
+                path: "nested.int32".to_owned(),
+                old: Value::I32(1),
+                new: Value::I32(2),
+            },
+            Difference::Changed {
+                path: "optional_enum".to_owned(),
+                old: Value::EnumNumber(1),
+                new: Value::EnumNumber(0),
+            },
+        ]
+    );
+
+    let empty = ComplexType::default().transcode_to_dynamic();
+    assert_eq!(
+        describe(empty.diff(&new, &DiffOptions::new())),
+        vec!["added nested: {int32:2,string:\"foo\"}"]
+    );
+    assert_eq!(
+        describe(new.diff(&empty, &DiffOptions::new())),
+        vec!["removed nested: {int32:2,string:\"foo\"}"]
+    );
+}
+
+#[test]
+fn diff_maps_and_lists() {
+    let old = ComplexType {
+        string_map: HashMap::from_iter([
+            ("a".to_owned(), Scalars::default()),
+            ("b".to_owned(), Scalars::default()),
+        ]),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let new = ComplexType {
+        string_map: HashMap::from_iter([
+            (
+                "b".to_owned(),
+                Scalars {
+                    bool: true,
+                    ..Default::default()
+                },
+            ),
+            ("c\n".to_owned(), Scalars::default()),
+        ]),
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        my_enum: vec![3, 3, 1],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        describe(old.diff(&new, &DiffOptions::new())),
+        vec![
+            "removed string_map[\"a\"]: {}",
+            "changed string_map[\"b\"].bool: false -> true",
+            "added string_map[\"c\\n\"]: {}",
+            "changed my_enum[0]: 1 -> 3",
+            "added my_enum[2]: 1",
+        ]
+    );
+
+    let my_enum = old.descriptor().get_field_by_name("my_enum").unwrap();
+    let options =
+        DiffOptions::new().repeated_field_comparison(&my_enum, RepeatedFieldComparison::Set);
+    assert_eq!(
+        describe(old.diff(&new, &options)),
+        vec![
+            "removed string_map[\"a\"]: {}",
+            "changed string_map[\"b\"].bool: false -> true",
+            "added string_map[\"c\\n\"]: {}",
+            "added my_enum[1]: 3",
+        ]
+    );
+}
+
+#[test]
+fn diff_repeated_field_as_map() {
+    let field = |name: &str, number: i32| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        ..Default::default()
+    };
+
+    let desc = test_file_descriptor()
+        .get_message_by_name("google.protobuf.DescriptorProto")
+        .unwrap();
+    let to_dynamic = |message: DescriptorProto| {
+        DynamicMessage::decode(desc.clone(), message.encode_to_vec().as_slice()).unwrap()
+    };
+
+    let old = to_dynamic(DescriptorProto {
+        field: vec![field("a", 1), field("b", 2), field("c", 3)],
+        ..Default::default()
+    });
+    let new = to_dynamic(DescriptorProto {
+        field: vec![field("d", 4), field("c", 3), field("a2", 1)],
+        ..Default::default()
+    });
+
+    let field_desc = desc.get_field_by_name("field").unwrap();
+    let number_desc = field_desc
+        .kind()
+        .as_message()
+        .unwrap()
+        .get_field_by_name("number")
+        .unwrap();
+    let options = DiffOptions::new()
+        .repeated_field_comparison(&field_desc, RepeatedFieldComparison::Map(number_desc));
+
+    assert_eq!(
+        describe(old.diff(&new, &options)),
+        vec![
+            "changed field[2].name: \"a\" -> \"a2\"",
+            "removed field[1]: {name:\"b\",number:2}",
+            "added field[0]: {name:\"d\",number:4}",
+        ]
+    );
+
+    let options = options.ignore_path("field.name");
+    assert_eq!(
+        describe(old.diff(&new, &options)),
+        vec![
+            "removed field[1]: {name:\"b\",number:2}",
+            "added field[0]: {name:\"d\",number:4}",
+        ]
+    );
+}
+
+#[test]
+fn diff_float_tolerance() {
+    let old = ScalarArrays {
+        double: vec![1.0, f64::NAN],
+        float: vec![100.0],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let new = ScalarArrays {
+        double: vec![1.001, f64::NAN],
+        float: vec![101.0],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    assert_eq!(
+        describe(old.diff(&new, &DiffOptions::new())),
+        vec![
+            "changed double[0]: 1.0 -> 1.001",
+            "changed double[1]: NaN -> NaN",
+            "changed float[0]: 100.0 -> 101.0",
+        ]
+    );
+    assert_eq!(
+        describe(
+            old.diff(
+                &new,
+                &DiffOptions::new()
+                    .float_tolerance(0.0, 0.01)
+                    .treat_nan_as_equal(true)
+            )
+        ),
+        vec!["changed float[0]: 100.0 -> 101.0"]
+    );
+    assert_eq!(
+        old.diff(
+            &new,
+            &DiffOptions::new()
+                .float_tolerance(0.01, 0.0)
+                .treat_nan_as_equal(true)
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn diff_unknown_fields() {
+    let old = Scalars {
+        int32: 1,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let new = DynamicMessage::decode(old.descriptor(), b"\x18\x01\xa0\x06\x05".as_ref()).unwrap();
+
+    assert_eq!(
+        old.diff(&new, &DiffOptions::new()),
+        vec![Difference::Added {
+            path: "100".to_owned(),
+            value: Value::Bytes(b"\xa0\x06\x05".as_ref().into()),
+        }]
+    );
+    assert_eq!(
+        old.diff(&new, &DiffOptions::new().ignore_unknown_fields(true)),
+        vec![]
+    );
+    assert_eq!(
+        old.diff(&new, &DiffOptions::new().ignore_path("100")),
+        vec![]
+    );
+}
+
+#[test]
+fn diff_type_mismatch() {
+    let old = Scalars::default().transcode_to_dynamic();
+    let new = ComplexType::default().transcode_to_dynamic();
+
+    let differences = old.diff(&new, &DiffOptions::new());
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "");
+}
@@ -0,0 +1,239 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost_reflect::{
+    example::{self, ExampleOptions},
+    DescriptorPool, DynamicMessage, MapKey, MessageDescriptor, ReflectMessage, Value,
+};
+use prost_types::{
+    field_descriptor_proto::Type, DescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    FileDescriptorSet,
+};
+
+use crate::test_file_descriptor;
+
+fn user_descriptor() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("test.ExampleUser")
+        .unwrap()
+}
+
+fn now() -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+}
+
+fn user_example(options: &ExampleOptions) -> DynamicMessage {
+    DynamicMessage::example(user_descriptor(), &options.clone().now(now()))
+}
+
+fn get_str(message: &DynamicMessage, name: &str) -> String {
+    message
+        .get_field_by_name(name)
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_owned()
+}
+
+#[test]
+fn deterministic() {
+    let options = ExampleOptions::new().seed(5);
+    assert_eq!(user_example(&options), user_example(&options));
+    assert_ne!(
+        user_example(&options),
+        user_example(&ExampleOptions::new().seed(6))
+    );
+}
+
+#[test]
+fn every_field_set_once() {
+    let message = user_example(&ExampleOptions::new());
+    for field in user_descriptor().fields() {
+        match field.name() {
+            "backup_email" => assert!(!message.has_field(&field)),
+            _ => assert!(message.has_field(&field), "{} is not set", field.name()),
+        }
+    }
+
+    assert_eq!(
+        message
+            .get_field_by_name("tags")
+            .unwrap()
+            .as_list()
+            .unwrap()
+            .len(),
+        1
+    );
+    let counters = message.get_field_by_name("counters").unwrap();
+    let counters = counters.as_map().unwrap();
+    assert_eq!(counters.len(), 1);
+    assert!(counters.contains_key(&MapKey::String("counters_key".to_owned())));
+}
+
+#[test]
+fn field_name_formats() {
+    let message = user_example(&ExampleOptions::new());
+
+    let email = get_str(&message, "email");
+    assert!(email.ends_with("@example.com"), "{}", email);
+
+    let user_id = get_str(&message, "user_id");
+    assert_eq!(user_id.len(), 36);
+    assert_eq!(user_id.as_bytes()[14], b'4');
+    assert_eq!(
+        user_id.split('-').map(str::len).collect::<Vec<_>>(),
+        [8, 4, 4, 4, 12]
+    );
+
+    assert!(get_str(&message, "homepage_url").starts_with("https://example.com/"));
+    assert!(get_str(&message, "phone").starts_with("+1-555-"));
+    assert_eq!(get_str(&message, "display_name").split(' ').count(), 2);
+    assert_eq!(
+        *message.get_field_by_name("tags").unwrap(),
+        Value::List(vec![Value::String("example tags".to_owned())])
+    );
+
+    let age = message.get_field_by_name("age").unwrap().as_u32().unwrap();
+    assert!((18..=80).contains(&age));
+    let latitude = message
+        .get_field_by_name("latitude")
+        .unwrap()
+        .as_f64()
+        .unwrap();
+    assert!((-90.0..=90.0).contains(&latitude));
+
+    // Wrapper types use the name of the containing field.
+    let nickname = message.get_field_by_name("nickname").unwrap();
+    let nickname = get_str(nickname.as_message().unwrap(), "value");
+    assert_eq!(nickname.split(' ').count(), 2);
+}
+
+#[test]
+fn field_name_words() {
+    let field = |name: &str, number: i32, ty: Type| FieldDescriptorProto {
+        name: Some(name.to_owned()),
+        number: Some(number),
+        r#type: Some(ty as i32),
+        ..Default::default()
+    };
+    let file = FileDescriptorProto {
+        name: Some("names.proto".to_owned()),
+        package: Some("names".to_owned()),
+        message_type: vec![DescriptorProto {
+            name: Some("Names".to_owned()),
+            field: vec![
+                field("page_size", 1, Type::Uint32),
+                field("report_count", 2, Type::Uint32),
+                field("passport_number", 3, Type::Uint32),
+                field("security_token", 4, Type::String),
+                field("source_uri", 5, Type::String),
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let pool =
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] }).unwrap();
+    let desc = pool.get_message_by_name("names.Names").unwrap();
+
+    for seed in 0..16 {
+        let message = DynamicMessage::example(desc.clone(), &ExampleOptions::new().seed(seed));
+        let int = |name: &str| message.get_field_by_name(name).unwrap().as_u32().unwrap();
+
+        // Not treated as an age.
+        assert!((1..=10).contains(&int("page_size")));
+        // Not treated as a port.
+        assert!((1..=10).contains(&int("report_count")));
+        assert_ne!(int("passport_number"), 8080);
+        // Not treated as a URI.
+        assert_eq!(
+            get_str(&message, "security_token"),
+            "example security token"
+        );
+        assert!(get_str(&message, "source_uri").starts_with("https://example.com/"));
+    }
+}
+
+#[test]
+fn enum_first_non_zero_value() {
+    let message = user_example(&ExampleOptions::new());
+    assert_eq!(
+        *message.get_field_by_name("role").unwrap(),
+        Value::EnumNumber(1)
+    );
+}
+
+#[test]
+fn well_known_types() {
+    let message = user_example(&ExampleOptions::new());
+
+    let created_at: prost_types::Timestamp = message
+        .get_field_by_name("created_at")
+        .unwrap()
+        .as_message()
+        .unwrap()
+        .transcode_to()
+        .unwrap();
+    assert!(created_at.seconds <= 1_700_000_000);
+    assert!(created_at.seconds >= 1_700_000_000 - 30 * 24 * 60 * 60);
+
+    let session_timeout: prost_types::Duration = message
+        .get_field_by_name("session_timeout")
+        .unwrap()
+        .as_message()
+        .unwrap()
+        .transcode_to()
+        .unwrap();
+    assert!((1..=3600).contains(&session_timeout.seconds));
+
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(
+        json["metadata"],
+        serde_json::json!({ "metadata": "example metadata" })
+    );
+    assert!(json["createdAt"].as_str().unwrap().ends_with('Z'));
+}
+
+#[test]
+fn max_depth() {
+    let depth = |message: &DynamicMessage| {
+        let mut depth = 0;
+        let mut message = message.clone();
+        while let Some(Value::Message(manager)) = message
+            .get_field_by_name("manager")
+            .map(|value| value.into_owned())
+        {
+            if !message.has_field_by_name("manager") {
+                break;
+            }
+            depth += 1;
+            message = manager;
+        }
+        depth
+    };
+
+    assert_eq!(depth(&user_example(&ExampleOptions::new())), 4);
+    assert_eq!(depth(&user_example(&ExampleOptions::new().max_depth(1))), 1);
+
+    let message = user_example(&ExampleOptions::new().max_depth(0));
+    assert!(!message.has_field_by_name("manager"));
+    assert!(!message.has_field_by_name("created_at"));
+    assert!(message.has_field_by_name("email"));
+}
+
+#[test]
+fn service_examples() {
+    let service = test_file_descriptor()
+        .get_service_by_name("test.ExampleUserService")
+        .unwrap();
+    let options = ExampleOptions::new().now(now());
+    let examples = example::service(&service, &options);
+    assert_eq!(examples.len(), 1);
+    assert_eq!(examples[0].method().name(), "GetUser");
+    assert_eq!(
+        examples[0].request().descriptor().full_name(),
+        "test.GetExampleUserRequest"
+    );
+    assert_eq!(examples[0].response().descriptor(), user_descriptor());
+    assert_eq!(get_str(examples[0].request(), "user_id").len(), 36);
+    assert_eq!(examples, example::service(&service, &options));
+}
@@ -0,0 +1,244 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+use prost_reflect::{
+    field_mask::{self, FieldMaskError, MergeOptions},
+    ReflectMessage,
+};
+use prost_types::FieldMask;
+
+use crate::proto::{ComplexType, Scalars};
+
+fn mask(paths: &[&str]) -> FieldMask {
+    FieldMask {
+        paths: paths.iter().map(|&p| p.to_owned()).collect(),
+    }
+}
+
+#[test]
+fn validate_field_mask() {
+    let desc = ComplexType::default().descriptor();
+
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["nested.int32", "string_map", "my_enum"])),
+        Ok(())
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["nested.notfound"])),
+        Err(FieldMaskError::FieldNotFound {
+            path: "nested.notfound".to_owned(),
+            message_name: "test.Scalars".to_owned(),
+            field_name: "notfound".to_owned(),
+        })
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["string_map.int32"])),
+        Err(FieldMaskError::InvalidSubPath {
+            path: "string_map.int32".to_owned(),
+            field_name: "test.ComplexType.string_map".to_owned(),
+        })
+    );
+    assert_eq!(
+        field_mask::validate(&desc, &mask(&["optional_enum.foo"])),
+        Err(FieldMaskError::InvalidSubPath {
+            path: "optional_enum.foo".to_owned(),
+            field_name: "test.ComplexType.optional_enum".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn normalize_union_intersect() {
+    assert_eq!(
+        field_mask::normalize(&mask(&["b.c", "a", "b", "a.x", "a"])),
+        mask(&["a", "b"])
+    );
+    assert_eq!(
+        field_mask::union(&mask(&["a.b", "c"]), &mask(&["a.d", "e"])),
+        mask(&["a.b", "a.d", "c", "e"])
+    );
+    assert_eq!(
+        field_mask::intersect(&mask(&["a", "b.c"]), &mask(&["a.x", "b", "d"])),
+        mask(&["a.x", "b.c"])
+    );
+    assert_eq!(
+        field_mask::intersect(&mask(&["a.b"]), &mask(&["c"])),
+        mask(&[])
+    );
+}
+
+#[test]
+fn trim_message() {
+    let mut message = ComplexType {
+        string_map: HashMap::from_iter([("1".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 1,
+            int64: 2,
+            ..Default::default()
+        }),
+        my_enum: vec![1, 2],
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    message.trim(&mask(&["nested.int64", "my_enum"])).unwrap();
+
+    assert_eq!(
+        message.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int64: 2,
+                ..Default::default()
+            }),
+            my_enum: vec![1, 2],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn merge_with_field_mask() {
+    let destination = ComplexType {
+        int_map: HashMap::from_iter([(1, Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 1,
+            int64: 2,
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let source = ComplexType {
+        int_map: HashMap::from_iter([(2, Scalars::default())]),
+        nested: Some(Scalars {
+            int32: 5,
+            ..Default::default()
+        }),
+        my_enum: vec![2],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let paths = mask(&["int_map", "nested", "my_enum", "optional_enum"]);
+
+    let mut merged = destination.clone();
+    merged
+        .merge_with_field_mask(&source, &paths, &MergeOptions::new())
+        .unwrap();
+    assert_eq!(
+        merged.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            int_map: HashMap::from_iter([(1, Scalars::default()), (2, Scalars::default())]),
+            nested: Some(Scalars {
+                int32: 5,
+                int64: 2,
+                ..Default::default()
+            }),
+            my_enum: vec![1, 2],
+            ..Default::default()
+        }
+    );
+
+    let mut replaced = destination.clone();
+    replaced
+        .merge_with_field_mask(
+            &source,
+            &paths,
+            &MergeOptions::new()
+                .replace_message_fields(true)
+                .replace_repeated_fields(true),
+        )
+        .unwrap();
+    assert_eq!(
+        replaced.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            int_map: HashMap::from_iter([(2, Scalars::default())]),
+            nested: Some(Scalars {
+                int32: 5,
+                ..Default::default()
+            }),
+            my_enum: vec![2],
+            ..Default::default()
+        }
+    );
+
+    let mut nested = destination;
+    nested
+        .merge_with_field_mask(&source, &mask(&["nested.int64"]), &MergeOptions::new())
+        .unwrap();
+    assert_eq!(
+        nested.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            int_map: HashMap::from_iter([(1, Scalars::default())]),
+            nested: Some(Scalars {
+                int32: 1,
+                ..Default::default()
+            }),
+            my_enum: vec![1],
+            optional_enum: 3,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn merge_with_field_mask_unset_source_fields() {
+    let destination = ComplexType {
+        nested: Some(Scalars {
+            int32: 1,
+            ..Default::default()
+        }),
+        my_enum: vec![1],
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let source = ComplexType::default().transcode_to_dynamic();
+    let paths = mask(&["nested", "my_enum", "optional_enum"]);
+
+    let mut merged = destination.clone();
+    merged
+        .merge_with_field_mask(&source, &paths, &MergeOptions::new())
+        .unwrap();
+    assert_eq!(
+        merged.transcode_to::<ComplexType>().unwrap(),
+        ComplexType {
+            nested: Some(Scalars {
+                int32: 1,
+                ..Default::default()
+            }),
+            my_enum: vec![1],
+            ..Default::default()
+        }
+    );
+
+    let mut replaced = destination;
+    replaced
+        .merge_with_field_mask(
+            &source,
+            &paths,
+            &MergeOptions::new()
+                .replace_message_fields(true)
+                .replace_repeated_fields(true),
+        )
+        .unwrap();
+    assert_eq!(
+        replaced.transcode_to::<ComplexType>().unwrap(),
+        ComplexType::default()
+    );
+}
+
+#[test]
+fn merge_with_field_mask_type_mismatch() {
+    let mut destination = ComplexType::default().transcode_to_dynamic();
+    let source = Scalars::default().transcode_to_dynamic();
+
+    assert_eq!(
+        destination.merge_with_field_mask(&source, &mask(&["nested"]), &MergeOptions::new()),
+        Err(FieldMaskError::MessageTypeMismatch {
+            expected: "test.ComplexType".to_owned(),
+            actual: "test.Scalars".to_owned(),
+        })
+    );
+}
@@ -0,0 +1,355 @@
+use prost_reflect::{
+    DynamicMessage, FieldPath, FieldPathError, FieldPathSegment, MapKey, MessageDescriptor, Value,
+};
+
+use crate::test_file_descriptor;
+
+fn complex_type() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("test.ComplexType")
+        .unwrap()
+}
+
+fn desc2_message() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap()
+}
+
+fn path(desc: &MessageDescriptor, path: &str) -> FieldPath {
+    FieldPath::parse(desc, path).unwrap()
+}
+
+fn parse_err(desc: &MessageDescriptor, path: &str) -> String {
+    FieldPath::parse(desc, path).unwrap_err().to_string()
+}
+
+#[test]
+fn parse_segments() {
+    let desc = complex_type();
+
+    let parsed = path(&desc, r#"string_map["env"].int32"#);
+    assert_eq!(parsed.message_descriptor(), &desc);
+    assert_eq!(parsed.segments().len(), 3);
+    assert!(matches!(
+        &parsed.segments()[0],
+        FieldPathSegment::Field(field) if field.name() == "string_map"
+    ));
+    assert_eq!(
+        parsed.segments()[1],
+        FieldPathSegment::Key(MapKey::String("env".to_owned()))
+    );
+    assert!(matches!(
+        &parsed.segments()[2],
+        FieldPathSegment::Field(field) if field.name() == "int32"
+    ));
+
+    let parsed = path(&desc, "my_enum[3]");
+    assert_eq!(parsed.segments()[1], FieldPathSegment::Index(3));
+
+    let parsed = path(&desc, "int_map[-5]");
+    assert_eq!(parsed.segments()[1], FieldPathSegment::Key(MapKey::I32(-5)));
+
+    let parsed = path(&desc2_message(), "[my.package2.MyMessage.in_extendee]");
+    assert!(matches!(
+        &parsed.segments()[0],
+        FieldPathSegment::Extension(ext) if ext.name() == "in_extendee"
+    ));
+}
+
+#[test]
+fn parse_string_key_escapes() {
+    let desc = complex_type();
+
+    let parsed = path(&desc, r#"string_map['a\"b\n\x41\101\\']"#);
+    assert_eq!(
+        parsed.segments()[1],
+        FieldPathSegment::Key(MapKey::String("a\"b\nAA\\".to_owned()))
+    );
+    assert_eq!(parsed.to_string(), r#"string_map["a\"b\nAA\\"]"#);
+}
+
+#[test]
+fn display_roundtrip() {
+    let desc = complex_type();
+    for text in [
+        "nested.string",
+        "my_enum[0]",
+        "int_map[7].bytes",
+        r#"string_map["key"]"#,
+    ] {
+        let parsed = path(&desc, text);
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(path(&desc, &parsed.to_string()), parsed);
+    }
+
+    let parsed = path(&desc2_message(), "[.my.package2.in_file]");
+    assert_eq!(parsed.to_string(), "[my.package2.in_file]");
+}
+
+#[test]
+fn parse_errors() {
+    let desc = complex_type();
+
+    assert_eq!(
+        parse_err(&desc, ""),
+        "invalid field path: expected a field name at position 0"
+    );
+    assert_eq!(
+        parse_err(&desc, "nested."),
+        "invalid field path: expected a field name at position 7"
+    );
+    assert_eq!(
+        parse_err(&desc, "nested/string"),
+        "invalid field path: expected '.' or '[' at position 6"
+    );
+    assert_eq!(
+        parse_err(&desc, "my_enum[x]"),
+        "invalid field path: expected an index at position 8"
+    );
+    assert_eq!(
+        parse_err(&desc, "my_enum[1"),
+        "invalid field path: expected ']' at position 9"
+    );
+    assert_eq!(
+        parse_err(&desc, r#"string_map["abc]"#),
+        "invalid field path: expected the end of the string at position 16"
+    );
+    assert_eq!(
+        parse_err(&desc, "nested.missing"),
+        "message 'test.Scalars' has no field named 'missing'"
+    );
+    assert_eq!(
+        parse_err(&desc, "[test.missing]"),
+        "message 'test.ComplexType' has no extension named 'test.missing'"
+    );
+    assert_eq!(
+        parse_err(&desc, "nested.int32.foo"),
+        "'nested.int32' is not a message"
+    );
+    assert_eq!(
+        parse_err(&desc, "int_map.bytes"),
+        "'int_map' is not a message"
+    );
+    assert_eq!(
+        parse_err(&desc, "nested[0]"),
+        "'nested' is not a repeated or map field"
+    );
+    assert_eq!(
+        parse_err(&desc, "string_map[env]"),
+        "invalid key 'env' for map field 'string_map'"
+    );
+    assert_eq!(
+        parse_err(&desc, r#"int_map["1"]"#),
+        r#"invalid key '"1"' for map field 'int_map'"#
+    );
+}
+
+#[test]
+fn get_path_defaults() {
+    let desc = complex_type();
+    let message = DynamicMessage::new(desc.clone());
+
+    assert_eq!(
+        message
+            .get_path(&path(&desc, "nested.int32"))
+            .unwrap()
+            .as_ref(),
+        &Value::I32(0)
+    );
+    assert_eq!(message.get_path(&path(&desc, "my_enum[0]")), None);
+    assert_eq!(
+        message.get_path(&path(&desc, r#"string_map["a"].int32"#)),
+        None
+    );
+    assert!(!message.has_field_by_name("nested"));
+}
+
+#[test]
+fn set_and_get_path() {
+    let desc = complex_type();
+    let mut message = DynamicMessage::new(desc.clone());
+
+    message
+        .set_path(
+            &path(&desc, "nested.string"),
+            Value::String("hello".to_owned()),
+        )
+        .unwrap();
+    message
+        .set_path(&path(&desc, r#"string_map["env"].int32"#), Value::I32(5))
+        .unwrap();
+    message
+        .set_path(
+            &path(&desc, "int_map[3]"),
+            Value::Message(DynamicMessage::new(
+                test_file_descriptor()
+                    .get_message_by_name("test.Scalars")
+                    .unwrap(),
+            )),
+        )
+        .unwrap();
+    message
+        .set_path(
+            &path(&desc, "my_enum"),
+            Value::List(vec![Value::EnumNumber(1); 2]),
+        )
+        .unwrap();
+    message
+        .set_path(&path(&desc, "my_enum[1]"), Value::EnumNumber(3))
+        .unwrap();
+
+    assert_eq!(
+        message
+            .get_path(&path(&desc, "nested.string"))
+            .unwrap()
+            .as_ref(),
+        &Value::String("hello".to_owned())
+    );
+    assert_eq!(
+        message
+            .get_path(&path(&desc, r#"string_map["env"].int32"#))
+            .unwrap()
+            .as_ref(),
+        &Value::I32(5)
+    );
+    assert!(message.get_path(&path(&desc, "int_map[3]")).is_some());
+    assert_eq!(
+        message.get_path(&path(&desc, "my_enum")).unwrap().as_ref(),
+        &Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(3)])
+    );
+}
+
+#[test]
+fn set_path_errors() {
+    let desc = complex_type();
+    let mut message = DynamicMessage::new(desc.clone());
+
+    assert_eq!(
+        message.set_path(&path(&desc, "my_enum[2]"), Value::EnumNumber(1)),
+        Err(FieldPathError::IndexOutOfBounds {
+            path: "my_enum".to_owned(),
+            index: 2,
+            len: 0,
+        })
+    );
+    assert_eq!(
+        message
+            .set_path(&path(&desc, "nested.int32"), Value::Bool(true))
+            .unwrap_err()
+            .to_string(),
+        "invalid value 'true' for 'nested.int32'"
+    );
+    assert_eq!(
+        message
+            .set_path(&path(&desc, "my_enum[0]"), Value::String("FOO".to_owned()))
+            .unwrap_err()
+            .to_string(),
+        r#"invalid value '"FOO"' for 'my_enum[0]'"#
+    );
+    // Values are checked before any intermediate messages are created.
+    assert!(!message.has_field_by_name("nested"));
+}
+
+#[test]
+fn set_path_extension() {
+    let desc = desc2_message();
+    let mut message = DynamicMessage::new(desc.clone());
+
+    let ext_path = path(&desc, "[my.package2.MyMessage.in_extendee]");
+    message.set_path(&ext_path, Value::I32(7)).unwrap();
+
+    let ext = desc
+        .get_extension_by_full_name("my.package2.MyMessage.in_extendee")
+        .unwrap();
+    assert_eq!(message.get_extension(&ext).as_ref(), &Value::I32(7));
+    assert_eq!(
+        message.get_path(&ext_path).unwrap().as_ref(),
+        &Value::I32(7)
+    );
+
+    message.clear_path(&ext_path);
+    assert!(!message.has_extension(&ext));
+}
+
+#[test]
+fn get_path_mut() {
+    let desc = complex_type();
+    let mut message = DynamicMessage::new(desc.clone());
+
+    *message.get_path_mut(&path(&desc, "nested.uint64")).unwrap() = Value::U64(9);
+    assert_eq!(
+        message
+            .get_path(&path(&desc, "nested.uint64"))
+            .unwrap()
+            .as_ref(),
+        &Value::U64(9)
+    );
+
+    assert!(message
+        .get_path_mut(&path(&desc, r#"string_map["missing"]"#))
+        .is_none());
+    assert!(message.get_path_mut(&path(&desc, "my_enum[0]")).is_none());
+    assert!(!message.has_field_by_name("string_map"));
+}
+
+#[test]
+fn clear_path() {
+    let desc = complex_type();
+    let mut message = DynamicMessage::new(desc.clone());
+    message
+        .set_path(&path(&desc, "nested.string"), Value::String("a".to_owned()))
+        .unwrap();
+    message
+        .set_path(&path(&desc, r#"string_map["a"].int32"#), Value::I32(1))
+        .unwrap();
+    message
+        .set_path(&path(&desc, r#"string_map["b"].int32"#), Value::I32(2))
+        .unwrap();
+    message
+        .set_path(
+            &path(&desc, "my_enum"),
+            Value::List(vec![Value::EnumNumber(1), Value::EnumNumber(3)]),
+        )
+        .unwrap();
+
+    message.clear_path(&path(&desc, "nested.string"));
+    assert!(message.has_field_by_name("nested"));
+    assert_eq!(
+        message
+            .get_path(&path(&desc, "nested.string"))
+            .unwrap()
+            .as_ref(),
+        &Value::String(String::new())
+    );
+
+    message.clear_path(&path(&desc, r#"string_map["a"]"#));
+    assert_eq!(message.get_path(&path(&desc, r#"string_map["a"]"#)), None);
+    assert!(message
+        .get_path(&path(&desc, r#"string_map["b"]"#))
+        .is_some());
+
+    message.clear_path(&path(&desc, "my_enum[0]"));
+    message.clear_path(&path(&desc, "my_enum[5]"));
+    assert_eq!(
+        message.get_path(&path(&desc, "my_enum")).unwrap().as_ref(),
+        &Value::List(vec![Value::EnumNumber(3)])
+    );
+
+    // Clearing beneath unset values does not create them.
+    message.clear_path(&path(&desc, r#"int_map[1].int32"#));
+    message.clear_path(&path(&desc, r#"string_map["c"].int32"#));
+    assert!(!message.has_field_by_name("int_map"));
+    assert_eq!(message.get_path(&path(&desc, r#"string_map["c"]"#)), None);
+
+    message.clear_path(&path(&desc, "nested"));
+    assert!(!message.has_field_by_name("nested"));
+}
+
+#[test]
+#[should_panic(expected = "field path is for a different message type")]
+fn path_for_other_message() {
+    let desc = complex_type();
+    let message = DynamicMessage::new(desc2_message());
+    message.get_path(&path(&desc, "nested"));
+}
@@ -1,17 +1,20 @@
 use std::{
     collections::{BTreeMap, HashMap},
+    error::Error,
     fmt::Debug,
     iter::FromIterator,
 };
 
 use proptest::{prelude::*, test_runner::TestCaseError};
 use prost::Message;
-use prost_reflect::{DeserializeOptions, DynamicMessage, ReflectMessage, SerializeOptions};
+use prost_reflect::{
+    DeserializeError, DeserializeOptions, DeserializePathSegment, DynamicMessage, ReflectMessage,
+    SerializeOptions,
+};
 use prost_types::FileDescriptorSet;
 use serde_json::json;
 
 use crate::{
-    arbitrary,
     proto::{
         contains_group, message_with_oneof, ComplexType, ContainsGroup, MessageWithAliasedEnum,
         MessageWithOneof, Point, ScalarArrays, Scalars, WellKnownTypes,
@@ -644,6 +647,22 @@ fn deserialize_aliased_enum() {
     assert_eq!(value2, MessageWithAliasedEnum { aliased: 1 },);
 }
 
+#[test]
+fn deserialize_enum_float() {
+    let err = try_from_json_string_with_options(
+        r#"{ "optionalEnum": 1.0 }"#,
+        "test.ComplexType",
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("invalid type: floating point `1.0`, expected a string or integer"),
+        "{}",
+        err
+    );
+}
+
 #[test]
 fn deserialize_array() {
     let value: ScalarArrays = from_json(
@@ -1217,6 +1236,194 @@ fn deserialize_timestamp_seconds_out_of_range() {
         from_json(json!("0000-01-01T00:00:00Z"), "google.protobuf.Timestamp");
 }
 
+#[test]
+fn deserialize_error_path() {
+    let options = DeserializeOptions::new();
+    let err = try_from_json_string_with_path(
+        r#"{ "intMap": { "5": { "int32": "x" } } }"#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.intMap[\"5\"].int32: invalid digit found in string at line 1 column 33"
+    );
+    assert_eq!(
+        err.source().unwrap().to_string(),
+        "invalid digit found in string at line 1 column 33"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "int32": [1, 2, 3.5] }"#,
+        "test.ScalarArrays",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.int32[2]: expected integer value at line 1 column 21"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "string_map": { "a\"b": { "bool": true, "bytes": "!" } } }"#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "at $.string_map[\"a\\\"b\"].bytes: invalid base64: Invalid symbol 33, offset 0. at line 1 column 54");
+
+    let err = try_from_json_string_with_path(
+        r#"{ "nested": { "unknown": 1 } }"#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.nested: unrecognized field name 'unknown' at line 1 column 23"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "oneofField1": "a", "oneofField2": 1 }"#,
+        "test.MessageWithOneof",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.oneofField2: multiple fields provided for oneof 'test_oneof' at line 1 column 40"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "myEnum": ["FOO", "BAZ"] }"#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.myEnum[1]: unrecognized enum value 'BAZ' at line 1 column 25"
+    );
+}
+
+#[test]
+fn deserialize_error_path_well_known_types() {
+    let options = DeserializeOptions::new();
+    let err = try_from_json_string_with_path(
+        r#"{ "@type": "type.googleapis.com/test.Point", "latitude": 1, "longitude": "x" }"#,
+        "google.protobuf.Any",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.longitude: invalid digit found in string at line 1 column 76"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "latitude": true, "@type": "type.googleapis.com/test.Point" }"#,
+        "google.protobuf.Any",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "at $.latitude: Invalid type boolean `true`. Expected a 32-bit signed integer at line 1 column 63");
+
+    let err = try_from_json_string_with_path(
+        r#"{ "@type": "type.googleapis.com/google.protobuf.Duration", "value": "1" }"#,
+        "google.protobuf.Any",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.value: failed to parse duration at line 1 column 71"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "value": "1", "@type": "type.googleapis.com/google.protobuf.Duration" }"#,
+        "google.protobuf.Any",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.value: failed to parse duration at line 1 column 73"
+    );
+
+    let err = try_from_json_string_with_path(
+        r#"{ "struct": { "a": [1, { "b": [true, 2 }] } }"#,
+        "test.WellKnownTypes",
+        &options,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "at $.struct[\"a\"][1][\"b\"][2]: expected `,` or `]` at line 1 column 40"
+    );
+}
+
+#[test]
+fn deserialize_with_path() {
+    let mut de = serde_json::Deserializer::from_str(r#"{ "stringMap": { "k": { "int64": {} } } }"#);
+    let err = DynamicMessage::deserialize_with_path(
+        ComplexType::default().descriptor(),
+        &mut de,
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.path(),
+        &[
+            DeserializePathSegment::Field("stringMap".to_owned()),
+            DeserializePathSegment::Key("k".to_owned()),
+            DeserializePathSegment::Field("int64".to_owned()),
+        ]
+    );
+    assert_eq!(err.json_path(), "$.stringMap[\"k\"].int64");
+    assert_eq!(err.to_string(), "at $.stringMap[\"k\"].int64: invalid type: map, expected a 64-bit signed integer or decimal string at line 1 column 35");
+
+    let inner = err.into_inner();
+    assert!(inner.is_data());
+    assert_eq!((inner.line(), inner.column()), (1, 35));
+
+    let mut de = serde_json::Deserializer::from_str(r#"{ "unknown": 1 }"#);
+    let err = DynamicMessage::deserialize_with_path(
+        ComplexType::default().descriptor(),
+        &mut de,
+        &DeserializeOptions::new(),
+    )
+    .unwrap_err();
+    assert!(err.path().is_empty());
+    assert_eq!(err.json_path(), "$");
+    assert_eq!(err.to_string(), err.inner().to_string());
+}
+
+#[test]
+fn deserialize_error_unchanged() {
+    let options = DeserializeOptions::new();
+    let err = try_from_json_string_with_options(
+        r#"{ "nested": { "int32": "x" } }"#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.is_data());
+    assert_eq!(
+        err.to_string(),
+        "invalid digit found in string at line 1 column 26"
+    );
+
+    let err = try_from_json_string_with_options(
+        r#"{ "nested": { "int32": "#,
+        "test.ComplexType",
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.is_eof());
+}
+
 #[test]
 fn roundtrip_group() {
     roundtrip_json(&ContainsGroup {
@@ -1267,7 +1474,7 @@ proptest! {
     }
 
     #[test]
-    fn deserialize_error_scalars(json in arbitrary::json()) {
+    fn deserialize_error_scalars(json in arb_json()) {
         let _ = try_from_json_string_with_options(&json, ".test.Scalars", &DeserializeOptions::default());
         let _ = try_from_json_string_with_options(&json, ".test.Scalars", &DeserializeOptions::default().deny_unknown_fields(false));
     }
@@ -1292,7 +1499,7 @@ proptest! {
     }
 
     #[test]
-    fn deserialize_error_scalar_arrays(json in arbitrary::json()) {
+    fn deserialize_error_scalar_arrays(json in arb_json()) {
         let _ = try_from_json_string_with_options(&json, ".test.ScalarArrays", &DeserializeOptions::default());
         let _ = try_from_json_string_with_options(&json, ".test.ScalarArrays", &DeserializeOptions::default().deny_unknown_fields(false));
     }
@@ -1317,7 +1524,7 @@ proptest! {
     }
 
     #[test]
-    fn deserialize_error_complex_type(json in arbitrary::json()) {
+    fn deserialize_error_complex_type(json in arb_json()) {
         let _ = try_from_json_string_with_options(&json, ".test.ComplexType", &DeserializeOptions::default());
         let _ = try_from_json_string_with_options(&json, ".test.ComplexType", &DeserializeOptions::default().deny_unknown_fields(false));
     }
@@ -1342,7 +1549,7 @@ proptest! {
     }
 
     #[test]
-    fn deserialize_error_well_known_types(json in arbitrary::json()) {
+    fn deserialize_error_well_known_types(json in arb_json()) {
         let _ = try_from_json_string_with_options(&json, ".test.WellKnownTypes", &DeserializeOptions::default());
         let _ = try_from_json_string_with_options(&json, ".test.WellKnownTypes", &DeserializeOptions::default().deny_unknown_fields(false));
     }
@@ -1462,6 +1669,24 @@ fn try_from_json_string_with_options(
     Ok(message)
 }
 
+fn try_from_json_string_with_path(
+    json: &str,
+    message_name: &str,
+    options: &DeserializeOptions,
+) -> Result<DynamicMessage, DeserializeError<serde_json::Error>> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize_with_path(
+        test_file_descriptor()
+            .get_message_by_name(message_name)
+            .unwrap(),
+        &mut de,
+        options,
+    )?;
+    de.end().unwrap();
+
+    Ok(message)
+}
+
 fn from_json_string_with_options<T>(
     json: &str,
     message_name: &str,
@@ -1497,3 +1722,57 @@ where
     prop_assert_eq!(message, &roundtripped_message);
     Ok(())
 }
+
+fn arb_json() -> impl Strategy<Value = String> {
+    fn arb_json_key() -> impl Strategy<Value = String> {
+        // Use real field names to make the deserialization error test more interesting
+        prop_oneof![
+            2 => Just("float".to_owned()),
+            2 => Just("double".to_owned()),
+            2 => Just("int32".to_owned()),
+            2 => Just("int64".to_owned()),
+            2 => Just("uint32".to_owned()),
+            2 => Just("uint64".to_owned()),
+            2 => Just("bool".to_owned()),
+            2 => Just("string".to_owned()),
+            2 => Just("bytes".to_owned()),
+            1 => Just("string_map".to_owned()),
+            1 => Just("int_map".to_owned()),
+            1 => Just("nested".to_owned()),
+            1 => Just("my_enum".to_owned()),
+            1 => Just("optional_enum".to_owned()),
+            1 => Just("timestamp".to_owned()),
+            1 => Just("duration".to_owned()),
+            1 => Just("struct".to_owned()),
+            1 => Just("mask".to_owned()),
+            1 => Just("list".to_owned()),
+            1 => Just("null".to_owned()),
+            1 => Just("empty".to_owned()),
+            1 => Just("sint32".to_owned()),
+            1 => Just("sint64".to_owned()),
+            1 => Just("fixed32".to_owned()),
+            1 => Just("fixed64".to_owned()),
+            1 => Just("sfixed32".to_owned()),
+            1 => Just("sfixed64".to_owned()),
+        ]
+    }
+
+    fn arb_json_value() -> impl Strategy<Value = serde_json::Value> {
+        let leaf = prop_oneof![
+            Just(serde_json::Value::Null),
+            any::<bool>().prop_map(serde_json::Value::from),
+            any::<f64>().prop_map(serde_json::Value::from),
+            ".*".prop_map(serde_json::Value::from),
+        ];
+        leaf.prop_recursive(4, 32, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(serde_json::Value::Array),
+                prop::collection::hash_map(arb_json_key(), inner, 0..4)
+                    .prop_map(|map| serde_json::Map::from_iter(map).into()),
+            ]
+        })
+    }
+
+    prop::collection::hash_map(arb_json_key(), arb_json_value(), 0..10)
+        .prop_map(|map| serde_json::Value::Object(map.into_iter().collect()).to_string())
+}
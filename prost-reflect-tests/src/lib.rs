@@ -1,16 +1,62 @@
 use prost_reflect::{DescriptorPool, ReflectMessage};
+#[cfg(test)]
+use prost_reflect::{DynamicMessage, SerializeOptions};
 use proto::Scalars;
 
 #[cfg(test)]
-mod arbitrary;
+mod any;
+#[cfg(test)]
+mod canonical;
+#[cfg(test)]
+mod cel;
+#[cfg(test)]
+mod convert;
 #[cfg(test)]
 mod decode;
 #[cfg(test)]
+mod decode_options;
+#[cfg(test)]
+mod delimited;
+#[cfg(test)]
 mod desc;
 #[cfg(test)]
+mod deserializer;
+#[cfg(test)]
+mod diff;
+#[cfg(test)]
+mod example;
+#[cfg(test)]
+mod field_mask;
+#[cfg(test)]
+mod field_path;
+#[cfg(test)]
 mod json;
 #[cfg(test)]
+mod limits;
+#[cfg(test)]
+mod message_ref;
+#[cfg(test)]
+mod raw;
+#[cfg(test)]
+mod redact;
+#[cfg(test)]
+mod reparse;
+#[cfg(test)]
+mod serializer;
+#[cfg(test)]
+mod strategy;
+#[cfg(test)]
+mod structs;
+#[cfg(test)]
 mod text_format;
+#[cfg(test)]
+mod timestamp;
+#[cfg(test)]
+mod unknown;
+#[cfg(test)]
+mod validate;
+#[cfg(test)]
+mod visit;
 
 pub mod proto {
     #![allow(clippy::all)]
@@ -31,3 +77,20 @@ pub fn test_file_descriptor() -> DescriptorPool {
 
     DescriptorPool::global()
 }
+
+/// Deserializes a message of type `name` from the test descriptors from its JSON representation.
+#[cfg(test)]
+pub fn message(name: &str, json: serde_json::Value) -> DynamicMessage {
+    let desc = test_file_descriptor().get_message_by_name(name).unwrap();
+    DynamicMessage::deserialize(desc, json).unwrap()
+}
+
+/// Serializes `message` to a JSON string using `options`.
+#[cfg(test)]
+pub fn to_json(message: &DynamicMessage, options: &SerializeOptions) -> String {
+    let mut serializer = serde_json::Serializer::new(Vec::new());
+    message
+        .serialize_with_options(&mut serializer, options)
+        .unwrap();
+    String::from_utf8(serializer.into_inner()).unwrap()
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+
+use prost_reflect::{text_format::FormatOptions, DynamicMessage, ReflectMessage, SerializeOptions};
+
+use crate::{
+    proto::{ComplexType, Scalars, WellKnownTypes},
+    to_json,
+};
+
+fn complex_message() -> DynamicMessage {
+    ComplexType {
+        string_map: HashMap::from([(
+            "a".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            string: "hello, world".to_owned(),
+            bytes: b"\x00\x01\x02\x03\x04\x05".to_vec(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3, 1, 3, 0],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn text_format_max_string_len() {
+    let options = FormatOptions::new().max_string_len(5);
+    assert_eq!(
+        complex_message().to_text_format_with_options(&options),
+        r#"string_map:[{key:"a",value{int32:5}}],nested{string:"hello"...(12 bytes),bytes:"\000\001\002\003\004"...(6 bytes)},my_enum:[FOO,BAR,FOO,BAR,DEFAULT]"#
+    );
+}
+
+#[test]
+fn text_format_max_collection_len() {
+    let options = FormatOptions::new().max_collection_len(2);
+    assert_eq!(
+        complex_message().to_text_format_with_options(&options),
+        r#"string_map:[{key:"a",value{int32:5}}],nested{string:"hello, world",bytes:"\000\001\002\003\004\005"},my_enum:[FOO,BAR,...(3 more)]"#
+    );
+
+    let options = FormatOptions::new().max_collection_len(0).pretty(true);
+    assert_eq!(
+        complex_message().to_text_format_with_options(&options),
+        "string_map: [...(1 more)]\nnested {\n  string: \"hello, world\"\n  bytes: \"\\000\\001\\002\\003\\004\\005\"\n}\nmy_enum: [...(5 more)]"
+    );
+}
+
+#[test]
+fn text_format_max_depth() {
+    let options = FormatOptions::new().max_depth(0);
+    assert_eq!(
+        complex_message().to_text_format_with_options(&options),
+        r#"string_map:[{key:"a",value{...}}],nested{...},my_enum:[FOO,BAR,FOO,BAR,DEFAULT]"#
+    );
+
+    let options = FormatOptions::new().max_depth(1);
+    assert_eq!(
+        complex_message().to_text_format_with_options(&options),
+        complex_message().to_text_format()
+    );
+}
+
+#[test]
+fn text_format_max_output_len() {
+    let message = complex_message();
+    let full = message.to_text_format();
+
+    let options = FormatOptions::new().max_output_len(50);
+    assert_eq!(
+        message.to_text_format_with_options(&options),
+        format!("{}...(truncated)", &full[..50])
+    );
+
+    let options = FormatOptions::new().max_output_len(full.len());
+    assert_eq!(message.to_text_format_with_options(&options), full);
+}
+
+#[test]
+fn text_format_max_output_len_char_boundary() {
+    let message = Scalars {
+        string: "ééé".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    // Non-ASCII characters are escaped, so the output is cut off between escapes.
+    let options = FormatOptions::new().max_output_len(12);
+    assert_eq!(
+        message.to_text_format_with_options(&options),
+        r#"string:"\303...(truncated)"#
+    );
+}
+
+#[test]
+fn json_max_string_len() {
+    let options = SerializeOptions::new().max_string_len(5);
+    assert_eq!(
+        to_json(&complex_message(), &options),
+        r#"{"stringMap":{"a":{"int32":5}},"nested":{"string":"hello...(12 bytes)","bytes":"AAECAwQ=...(6 bytes)"},"myEnum":["FOO","BAR","FOO","BAR","DEFAULT"]}"#
+    );
+
+    let message = Scalars {
+        string: "aéb".to_owned(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let options = SerializeOptions::new().max_string_len(2);
+    assert_eq!(to_json(&message, &options), r#"{"string":"a...(4 bytes)"}"#);
+}
+
+#[test]
+fn json_max_collection_len() {
+    let options = SerializeOptions::new().max_collection_len(2);
+    assert_eq!(
+        to_json(&complex_message(), &options),
+        r#"{"stringMap":{"a":{"int32":5}},"nested":{"string":"hello, world","bytes":"AAECAwQF"},"myEnum":["FOO","BAR","...(3 more)"]}"#
+    );
+
+    let options = SerializeOptions::new().max_collection_len(0);
+    assert_eq!(
+        to_json(&complex_message(), &options),
+        r#"{"stringMap":{"...":"(1 more)"},"nested":{"string":"hello, world","bytes":"AAECAwQF"},"myEnum":["...(5 more)"]}"#
+    );
+}
+
+#[test]
+fn json_max_depth() {
+    let options = SerializeOptions::new().max_depth(0);
+    assert_eq!(
+        to_json(&complex_message(), &options),
+        r#"{"stringMap":{"a":"{...}"},"nested":"{...}","myEnum":["FOO","BAR","FOO","BAR","DEFAULT"]}"#
+    );
+}
+
+#[test]
+fn json_max_output_len() {
+    let message = complex_message();
+
+    let options = SerializeOptions::new().max_output_len(30);
+    let json = to_json(&message, &options);
+    assert_eq!(
+        json,
+        r#"{"stringMap":{"a":{"int32":5}},"nested":{"string":"h...(12 bytes)","...":"(truncated)"}}"#
+    );
+    serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+    let options = SerializeOptions::new().max_output_len(1000);
+    assert_eq!(
+        to_json(&message, &options),
+        to_json(&message, &SerializeOptions::new())
+    );
+}
+
+#[test]
+fn json_max_output_len_long_value() {
+    let string = "a".repeat(1_000_000);
+    let options = SerializeOptions::new().max_output_len(1000);
+
+    let message = Scalars {
+        string: string.clone(),
+        bytes: vec![0; 1_000_000],
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let json = to_json(&message, &options);
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"string":"{}...(1000000 bytes)","...":"(truncated)"}}"#,
+            "a".repeat(994)
+        )
+    );
+
+    let message = WellKnownTypes {
+        string: Some(string),
+        bytes: Some(vec![0; 1_000_000]),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    let json = to_json(&message, &options);
+    assert!(json.len() < 1100, "{}", json.len());
+    assert!(json.contains("...(1000000 bytes)"), "{}", json);
+    serde_json::from_str::<serde_json::Value>(&json).unwrap();
+}
+
+#[test]
+fn json_limits_well_known_types() {
+    let message = WellKnownTypes {
+        r#struct: Some(prost_types::Struct {
+            fields: BTreeMap::from([
+                (
+                    "list".to_owned(),
+                    prost_types::Value {
+                        kind: Some(prost_types::value::Kind::ListValue(
+                            prost_types::ListValue {
+                                values: vec![
+                                    prost_types::Value {
+                                        kind: Some(prost_types::value::Kind::NumberValue(1.0)),
+                                    };
+                                    4
+                                ],
+                            },
+                        )),
+                    },
+                ),
+                (
+                    "string".to_owned(),
+                    prost_types::Value {
+                        kind: Some(prost_types::value::Kind::StringValue("abcdefgh".to_owned())),
+                    },
+                ),
+            ]),
+        }),
+        string: Some("abcdefgh".to_owned()),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = SerializeOptions::new()
+        .max_string_len(3)
+        .max_collection_len(2);
+    assert_eq!(
+        to_json(&message, &options),
+        r#"{"struct":{"list":[1.0,1.0,"...(2 more)"],"string":"abc...(8 bytes)"},"string":"abc...(8 bytes)"}"#
+    );
+}
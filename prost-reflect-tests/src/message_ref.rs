@@ -0,0 +1,201 @@
+use std::{collections::HashMap, iter::FromIterator};
+
+use prost::Message;
+use prost_reflect::{DynamicMessage, DynamicMessageRef, ReflectMessage, Value};
+
+use crate::proto::{
+    contains_group, message_with_oneof, ComplexType, ContainsGroup, MessageWithOneof, ScalarArrays,
+    Scalars,
+};
+
+fn assert_fields_match<T>(message: &T)
+where
+    T: ReflectMessage,
+{
+    let desc = message.descriptor();
+    let bytes = message.encode_to_vec();
+    let dynamic_message = DynamicMessage::decode(desc.clone(), bytes.as_slice()).unwrap();
+    let message_ref = DynamicMessageRef::new(desc.clone(), &bytes).unwrap();
+
+    for field in desc.fields() {
+        assert_eq!(
+            message_ref.get_field(&field).unwrap(),
+            *dynamic_message.get_field(&field),
+            "field '{}' does not match",
+            field.name()
+        );
+    }
+    assert_eq!(message_ref.to_dynamic_message().unwrap(), dynamic_message);
+}
+
+#[test]
+fn message_ref_scalars() {
+    assert_fields_match(&Scalars {
+        double: 1.1,
+        float: 2.2,
+        int32: -3,
+        int64: 4,
+        uint32: 5,
+        sint64: -6,
+        fixed32: 7,
+        sfixed64: -8,
+        r#bool: true,
+        string: "9".to_owned(),
+        bytes: b"10".to_vec(),
+        ..Default::default()
+    });
+    assert_fields_match(&ScalarArrays {
+        double: vec![1.1, 2.2],
+        int32: vec![5, -6],
+        sint64: vec![15, -16],
+        string: vec!["25".to_owned(), "26".to_owned()],
+        ..Default::default()
+    });
+    assert_fields_match(&ComplexType {
+        string_map: HashMap::from_iter([("a".to_owned(), Scalars::default())]),
+        int_map: HashMap::from_iter([(
+            1,
+            Scalars {
+                int32: 2,
+                ..Default::default()
+            },
+        )]),
+        my_enum: vec![0, 1, -4],
+        optional_enum: 3,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn message_ref_nested_message() {
+    let mut bytes = ComplexType {
+        nested: Some(Scalars {
+            int32: 1,
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    ComplexType {
+        nested: Some(Scalars {
+            int64: 2,
+            string: "bar".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .encode(&mut bytes)
+    .unwrap();
+
+    let desc = ComplexType::default().descriptor();
+    let message = DynamicMessageRef::new(desc.clone(), &bytes).unwrap();
+
+    let nested_field = desc.get_field_by_name("nested").unwrap();
+    assert!(message.has_field(&nested_field));
+    let nested = message.get_message(&nested_field).unwrap().unwrap();
+    assert_eq!(
+        nested.get_field_by_name("int32").unwrap().unwrap(),
+        Value::I32(1)
+    );
+    assert_eq!(
+        nested.get_field_by_name("int64").unwrap().unwrap(),
+        Value::I64(2)
+    );
+    assert_eq!(
+        nested.get_field_by_name("string").unwrap().unwrap(),
+        Value::String("bar".to_owned())
+    );
+    assert!(nested.get_field_by_name("notfound").is_none());
+
+    let empty = DynamicMessageRef::new(desc, &[]).unwrap();
+    assert!(!empty.has_field(&nested_field));
+    assert!(empty.get_message(&nested_field).unwrap().is_none());
+}
+
+#[test]
+fn message_ref_oneof() {
+    let mut bytes = MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField1(
+            "hello".to_owned(),
+        )),
+    }
+    .encode_to_vec();
+    MessageWithOneof {
+        test_oneof: Some(message_with_oneof::TestOneof::OneofField2(5)),
+    }
+    .encode(&mut bytes)
+    .unwrap();
+
+    let desc = MessageWithOneof::default().descriptor();
+    let message = DynamicMessageRef::new(desc.clone(), &bytes).unwrap();
+
+    assert!(!message.has_field(&desc.get_field_by_name("oneof_field_1").unwrap()));
+    assert!(message.has_field(&desc.get_field_by_name("oneof_field_2").unwrap()));
+    assert_eq!(
+        message.get_field_by_name("oneof_field_1").unwrap().unwrap(),
+        Value::String(String::new())
+    );
+    assert_eq!(
+        message.get_field_by_number(2).unwrap().unwrap(),
+        Value::I32(5)
+    );
+}
+
+#[test]
+fn message_ref_groups() {
+    let message = ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "bar".to_string(),
+            b: None,
+        }),
+        optionalgroup: None,
+        repeatedgroup: vec![
+            contains_group::RepeatedGroup {
+                e: "hello".to_string(),
+                f: Some(10),
+            },
+            contains_group::RepeatedGroup {
+                e: "world".to_string(),
+                f: None,
+            },
+        ],
+    };
+    assert_fields_match(&message);
+
+    let desc = message.descriptor();
+    let bytes = message.encode_to_vec();
+    let message_ref = DynamicMessageRef::new(desc.clone(), &bytes).unwrap();
+
+    let required_group = message_ref
+        .get_message(&desc.get_field_by_name("requiredgroup").unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        required_group.get_field_by_name("a").unwrap().unwrap(),
+        Value::String("bar".to_owned())
+    );
+
+    let repeated_group = message_ref
+        .get_message_list(&desc.get_field_by_name("repeatedgroup").unwrap())
+        .unwrap();
+    assert_eq!(repeated_group.len(), 2);
+    assert_eq!(
+        repeated_group[1].get_field_by_name("e").unwrap().unwrap(),
+        Value::String("world".to_owned())
+    );
+}
+
+#[test]
+fn message_ref_invalid() {
+    let desc = Scalars::default().descriptor();
+
+    // Truncated length-delimited field.
+    assert!(DynamicMessageRef::new(desc.clone(), b"\x72\x05ab").is_err());
+
+    // The structure is valid, but the field has the wrong wire type.
+    let message = DynamicMessageRef::new(desc.clone(), b"\x1d\x01\x00\x00\x00").unwrap();
+    assert!(message
+        .get_field(&desc.get_field_by_name("int32").unwrap())
+        .is_err());
+}
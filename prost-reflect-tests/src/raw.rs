@@ -0,0 +1,159 @@
+use prost::{bytes::Bytes, Message};
+use prost_reflect::{
+    raw::{decode_raw, RawMessage, RawValue},
+    DynamicMessage, ReflectMessage, UnknownFieldSet,
+};
+
+use crate::proto::{contains_group, ComplexType, ContainsGroup, ScalarArrays, Scalars};
+
+#[test]
+fn decode_raw_scalars() {
+    let bytes = Scalars {
+        double: 1.0,
+        float: 1.0,
+        int32: -1,
+        uint32: 5,
+        r#bool: true,
+        string: "hello world".to_owned(),
+        bytes: b"\x00\x80".to_vec(),
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    let message = decode_raw(bytes.as_slice()).unwrap();
+    let values: Vec<(u32, &RawValue)> = message
+        .fields()
+        .iter()
+        .map(|field| (field.number(), field.value()))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            (1, &RawValue::Fixed64(1.0f64.to_bits())),
+            (2, &RawValue::Fixed32(1.0f32.to_bits())),
+            (3, &RawValue::Varint(u64::MAX)),
+            (5, &RawValue::Varint(5)),
+            (13, &RawValue::Varint(1)),
+            (14, &RawValue::String("hello world".to_owned())),
+            (15, &RawValue::Bytes(Bytes::from_static(b"\x00\x80"))),
+        ]
+    );
+
+    assert_eq!(
+        format!("{:#}", message),
+        "1: 0x3ff0000000000000\n\
+        2: 0x3f800000\n\
+        3: 18446744073709551615\n\
+        5: 5\n\
+        13: 1\n\
+        14: \"hello world\"\n\
+        15: \"\\000\\200\""
+    );
+}
+
+#[test]
+fn decode_raw_packed() {
+    let bytes = ScalarArrays {
+        int32: vec![1, 2, 300],
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    let message = decode_raw(bytes.as_slice()).unwrap();
+    let field = message.get(3).next().unwrap();
+    assert_eq!(field.value(), &RawValue::Packed(vec![1, 2, 300]));
+    assert_eq!(format!("{}", message), "3:[1,2,300]");
+    assert_eq!(format!("{:#}", message), "3: [1, 2, 300]");
+}
+
+#[test]
+fn decode_raw_nested() {
+    let bytes = ComplexType {
+        nested: Some(Scalars {
+            int32: 5,
+            string: "foo".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![2],
+        ..Default::default()
+    }
+    .encode_to_vec();
+
+    let message = decode_raw(bytes.as_slice()).unwrap();
+    assert_eq!(
+        format!("{:#}", message),
+        "3 {\n  3: 5\n  14: \"foo\"\n}\n4: [2]"
+    );
+
+    let nested = match message.get(3).next().unwrap().value() {
+        RawValue::Message(nested) => nested,
+        value => panic!("expected message, found {:?}", value),
+    };
+    assert_eq!(nested.fields().len(), 2);
+    assert!(message.get(1).next().is_none());
+}
+
+#[test]
+fn decode_raw_group() {
+    let bytes = ContainsGroup {
+        requiredgroup: Some(contains_group::RequiredGroup {
+            a: "bar".to_owned(),
+            b: None,
+        }),
+        optionalgroup: None,
+        repeatedgroup: vec![],
+    }
+    .encode_to_vec();
+
+    let message = decode_raw(bytes.as_slice()).unwrap();
+    assert!(matches!(message.fields()[0].value(), RawValue::Group(_)));
+    assert_eq!(format!("{}", message), r#"1{1:"bar"}"#);
+}
+
+#[test]
+fn decode_raw_empty() {
+    let message = decode_raw(b"\x0a\x00".as_ref()).unwrap();
+    assert_eq!(
+        message.fields()[0].value(),
+        &RawValue::String(String::new())
+    );
+    assert_eq!(format!("{}", message), r#"1:"""#);
+
+    assert_eq!(decode_raw(b"".as_ref()).unwrap(), RawMessage::default());
+}
+
+#[test]
+fn decode_raw_invalid() {
+    assert!(decode_raw(b"\x0a\x05ab".as_ref()).is_err());
+    assert!(decode_raw(b"\x00".as_ref()).is_err());
+}
+
+#[test]
+fn decode_raw_deeply_nested() {
+    let mut bytes = Vec::new();
+    for _ in 0..200 {
+        let mut outer = Vec::new();
+        prost::encoding::bytes::encode(1, &bytes, &mut outer);
+        bytes = outer;
+    }
+
+    // Decoding must not overflow the stack, even though every level is a valid message.
+    let mut message = decode_raw(bytes.as_slice()).unwrap();
+    let mut depth = 0;
+    while let Some(RawValue::Message(nested)) =
+        message.fields().first().map(|field| field.value().clone())
+    {
+        message = nested;
+        depth += 1;
+    }
+    assert_eq!(depth, 100);
+}
+
+#[test]
+fn raw_message_from_unknown_fields() {
+    let bytes = b"\xa0\x06\x01";
+    let message = DynamicMessage::decode(Scalars::default().descriptor(), bytes.as_ref()).unwrap();
+    let unknown: UnknownFieldSet = message.unknown_fields().cloned().collect();
+    let raw = RawMessage::from(unknown);
+    assert_eq!(format!("{}", raw), "100:1");
+}
@@ -0,0 +1,143 @@
+use prost_reflect::{
+    bytes::Bytes, text_format::FormatOptions, DynamicMessage, ExtensionDescriptor,
+    MessageDescriptor, RedactOptions, SerializeOptions, Value,
+};
+
+use crate::{test_file_descriptor, to_json};
+
+fn sensitive_descriptor() -> MessageDescriptor {
+    test_file_descriptor()
+        .get_message_by_name("custom.options.Sensitive")
+        .unwrap()
+}
+
+fn field_extension() -> ExtensionDescriptor {
+    test_file_descriptor()
+        .get_extension_by_name("custom.options.field")
+        .unwrap()
+}
+
+fn sensitive_message() -> DynamicMessage {
+    let desc = sensitive_descriptor();
+    DynamicMessage::parse_text_format(
+        desc,
+        r#"
+            token: "abc"
+            secret: "xyz"
+            id: 5
+            email: "a@example.com"
+            passwords: ["hunter2"]
+            pin: 1234
+            nested {
+                token: "def"
+                id: 6
+                details { a: 1 }
+            }
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn is_field_redacted() {
+    let desc = sensitive_descriptor();
+    let options = RedactOptions::new();
+
+    let redacted = |name: &str, options: &RedactOptions| {
+        options.is_field_redacted(&desc.get_field_by_name(name).unwrap())
+    };
+
+    assert!(redacted("token", &options));
+    assert!(redacted("secret", &options));
+    assert!(redacted("passwords", &options));
+    assert!(redacted("details", &options));
+    assert!(!redacted("id", &options));
+    assert!(!redacted("pin", &options));
+    assert!(!redacted("email", &options));
+
+    let options = RedactOptions::new()
+        .debug_redact(false)
+        .option_extension(field_extension());
+    assert!(!redacted("token", &options));
+    assert!(!redacted("pin", &options));
+    assert!(redacted("email", &options));
+}
+
+#[test]
+fn display_redacts_debug_redact_fields() {
+    let message = sensitive_message();
+    assert_eq!(
+        message.to_string(),
+        r#"token:[REDACTED],secret:[REDACTED],id:5,email:"a@example.com",nested{token:[REDACTED],id:6,details:[REDACTED]},passwords:[REDACTED],pin:1234"#
+    );
+    assert_eq!(
+        Value::Message(message).to_string(),
+        r#"{token:[REDACTED],secret:[REDACTED],id:5,email:"a@example.com",nested{token:[REDACTED],id:6,details:[REDACTED]},passwords:[REDACTED],pin:1234}"#
+    );
+}
+
+#[test]
+fn text_format_redaction() {
+    let message = sensitive_message();
+
+    assert!(message.to_text_format().contains(r#"token:"abc""#));
+
+    let options = FormatOptions::new().pretty(true).redact(
+        RedactOptions::new()
+            .option_extension(field_extension())
+            .placeholder("***"),
+    );
+    assert_eq!(
+        message.to_text_format_with_options(&options),
+        "token: ***\nsecret: ***\nid: 5\nemail: ***\nnested {\n  token: ***\n  id: 6\n  details: ***\n}\npasswords: ***\npin: 1234"
+    );
+}
+
+#[test]
+fn json_redaction() {
+    let message = sensitive_message();
+
+    assert!(to_json(&message, &SerializeOptions::new()).contains(r#""token":"abc""#));
+
+    let options = SerializeOptions::new().redact(RedactOptions::new());
+    assert_eq!(
+        to_json(&message, &options),
+        r#"{"token":"[REDACTED]","secret":"[REDACTED]","id":5,"email":"a@example.com","nested":{"token":"[REDACTED]","id":6,"details":"[REDACTED]"},"passwords":"[REDACTED]","pin":1234}"#
+    );
+}
+
+#[test]
+fn redact_in_place() {
+    let mut message = sensitive_message();
+    message.redact(&RedactOptions::new().option_extension(field_extension()));
+
+    let expected = DynamicMessage::parse_text_format(
+        sensitive_descriptor(),
+        r#"
+            token: "[REDACTED]"
+            secret: "[REDACTED]"
+            id: 5
+            email: "[REDACTED]"
+            pin: 1234
+            nested {
+                token: "[REDACTED]"
+                id: 6
+            }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(message, expected);
+    assert_eq!(
+        message.get_field_by_name("secret").unwrap().as_ref(),
+        &Value::Bytes(Bytes::from_static(b"[REDACTED]"))
+    );
+}
+
+#[test]
+#[should_panic(expected = "is not a field option")]
+fn option_extension_must_extend_field_options() {
+    let extension = test_file_descriptor()
+        .get_extension_by_name("custom.options.message")
+        .unwrap();
+    let _ = RedactOptions::new().option_extension(extension);
+}
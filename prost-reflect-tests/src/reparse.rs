@@ -0,0 +1,230 @@
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, ReflectMessage, Value};
+
+use crate::test_file_descriptor;
+
+/// Returns a pool containing the given files from the test descriptors, with all extensions removed.
+fn pool_without_extensions(files: &[&str]) -> DescriptorPool {
+    let mut pool = DescriptorPool::new();
+    for file in test_file_descriptor().file_descriptor_protos() {
+        if files.contains(&file.name()) {
+            let mut file = file.clone();
+            file.extension.clear();
+            for message in &mut file.message_type {
+                message.extension.clear();
+            }
+            pool.add_file_descriptor_proto(file).unwrap();
+        }
+    }
+    pool
+}
+
+#[test]
+fn reparse_unknown_extensions() {
+    let old_pool = pool_without_extensions(&["desc2.proto"]);
+    let old_desc = old_pool
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+
+    // int = 1, in_extendee = 5, in_file = 1.5
+    let bytes = b"\x08\x01\xf8\x06\x05\x85\x07\x00\x00\xc0\x3f";
+    let mut message = DynamicMessage::decode(old_desc, bytes.as_ref()).unwrap();
+    assert_eq!(message.unknown_fields().count(), 2);
+
+    let new_pool = test_file_descriptor();
+    message.reparse_unknown_fields_with(&new_pool).unwrap();
+    assert_eq!(message.unknown_fields().count(), 0);
+    assert_eq!(message.descriptor().parent_pool(), &new_pool);
+
+    let desc = message.descriptor();
+    assert_eq!(
+        message.get_field_by_name("int").unwrap().as_ref(),
+        &Value::I32(1)
+    );
+    assert_eq!(
+        message
+            .get_extension(&desc.get_extension(111).unwrap())
+            .as_ref(),
+        &Value::I32(5)
+    );
+    assert_eq!(
+        message
+            .get_extension(&desc.get_extension(112).unwrap())
+            .as_ref(),
+        &Value::F32(1.5)
+    );
+    assert_eq!(message.encode_to_vec(), bytes);
+}
+
+#[test]
+fn reparse_unknown_fields_nested() {
+    let new_pool = test_file_descriptor();
+    let message_options = new_pool
+        .get_message_by_name("google.protobuf.MessageOptions")
+        .unwrap();
+    let extension = message_options.get_extension(1001).unwrap();
+    assert_eq!(extension.full_name(), "custom.options.message");
+
+    let mut options = DynamicMessage::new(message_options);
+    options.set_extension(&extension, Value::String("abc".to_owned()));
+    let mut nested = DynamicMessage::new(
+        new_pool
+            .get_message_by_name("google.protobuf.DescriptorProto")
+            .unwrap(),
+    );
+    nested.set_field_by_name("options", Value::Message(options));
+    let mut message = DynamicMessage::new(nested.descriptor());
+    message.set_field_by_name("name", Value::String("Outer".to_owned()));
+    message.set_field_by_name("nested_type", Value::List(vec![Value::Message(nested)]));
+    let bytes = message.encode_to_vec();
+
+    let old_pool = pool_without_extensions(&["google/protobuf/descriptor.proto"]);
+    let mut decoded = DynamicMessage::decode(
+        old_pool
+            .get_message_by_name("google.protobuf.DescriptorProto")
+            .unwrap(),
+        bytes.as_slice(),
+    )
+    .unwrap();
+    let nested_options = |message: &DynamicMessage| {
+        message
+            .get_field_by_name("nested_type")
+            .unwrap()
+            .as_list()
+            .unwrap()[0]
+            .as_message()
+            .unwrap()
+            .get_field_by_name("options")
+            .unwrap()
+            .as_message()
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(nested_options(&decoded).unknown_fields().count(), 1);
+
+    decoded.reparse_unknown_fields_with(&new_pool).unwrap();
+    let options = nested_options(&decoded);
+    assert_eq!(options.unknown_fields().count(), 0);
+    assert_eq!(
+        options.get_extension(&extension).as_ref(),
+        &Value::String("abc".to_owned())
+    );
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn reparse_unknown_fields_current_descriptor() {
+    let new_pool = test_file_descriptor();
+    let extension = new_pool
+        .get_message_by_name("google.protobuf.MessageOptions")
+        .unwrap()
+        .get_extension(1001)
+        .unwrap();
+
+    // Options created from a pool without the extension, then set on a message from the new pool.
+    let old_pool = pool_without_extensions(&["google/protobuf/descriptor.proto"]);
+    let mut options = DynamicMessage::new(
+        old_pool
+            .get_message_by_name("google.protobuf.MessageOptions")
+            .unwrap(),
+    );
+    // custom.options.message = "abc"
+    options.merge(b"\xca\x3e\x03abc".as_ref()).unwrap();
+    assert_eq!(options.unknown_fields().count(), 1);
+
+    let mut message = DynamicMessage::new(
+        new_pool
+            .get_message_by_name("google.protobuf.DescriptorProto")
+            .unwrap(),
+    );
+    message.set_field_by_name("options", Value::Message(options));
+
+    message.reparse_unknown_fields().unwrap();
+    let options = message.get_field_by_name("options").unwrap();
+    let options = options.as_message().unwrap();
+    assert_eq!(options.unknown_fields().count(), 0);
+    assert_eq!(
+        options.get_extension(&extension).as_ref(),
+        &Value::String("abc".to_owned())
+    );
+
+    // Extensions added to the pool after the descriptor was obtained are not picked up.
+    let mut pool = old_pool;
+    let mut message = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.MessageOptions")
+            .unwrap(),
+    );
+    message.merge(b"\xca\x3e\x03abc".as_ref()).unwrap();
+    let options_file = new_pool
+        .get_file_by_name("options.proto")
+        .unwrap()
+        .file_descriptor_proto()
+        .clone();
+    pool.add_file_descriptor_proto(options_file).unwrap();
+
+    message.reparse_unknown_fields().unwrap();
+    assert_eq!(message.unknown_fields().count(), 1);
+    message.reparse_unknown_fields_with(&pool).unwrap();
+    assert_eq!(message.unknown_fields().count(), 0);
+}
+
+#[test]
+fn reparse_unknown_fields_missing_message() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("my.package2.MyMessage")
+            .unwrap(),
+    );
+    let err = message
+        .reparse_unknown_fields_with(&DescriptorPool::new())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("message 'my.package2.MyMessage' not found in descriptor pool"));
+}
+
+#[test]
+fn set_descriptor() {
+    let new_desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    let mut message = DynamicMessage::new(new_desc.clone());
+    message.set_field_by_name("int", Value::I32(3));
+    message.set_extension(&new_desc.get_extension(111).unwrap(), Value::I32(5));
+
+    // Switching to a descriptor without the extension moves its value into the unknown fields.
+    let old_desc = pool_without_extensions(&["desc2.proto"])
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    message.set_descriptor(old_desc.clone()).unwrap();
+    assert_eq!(message.descriptor(), old_desc);
+    assert_eq!(
+        message.get_field_by_name("int").unwrap().as_ref(),
+        &Value::I32(3)
+    );
+    assert_eq!(message.unknown_fields().count(), 1);
+
+    message.set_descriptor(new_desc.clone()).unwrap();
+    assert_eq!(message.unknown_fields().count(), 0);
+    assert_eq!(
+        message
+            .get_extension(&new_desc.get_extension(111).unwrap())
+            .as_ref(),
+        &Value::I32(5)
+    );
+}
+
+#[test]
+fn set_descriptor_incompatible() {
+    let pool = test_file_descriptor();
+    let mut message = DynamicMessage::new(pool.get_message_by_name("test.Scalars").unwrap());
+    message.set_field_by_name("string", Value::String("hello".to_owned()));
+    message.set_field_by_name("int32", Value::I32(1));
+    let expected = message.clone();
+
+    // Field 3 of `test.ComplexType` is a message, so the varint value of `int32` cannot be decoded.
+    assert!(message
+        .set_descriptor(pool.get_message_by_name("test.ComplexType").unwrap())
+        .is_err());
+    assert_eq!(message, expected);
+}
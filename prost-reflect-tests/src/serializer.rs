@@ -0,0 +1,403 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use prost_reflect::{
+    DeserializeOptions, DynamicMessage, MessageDescriptor, MessageSerializer, ReflectMessage,
+};
+use prost_types::{value::Kind, ListValue, Struct, Timestamp};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::proto::{message_with_oneof, ComplexType, Point, Scalars, WellKnownTypes};
+
+fn from_serialize<T>(value: &impl Serialize) -> T
+where
+    T: ReflectMessage + Default,
+{
+    DynamicMessage::from_serialize(T::default().descriptor(), value)
+        .unwrap()
+        .transcode_to()
+        .unwrap()
+}
+
+fn error_string<T: ReflectMessage + Default>(value: &impl Serialize) -> String {
+    DynamicMessage::from_serialize(T::default().descriptor(), value)
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn serialize_struct() {
+    #[derive(Serialize)]
+    struct Scalars {
+        double: f64,
+        float: f32,
+        int32: i32,
+        int64: i64,
+        uint64: u64,
+        sint64: i64,
+        #[serde(rename = "bool")]
+        boolean: bool,
+        string: &'static str,
+        bytes: Vec<u8>,
+    }
+
+    let message: crate::proto::Scalars = from_serialize(&Scalars {
+        double: 1.5,
+        float: 2.5,
+        int32: -3,
+        int64: i64::MAX,
+        uint64: u64::MAX,
+        sint64: i64::MIN,
+        boolean: true,
+        string: "hello",
+        bytes: b"\x00\xff".to_vec(),
+    });
+
+    assert_eq!(
+        message,
+        crate::proto::Scalars {
+            double: 1.5,
+            float: 2.5,
+            int32: -3,
+            int64: i64::MAX,
+            uint64: u64::MAX,
+            sint64: i64::MIN,
+            r#bool: true,
+            string: "hello".to_owned(),
+            bytes: b"\x00\xff".to_vec(),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn serialize_json_conventions() {
+    let message: Scalars = from_serialize(&json!({
+        "int64": "-9007199254740993",
+        "fixed64": "18446744073709551615",
+        "double": "NaN",
+        "bytes": "aaa+bbb/WA==",
+    }));
+
+    assert_eq!(message.int64, -9_007_199_254_740_993);
+    assert_eq!(message.fixed64, u64::MAX);
+    assert!(message.double.is_nan());
+    assert_eq!(message.bytes, b"i\xa6\xbem\xb6\xffX");
+}
+
+#[test]
+fn serialize_matches_deserialize() {
+    let value = json!({
+        "stringMap": {
+            "a": { "int32": 1, "string": "x" },
+        },
+        "int_map": {
+            "-5": { "uint32": 7 },
+        },
+        "nested": { "bool": true },
+        "myEnum": ["FOO", 3, "NEG"],
+        "optionalEnum": "BAR",
+    });
+
+    let desc = ComplexType::default().descriptor();
+    let serialized = DynamicMessage::from_serialize(desc.clone(), &value).unwrap();
+    let deserialized = DynamicMessage::deserialize(desc, value).unwrap();
+    assert_eq!(serialized, deserialized);
+}
+
+#[test]
+fn serialize_field_names() {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct JsonNames {
+        optional_enum: MyEnum,
+        string_map: HashMap<&'static str, Point>,
+    }
+
+    #[derive(Serialize)]
+    struct ProtoNames {
+        optional_enum: MyEnum,
+        int_map: BTreeMap<i32, Point>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum MyEnum {
+        Bar,
+    }
+
+    #[derive(Serialize)]
+    struct Point {
+        int32: i32,
+    }
+
+    let message: ComplexType = from_serialize(&JsonNames {
+        optional_enum: MyEnum::Bar,
+        string_map: HashMap::from([("a", Point { int32: 5 })]),
+    });
+    assert_eq!(message.optional_enum, 3);
+    assert_eq!(message.string_map["a"].int32, 5);
+
+    let message: ComplexType = from_serialize(&ProtoNames {
+        optional_enum: MyEnum::Bar,
+        int_map: BTreeMap::from([(-1, Point { int32: 6 })]),
+    });
+    assert_eq!(message.optional_enum, 3);
+    assert_eq!(message.int_map[&-1].int32, 6);
+}
+
+#[test]
+fn serialize_option_fields() {
+    #[derive(Serialize)]
+    struct ComplexType {
+        nested: Option<HashMap<&'static str, i32>>,
+        optional_enum: Option<i32>,
+    }
+
+    let message: crate::proto::ComplexType = from_serialize(&ComplexType {
+        nested: None,
+        optional_enum: None,
+    });
+    assert_eq!(message, crate::proto::ComplexType::default());
+
+    let message: crate::proto::ComplexType = from_serialize(&ComplexType {
+        nested: Some(HashMap::new()),
+        optional_enum: Some(-4),
+    });
+    assert_eq!(message.nested, Some(Scalars::default()));
+    assert_eq!(message.optional_enum, -4);
+}
+
+#[test]
+fn serialize_oneof_variant() {
+    #[derive(Serialize)]
+    struct MessageWithOneof {
+        #[serde(flatten)]
+        test_oneof: TestOneof,
+    }
+
+    #[derive(Serialize)]
+    enum TestOneof {
+        #[serde(rename = "oneof_field_1")]
+        OneofField1(String),
+        #[serde(rename = "oneofField2")]
+        #[allow(dead_code)]
+        OneofField2(i32),
+    }
+
+    let message: crate::proto::MessageWithOneof = from_serialize(&MessageWithOneof {
+        test_oneof: TestOneof::OneofField1("hello".to_owned()),
+    });
+    assert_eq!(
+        message.test_oneof,
+        Some(message_with_oneof::TestOneof::OneofField1(
+            "hello".to_owned()
+        ))
+    );
+
+    let message: crate::proto::MessageWithOneof = from_serialize(&TestOneof::OneofField2(5));
+    assert_eq!(
+        message.test_oneof,
+        Some(message_with_oneof::TestOneof::OneofField2(5))
+    );
+
+    assert_eq!(
+        error_string::<crate::proto::MessageWithOneof>(
+            &json!({ "oneofField1": "a", "oneofField2": 1 })
+        ),
+        "multiple fields provided for oneof 'test_oneof'"
+    );
+}
+
+#[test]
+fn serialize_well_known_types() {
+    #[derive(Serialize)]
+    struct WellKnownTypes {
+        timestamp: std::time::SystemTime,
+        duration: Duration,
+        #[serde(rename = "struct")]
+        structure: BTreeMap<&'static str, serde_json::Value>,
+        int32: Option<i32>,
+        int64: Option<i64>,
+        string: Option<&'static str>,
+        bytes: Option<Vec<u8>>,
+        mask: &'static str,
+        list: Vec<serde_json::Value>,
+        null: (),
+        empty: (),
+    }
+
+    let message: crate::proto::WellKnownTypes = from_serialize(&WellKnownTypes {
+        timestamp: UNIX_EPOCH + Duration::new(63_108_020, 21_000_000),
+        duration: Duration::from_millis(1_500),
+        structure: BTreeMap::from([("number", json!(42))]),
+        int32: None,
+        int64: Some(i64::MIN),
+        string: Some(""),
+        bytes: Some(vec![1, 2]),
+        mask: "fieldOne,fieldTwo.b",
+        list: vec![json!(null), json!(true)],
+        null: (),
+        empty: (),
+    });
+
+    assert_eq!(
+        message,
+        crate::proto::WellKnownTypes {
+            timestamp: Some(Timestamp {
+                seconds: 63_108_020,
+                nanos: 21_000_000,
+            }),
+            duration: Some(prost_types::Duration {
+                seconds: 1,
+                nanos: 500_000_000,
+            }),
+            r#struct: Some(Struct {
+                fields: BTreeMap::from([(
+                    "number".to_owned(),
+                    prost_types::Value {
+                        kind: Some(Kind::NumberValue(42.0)),
+                    },
+                )]),
+            }),
+            int32: None,
+            int64: Some(i64::MIN),
+            string: Some(String::new()),
+            bytes: Some(vec![1, 2]),
+            mask: Some(prost_types::FieldMask {
+                paths: vec!["field_one".to_owned(), "field_two.b".to_owned()],
+            }),
+            list: Some(ListValue {
+                values: vec![
+                    prost_types::Value {
+                        kind: Some(Kind::NullValue(0)),
+                    },
+                    prost_types::Value {
+                        kind: Some(Kind::BoolValue(true)),
+                    },
+                ],
+            }),
+            empty: Some(()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn serialize_timestamp_string() {
+    let message: WellKnownTypes = from_serialize(&json!({
+        "timestamp": "1970-01-01T00:00:01.5Z",
+        "duration": "-2.5s",
+        "mask": ["a.b_c"],
+    }));
+    assert_eq!(
+        message.timestamp,
+        Some(Timestamp {
+            seconds: 1,
+            nanos: 500_000_000
+        })
+    );
+    assert_eq!(
+        message.duration,
+        Some(prost_types::Duration {
+            seconds: -2,
+            nanos: -500_000_000
+        })
+    );
+    assert_eq!(message.mask.unwrap().paths, ["a.b_c"]);
+}
+
+#[test]
+fn serialize_any() {
+    let desc = any_descriptor();
+    let message = DynamicMessage::from_serialize(
+        desc,
+        &json!({
+            "@type": "type.googleapis.com/test.Point",
+            "latitude": 1,
+            "longitude": 2,
+        }),
+    )
+    .unwrap();
+
+    let any: prost_types::Any = message.transcode_to().unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/test.Point");
+    assert_eq!(
+        prost::Message::decode(any.value.as_slice()),
+        Ok(Point {
+            latitude: 1,
+            longitude: 2
+        })
+    );
+}
+
+fn any_descriptor() -> MessageDescriptor {
+    crate::test_file_descriptor()
+        .get_message_by_name("google.protobuf.Any")
+        .unwrap()
+}
+
+#[test]
+fn serialize_error_paths() {
+    assert_eq!(
+        error_string::<ComplexType>(&json!({
+            "stringMap": { "a": { "int32": "x" } },
+        })),
+        r#"stringMap["a"].int32: invalid digit found in string"#
+    );
+    assert_eq!(
+        error_string::<ComplexType>(&json!({ "myEnum": ["FOO", "BAZ"] })),
+        "myEnum[1]: unrecognized enum value 'BAZ'"
+    );
+    assert_eq!(
+        error_string::<ComplexType>(&json!({ "nested": { "int32": 1.5 } })),
+        "nested.int32: expected integer value"
+    );
+    assert_eq!(
+        error_string::<ComplexType>(&json!({ "nested": { "unknown": 1 } })),
+        "nested: unrecognized field name 'unknown'"
+    );
+    assert_eq!(
+        error_string::<ComplexType>(&json!({ "myEnum": 1 })),
+        "myEnum: invalid type: integer `1`, expected repeated enum 'test.ComplexType.MyEnum'"
+    );
+    assert_eq!(
+        error_string::<Scalars>(&json!({ "bytes": [1, 256] })),
+        "bytes[1]: value 256 is out of range for a byte"
+    );
+    assert_eq!(
+        error_string::<WellKnownTypes>(&json!({
+            "timestamp": { "secs_since_epoch": 999_999_999_999i64, "nanos_since_epoch": 0 },
+        })),
+        "timestamp: timestamp out of range"
+    );
+
+    let err =
+        DynamicMessage::from_serialize(Point::default().descriptor(), &json!({ "latitude": [] }))
+            .unwrap_err();
+    assert_eq!(err.path(), "latitude");
+    assert_eq!(err.message(), "invalid type: sequence, expected int32");
+}
+
+#[test]
+fn serialize_unknown_fields() {
+    let value = json!({ "latitude": 1, "unknown": true });
+    let desc = Point::default().descriptor();
+
+    assert!(DynamicMessage::from_serialize(desc.clone(), &value).is_err());
+
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    let message = value
+        .serialize(MessageSerializer::with_options(desc, &options))
+        .unwrap();
+    assert_eq!(
+        message.transcode_to::<Point>().unwrap(),
+        Point {
+            latitude: 1,
+            longitude: 0
+        }
+    );
+}
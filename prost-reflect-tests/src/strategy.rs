@@ -0,0 +1,227 @@
+use proptest::{prelude::*, test_runner::TestRunner};
+use prost::Message;
+use prost_reflect::{
+    arbitrary::{self, ArbitraryOptions},
+    DeserializeOptions, DynamicMessage, MapKey, MessageDescriptor, ReflectMessage, Value,
+};
+
+use crate::test_file_descriptor;
+
+/// Generates arbitrary instances of a generated message type, using the strategy from
+/// [`arbitrary::message()`].
+pub fn arbitrary_message<T>() -> BoxedStrategy<T>
+where
+    T: ReflectMessage + Default + 'static,
+{
+    arbitrary::message(T::default().descriptor())
+        .prop_map(|message| message.transcode_to().unwrap())
+        .boxed()
+}
+
+fn message_desc(name: &str) -> MessageDescriptor {
+    test_file_descriptor().get_message_by_name(name).unwrap()
+}
+
+fn roundtrip_binary(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let decoded =
+        DynamicMessage::decode(message.descriptor(), message.encode_to_vec().as_slice()).unwrap();
+    prop_assert_eq!(&decoded, message);
+    Ok(())
+}
+
+fn roundtrip_json(message: &DynamicMessage) -> Result<(), TestCaseError> {
+    let json = serde_json::to_string(message).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let decoded = DynamicMessage::deserialize_with_options(
+        message.descriptor(),
+        &mut deserializer,
+        &DeserializeOptions::new().deny_unknown_fields(true),
+    )
+    .unwrap();
+    prop_assert_eq!(&decoded, message, "json: {}", json);
+    Ok(())
+}
+
+fn generate(desc: MessageDescriptor, options: &ArbitraryOptions) -> Vec<DynamicMessage> {
+    let strategy = arbitrary::message_with_options(desc, options);
+    let mut runner = TestRunner::deterministic();
+    (0..64)
+        .map(|_| strategy.new_tree(&mut runner).unwrap().current())
+        .collect()
+}
+
+#[test]
+fn field_presence_none() {
+    let options = ArbitraryOptions::new().field_presence(0.0);
+    for message in generate(message_desc("test.ComplexType"), &options) {
+        assert_eq!(message.fields().count(), 0);
+    }
+}
+
+#[test]
+fn required_fields() {
+    let desc = message_desc("test2.ContainsGroup");
+    let options = ArbitraryOptions::new().field_presence(0.0);
+    for message in generate(desc.clone(), &options) {
+        assert_eq!(message.fields().count(), 0);
+    }
+
+    let group_desc = desc.get_field_by_name("requiredgroup").unwrap();
+    let options = ArbitraryOptions::new().field_presence(1.0);
+    for message in generate(desc.clone(), &options) {
+        let group = message.get_field(&group_desc);
+        let group = group.as_message().unwrap();
+        assert!(group.has_field_by_name("a"));
+    }
+}
+
+#[test]
+fn oneof_exclusive() {
+    let desc = message_desc("test.MessageWithOneof");
+    let oneof = desc.oneofs().next().unwrap();
+    let options = ArbitraryOptions::new().field_presence(1.0);
+    for message in generate(desc.clone(), &options) {
+        assert_eq!(
+            oneof
+                .fields()
+                .filter(|field| message.has_field(field))
+                .count(),
+            1
+        );
+    }
+}
+
+#[test]
+fn enum_values() {
+    let desc = message_desc("test.ComplexType");
+    let enum_desc = desc
+        .get_field_by_name("optional_enum")
+        .unwrap()
+        .kind()
+        .as_enum()
+        .cloned()
+        .unwrap();
+    let options = ArbitraryOptions::new().field_presence(1.0);
+    for message in generate(desc, &options) {
+        for value in message
+            .get_field_by_name("my_enum")
+            .unwrap()
+            .as_list()
+            .unwrap()
+        {
+            assert!(enum_desc
+                .get_value(value.as_enum_number().unwrap())
+                .is_some());
+        }
+    }
+}
+
+#[test]
+fn map_keys() {
+    let options = ArbitraryOptions::new().field_presence(1.0);
+    for message in generate(message_desc("test.ComplexType"), &options) {
+        let int_map = message.get_field_by_name("int_map").unwrap();
+        let int_map = int_map.as_map().unwrap();
+        assert!(!int_map.is_empty());
+        assert!(int_map.keys().all(|key| matches!(key, MapKey::I32(_))));
+    }
+}
+
+#[test]
+fn max_depth() {
+    let options = ArbitraryOptions::new().field_presence(1.0).max_depth(0);
+    for message in generate(message_desc("test.ComplexType"), &options) {
+        assert!(!message.has_field_by_name("nested"));
+        assert!(!message.has_field_by_name("string_map"));
+        assert!(message.has_field_by_name("my_enum"));
+    }
+
+    let options = ArbitraryOptions::new().field_presence(1.0).max_depth(1);
+    for message in generate(message_desc("test.ComplexType"), &options) {
+        assert!(message.has_field_by_name("nested"));
+        assert!(message.has_field_by_name("string_map"));
+    }
+}
+
+#[test]
+fn max_collection_len() {
+    let options = ArbitraryOptions::new()
+        .field_presence(1.0)
+        .max_collection_len(2);
+    for message in generate(message_desc("test.ScalarArrays"), &options) {
+        for (_, value) in message.fields() {
+            assert!((1..=2).contains(&value.as_list().unwrap().len()));
+        }
+    }
+
+    let options = ArbitraryOptions::new()
+        .field_presence(1.0)
+        .max_collection_len(0);
+    for message in generate(message_desc("test.ScalarArrays"), &options) {
+        assert_eq!(message.fields().count(), 0);
+    }
+}
+
+#[test]
+fn non_finite_floats() {
+    let options = ArbitraryOptions::new().field_presence(1.0);
+    for message in generate(message_desc("test.Scalars"), &options) {
+        assert!(message
+            .get_field_by_name("double")
+            .unwrap()
+            .as_f64()
+            .unwrap()
+            .is_finite());
+    }
+
+    let options = ArbitraryOptions::new()
+        .field_presence(1.0)
+        .non_finite_floats(true);
+    assert!(generate(message_desc("test.Scalars"), &options)
+        .iter()
+        .any(|message| {
+            !matches!(*message.get_field_by_name("double").unwrap(), Value::F64(value) if value.is_finite())
+        }));
+}
+
+proptest! {
+    #[test]
+    fn roundtrip_binary_scalars(message in arbitrary::message(message_desc("test.Scalars"))) {
+        roundtrip_binary(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_binary_complex_type(message in arbitrary::message(message_desc("test.ComplexType"))) {
+        roundtrip_binary(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_binary_well_known_types(message in arbitrary::message(message_desc("test.WellKnownTypes"))) {
+        roundtrip_binary(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_binary_contains_group(message in arbitrary::message(message_desc("test2.ContainsGroup"))) {
+        roundtrip_binary(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_json_scalar_arrays(message in arbitrary::message(message_desc("test.ScalarArrays"))) {
+        roundtrip_json(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_json_complex_type(message in arbitrary::message(message_desc("test.ComplexType"))) {
+        roundtrip_json(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_json_well_known_types(message in arbitrary::message(message_desc("test.WellKnownTypes"))) {
+        roundtrip_json(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_json_oneof(message in arbitrary::message(message_desc("test.MessageWithOneof"))) {
+        roundtrip_json(&message)?;
+    }
+}
@@ -0,0 +1,217 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use proptest::{prelude::*, test_runner::TestCaseError};
+use prost_reflect::{
+    DeserializeOptions, DeserializePathSegment, DynamicMessage, ReflectMessage, SerializeOptions,
+};
+use prost_types::{value::Kind, Struct};
+
+use crate::proto::{ComplexType, Point, ScalarArrays, Scalars, WellKnownTypes};
+
+fn roundtrip_struct<T>(message: &T) -> Result<(), TestCaseError>
+where
+    T: PartialEq + Debug + ReflectMessage + Default,
+{
+    roundtrip_struct_with_options(message, &SerializeOptions::new())
+}
+
+fn roundtrip_struct_with_options<T>(
+    message: &T,
+    options: &SerializeOptions,
+) -> Result<(), TestCaseError>
+where
+    T: PartialEq + Debug + ReflectMessage + Default,
+{
+    let dynamic = message.transcode_to_dynamic();
+    let struct_message = dynamic.to_struct_with_options(options).unwrap();
+    prop_assert_eq!(
+        struct_message.descriptor().full_name().to_owned(),
+        "google.protobuf.Struct"
+    );
+
+    let roundtripped = DynamicMessage::from_struct(message.descriptor(), &struct_message).unwrap();
+    prop_assert_eq!(message, &roundtripped.transcode_to::<T>().unwrap());
+    Ok(())
+}
+
+fn string_value(value: &str) -> prost_types::Value {
+    prost_types::Value {
+        kind: Some(Kind::StringValue(value.to_owned())),
+    }
+}
+
+fn list_value(values: Vec<prost_types::Value>) -> prost_types::Value {
+    prost_types::Value {
+        kind: Some(Kind::ListValue(prost_types::ListValue { values })),
+    }
+}
+
+fn number_value(value: f64) -> prost_types::Value {
+    prost_types::Value {
+        kind: Some(Kind::NumberValue(value)),
+    }
+}
+
+#[test]
+fn to_struct_json_mapping() {
+    let message = Scalars {
+        int32: 5,
+        int64: i64::MAX,
+        bytes: b"\x00\xff".to_vec(),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let struct_message: Struct = message.to_struct().unwrap().transcode_to().unwrap();
+    assert_eq!(
+        struct_message.fields,
+        BTreeMap::from([
+            ("int32".to_owned(), number_value(5.0)),
+            ("int64".to_owned(), string_value("9223372036854775807")),
+            ("bytes".to_owned(), string_value("AP8=")),
+        ])
+    );
+}
+
+#[test]
+fn to_struct_with_options() {
+    let message = ComplexType {
+        optional_enum: 3,
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+
+    let options = SerializeOptions::new()
+        .use_enum_numbers(true)
+        .use_proto_field_name(true);
+    let struct_message: Struct = message
+        .to_struct_with_options(&options)
+        .unwrap()
+        .transcode_to()
+        .unwrap();
+    assert_eq!(
+        struct_message.fields,
+        BTreeMap::from([("optional_enum".to_owned(), number_value(3.0))])
+    );
+}
+
+#[test]
+fn to_struct_not_an_object() {
+    let message = prost_types::Timestamp::default().transcode_to_dynamic();
+    let err = message.to_struct().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid type: string \"1970-01-01T00:00:00Z\", expected a map"
+    );
+}
+
+#[test]
+fn from_struct_value() {
+    let value = prost_types::Value {
+        kind: Some(Kind::StructValue(Struct {
+            fields: BTreeMap::from([
+                ("latitude".to_owned(), number_value(1.0)),
+                ("longitude".to_owned(), string_value("-2")),
+            ]),
+        })),
+    }
+    .transcode_to_dynamic();
+
+    let message = DynamicMessage::from_struct(Point::default().descriptor(), &value).unwrap();
+    assert_eq!(
+        message.transcode_to::<Point>().unwrap(),
+        Point {
+            latitude: 1,
+            longitude: -2,
+        }
+    );
+
+    let value = Struct {
+        fields: BTreeMap::from([
+            ("optionalEnum".to_owned(), number_value(3.0)),
+            (
+                "myEnum".to_owned(),
+                list_value(vec![number_value(1.0), string_value("NEG")]),
+            ),
+        ]),
+    }
+    .transcode_to_dynamic();
+    let message = DynamicMessage::from_struct(ComplexType::default().descriptor(), &value)
+        .unwrap()
+        .transcode_to::<ComplexType>()
+        .unwrap();
+    assert_eq!(message.optional_enum, 3);
+    assert_eq!(message.my_enum, [1, -4]);
+}
+
+#[test]
+fn from_struct_errors() {
+    let value = Struct {
+        fields: BTreeMap::from([("latitude".to_owned(), number_value(1.5))]),
+    }
+    .transcode_to_dynamic();
+
+    let err = DynamicMessage::from_struct(Point::default().descriptor(), &value).unwrap_err();
+    assert_eq!(
+        err.path(),
+        [DeserializePathSegment::Field("latitude".to_owned())]
+    );
+    assert_eq!(err.to_string(), "at $.latitude: expected integer value");
+
+    let value = Struct {
+        fields: BTreeMap::from([("unknown".to_owned(), number_value(1.0))]),
+    }
+    .transcode_to_dynamic();
+    assert_eq!(
+        DynamicMessage::from_struct(Point::default().descriptor(), &value)
+            .unwrap_err()
+            .to_string(),
+        "unrecognized field name 'unknown'"
+    );
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    assert_eq!(
+        DynamicMessage::from_struct_with_options(Point::default().descriptor(), &value, &options)
+            .unwrap()
+            .transcode_to::<Point>()
+            .unwrap(),
+        Point::default()
+    );
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 32,
+        .. ProptestConfig::default()
+    })]
+
+    #[test]
+    fn roundtrip_arb_scalars(message: Scalars) {
+        roundtrip_struct(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_arb_scalar_arrays(message: ScalarArrays) {
+        roundtrip_struct(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_arb_complex_type(message: ComplexType) {
+        roundtrip_struct(&message)?;
+    }
+
+    #[test]
+    fn roundtrip_arb_complex_type_options(message: ComplexType) {
+        roundtrip_struct_with_options(
+            &message,
+            &SerializeOptions::new()
+                .use_enum_numbers(true)
+                .use_proto_field_name(true)
+                .skip_default_fields(false),
+        )?;
+    }
+
+    #[test]
+    fn roundtrip_arb_well_known_types(message: WellKnownTypes) {
+        roundtrip_struct(&message)?;
+    }
+}
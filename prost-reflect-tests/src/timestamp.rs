@@ -0,0 +1,200 @@
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use prost_reflect::{DynamicMessage, ReflectMessage, TimeError, Value};
+
+use crate::{proto::WellKnownTypes, test_file_descriptor};
+
+fn timestamp(seconds: i64, nanos: i32) -> DynamicMessage {
+    prost_types::Timestamp { seconds, nanos }.transcode_to_dynamic()
+}
+
+fn duration(seconds: i64, nanos: i32) -> DynamicMessage {
+    prost_types::Duration { seconds, nanos }.transcode_to_dynamic()
+}
+
+#[test]
+fn system_time() {
+    let message = timestamp(1_700_000_000, 5);
+    assert_eq!(
+        message.as_timestamp(),
+        Some(UNIX_EPOCH + Duration::new(1_700_000_000, 5))
+    );
+    assert_eq!(
+        timestamp(-1, 999_999_999).as_timestamp(),
+        Some(UNIX_EPOCH - Duration::from_nanos(1))
+    );
+
+    let value = Value::try_from(UNIX_EPOCH + Duration::new(1_700_000_000, 5)).unwrap();
+    assert_eq!(value.as_message(), Some(&message));
+}
+
+#[test]
+fn std_duration() {
+    let message = duration(90, 500_000_000);
+    assert_eq!(message.as_duration(), Some(Duration::from_millis(90_500)));
+    assert_eq!(duration(-1, 0).as_duration(), None);
+
+    let value = Value::try_from(Duration::from_millis(90_500)).unwrap();
+    assert_eq!(value.as_message(), Some(&message));
+}
+
+#[test]
+fn range_validation() {
+    assert!(timestamp(253402300799, 999_999_999)
+        .as_timestamp()
+        .is_some());
+    assert_eq!(timestamp(253402300800, 0).as_timestamp(), None);
+    assert_eq!(timestamp(-62135596801, 0).as_timestamp(), None);
+    assert_eq!(timestamp(0, -1).as_timestamp(), None);
+    assert_eq!(timestamp(0, 1_000_000_000).as_timestamp(), None);
+
+    assert_eq!(
+        SystemTime::try_from(&timestamp(253402300800, 0)),
+        Err(TimeError::OutOfRange {
+            message_name: "google.protobuf.Timestamp"
+        })
+    );
+    assert_eq!(
+        Value::try_from(UNIX_EPOCH + Duration::from_secs(253402300800)),
+        Err(TimeError::OutOfRange {
+            message_name: "google.protobuf.Timestamp"
+        })
+    );
+
+    assert!(duration(315_576_000_000, 999_999_999)
+        .as_duration()
+        .is_some());
+    assert_eq!(duration(315_576_000_001, 0).as_duration(), None);
+    assert_eq!(duration(1, -1).as_duration(), None);
+    assert_eq!(
+        Value::try_from(Duration::from_secs(315_576_000_001)),
+        Err(TimeError::OutOfRange {
+            message_name: "google.protobuf.Duration"
+        })
+    );
+}
+
+#[test]
+fn invalid_type() {
+    assert_eq!(duration(1, 0).as_timestamp(), None);
+    assert_eq!(
+        Duration::try_from(&timestamp(1, 0)),
+        Err(TimeError::InvalidType {
+            expected: "google.protobuf.Duration",
+            actual: "google.protobuf.Timestamp".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn value_for_kind() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("test.WellKnownTypes")
+        .unwrap();
+    let timestamp_kind = desc.get_field_by_name("timestamp").unwrap().kind();
+    let duration_kind = desc.get_field_by_name("duration").unwrap().kind();
+
+    let time = UNIX_EPOCH + Duration::from_secs(10);
+    let value = Value::timestamp_for(&timestamp_kind, time).unwrap();
+    let message = value.as_message().unwrap();
+    assert_eq!(Some(&message.descriptor()), timestamp_kind.as_message());
+    assert_eq!(message.descriptor().parent_pool(), &test_file_descriptor());
+    assert_eq!(message.as_timestamp(), Some(time));
+
+    let value = Value::duration_for(&duration_kind, Duration::from_millis(1_500)).unwrap();
+    let message = value.as_message().unwrap();
+    assert_eq!(Some(&message.descriptor()), duration_kind.as_message());
+    assert_eq!(message.as_duration(), Some(Duration::from_millis(1_500)));
+
+    assert_eq!(
+        Value::timestamp_for(&duration_kind, time),
+        Err(TimeError::InvalidType {
+            expected: "google.protobuf.Timestamp",
+            actual: "google.protobuf.Duration".to_owned(),
+        })
+    );
+    assert_eq!(
+        Value::duration_for(&timestamp_kind, Duration::from_secs(1)),
+        Err(TimeError::InvalidType {
+            expected: "google.protobuf.Duration",
+            actual: "google.protobuf.Timestamp".to_owned(),
+        })
+    );
+}
+
+#[test]
+fn set_field() {
+    let mut message = DynamicMessage::new(
+        test_file_descriptor()
+            .get_message_by_name("test.WellKnownTypes")
+            .unwrap(),
+    );
+    message.set_field_by_name(
+        "timestamp",
+        Value::try_from(UNIX_EPOCH + Duration::from_secs(10)).unwrap(),
+    );
+    message.set_field_by_name(
+        "duration",
+        Value::try_from(Duration::from_millis(1_500)).unwrap(),
+    );
+
+    let message: WellKnownTypes = message.transcode_to().unwrap();
+    assert_eq!(
+        message.timestamp,
+        Some(prost_types::Timestamp {
+            seconds: 10,
+            nanos: 0
+        })
+    );
+    assert_eq!(
+        message.duration,
+        Some(prost_types::Duration {
+            seconds: 1,
+            nanos: 500_000_000
+        })
+    );
+}
+
+#[test]
+fn chrono() {
+    use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+
+    let message = timestamp(1_700_000_000, 5);
+    let time = DateTime::<Utc>::from_timestamp(1_700_000_000, 5).unwrap();
+    assert_eq!(DateTime::<Utc>::try_from(&message), Ok(time));
+    assert_eq!(Value::try_from(time).unwrap().as_message(), Some(&message));
+
+    let offset_time = time.with_timezone(&FixedOffset::east_opt(3600).unwrap());
+    assert_eq!(
+        Value::try_from(offset_time).unwrap().as_message(),
+        Some(&message)
+    );
+
+    let message = duration(-90, -500_000_000);
+    let delta = TimeDelta::milliseconds(-90_500);
+    assert_eq!(TimeDelta::try_from(&message), Ok(delta));
+    assert_eq!(Value::try_from(delta).unwrap().as_message(), Some(&message));
+
+    assert!(Value::try_from(DateTime::<Utc>::MAX_UTC).is_err());
+    assert!(Value::try_from(TimeDelta::MAX).is_err());
+}
+
+#[test]
+fn time() {
+    use time::OffsetDateTime;
+
+    let message = timestamp(1_700_000_000, 5);
+    let time = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_000_000_005).unwrap();
+    assert_eq!(OffsetDateTime::try_from(&message), Ok(time));
+    assert_eq!(Value::try_from(time).unwrap().as_message(), Some(&message));
+
+    let message = duration(-90, -500_000_000);
+    let delta = time::Duration::milliseconds(-90_500);
+    assert_eq!(time::Duration::try_from(&message), Ok(delta));
+    assert_eq!(Value::try_from(delta).unwrap().as_message(), Some(&message));
+
+    assert!(Value::try_from(time::Duration::MAX).is_err());
+}
@@ -0,0 +1,114 @@
+use std::iter::FromIterator;
+
+use prost::{bytes::Bytes, Message};
+use prost_reflect::{
+    DynamicMessage, ReflectMessage, UnknownField, UnknownFieldSet, UnknownFieldValue,
+};
+
+use crate::{
+    proto::{ComplexType, Scalars},
+    test_file_descriptor,
+};
+
+#[test]
+fn unknown_field_values() {
+    let bytes = b"\xa0\x06\x96\x01\xad\x06\x00\x00\x80\x3f\xb1\x06\x00\x00\x00\x00\x00\x00\xf0\x3f\xba\x06\x03foo\xc3\x06\x08\x01\xc4\x06";
+    let message = DynamicMessage::decode(Scalars::default().descriptor(), bytes.as_ref()).unwrap();
+
+    let fields: Vec<&UnknownField> = message.unknown_fields().collect();
+    assert_eq!(fields.len(), 5);
+
+    assert_eq!(fields[0].number(), 100);
+    assert_eq!(fields[0].value().as_varint(), Some(150));
+    assert_eq!(fields[0].value().as_fixed32(), None);
+
+    assert_eq!(
+        fields[1].value().as_fixed32().map(f32::from_bits),
+        Some(1.0)
+    );
+    assert_eq!(
+        fields[2].value().as_fixed64().map(f64::from_bits),
+        Some(1.0)
+    );
+
+    assert_eq!(
+        fields[3].value(),
+        &UnknownFieldValue::LengthDelimited(Bytes::from_static(b"foo"))
+    );
+    assert_eq!(fields[3].value().as_str(), Some("foo"));
+
+    let group = fields[4].value().as_group().unwrap();
+    assert_eq!(group.len(), 1);
+    assert_eq!(group.iter().next().unwrap(), &UnknownField::varint(1, 1));
+}
+
+#[test]
+fn construct_unknown_fields() {
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message.add_unknown_field(UnknownField::varint(100, 150));
+    message.add_unknown_field(UnknownField::fixed32(101, 1.0f32.to_bits()));
+    message.add_unknown_field(UnknownField::fixed64(102, 1.0f64.to_bits()));
+    message.add_unknown_field(UnknownField::length_delimited(103, "foo"));
+    message.add_unknown_field(UnknownField::group(104, [UnknownField::varint(1, 1)]));
+    message.add_unknown_field(UnknownField::new(100, UnknownFieldValue::Varint(1)));
+
+    assert_eq!(
+        message.encode_to_vec(),
+        b"\xa0\x06\x96\x01\xa0\x06\x01\xad\x06\x00\x00\x80\x3f\xb1\x06\x00\x00\x00\x00\x00\x00\xf0\x3f\xba\x06\x03foo\xc3\x06\x08\x01\xc4\x06"
+    );
+
+    let fields: Vec<UnknownField> = message.take_unknown_fields().collect();
+    assert_eq!(fields.len(), 6);
+    assert_eq!(
+        fields[5].clone().into_value(),
+        UnknownFieldValue::Group(UnknownFieldSet::from_iter([UnknownField::varint(1, 1)]))
+    );
+}
+
+#[test]
+#[should_panic(expected = "message 'test.Scalars' has a field with number 3")]
+fn add_unknown_field_known_number() {
+    let mut message = DynamicMessage::new(Scalars::default().descriptor());
+    message.add_unknown_field(UnknownField::varint(3, 1));
+}
+
+#[test]
+#[should_panic(expected = "invalid field number 0")]
+fn unknown_field_invalid_number() {
+    UnknownField::varint(0, 1);
+}
+
+#[test]
+fn decode_unknown_message() {
+    let nested = Scalars {
+        int32: 5,
+        string: "hello".to_owned(),
+        ..Default::default()
+    };
+    // Decode a ComplexType as Scalars, so that the nested message field is unknown.
+    let bytes = ComplexType {
+        nested: Some(nested.clone()),
+        ..Default::default()
+    }
+    .encode_to_vec();
+    let message = DynamicMessage::decode(
+        test_file_descriptor()
+            .get_message_by_name("google.protobuf.Empty")
+            .unwrap(),
+        bytes.as_slice(),
+    )
+    .unwrap();
+
+    let field = message.unknown_fields().next().unwrap();
+    assert_eq!(field.number(), 3);
+    let set = field.value().decode_message().unwrap();
+    assert_eq!(set.encode_to_vec(), nested.encode_to_vec());
+    let fields: Vec<&UnknownField> = set.iter().collect();
+    assert_eq!(fields[0].value().as_varint(), Some(5));
+    assert_eq!(fields[1].value().as_str(), Some("hello"));
+
+    let invalid = UnknownField::length_delimited(1, b"\xff".as_ref());
+    assert_eq!(invalid.value().decode_message(), None);
+    assert_eq!(invalid.value().as_str(), None);
+    assert_eq!(UnknownField::varint(1, 1).value().decode_message(), None);
+}
@@ -0,0 +1,358 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use prost_reflect::{
+    validate::{ValidateOptions, Validator, Violation},
+    DynamicMessage,
+};
+use serde_json::json;
+
+use crate::{message, test_file_descriptor};
+
+fn violations(message: &DynamicMessage) -> Vec<(String, String)> {
+    violations_with_options(message, &ValidateOptions::new())
+}
+
+fn violations_with_options(
+    message: &DynamicMessage,
+    options: &ValidateOptions,
+) -> Vec<(String, String)> {
+    match message.validate_with_options(options) {
+        Ok(()) => vec![],
+        Err(err) => err
+            .into_violations()
+            .iter()
+            .map(|violation: &Violation| {
+                (
+                    violation.field_path().to_owned(),
+                    violation.rule_id().to_owned(),
+                )
+            })
+            .collect(),
+    }
+}
+
+fn paths(violations: &[(&str, &str)]) -> Vec<(String, String)> {
+    violations
+        .iter()
+        .map(|(path, rule_id)| (path.to_string(), rule_id.to_string()))
+        .collect()
+}
+
+fn valid_user() -> serde_json::Value {
+    json!({
+        "name": "alice",
+        "email": "alice@example.com",
+        "age": 30,
+        "role": "ROLE_ADMIN",
+        "tags": ["a", "b"],
+        "address": { "city": "London", "countryCode": "GB" },
+        "quotas": { "cpu": 4 },
+        "phone": "555-1234",
+    })
+}
+
+#[test]
+fn valid_message() {
+    let message = message("validate.CreateUserRequest", valid_user());
+    assert_eq!(message.validate(), Ok(()));
+}
+
+#[test]
+fn no_constraints() {
+    let message = message("test.Point", json!({ "latitude": 1 }));
+    assert_eq!(message.validate(), Ok(()));
+}
+
+#[test]
+fn field_violations() {
+    let message = message(
+        "validate.CreateUserRequest",
+        json!({
+            "name": "a very long name",
+            "email": "not an email",
+            "age": 12,
+            "role": 7,
+            "tags": ["a", "B", "a", "c"],
+            "quotas": { "x": 1, "yy": 0, "zz": 2 },
+            "nickname": "",
+            "website": "not a uri",
+            "previousAddresses": [{ "city": "Paris", "countryCode": "FRA" }],
+            "referrer": "too long",
+            "id": "1234",
+        }),
+    );
+
+    assert_eq!(
+        violations(&message),
+        paths(&[
+            ("name", "string.max_len"),
+            ("email", "string.email"),
+            ("age", "uint32.gte_lte"),
+            ("role", "enum.defined_only"),
+            ("tags", "repeated.max_items"),
+            ("tags", "repeated.unique"),
+            ("tags[1]", "string.pattern"),
+            ("address", "required"),
+            ("quotas", "map.max_pairs"),
+            ("quotas[\"x\"]", "string.min_len"),
+            ("quotas[\"yy\"]", "int32.gt"),
+            ("nickname", "string.min_len"),
+            ("website", "string.uri"),
+            ("previous_addresses[0].country_code", "string.len"),
+            ("referrer", "string.max_len"),
+            ("id", "string.uuid"),
+        ])
+    );
+}
+
+#[test]
+fn violation_messages() {
+    let message = message(
+        "validate.CreateUserRequest",
+        json!({
+            "name": "",
+            "email": "",
+            "age": 20,
+            "address": { "city": "London", "countryCode": "GB" },
+        }),
+    );
+
+    let err = message.validate().unwrap_err();
+    let messages: Vec<String> = err
+        .violations()
+        .iter()
+        .map(|violation| violation.to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "contact: exactly one field is required in oneof",
+            "name: value length must be at least 1 characters",
+            "email: value is empty, which is not a valid email address",
+        ]
+    );
+    assert_eq!(err.violations()[2].rule_id(), "string.email_empty");
+    assert_eq!(
+        err.to_string(),
+        "validation failed: contact: exactly one field is required in oneof; \
+        name: value length must be at least 1 characters; \
+        email: value is empty, which is not a valid email address"
+    );
+}
+
+#[test]
+fn fail_fast() {
+    let message = message(
+        "validate.CreateUserRequest",
+        json!({ "name": "", "email": "", "age": 1 }),
+    );
+
+    assert_eq!(violations(&message).len(), 5);
+    assert_eq!(
+        violations_with_options(&message, &ValidateOptions::new().fail_fast(true)),
+        paths(&[("contact", "required")])
+    );
+}
+
+#[test]
+fn ignore_if_unpopulated() {
+    let mut user = valid_user();
+    user["id"] = json!("");
+    assert_eq!(
+        message("validate.CreateUserRequest", user.clone()).validate(),
+        Ok(())
+    );
+
+    user["id"] = json!("f47ac10b-58cc-4372-a567-0e02b2c3d479");
+    assert_eq!(
+        message("validate.CreateUserRequest", user).validate(),
+        Ok(())
+    );
+}
+
+#[test]
+fn reuse_validator() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("validate.CreateUserRequest")
+        .unwrap();
+    let validator = Validator::new(&desc).unwrap();
+
+    let valid = message("validate.CreateUserRequest", valid_user());
+    assert_eq!(validator.validate(&valid), Ok(()));
+
+    let mut user = valid_user();
+    user["tags"] = json!(["a", "B"]);
+    user["address"]["countryCode"] = json!("GBR");
+    let invalid = message("validate.CreateUserRequest", user);
+    let err = validator.validate(&invalid).unwrap_err();
+    let violations: Vec<(&str, &str)> = err
+        .violations()
+        .iter()
+        .map(|violation| (violation.field_path(), violation.rule_id()))
+        .collect();
+    assert_eq!(
+        violations,
+        [
+            ("tags[1]", "string.pattern"),
+            ("address.country_code", "string.len"),
+        ]
+    );
+    assert_eq!(invalid.validate(), Err(err));
+
+    let other = message("validate.DisabledRules", json!({}));
+    assert_eq!(validator.validate(&other), Ok(()));
+    let other = message("validate.Address", json!({ "city": "" }));
+    assert_eq!(
+        validator.validate(&other).unwrap_err().violations().len(),
+        2
+    );
+}
+
+#[test]
+fn disabled_message() {
+    let message = message("validate.DisabledRules", json!({}));
+    assert_eq!(message.validate(), Ok(()));
+}
+
+#[test]
+fn ignore_always_before_required() {
+    let message = message("validate.IgnoredRules", json!({}));
+    assert_eq!(message.validate(), Ok(()));
+}
+
+#[test]
+fn invalid_pattern() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("validate.InvalidPattern")
+        .unwrap();
+    let err = Validator::new(&desc).unwrap_err();
+    assert_eq!(err.field(), "validate.InvalidPattern.name");
+    assert!(err.message().starts_with("invalid regex pattern `[a-z`"));
+
+    let message = message("validate.InvalidPattern", json!({ "name": "abc" }));
+    let err = message.validate().unwrap_err();
+    assert!(err.violations().is_empty());
+    assert_eq!(
+        err.constraint_error().unwrap().field(),
+        "validate.InvalidPattern.name"
+    );
+    assert!(err
+        .to_string()
+        .starts_with("invalid constraints for field 'validate.InvalidPattern.name'"));
+}
+
+#[test]
+fn numeric_rules() {
+    let valid = message(
+        "validate.NumericRules",
+        json!({
+            "exclusiveRange": 11,
+            "inList": "2",
+            "finite": 1.0,
+            "constValue": 1.5,
+            "notInList": "1",
+            "greater": "101",
+        }),
+    );
+    assert_eq!(valid.validate(), Ok(()));
+
+    let invalid = message(
+        "validate.NumericRules",
+        json!({
+            "exclusiveRange": 5,
+            "inList": "4",
+            "finite": "Infinity",
+            "constValue": 2.5,
+            "notInList": "0",
+            "greater": "100",
+        }),
+    );
+    assert_eq!(
+        violations(&invalid),
+        paths(&[
+            ("exclusive_range", "int32.gt_lt_exclusive"),
+            ("in_list", "int64.in"),
+            ("finite", "double.finite"),
+            ("const_value", "float.const"),
+            ("not_in_list", "sint64.not_in"),
+            ("greater", "fixed64.gt"),
+        ])
+    );
+
+    let err = invalid.validate().unwrap_err();
+    assert_eq!(
+        err.violations()[0].message(),
+        "value must be greater than 10 or less than 0"
+    );
+    assert_eq!(
+        err.violations()[1].message(),
+        "value must be in list [1, 2, 3]"
+    );
+}
+
+#[test]
+fn well_known_rules() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let options = ValidateOptions::new().now(now);
+
+    let valid = message(
+        "validate.WellKnownRules",
+        json!({
+            "timeout": "30s",
+            "createdAt": "2023-01-01T00:00:00Z",
+            "expiresAt": "2023-11-14T23:00:00Z",
+            "payload": {
+                "@type": "type.googleapis.com/validate.Address",
+                "city": "London",
+            },
+            "ip": "wKgAAQ==",
+            "token": "AQID",
+            "host": "example.com",
+            "address": "127.0.0.1",
+            "accepted": true,
+        }),
+    );
+    assert_eq!(violations_with_options(&valid, &options), vec![]);
+
+    let invalid = message(
+        "validate.WellKnownRules",
+        json!({
+            "timeout": "61s",
+            "createdAt": "2024-01-01T00:00:00Z",
+            "expiresAt": "2023-11-15T00:00:00Z",
+            "payload": {
+                "@type": "type.googleapis.com/test.Point",
+            },
+            "ip": "AQID",
+            "token": "AgMEBQY=",
+            "host": "-example.com",
+            "address": "not an address!",
+            "accepted": false,
+        }),
+    );
+    assert_eq!(
+        violations_with_options(&invalid, &options),
+        paths(&[
+            ("timeout", "duration.gt_lte"),
+            ("created_at", "timestamp.lt_now"),
+            ("expires_at", "timestamp.within"),
+            ("payload", "any.in"),
+            ("ip", "bytes.ip"),
+            ("token", "bytes.max_len"),
+            ("token", "bytes.prefix"),
+            ("host", "string.hostname"),
+            ("address", "string.address"),
+            ("accepted", "bool.const"),
+        ])
+    );
+
+    let err = invalid.validate_with_options(&options).unwrap_err();
+    assert_eq!(
+        err.violations()[0].message(),
+        "value must be greater than 0s and less than or equal to 60s"
+    );
+    assert_eq!(
+        err.violations()[6].message(),
+        "value does not have prefix 01"
+    );
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use prost_reflect::{
+    visit::{Control, ControlMut, Visitor, VisitorMut},
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, FieldPath, FieldPathSegment, MapKey,
+    ReflectMessage, UnknownField, Value,
+};
+
+use crate::{
+    proto::{ComplexType, Scalars},
+    test_file_descriptor,
+};
+
+fn path_string(path: &[FieldPathSegment]) -> String {
+    let mut result = String::new();
+    for segment in path {
+        match segment {
+            FieldPathSegment::Field(field) => {
+                if !result.is_empty() {
+                    result.push('.');
+                }
+                result.push_str(field.name());
+            }
+            FieldPathSegment::Extension(extension) => {
+                if !result.is_empty() {
+                    result.push('.');
+                }
+                result.push_str(&format!("[{}]", extension.full_name()));
+            }
+            FieldPathSegment::Index(index) => result.push_str(&format!("[{}]", index)),
+            FieldPathSegment::Key(key) => result.push_str(&format!("[{}]", key_string(key))),
+        }
+    }
+    result
+}
+
+fn key_string(key: &MapKey) -> String {
+    match key {
+        MapKey::String(value) => format!("{:?}", value),
+        MapKey::I32(value) => value.to_string(),
+        _ => unimplemented!(),
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    visited: Vec<String>,
+    skip: Option<&'static str>,
+}
+
+impl Visitor for Recorder {
+    fn visit_field(
+        &mut self,
+        path: &[FieldPathSegment],
+        field: &FieldDescriptor,
+        _: &Value,
+    ) -> Control {
+        self.visited
+            .push(format!("field {} {}", path_string(path), field.name()));
+        if Some(field.name()) == self.skip {
+            Control::Skip
+        } else {
+            Control::Continue
+        }
+    }
+
+    fn visit_extension(
+        &mut self,
+        path: &[FieldPathSegment],
+        _: &ExtensionDescriptor,
+        value: &Value,
+    ) -> Control {
+        self.visited
+            .push(format!("extension {} {}", path_string(path), value));
+        Control::Continue
+    }
+
+    fn visit_list_element(
+        &mut self,
+        path: &[FieldPathSegment],
+        index: usize,
+        value: &Value,
+    ) -> Control {
+        self.visited
+            .push(format!("element {} {} {}", path_string(path), index, value));
+        Control::Continue
+    }
+
+    fn visit_map_entry(&mut self, path: &[FieldPathSegment], key: &MapKey, _: &Value) -> Control {
+        self.visited
+            .push(format!("entry {} {}", path_string(path), key_string(key)));
+        Control::Continue
+    }
+
+    fn visit_unknown_field(&mut self, path: &[FieldPathSegment], field: &UnknownField) {
+        self.visited
+            .push(format!("unknown {} {}", path_string(path), field.number()));
+    }
+}
+
+fn complex_message() -> DynamicMessage {
+    ComplexType {
+        string_map: HashMap::from([(
+            "a".to_owned(),
+            Scalars {
+                int32: 5,
+                ..Default::default()
+            },
+        )]),
+        nested: Some(Scalars {
+            string: "hello".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![1, 3],
+        ..Default::default()
+    }
+    .transcode_to_dynamic()
+}
+
+#[test]
+fn visit_depth_first() {
+    let mut message = complex_message();
+    message.add_unknown_field(UnknownField::varint(100, 1));
+
+    let mut visitor = Recorder::default();
+    message.visit(&mut visitor);
+    assert_eq!(
+        visitor.visited,
+        [
+            "field string_map string_map",
+            "entry string_map[\"a\"] \"a\"",
+            "field string_map[\"a\"].int32 int32",
+            "field nested nested",
+            "field nested.string string",
+            "field my_enum my_enum",
+            "element my_enum[0] 0 1",
+            "element my_enum[1] 1 3",
+            "unknown  100",
+        ]
+    );
+}
+
+#[test]
+fn visit_skip() {
+    let message = complex_message();
+
+    let mut visitor = Recorder {
+        skip: Some("string_map"),
+        ..Default::default()
+    };
+    message.visit(&mut visitor);
+    assert_eq!(
+        visitor.visited,
+        [
+            "field string_map string_map",
+            "field nested nested",
+            "field nested.string string",
+            "field my_enum my_enum",
+            "element my_enum[0] 0 1",
+            "element my_enum[1] 1 3",
+        ]
+    );
+}
+
+#[test]
+fn visit_extensions() {
+    let desc = test_file_descriptor()
+        .get_message_by_name("my.package2.MyMessage")
+        .unwrap();
+    let message = DynamicMessage::decode(desc, b"\x08\x01\xf8\x06\x05".as_ref()).unwrap();
+
+    let mut visitor = Recorder::default();
+    message.visit(&mut visitor);
+    assert_eq!(
+        visitor.visited,
+        [
+            "field int int",
+            "extension [my.package2.MyMessage.in_extendee] 5",
+        ]
+    );
+}
+
+#[test]
+fn visit_as_trait_object() {
+    let message = complex_message();
+
+    let mut recorder = Recorder::default();
+    let visitor: &mut dyn Visitor = &mut recorder;
+    message.visit(visitor);
+    assert_eq!(recorder.visited.len(), 8);
+}
+
+struct Redactor;
+
+impl VisitorMut for Redactor {
+    fn visit_field_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        field: &FieldDescriptor,
+        value: &mut Value,
+    ) -> ControlMut {
+        match field.name() {
+            "string" => {
+                *value = Value::String("[REDACTED]".to_owned());
+                ControlMut::Skip
+            }
+            "int32" => ControlMut::Remove,
+            _ => ControlMut::Continue,
+        }
+    }
+
+    fn visit_list_element_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        index: usize,
+        _: &mut Value,
+    ) -> ControlMut {
+        if index == 0 {
+            ControlMut::Remove
+        } else {
+            ControlMut::Continue
+        }
+    }
+
+    fn visit_unknown_field_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        _: &mut UnknownField,
+    ) -> ControlMut {
+        ControlMut::Remove
+    }
+}
+
+#[test]
+fn visit_mut_rewrite_and_remove() {
+    let mut message = complex_message();
+    message.add_unknown_field(UnknownField::varint(100, 1));
+    message.visit_mut(&mut Redactor);
+
+    let expected = ComplexType {
+        string_map: HashMap::from([("a".to_owned(), Scalars::default())]),
+        nested: Some(Scalars {
+            string: "[REDACTED]".to_owned(),
+            ..Default::default()
+        }),
+        my_enum: vec![3],
+        ..Default::default()
+    };
+    assert_eq!(message, expected.transcode_to_dynamic());
+    assert_eq!(message.unknown_fields().count(), 0);
+}
+
+struct RemoveEntries;
+
+impl VisitorMut for RemoveEntries {
+    fn visit_map_entry_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        key: &MapKey,
+        _: &mut Value,
+    ) -> ControlMut {
+        assert!(matches!(path.last(), Some(FieldPathSegment::Key(k)) if k == key));
+        if key == &MapKey::I32(1) {
+            ControlMut::Remove
+        } else {
+            ControlMut::Continue
+        }
+    }
+
+    fn visit_field_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        field: &FieldDescriptor,
+        _: &mut Value,
+    ) -> ControlMut {
+        if field.name() == "nested" {
+            ControlMut::Remove
+        } else {
+            ControlMut::Continue
+        }
+    }
+}
+
+#[test]
+fn visit_mut_map_entries() {
+    let mut message = ComplexType {
+        int_map: HashMap::from([
+            (1, Scalars::default()),
+            (
+                2,
+                Scalars {
+                    int64: 3,
+                    ..Default::default()
+                },
+            ),
+        ]),
+        nested: Some(Scalars::default()),
+        ..Default::default()
+    }
+    .transcode_to_dynamic();
+    message.visit_mut(&mut RemoveEntries);
+
+    let path = FieldPath::parse(&message.descriptor(), "int_map[2].int64").unwrap();
+    assert_eq!(message.get_path(&path).unwrap().as_ref(), &Value::I64(3));
+    let map = message.get_field_by_name("int_map").unwrap();
+    assert_eq!(map.as_map().unwrap().len(), 1);
+    assert!(!message.has_field_by_name("nested"));
+}
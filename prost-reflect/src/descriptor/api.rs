@@ -1010,6 +1010,15 @@ impl FieldDescriptor {
         )
     }
 
+    /// Gets the encoded options for this field, or an empty slice if none are set.
+    pub(crate) fn encoded_options(&self) -> &[u8] {
+        self.raw()
+            .options
+            .as_ref()
+            .map(|options| options.encoded.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Gets the unique number for this message field.
     pub fn number(&self) -> u32 {
         self.inner().number
@@ -1204,6 +1213,15 @@ impl ExtensionDescriptor {
         )
     }
 
+    /// Gets the encoded options for this field, or an empty slice if none are set.
+    pub(crate) fn encoded_options(&self) -> &[u8] {
+        self.raw()
+            .options
+            .as_ref()
+            .map(|options| options.encoded.as_slice())
+            .unwrap_or_default()
+    }
+
     /// Gets the number for this extension field.
     pub fn number(&self) -> u32 {
         self.inner().number
@@ -0,0 +1,263 @@
+use std::{error::Error, fmt, sync::Arc};
+
+use prost::{DecodeError, Message};
+
+use crate::{
+    descriptor::{GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN},
+    DescriptorPool, DynamicMessage, MessageDescriptor, ReflectMessage,
+};
+
+/// A source of message types for the type URLs of [`Any`](prost_types::Any) messages.
+///
+/// The default implementation, used when no resolver is given, looks up messages in the
+/// [`DescriptorPool`] of the `Any` message itself. A custom resolver can be used when the contained
+/// messages are defined elsewhere, for example in other pools or in a registry fetched at runtime.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, MessageDescriptor, any::TypeResolver};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// struct PrefixResolver(DescriptorPool);
+///
+/// impl TypeResolver for PrefixResolver {
+///     fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+///         let name = type_url.strip_prefix("example.com/types/")?;
+///         self.0.get_message_by_name(name)
+///     }
+/// }
+///
+/// let resolver = PrefixResolver(pool);
+/// assert!(resolver.resolve("example.com/types/package.MyMessage").is_some());
+/// assert!(resolver.resolve("type.googleapis.com/package.MyMessage").is_none());
+/// ```
+pub trait TypeResolver: Send + Sync {
+    /// Returns the message type identified by `type_url`, or `None` if it is not known.
+    fn resolve(&self, type_url: &str) -> Option<MessageDescriptor>;
+}
+
+/// An error returned by [`DynamicMessage::unpack_any()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnpackAnyError {
+    /// The message to unpack is not a `google.protobuf.Any`.
+    NotAny {
+        /// The full name of the message type.
+        message_name: String,
+    },
+    /// The type URL of the `Any` message could not be resolved to a message type.
+    TypeNotFound {
+        /// The unresolved type URL.
+        type_url: String,
+    },
+    /// The value of the `Any` message is not a valid encoding of the resolved message type.
+    Decode(DecodeError),
+}
+
+impl TypeResolver for DescriptorPool {
+    /// Looks up the message named by the last segment of `type_url` in this pool.
+    ///
+    /// The domain of the type URL is ignored, so `type.googleapis.com/foo.Bar` and
+    /// `example.com/types/foo.Bar` both resolve to the message `foo.Bar`.
+    fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+        let (_, message_name) = type_url.rsplit_once('/')?;
+        self.get_message_by_name(message_name)
+    }
+}
+
+impl TypeResolver for Vec<DescriptorPool> {
+    /// Resolves `type_url` in each pool in order, returning the first message found.
+    fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+        self.iter().find_map(|pool| pool.resolve(type_url))
+    }
+}
+
+impl<T> TypeResolver for Arc<T>
+where
+    T: TypeResolver + ?Sized,
+{
+    fn resolve(&self, type_url: &str) -> Option<MessageDescriptor> {
+        (**self).resolve(type_url)
+    }
+}
+
+/// A type resolver stored in an options struct.
+#[derive(Clone)]
+pub(crate) struct SharedTypeResolver(Arc<dyn TypeResolver>);
+
+impl SharedTypeResolver {
+    #[cfg_attr(not(any(feature = "serde", feature = "text-format")), allow(dead_code))]
+    pub(crate) fn new<R>(resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        SharedTypeResolver(Arc::new(resolver))
+    }
+
+    pub(crate) fn get(&self) -> &dyn TypeResolver {
+        &*self.0
+    }
+}
+
+impl fmt::Debug for SharedTypeResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedTypeResolver").finish_non_exhaustive()
+    }
+}
+
+/// Resolves `type_url` using `resolver`, or in `pool` if no resolver is set.
+///
+/// Without a resolver, only type URLs in the `type.googleapis.com` and `type.googleprod.com`
+/// domains are supported.
+pub(crate) fn resolve_type_url(
+    resolver: Option<&SharedTypeResolver>,
+    pool: &DescriptorPool,
+    type_url: &str,
+) -> Option<MessageDescriptor> {
+    match resolver {
+        Some(resolver) => resolver.get().resolve(type_url),
+        None => {
+            let message_name = type_url
+                .strip_prefix(GOOGLE_APIS_DOMAIN)
+                .or_else(|| type_url.strip_prefix(GOOGLE_PROD_DOMAIN))?;
+            pool.get_message_by_name(message_name)
+        }
+    }
+}
+
+impl DynamicMessage {
+    /// Packs this message into a `google.protobuf.Any` message.
+    ///
+    /// The type URL of the result is `type.googleapis.com/` followed by the full name of this message's
+    /// type. If the descriptor pool of this message contains the `google.protobuf.Any` type, it is
+    /// used for the result, so that [`unpack_any()`](DynamicMessage::unpack_any) can find the
+    /// contained type without a custom resolver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let any = dynamic_message.pack_any();
+    /// assert_eq!(
+    ///     any.get_field_by_name("type_url").unwrap().as_str(),
+    ///     Some("type.googleapis.com/package.MyMessage"),
+    /// );
+    /// assert_eq!(any.unpack_any().unwrap(), dynamic_message);
+    /// ```
+    pub fn pack_any(&self) -> DynamicMessage {
+        let any = prost_types::Any {
+            type_url: format!("{}{}", GOOGLE_APIS_DOMAIN, self.desc.full_name()),
+            value: self.encode_to_vec(),
+        };
+
+        match self
+            .desc
+            .parent_pool()
+            .get_message_by_name("google.protobuf.Any")
+        {
+            Some(any_desc) => {
+                let mut message = DynamicMessage::new(any_desc);
+                message
+                    .transcode_from(&any)
+                    .expect("google.protobuf.Any has an unexpected definition");
+                message
+            }
+            None => any.transcode_to_dynamic(),
+        }
+    }
+
+    /// Unpacks the message contained in this `google.protobuf.Any` message.
+    ///
+    /// The type URL is resolved using the descriptor pool of this message. Only the `type.googleapis.com`
+    /// and `type.googleprod.com` domains are supported. Use
+    /// [`unpack_any_with_resolver()`](DynamicMessage::unpack_any_with_resolver) to look up types
+    /// defined elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this message is not a `google.protobuf.Any`, if the type URL cannot be
+    /// resolved, or if the contained value fails to decode.
+    pub fn unpack_any(&self) -> Result<DynamicMessage, UnpackAnyError> {
+        self.unpack_any_inner(None)
+    }
+
+    /// Unpacks the message contained in this `google.protobuf.Any` message, using `resolver` to find
+    /// the message type for its type URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this message is not a `google.protobuf.Any`, if the type URL cannot be
+    /// resolved, or if the contained value fails to decode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, ReflectMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let any = prost_types::Any {
+    ///     type_url: "example.com/package.MyMessage".to_owned(),
+    ///     value: b"\x08\x96\x01".to_vec(),
+    /// }
+    /// .transcode_to_dynamic();
+    ///
+    /// let message = any.unpack_any_with_resolver(&pool).unwrap();
+    /// assert_eq!(message.descriptor().full_name(), "package.MyMessage");
+    /// ```
+    pub fn unpack_any_with_resolver(
+        &self,
+        resolver: &dyn TypeResolver,
+    ) -> Result<DynamicMessage, UnpackAnyError> {
+        self.unpack_any_inner(Some(resolver))
+    }
+
+    fn unpack_any_inner(
+        &self,
+        resolver: Option<&dyn TypeResolver>,
+    ) -> Result<DynamicMessage, UnpackAnyError> {
+        if self.desc.full_name() != "google.protobuf.Any" {
+            return Err(UnpackAnyError::NotAny {
+                message_name: self.desc.full_name().to_owned(),
+            });
+        }
+
+        let any: prost_types::Any = self.transcode_to().map_err(UnpackAnyError::Decode)?;
+        let desc = match resolver {
+            Some(resolver) => resolver.resolve(&any.type_url),
+            None => resolve_type_url(None, self.desc.parent_pool(), &any.type_url),
+        };
+        let desc = desc.ok_or(UnpackAnyError::TypeNotFound {
+            type_url: any.type_url,
+        })?;
+
+        DynamicMessage::decode(desc, any.value.as_slice()).map_err(UnpackAnyError::Decode)
+    }
+}
+
+impl fmt::Display for UnpackAnyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnpackAnyError::NotAny { message_name } => write!(
+                f,
+                "expected a message of type 'google.protobuf.Any', but found '{}'",
+                message_name
+            ),
+            UnpackAnyError::TypeNotFound { type_url } => {
+                write!(f, "message type not found for type url '{}'", type_url)
+            }
+            UnpackAnyError::Decode(err) => write!(f, "failed to decode Any value: {}", err),
+        }
+    }
+}
+
+impl Error for UnpackAnyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UnpackAnyError::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
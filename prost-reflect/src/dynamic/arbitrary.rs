@@ -0,0 +1,408 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use proptest::{prelude::*, strategy::Union};
+use prost::bytes::Bytes;
+
+use crate::{
+    dynamic::wkt::{
+        MAX_DURATION_NANOS, MAX_DURATION_SECONDS, MAX_TIMESTAMP_SECONDS, MIN_TIMESTAMP_SECONDS,
+    },
+    Cardinality, DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value,
+};
+
+/// Options to control the messages produced by [`message_with_options()`].
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub struct ArbitraryOptions {
+    max_depth: u32,
+    max_collection_len: usize,
+    max_string_len: usize,
+    field_presence: f64,
+    non_finite_floats: bool,
+}
+
+/// Returns a strategy which generates arbitrary messages of the given type, using the default options.
+///
+/// See [`message_with_options()`] for details.
+pub fn message(desc: MessageDescriptor) -> BoxedStrategy<DynamicMessage> {
+    message_with_options(desc, &ArbitraryOptions::new())
+}
+
+/// Returns a strategy which generates arbitrary messages of the given type.
+///
+/// Generated messages are always valid for their descriptor:
+///
+/// - enum fields are set to one of the values defined by the enum type.
+/// - map keys are generated according to the key type of the map.
+/// - at most one field of each oneof is set.
+/// - `google.protobuf.Timestamp`, `google.protobuf.Duration` and `google.protobuf.FieldMask` values are within
+///   the ranges supported by the JSON mapping.
+///
+/// Fields of type `google.protobuf.Any` are never set, since their payload type cannot be chosen from
+/// the descriptor. Extension fields and unknown fields are also never set.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DynamicMessage, DescriptorPool, arbitrary};
+/// # use proptest::{prelude::*, test_runner::TestRunner};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// let message_desc = pool.get_message_by_name("package.MyMessage").unwrap();
+///
+/// let mut runner = TestRunner::default();
+/// runner.run(&arbitrary::message(message_desc.clone()), |message| {
+///     let decoded = DynamicMessage::decode(message_desc.clone(), message.encode_to_vec().as_slice()).unwrap();
+///     prop_assert_eq!(decoded, message);
+///     Ok(())
+/// }).unwrap();
+/// ```
+pub fn message_with_options(
+    desc: MessageDescriptor,
+    options: &ArbitraryOptions,
+) -> BoxedStrategy<DynamicMessage> {
+    Generator {
+        options,
+        cache: RefCell::default(),
+    }
+    .message(&desc, 0)
+}
+
+impl ArbitraryOptions {
+    /// Creates a new instance of [`ArbitraryOptions`], with the default options.
+    pub const fn new() -> Self {
+        ArbitraryOptions {
+            max_depth: 4,
+            max_collection_len: 4,
+            max_string_len: 16,
+            field_presence: 0.5,
+            non_finite_floats: false,
+        }
+    }
+
+    /// The maximum depth of nested messages to generate.
+    ///
+    /// Message fields nested more deeply than this are left unset, even if they are required. A depth
+    /// of `0` generates only the scalar fields of the top-level message.
+    ///
+    /// The default value is `4`.
+    pub const fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The maximum number of elements of repeated and map fields.
+    ///
+    /// The default value is `4`.
+    pub const fn max_collection_len(mut self, len: usize) -> Self {
+        self.max_collection_len = len;
+        self
+    }
+
+    /// The maximum number of characters in `string` fields, or bytes in `bytes` fields.
+    ///
+    /// The default value is `16`.
+    pub const fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    /// The probability of each field or oneof being set, between `0.0` and `1.0`.
+    ///
+    /// Required fields are always set, subject to [`max_depth`](Self::max_depth). For repeated and map fields,
+    /// this is the probability of the field having at least one element.
+    ///
+    /// The default value is `0.5`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between `0.0` and `1.0`.
+    pub fn field_presence(mut self, probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "field presence probability must be between 0.0 and 1.0"
+        );
+        self.field_presence = probability;
+        self
+    }
+
+    /// Whether to generate infinite and NaN values for `float` and `double` fields.
+    ///
+    /// These values are not equal to themselves and cannot be represented by `google.protobuf.Value`
+    /// in JSON, so they are disabled by default.
+    pub const fn non_finite_floats(mut self, yes: bool) -> Self {
+        self.non_finite_floats = yes;
+        self
+    }
+}
+
+impl Default for ArbitraryOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds the strategies for each message type, sharing them between fields with the same type and depth.
+struct Generator<'a> {
+    options: &'a ArbitraryOptions,
+    cache: RefCell<HashMap<(String, u32), BoxedStrategy<DynamicMessage>>>,
+}
+
+/// The strategy for a single field or oneof, which produces the fields to set.
+type FieldStrategy = BoxedStrategy<Option<(FieldDescriptor, Value)>>;
+
+impl Generator<'_> {
+    fn message(&self, desc: &MessageDescriptor, depth: u32) -> BoxedStrategy<DynamicMessage> {
+        let key = (desc.full_name().to_owned(), depth);
+        if let Some(strategy) = self.cache.borrow().get(&key) {
+            return strategy.clone();
+        }
+
+        let strategy = match desc.full_name() {
+            "google.protobuf.Any" => Just(DynamicMessage::new(desc.clone())).boxed(),
+            "google.protobuf.Timestamp" => timestamp(desc.clone()),
+            "google.protobuf.Duration" => duration(desc.clone()),
+            "google.protobuf.FieldMask" => field_mask(desc.clone(), self.options),
+            // A value with no kind set is indistinguishable from `null` in JSON.
+            "google.protobuf.Value" => self.fields(desc, depth, 1.0),
+            _ => self.fields(desc, depth, self.options.field_presence),
+        };
+
+        self.cache.borrow_mut().insert(key, strategy.clone());
+        strategy
+    }
+
+    fn fields(
+        &self,
+        desc: &MessageDescriptor,
+        depth: u32,
+        field_presence: f64,
+    ) -> BoxedStrategy<DynamicMessage> {
+        let mut seen_oneofs = HashSet::new();
+        let mut fields: Vec<FieldStrategy> = Vec::new();
+        for field_desc in desc.fields() {
+            if let Some(oneof_desc) = field_desc.containing_oneof() {
+                if seen_oneofs.insert(oneof_desc.name().to_owned()) {
+                    let variants: Vec<_> = oneof_desc
+                        .fields()
+                        .filter_map(|field_desc| {
+                            let value = self.value(&field_desc, depth)?;
+                            Some(value.prop_map(move |value| (field_desc.clone(), value)))
+                        })
+                        .collect();
+                    if !variants.is_empty() {
+                        fields.push(optional(field_presence, Union::new(variants)));
+                    }
+                }
+            } else if let Some(value) = self.value(&field_desc, depth) {
+                let probability = if field_desc.cardinality() == Cardinality::Required {
+                    1.0
+                } else {
+                    field_presence
+                };
+                fields.push(optional(
+                    probability,
+                    value.prop_map(move |value| (field_desc.clone(), value)),
+                ));
+            }
+        }
+
+        let desc = desc.clone();
+        fields
+            .prop_map(move |fields| {
+                let mut message = DynamicMessage::new(desc.clone());
+                for (field_desc, value) in fields.into_iter().flatten() {
+                    // Default values of fields without presence are not encoded, so leave them unset.
+                    if field_desc.supports_presence() || !value.is_default_for_field(&field_desc) {
+                        message.set_field(&field_desc, value);
+                    }
+                }
+                message
+            })
+            .boxed()
+    }
+
+    /// Returns a strategy for a non-default value of the field, or `None` if the field should never be set.
+    fn value(&self, field_desc: &FieldDescriptor, depth: u32) -> Option<BoxedStrategy<Value>> {
+        let max_len = self.options.max_collection_len;
+        if field_desc.is_map() {
+            let entry_desc = match field_desc.kind() {
+                Kind::Message(entry_desc) => entry_desc,
+                _ => unreachable!(),
+            };
+            let key = map_key(&entry_desc.map_entry_key_field().kind());
+            let value = self.singular(&entry_desc.map_entry_value_field().kind(), depth)?;
+            if max_len == 0 {
+                return None;
+            }
+            // Duplicate keys are allowed to collapse, since some key types have very few values.
+            Some(
+                prop::collection::vec((key, value), 1..=max_len)
+                    .prop_map(|entries| Value::Map(entries.into_iter().collect()))
+                    .boxed(),
+            )
+        } else if field_desc.is_list() {
+            let value = self.singular(&field_desc.kind(), depth)?;
+            if max_len == 0 {
+                return None;
+            }
+            Some(
+                prop::collection::vec(value, 1..=max_len)
+                    .prop_map(Value::List)
+                    .boxed(),
+            )
+        } else {
+            self.singular(&field_desc.kind(), depth)
+        }
+    }
+
+    fn singular(&self, kind: &Kind, depth: u32) -> Option<BoxedStrategy<Value>> {
+        let strategy = match kind {
+            Kind::Double => self.double().prop_map(Value::F64).boxed(),
+            Kind::Float => self.float().prop_map(Value::F32).boxed(),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                any::<i32>().prop_map(Value::I32).boxed()
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                any::<i64>().prop_map(Value::I64).boxed()
+            }
+            Kind::Uint32 | Kind::Fixed32 => any::<u32>().prop_map(Value::U32).boxed(),
+            Kind::Uint64 | Kind::Fixed64 => any::<u64>().prop_map(Value::U64).boxed(),
+            Kind::Bool => any::<bool>().prop_map(Value::Bool).boxed(),
+            Kind::String => string(self.options.max_string_len)
+                .prop_map(Value::String)
+                .boxed(),
+            Kind::Bytes => prop::collection::vec(any::<u8>(), 0..=self.options.max_string_len)
+                .prop_map(|bytes| Value::Bytes(Bytes::from(bytes)))
+                .boxed(),
+            Kind::Enum(enum_desc) => {
+                let numbers: Vec<i32> = enum_desc.values().map(|value| value.number()).collect();
+                prop::sample::select(numbers)
+                    .prop_map(Value::EnumNumber)
+                    .boxed()
+            }
+            Kind::Message(message_desc) => {
+                if depth >= self.options.max_depth {
+                    return None;
+                }
+                self.message(message_desc, depth + 1)
+                    .prop_map(Value::Message)
+                    .boxed()
+            }
+        };
+        Some(strategy)
+    }
+
+    fn double(&self) -> BoxedStrategy<f64> {
+        use prop::num::f64::*;
+
+        if self.options.non_finite_floats {
+            ANY.boxed()
+        } else {
+            (POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO).boxed()
+        }
+    }
+
+    fn float(&self) -> BoxedStrategy<f32> {
+        use prop::num::f32::*;
+
+        if self.options.non_finite_floats {
+            ANY.boxed()
+        } else {
+            (POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO).boxed()
+        }
+    }
+}
+
+fn optional<S>(probability: f64, strategy: S) -> BoxedStrategy<Option<S::Value>>
+where
+    S: Strategy + 'static,
+    S::Value: Clone,
+{
+    if probability <= 0.0 {
+        Just(None).boxed()
+    } else if probability >= 1.0 {
+        strategy.prop_map(Some).boxed()
+    } else {
+        prop::option::weighted(probability, strategy).boxed()
+    }
+}
+
+fn map_key(kind: &Kind) -> BoxedStrategy<MapKey> {
+    match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => any::<i32>().prop_map(MapKey::I32).boxed(),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => any::<i64>().prop_map(MapKey::I64).boxed(),
+        Kind::Uint32 | Kind::Fixed32 => any::<u32>().prop_map(MapKey::U32).boxed(),
+        Kind::Uint64 | Kind::Fixed64 => any::<u64>().prop_map(MapKey::U64).boxed(),
+        Kind::Bool => any::<bool>().prop_map(MapKey::Bool).boxed(),
+        Kind::String => string(4).prop_map(MapKey::String).boxed(),
+        _ => panic!("invalid type for map key"),
+    }
+}
+
+fn string(max_len: usize) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<char>(), 0..=max_len).prop_map(String::from_iter)
+}
+
+fn timestamp(desc: MessageDescriptor) -> BoxedStrategy<DynamicMessage> {
+    (
+        MIN_TIMESTAMP_SECONDS..=MAX_TIMESTAMP_SECONDS,
+        0..=MAX_DURATION_NANOS as i32,
+    )
+        .prop_map(move |(seconds, nanos)| {
+            let mut message = DynamicMessage::new(desc.clone());
+            message
+                .transcode_from(&prost_types::Timestamp { seconds, nanos })
+                .unwrap();
+            message
+        })
+        .boxed()
+}
+
+fn duration(desc: MessageDescriptor) -> BoxedStrategy<DynamicMessage> {
+    let max_seconds = MAX_DURATION_SECONDS as i64;
+    let max_nanos = MAX_DURATION_NANOS as i32;
+    (-max_seconds..=max_seconds, -max_nanos..=max_nanos)
+        .prop_map(move |(seconds, nanos)| {
+            let mut duration = prost_types::Duration { seconds, nanos };
+            duration.normalize();
+
+            let mut message = DynamicMessage::new(desc.clone());
+            message.transcode_from(&duration).unwrap();
+            message
+        })
+        .boxed()
+}
+
+fn field_mask(
+    desc: MessageDescriptor,
+    options: &ArbitraryOptions,
+) -> BoxedStrategy<DynamicMessage> {
+    // Paths must be in snake case to be representable in JSON.
+    let parts = prop::collection::vec("[a-z]{1,3}(_[a-z]{1,3}){0,2}", 1..=3);
+    prop::collection::vec(parts, 0..=options.max_collection_len)
+        .prop_map(move |paths| {
+            let paths = paths
+                .into_iter()
+                .map(|parts| {
+                    let mut parts = parts.into_iter();
+                    let mut path = parts.next().unwrap();
+                    for part in parts {
+                        write!(path, ".{}", part).unwrap();
+                    }
+                    path
+                })
+                .collect();
+
+            let mut message = DynamicMessage::new(desc.clone());
+            message
+                .transcode_from(&prost_types::FieldMask { paths })
+                .unwrap();
+            message
+        })
+        .boxed()
+}
@@ -0,0 +1,415 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use prost::Message;
+
+use crate::{
+    dynamic::{fields::ValueAndDescriptor, unknown::UnknownFieldSet},
+    DynamicMessage, MapKey, Value,
+};
+
+/// Options to control how [`CanonicalDynamicMessage`] and [`CanonicalValue`] compare and hash values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CanonicalOptions {
+    ignore_unknown_fields: bool,
+}
+
+/// A wrapper around a [`DynamicMessage`] which implements [`Eq`], [`Ord`] and [`Hash`].
+///
+/// The implementations use a canonical form of the message, so they do not depend on the iteration order
+/// of map fields. Floating point values are compared by value, with all `NaN` values considered equal to
+/// each other and greater than any other value, and `-0.0` considered equal to `0.0`.
+///
+/// Messages of different types are ordered by the full name of their type. The ordering of messages of the
+/// same type is otherwise unspecified, but is consistent with the [`Eq`] and [`Hash`] implementations.
+///
+/// Two wrappers created with different [`CanonicalOptions`] are never equal.
+#[derive(Debug, Clone)]
+pub struct CanonicalDynamicMessage {
+    message: DynamicMessage,
+    options: CanonicalOptions,
+}
+
+/// A wrapper around a [`Value`] which implements [`Eq`], [`Ord`] and [`Hash`].
+///
+/// See [`CanonicalDynamicMessage`] for details of how values are compared.
+#[derive(Debug, Clone)]
+pub struct CanonicalValue {
+    value: Value,
+    options: CanonicalOptions,
+}
+
+impl CanonicalOptions {
+    /// Creates a new instance of [`CanonicalOptions`], with the default options chosen to compare all fields of a message.
+    pub const fn new() -> Self {
+        CanonicalOptions {
+            ignore_unknown_fields: false,
+        }
+    }
+
+    /// Whether to ignore unknown fields when comparing and hashing messages.
+    ///
+    /// The default value is `false`.
+    pub const fn ignore_unknown_fields(mut self, yes: bool) -> Self {
+        self.ignore_unknown_fields = yes;
+        self
+    }
+}
+
+impl Default for CanonicalOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CanonicalDynamicMessage {
+    /// Wraps `message`, using the default [`CanonicalOptions`].
+    pub fn new(message: DynamicMessage) -> Self {
+        Self::with_options(message, CanonicalOptions::new())
+    }
+
+    /// Wraps `message`, using the given options to compare and hash it.
+    pub fn with_options(message: DynamicMessage, options: CanonicalOptions) -> Self {
+        CanonicalDynamicMessage { message, options }
+    }
+
+    /// Returns the options used to compare and hash this message.
+    pub fn options(&self) -> CanonicalOptions {
+        self.options
+    }
+
+    /// Returns the wrapped message.
+    pub fn into_inner(self) -> DynamicMessage {
+        self.message
+    }
+}
+
+impl CanonicalValue {
+    /// Wraps `value`, using the default [`CanonicalOptions`].
+    pub fn new(value: Value) -> Self {
+        Self::with_options(value, CanonicalOptions::new())
+    }
+
+    /// Wraps `value`, using the given options to compare and hash it.
+    pub fn with_options(value: Value, options: CanonicalOptions) -> Self {
+        CanonicalValue { value, options }
+    }
+
+    /// Returns the options used to compare and hash this value.
+    pub fn options(&self) -> CanonicalOptions {
+        self.options
+    }
+
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> Value {
+        self.value
+    }
+}
+
+impl Deref for CanonicalDynamicMessage {
+    type Target = DynamicMessage;
+
+    fn deref(&self) -> &DynamicMessage {
+        &self.message
+    }
+}
+
+impl Deref for CanonicalValue {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        &self.value
+    }
+}
+
+impl From<DynamicMessage> for CanonicalDynamicMessage {
+    fn from(message: DynamicMessage) -> Self {
+        CanonicalDynamicMessage::new(message)
+    }
+}
+
+impl From<Value> for CanonicalValue {
+    fn from(value: Value) -> Self {
+        CanonicalValue::new(value)
+    }
+}
+
+impl PartialEq for CanonicalDynamicMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CanonicalDynamicMessage {}
+
+impl PartialOrd for CanonicalDynamicMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CanonicalDynamicMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.options
+            .cmp(&other.options)
+            .then_with(|| cmp_message(&self.message, &other.message, &self.options))
+    }
+}
+
+impl Hash for CanonicalDynamicMessage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.options.hash(state);
+        hash_message(&self.message, &self.options, state);
+    }
+}
+
+impl PartialEq for CanonicalValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CanonicalValue {}
+
+impl PartialOrd for CanonicalValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CanonicalValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.options
+            .cmp(&other.options)
+            .then_with(|| cmp_value(&self.value, &other.value, &self.options))
+    }
+}
+
+impl Hash for CanonicalValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.options.hash(state);
+        hash_value(&self.value, &self.options, state);
+    }
+}
+
+impl DynamicMessage {
+    /// Computes a hash of this message which does not depend on the iteration order of map fields.
+    ///
+    /// Messages which are equal according to [`CanonicalDynamicMessage`] with the same options always
+    /// have the same hash. The hash is computed using [`DefaultHasher`], so it is stable within a single
+    /// build of a program, but should not be persisted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, CanonicalOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let a = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x96\x01".as_ref()).unwrap();
+    /// let b = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\xa0\x06\x05".as_ref()).unwrap();
+    ///
+    /// assert_ne!(a.canonical_hash(&CanonicalOptions::new()), b.canonical_hash(&CanonicalOptions::new()));
+    /// let options = CanonicalOptions::new().ignore_unknown_fields(true);
+    /// assert_eq!(a.canonical_hash(&options), b.canonical_hash(&options));
+    /// ```
+    pub fn canonical_hash(&self, options: &CanonicalOptions) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hash_message(self, options, &mut hasher);
+        hasher.finish()
+    }
+}
+
+/// A field of a message, in the order used for comparison and hashing.
+enum Entry<'a> {
+    Value(u32, Cow<'a, Value>),
+    Unknown(&'a UnknownFieldSet),
+}
+
+impl Entry<'_> {
+    fn number(&self) -> u32 {
+        match self {
+            Entry::Value(number, _) => *number,
+            Entry::Unknown(unknown) => unknown.iter().next().map_or(0, |field| field.number()),
+        }
+    }
+}
+
+fn entries<'a>(
+    message: &'a DynamicMessage,
+    options: &CanonicalOptions,
+) -> impl Iterator<Item = Entry<'a>> + 'a {
+    let ignore_unknown_fields = options.ignore_unknown_fields;
+    message
+        .fields
+        .iter(&message.desc, false, false)
+        .filter_map(move |field| match field {
+            ValueAndDescriptor::Field(value, desc) => Some(Entry::Value(desc.number(), value)),
+            ValueAndDescriptor::Extension(value, desc) => Some(Entry::Value(desc.number(), value)),
+            ValueAndDescriptor::Unknown(unknown) if !ignore_unknown_fields => {
+                Some(Entry::Unknown(unknown))
+            }
+            ValueAndDescriptor::Unknown(_) => None,
+        })
+}
+
+fn cmp_message(lhs: &DynamicMessage, rhs: &DynamicMessage, options: &CanonicalOptions) -> Ordering {
+    if lhs.desc != rhs.desc {
+        return lhs.desc.full_name().cmp(rhs.desc.full_name());
+    }
+
+    let mut lhs = entries(lhs, options);
+    let mut rhs = entries(rhs, options);
+    loop {
+        let (l, r) = match (lhs.next(), rhs.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => (l, r),
+        };
+
+        let ordering = l.number().cmp(&r.number()).then_with(|| match (&l, &r) {
+            (Entry::Value(_, l), Entry::Value(_, r)) => cmp_value(l, r, options),
+            (Entry::Value(..), Entry::Unknown(_)) => Ordering::Less,
+            (Entry::Unknown(_), Entry::Value(..)) => Ordering::Greater,
+            (Entry::Unknown(l), Entry::Unknown(r)) => l.encode_to_vec().cmp(&r.encode_to_vec()),
+        });
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn cmp_value(lhs: &Value, rhs: &Value, options: &CanonicalOptions) -> Ordering {
+    match (lhs, rhs) {
+        (Value::Bool(l), Value::Bool(r)) => l.cmp(r),
+        (Value::I32(l), Value::I32(r)) => l.cmp(r),
+        (Value::I64(l), Value::I64(r)) => l.cmp(r),
+        (Value::U32(l), Value::U32(r)) => l.cmp(r),
+        (Value::U64(l), Value::U64(r)) => l.cmp(r),
+        (Value::F32(l), Value::F32(r)) => canonical_f32(*l).total_cmp(&canonical_f32(*r)),
+        (Value::F64(l), Value::F64(r)) => canonical_f64(*l).total_cmp(&canonical_f64(*r)),
+        (Value::String(l), Value::String(r)) => l.cmp(r),
+        (Value::Bytes(l), Value::Bytes(r)) => l.cmp(r),
+        (Value::EnumNumber(l), Value::EnumNumber(r)) => l.cmp(r),
+        (Value::Message(l), Value::Message(r)) => cmp_message(l, r, options),
+        (Value::List(l), Value::List(r)) => {
+            for (l, r) in l.iter().zip(r) {
+                let ordering = cmp_value(l, r, options);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            l.len().cmp(&r.len())
+        }
+        (Value::Map(l), Value::Map(r)) => {
+            let l = sorted_entries(l);
+            let r = sorted_entries(r);
+            for ((lk, lv), (rk, rv)) in l.iter().zip(&r) {
+                let ordering = lk.cmp(rk).then_with(|| cmp_value(lv, rv, options));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            l.len().cmp(&r.len())
+        }
+        _ => discriminant(lhs).cmp(&discriminant(rhs)),
+    }
+}
+
+fn hash_message<H: Hasher>(message: &DynamicMessage, options: &CanonicalOptions, state: &mut H) {
+    message.desc.full_name().hash(state);
+    for entry in entries(message, options) {
+        entry.number().hash(state);
+        match entry {
+            Entry::Value(_, value) => {
+                0u8.hash(state);
+                hash_value(&value, options, state);
+            }
+            Entry::Unknown(unknown) => {
+                1u8.hash(state);
+                unknown.encode_to_vec().hash(state);
+            }
+        }
+    }
+    // Terminate the list of fields, so that a message is never a prefix of a following value.
+    u32::MAX.hash(state);
+}
+
+fn hash_value<H: Hasher>(value: &Value, options: &CanonicalOptions, state: &mut H) {
+    discriminant(value).hash(state);
+    match value {
+        Value::Bool(value) => value.hash(state),
+        Value::I32(value) => value.hash(state),
+        Value::I64(value) => value.hash(state),
+        Value::U32(value) => value.hash(state),
+        Value::U64(value) => value.hash(state),
+        Value::F32(value) => canonical_f32(*value).to_bits().hash(state),
+        Value::F64(value) => canonical_f64(*value).to_bits().hash(state),
+        Value::String(value) => value.hash(state),
+        Value::Bytes(value) => value.hash(state),
+        Value::EnumNumber(value) => value.hash(state),
+        Value::Message(value) => hash_message(value, options, state),
+        Value::List(values) => {
+            values.len().hash(state);
+            for value in values {
+                hash_value(value, options, state);
+            }
+        }
+        Value::Map(values) => {
+            values.len().hash(state);
+            for (key, value) in sorted_entries(values) {
+                key.hash(state);
+                hash_value(value, options, state);
+            }
+        }
+    }
+}
+
+fn sorted_entries(map: &HashMap<MapKey, Value>) -> Vec<(&MapKey, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    entries
+}
+
+fn discriminant(value: &Value) -> u8 {
+    match value {
+        Value::Bool(_) => 0,
+        Value::I32(_) => 1,
+        Value::I64(_) => 2,
+        Value::U32(_) => 3,
+        Value::U64(_) => 4,
+        Value::F32(_) => 5,
+        Value::F64(_) => 6,
+        Value::String(_) => 7,
+        Value::Bytes(_) => 8,
+        Value::EnumNumber(_) => 9,
+        Value::Message(_) => 10,
+        Value::List(_) => 11,
+        Value::Map(_) => 12,
+    }
+}
+
+fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::NAN
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
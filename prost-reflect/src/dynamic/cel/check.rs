@@ -0,0 +1,570 @@
+use logos::Span;
+
+use super::{
+    error::CompileErrorKind,
+    parse::{Expr, ExprKind, Literal, MacroKind},
+    CelType, Env,
+};
+
+/// Infers the type of an expression, reporting type errors and resolving references to variables,
+/// enum values, types and message types.
+pub(super) struct Checker<'a> {
+    env: &'a Env,
+    locals: Vec<(String, CelType)>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(env: &'a Env) -> Self {
+        Checker {
+            env,
+            locals: Vec::new(),
+        }
+    }
+
+    pub fn check(&mut self, expr: &mut Expr) -> Result<CelType, CompileErrorKind> {
+        if expr.qualified_name().is_some() {
+            return self.resolve_qualified_name(expr);
+        }
+
+        let span = expr.span.clone();
+        match &mut expr.kind {
+            ExprKind::Literal(literal) => Ok(match literal {
+                Literal::Null => CelType::Null,
+                Literal::Bool(_) => CelType::Bool,
+                Literal::Int(_) => CelType::Int,
+                Literal::Uint(_) => CelType::Uint,
+                Literal::Double(_) => CelType::Double,
+                Literal::String(_) => CelType::String,
+                Literal::Bytes(_) => CelType::Bytes,
+            }),
+            ExprKind::Type(_) => Ok(CelType::Type),
+            ExprKind::Ident(_) => unreachable!("identifiers are resolved as qualified names"),
+            ExprKind::Select { operand, field } => {
+                let operand_type = self.check(operand)?;
+                select_type(&operand_type, field, span)
+            }
+            ExprKind::Has { operand, field } => {
+                let operand_type = self.check(operand)?;
+                select_type(&operand_type, field, span)?;
+                Ok(CelType::Bool)
+            }
+            ExprKind::Call {
+                function,
+                target,
+                args,
+            } => {
+                let mut arg_types = Vec::with_capacity(args.len() + 1);
+                let is_method = target.is_some();
+                if let Some(target) = target {
+                    arg_types.push(self.check(target)?);
+                }
+                for arg in args.iter_mut() {
+                    arg_types.push(self.check(arg)?);
+                }
+                check_call(function, is_method, &arg_types, span)
+            }
+            ExprKind::List(elements) => {
+                let mut elem_type: Option<CelType> = None;
+                for element in elements {
+                    let ty = self.check(element)?;
+                    elem_type = Some(match elem_type {
+                        None => ty,
+                        Some(prev) => unify(&prev, &ty).unwrap_or(CelType::Dyn),
+                    });
+                }
+                Ok(CelType::List(Box::new(elem_type.unwrap_or(CelType::Dyn))))
+            }
+            ExprKind::Map(entries) => {
+                let mut key_type: Option<CelType> = None;
+                let mut value_type: Option<CelType> = None;
+                for (key, value) in entries {
+                    let ty = self.check(key)?;
+                    if !matches!(
+                        strip_wrapper(&ty),
+                        CelType::Bool
+                            | CelType::Int
+                            | CelType::Uint
+                            | CelType::String
+                            | CelType::Dyn
+                    ) {
+                        return Err(CompileErrorKind::TypeMismatch {
+                            expected: "a map key type".to_owned(),
+                            actual: ty.to_string(),
+                            span: key.span.clone(),
+                        });
+                    }
+                    key_type = Some(match key_type {
+                        None => ty,
+                        Some(prev) => unify(&prev, &ty).unwrap_or(CelType::Dyn),
+                    });
+
+                    let ty = self.check(value)?;
+                    value_type = Some(match value_type {
+                        None => ty,
+                        Some(prev) => unify(&prev, &ty).unwrap_or(CelType::Dyn),
+                    });
+                }
+                Ok(CelType::Map(
+                    Box::new(key_type.unwrap_or(CelType::Dyn)),
+                    Box::new(value_type.unwrap_or(CelType::Dyn)),
+                ))
+            }
+            ExprKind::Message {
+                type_name,
+                fields,
+                desc,
+            } => {
+                let message_desc =
+                    self.env
+                        .pool
+                        .get_message_by_name(type_name)
+                        .ok_or_else(|| CompileErrorKind::MessageNotFound {
+                            message_name: type_name.clone(),
+                            span: span.clone(),
+                        })?;
+
+                for (name, name_span, value) in fields {
+                    let field = message_desc.get_field_by_name(name).ok_or_else(|| {
+                        CompileErrorKind::UndefinedField {
+                            field_name: name.clone(),
+                            type_name: message_desc.full_name().to_owned(),
+                            span: name_span.clone(),
+                        }
+                    })?;
+                    let field_type = CelType::for_field(&field);
+                    let value_type = self.check(value)?;
+                    if !is_assignable(&field_type, &value_type)
+                        && !is_nullable(&field_type, &value_type)
+                    {
+                        return Err(CompileErrorKind::TypeMismatch {
+                            expected: field_type.to_string(),
+                            actual: value_type.to_string(),
+                            span: value.span.clone(),
+                        });
+                    }
+                }
+
+                *desc = Some(message_desc.clone());
+                Ok(CelType::from(message_desc))
+            }
+            ExprKind::Comprehension {
+                kind,
+                range,
+                var,
+                args,
+            } => {
+                let range_type = self.check(range)?;
+                let var_type = match strip_wrapper(&range_type) {
+                    CelType::List(elem) => (**elem).clone(),
+                    CelType::Map(key, _) => (**key).clone(),
+                    CelType::Dyn => CelType::Dyn,
+                    _ => {
+                        return Err(CompileErrorKind::NoMatchingOverload {
+                            function: kind.name().to_owned(),
+                            arg_types: range_type.to_string(),
+                            span,
+                        })
+                    }
+                };
+
+                self.locals.push((var.clone(), var_type.clone()));
+                let result = self.check_comprehension(*kind, var_type, args);
+                self.locals.pop();
+                result
+            }
+        }
+    }
+
+    fn check_comprehension(
+        &mut self,
+        kind: MacroKind,
+        var_type: CelType,
+        args: &mut [Expr],
+    ) -> Result<CelType, CompileErrorKind> {
+        let (predicates, transform) = match kind {
+            MacroKind::Map => {
+                let (transform, predicates) = args
+                    .split_last_mut()
+                    .expect("map macro must have arguments");
+                (predicates, Some(transform))
+            }
+            _ => (args, None),
+        };
+
+        for predicate in predicates {
+            let ty = self.check(predicate)?;
+            if !is_assignable(&CelType::Bool, &ty) {
+                return Err(CompileErrorKind::TypeMismatch {
+                    expected: CelType::Bool.to_string(),
+                    actual: ty.to_string(),
+                    span: predicate.span.clone(),
+                });
+            }
+        }
+
+        match kind {
+            MacroKind::All | MacroKind::Exists | MacroKind::ExistsOne => Ok(CelType::Bool),
+            MacroKind::Filter => Ok(CelType::List(Box::new(var_type))),
+            MacroKind::Map => {
+                let ty = self.check(transform.expect("map macro must have a transform"))?;
+                Ok(CelType::List(Box::new(ty)))
+            }
+        }
+    }
+
+    fn lookup_variable(&self, name: &str) -> Option<CelType> {
+        if let Some((_, ty)) = self.locals.iter().rev().find(|(local, _)| local == name) {
+            return Some(ty.clone());
+        }
+        self.env.variables.get(name).cloned()
+    }
+
+    /// Resolves an identifier or chain of field selections such as `a.b.c`, which may refer to a
+    /// variable, an enum value or a type.
+    ///
+    /// Local variables introduced by macros take precedence. Otherwise the longest prefix which names a
+    /// declared variable is used, and any remaining segments are treated as field selections.
+    fn resolve_qualified_name(&mut self, expr: &mut Expr) -> Result<CelType, CompileErrorKind> {
+        let name = expr.qualified_name().expect("expected qualified name");
+        let segments: Vec<&str> = name.split('.').collect();
+
+        let first_is_local = self.locals.iter().any(|(local, _)| local == segments[0]);
+        for len in (1..=segments.len()).rev() {
+            if first_is_local && len != 1 {
+                continue;
+            }
+
+            let prefix = segments[..len].join(".");
+            let depth = segments.len() - len;
+            if let Some(ty) = self.lookup_variable(&prefix) {
+                let target = nth_operand(expr, depth);
+                target.kind = ExprKind::Ident(prefix);
+                return if depth == 0 {
+                    Ok(ty)
+                } else {
+                    self.check_selects(expr, ty, depth)
+                };
+            }
+
+            if depth == 0 {
+                if let Some(value) = self.env.resolve_enum_value(&prefix) {
+                    expr.kind = ExprKind::Literal(Literal::Int(value.into()));
+                    return Ok(CelType::Int);
+                }
+                if let Some(ty) = self.env.resolve_type(&prefix) {
+                    expr.kind = ExprKind::Type(ty);
+                    return Ok(CelType::Type);
+                }
+            }
+        }
+
+        Err(CompileErrorKind::UndeclaredReference {
+            name,
+            span: expr.span.clone(),
+        })
+    }
+
+    /// Checks the `depth` field selections wrapping a resolved variable of type `ty`.
+    fn check_selects(
+        &mut self,
+        expr: &mut Expr,
+        ty: CelType,
+        depth: usize,
+    ) -> Result<CelType, CompileErrorKind> {
+        if depth == 0 {
+            return Ok(ty);
+        }
+        let span = expr.span.clone();
+        match &mut expr.kind {
+            ExprKind::Select { operand, field } => {
+                let operand_type = self.check_selects(operand, ty, depth - 1)?;
+                select_type(&operand_type, field, span)
+            }
+            _ => unreachable!("expected field selection"),
+        }
+    }
+}
+
+fn nth_operand(expr: &mut Expr, depth: usize) -> &mut Expr {
+    if depth == 0 {
+        return expr;
+    }
+    match &mut expr.kind {
+        ExprKind::Select { operand, .. } => nth_operand(operand, depth - 1),
+        _ => unreachable!("expected field selection"),
+    }
+}
+
+fn select_type(
+    operand_type: &CelType,
+    field: &str,
+    span: Span,
+) -> Result<CelType, CompileErrorKind> {
+    match strip_wrapper(operand_type) {
+        CelType::Message(desc) => match desc.get_field_by_name(field) {
+            Some(field) => Ok(CelType::for_field(&field)),
+            None => Err(CompileErrorKind::UndefinedField {
+                field_name: field.to_owned(),
+                type_name: desc.full_name().to_owned(),
+                span,
+            }),
+        },
+        CelType::Map(key, value) if matches!(**key, CelType::String | CelType::Dyn) => {
+            Ok((**value).clone())
+        }
+        CelType::Dyn => Ok(CelType::Dyn),
+        ty => Err(CompileErrorKind::UndefinedField {
+            field_name: field.to_owned(),
+            type_name: ty.to_string(),
+            span,
+        }),
+    }
+}
+
+fn check_call(
+    function: &str,
+    is_method: bool,
+    args: &[CelType],
+    span: Span,
+) -> Result<CelType, CompileErrorKind> {
+    let no_overload = || CompileErrorKind::NoMatchingOverload {
+        function: function.to_owned(),
+        arg_types: args
+            .iter()
+            .map(|ty| ty.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        span: span.clone(),
+    };
+
+    // Functions which are generic in their argument types.
+    match (function, is_method, args) {
+        ("_==_" | "_!=_", false, [lhs, rhs]) => {
+            return if is_comparable(lhs, rhs) {
+                Ok(CelType::Bool)
+            } else {
+                Err(no_overload())
+            };
+        }
+        ("_?_:_", false, [condition, then, otherwise]) => {
+            if !is_assignable(&CelType::Bool, condition) {
+                return Err(no_overload());
+            }
+            return unify(then, otherwise).ok_or_else(no_overload);
+        }
+        ("@in", false, [elem, container]) => {
+            return match strip_wrapper(container) {
+                CelType::List(ty) | CelType::Map(ty, _) if is_comparable(elem, ty) => {
+                    Ok(CelType::Bool)
+                }
+                CelType::Dyn => Ok(CelType::Bool),
+                _ => Err(no_overload()),
+            };
+        }
+        ("_[_]", false, [container, index]) => {
+            return match strip_wrapper(container) {
+                CelType::List(elem)
+                    if matches!(
+                        strip_wrapper(index),
+                        CelType::Int | CelType::Uint | CelType::Dyn
+                    ) =>
+                {
+                    Ok((**elem).clone())
+                }
+                CelType::Map(key, value) if is_comparable(key, index) => Ok((**value).clone()),
+                CelType::Dyn => Ok(CelType::Dyn),
+                _ => Err(no_overload()),
+            };
+        }
+        ("_+_", false, [lhs, rhs]) => {
+            if let (CelType::List(lhs), CelType::List(rhs)) =
+                (strip_wrapper(lhs), strip_wrapper(rhs))
+            {
+                return Ok(CelType::List(Box::new(
+                    unify(lhs, rhs).unwrap_or(CelType::Dyn),
+                )));
+            }
+        }
+        ("size", _, [arg]) => {
+            if let CelType::List(_) | CelType::Map(_, _) = strip_wrapper(arg) {
+                return Ok(CelType::Int);
+            }
+        }
+        ("type", false, [_]) => return Ok(CelType::Type),
+        ("dyn", false, [_]) => return Ok(CelType::Dyn),
+        _ => (),
+    }
+
+    let overloads = overloads(function, is_method).ok_or_else(no_overload)?;
+    let mut result: Option<CelType> = None;
+    for (params, ret) in overloads {
+        if params.len() == args.len()
+            && params
+                .iter()
+                .zip(args)
+                .all(|(param, arg)| is_assignable(param, strip_wrapper(arg)))
+        {
+            result = match result {
+                None => Some(ret),
+                Some(prev) if prev == ret => Some(prev),
+                Some(_) => Some(CelType::Dyn),
+            };
+        }
+    }
+    result.ok_or_else(no_overload)
+}
+
+/// Returns the overloads of a function with non-generic types, as a list of parameter types and return
+/// types. For methods, the first parameter is the type of the target.
+fn overloads(function: &str, is_method: bool) -> Option<Vec<(Vec<CelType>, CelType)>> {
+    use CelType::*;
+
+    let numeric = |ret: fn(CelType) -> CelType| {
+        vec![
+            (vec![Int, Int], ret(Int)),
+            (vec![Uint, Uint], ret(Uint)),
+            (vec![Double, Double], ret(Double)),
+        ]
+    };
+    let conversion = |params: Vec<CelType>, ret: CelType| {
+        params
+            .into_iter()
+            .map(|param| (vec![param], ret.clone()))
+            .collect()
+    };
+
+    let overloads = match (function, is_method) {
+        ("!_", false) => vec![(vec![Bool], Bool)],
+        ("-_", false) => vec![(vec![Int], Int), (vec![Double], Double)],
+        ("_&&_" | "_||_", false) => vec![(vec![Bool, Bool], Bool)],
+        ("_<_" | "_<=_" | "_>_" | "_>=_", false) => {
+            let mut overloads = Vec::new();
+            for lhs in [Int, Uint, Double] {
+                for rhs in [Int, Uint, Double] {
+                    overloads.push((vec![lhs.clone(), rhs], Bool));
+                }
+            }
+            for ty in [Bool, String, Bytes, Timestamp, Duration] {
+                overloads.push((vec![ty.clone(), ty], Bool));
+            }
+            overloads
+        }
+        ("_+_", false) => {
+            let mut overloads = numeric(|ty| ty);
+            overloads.extend([
+                (vec![String, String], String),
+                (vec![Bytes, Bytes], Bytes),
+                (vec![Timestamp, Duration], Timestamp),
+                (vec![Duration, Timestamp], Timestamp),
+                (vec![Duration, Duration], Duration),
+            ]);
+            overloads
+        }
+        ("_-_", false) => {
+            let mut overloads = numeric(|ty| ty);
+            overloads.extend([
+                (vec![Timestamp, Timestamp], Duration),
+                (vec![Timestamp, Duration], Timestamp),
+                (vec![Duration, Duration], Duration),
+            ]);
+            overloads
+        }
+        ("_*_" | "_/_", false) => numeric(|ty| ty),
+        ("_%_", false) => vec![(vec![Int, Int], Int), (vec![Uint, Uint], Uint)],
+        ("size", _) => vec![(vec![String], Int), (vec![Bytes], Int)],
+        ("contains" | "startsWith" | "endsWith" | "matches", true) | ("matches", false) => {
+            vec![(vec![String, String], Bool)]
+        }
+        ("int", false) => conversion(vec![Int, Uint, Double, String, Timestamp], Int),
+        ("uint", false) => conversion(vec![Int, Uint, Double, String], Uint),
+        ("double", false) => conversion(vec![Int, Uint, Double, String], Double),
+        ("string", false) => conversion(
+            vec![Int, Uint, Double, String, Bytes, Bool, Timestamp, Duration],
+            String,
+        ),
+        ("bytes", false) => conversion(vec![String, Bytes], Bytes),
+        ("bool", false) => conversion(vec![Bool, String], Bool),
+        ("timestamp", false) => conversion(vec![String, Int, Timestamp], Timestamp),
+        ("duration", false) => conversion(vec![String, Duration], Duration),
+        (
+            "getFullYear" | "getMonth" | "getDayOfYear" | "getDayOfMonth" | "getDate"
+            | "getDayOfWeek",
+            true,
+        ) => vec![(vec![Timestamp], Int), (vec![Timestamp, String], Int)],
+        ("getHours" | "getMinutes" | "getSeconds" | "getMilliseconds", true) => vec![
+            (vec![Timestamp], Int),
+            (vec![Timestamp, String], Int),
+            (vec![Duration], Int),
+        ],
+        _ => return None,
+    };
+    Some(overloads)
+}
+
+/// Returns `true` if a value of type `from` may be used where a value of type `to` is expected.
+pub(super) fn is_assignable(to: &CelType, from: &CelType) -> bool {
+    match (to, from) {
+        (CelType::Dyn, _) | (_, CelType::Dyn) => true,
+        (CelType::Wrapper(_), CelType::Null) => true,
+        (CelType::Wrapper(to), from) => is_assignable(to, from),
+        (to, CelType::Wrapper(from)) => is_assignable(to, from),
+        (CelType::List(to), CelType::List(from)) => is_assignable(to, from),
+        (CelType::Map(to_key, to_value), CelType::Map(from_key, from_value)) => {
+            is_assignable(to_key, from_key) && is_assignable(to_value, from_value)
+        }
+        (to, from) => to == from,
+    }
+}
+
+/// Returns `true` if `null` may be assigned to a field of type `to`.
+fn is_nullable(to: &CelType, from: &CelType) -> bool {
+    *from == CelType::Null
+        && matches!(
+            to,
+            CelType::Message(_) | CelType::Timestamp | CelType::Duration | CelType::Wrapper(_)
+        )
+}
+
+/// Returns `true` if values of the given types may be compared for equality.
+fn is_comparable(lhs: &CelType, rhs: &CelType) -> bool {
+    let is_numeric = |ty: &CelType| {
+        matches!(
+            strip_wrapper(ty),
+            CelType::Int | CelType::Uint | CelType::Double
+        )
+    };
+    is_assignable(lhs, rhs)
+        || is_assignable(rhs, lhs)
+        || (is_numeric(lhs) && is_numeric(rhs))
+        || is_nullable(lhs, rhs)
+        || is_nullable(rhs, lhs)
+}
+
+/// Returns the common type of two branches of an expression, if they are compatible.
+fn unify(lhs: &CelType, rhs: &CelType) -> Option<CelType> {
+    match (lhs, rhs) {
+        _ if lhs == rhs => Some(lhs.clone()),
+        (CelType::Dyn, _) | (_, CelType::Dyn) => Some(CelType::Dyn),
+        (CelType::Null, ty) | (ty, CelType::Null) if is_nullable(ty, &CelType::Null) => {
+            Some(ty.clone())
+        }
+        (CelType::Wrapper(inner), ty) | (ty, CelType::Wrapper(inner)) if **inner == *ty => {
+            Some(CelType::Wrapper(inner.clone()))
+        }
+        (CelType::List(lhs), CelType::List(rhs)) => Some(CelType::List(Box::new(
+            unify(lhs, rhs).unwrap_or(CelType::Dyn),
+        ))),
+        (CelType::Map(lhs_key, lhs_value), CelType::Map(rhs_key, rhs_value)) => Some(CelType::Map(
+            Box::new(unify(lhs_key, rhs_key).unwrap_or(CelType::Dyn)),
+            Box::new(unify(lhs_value, rhs_value).unwrap_or(CelType::Dyn)),
+        )),
+        _ => None,
+    }
+}
+
+fn strip_wrapper(ty: &CelType) -> &CelType {
+    match ty {
+        CelType::Wrapper(inner) => inner,
+        ty => ty,
+    }
+}
@@ -0,0 +1,272 @@
+use logos::Span;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// An error that may occur while parsing or type-checking a CEL expression.
+#[derive(Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "cel")))]
+pub struct CompileError {
+    kind: Box<CompileErrorKind>,
+    #[cfg(feature = "miette")]
+    source: String,
+}
+
+impl CompileError {
+    #[cfg(feature = "miette")]
+    pub(super) fn new(kind: CompileErrorKind, source: &str) -> Self {
+        CompileError {
+            kind: Box::new(kind),
+            source: source.to_owned(),
+        }
+    }
+
+    #[cfg(not(feature = "miette"))]
+    pub(super) fn new(kind: CompileErrorKind, _: &str) -> Self {
+        CompileError {
+            kind: Box::new(kind),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "miette", derive(miette::Diagnostic))]
+pub(super) enum CompileErrorKind {
+    InvalidToken {
+        #[cfg_attr(feature = "miette", label("found here"))]
+        span: Span,
+    },
+    InvalidStringEscape {
+        #[cfg_attr(feature = "miette", label("defined here"))]
+        span: Span,
+    },
+    UnterminatedString {
+        #[cfg_attr(feature = "miette", label("starts here"))]
+        span: Span,
+    },
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        #[cfg_attr(feature = "miette", label("found here"))]
+        span: Span,
+    },
+    UnexpectedEof {
+        expected: String,
+    },
+    IntegerValueOutOfRange {
+        #[cfg_attr(feature = "miette", label("defined here"))]
+        span: Span,
+    },
+    NestingTooDeep {
+        #[cfg_attr(feature = "miette", label("found here"))]
+        span: Span,
+    },
+    InvalidMacroArgument {
+        macro_name: String,
+        #[cfg_attr(feature = "miette", label("used here"))]
+        span: Span,
+    },
+    UndeclaredReference {
+        name: String,
+        #[cfg_attr(feature = "miette", label("used here"))]
+        span: Span,
+    },
+    UndefinedField {
+        field_name: String,
+        type_name: String,
+        #[cfg_attr(feature = "miette", label("used here"))]
+        span: Span,
+    },
+    MessageNotFound {
+        message_name: String,
+        #[cfg_attr(feature = "miette", label("used here"))]
+        span: Span,
+    },
+    NoMatchingOverload {
+        function: String,
+        arg_types: String,
+        #[cfg_attr(feature = "miette", label("called here"))]
+        span: Span,
+    },
+    TypeMismatch {
+        expected: String,
+        actual: String,
+        #[cfg_attr(feature = "miette", label("defined here"))]
+        span: Span,
+    },
+}
+
+/// An error that may occur while evaluating a CEL expression.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "cel")))]
+pub enum EvalError {
+    /// A variable was declared in the environment, but not bound in the [`Activation`](super::Activation).
+    UnboundVariable {
+        /// The name of the variable.
+        name: String,
+    },
+    /// A function or operator was applied to values of types it does not support.
+    ///
+    /// This may occur at runtime for values of type `dyn`, such as the fields of a `google.protobuf.Struct`.
+    NoMatchingOverload {
+        /// The name of the function or operator.
+        function: String,
+    },
+    /// A field was selected which does not exist in the message.
+    NoSuchField {
+        /// The name of the field.
+        field_name: String,
+    },
+    /// A map was indexed with a key it does not contain.
+    NoSuchKey {
+        /// The key, formatted as a CEL literal.
+        key: String,
+    },
+    /// A list was indexed outside of its bounds.
+    IndexOutOfRange {
+        /// The index.
+        index: i64,
+        /// The length of the list.
+        len: usize,
+    },
+    /// An integer was divided by zero.
+    DivisionByZero,
+    /// The result of an arithmetic operation or conversion is out of range for its type.
+    Overflow {
+        /// The name of the function or operator.
+        function: String,
+    },
+    /// A function was passed an invalid value, such as a malformed timestamp string or regular expression.
+    InvalidArgument {
+        /// The name of the function.
+        function: String,
+        /// A description of the problem.
+        message: String,
+    },
+}
+
+impl Display for CompileErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileErrorKind::InvalidToken { .. } => write!(f, "invalid token"),
+            CompileErrorKind::InvalidStringEscape { .. } => write!(f, "invalid string escape"),
+            CompileErrorKind::UnterminatedString { .. } => write!(f, "unterminated string"),
+            CompileErrorKind::UnexpectedToken {
+                expected, found, ..
+            } => write!(f, "expected {}, but found '{}'", expected, found),
+            CompileErrorKind::UnexpectedEof { expected } => {
+                write!(f, "expected {}, but reached end of input", expected)
+            }
+            CompileErrorKind::IntegerValueOutOfRange { .. } => {
+                write!(f, "integer literal is out of range")
+            }
+            CompileErrorKind::NestingTooDeep { .. } => {
+                write!(f, "expression is nested too deeply")
+            }
+            CompileErrorKind::InvalidMacroArgument { macro_name, .. } => {
+                write!(f, "invalid argument to '{}' macro", macro_name)
+            }
+            CompileErrorKind::UndeclaredReference { name, .. } => {
+                write!(f, "undeclared reference to '{}'", name)
+            }
+            CompileErrorKind::UndefinedField {
+                field_name,
+                type_name,
+                ..
+            } => write!(
+                f,
+                "undefined field '{}' for type '{}'",
+                field_name, type_name
+            ),
+            CompileErrorKind::MessageNotFound { message_name, .. } => {
+                write!(f, "message type '{}' not found", message_name)
+            }
+            CompileErrorKind::NoMatchingOverload {
+                function,
+                arg_types,
+                ..
+            } => write!(
+                f,
+                "no matching overload for '{}' applied to ({})",
+                function, arg_types
+            ),
+            CompileErrorKind::TypeMismatch {
+                expected, actual, ..
+            } => write!(f, "expected type '{}', but found '{}'", expected, actual),
+        }
+    }
+}
+
+impl Error for CompileErrorKind {}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl Error for CompileError {}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+impl miette::Diagnostic for CompileError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.kind.code()
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.kind.severity()
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.kind.help()
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.kind.url()
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        self.kind.labels()
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn miette::Diagnostic> + 'a>> {
+        self.kind.related()
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn miette::Diagnostic> {
+        self.kind.diagnostic_source()
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::UnboundVariable { name } => write!(f, "variable '{}' is not bound", name),
+            EvalError::NoMatchingOverload { function } => {
+                write!(f, "no matching overload for '{}'", function)
+            }
+            EvalError::NoSuchField { field_name } => write!(f, "no such field '{}'", field_name),
+            EvalError::NoSuchKey { key } => write!(f, "no such key: {}", key),
+            EvalError::IndexOutOfRange { index, len } => write!(
+                f,
+                "index {} is out of range for list of length {}",
+                index, len
+            ),
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::Overflow { function } => {
+                write!(f, "result of '{}' is out of range", function)
+            }
+            EvalError::InvalidArgument { function, message } => {
+                write!(f, "invalid argument to '{}': {}", function, message)
+            }
+        }
+    }
+}
+
+impl Error for EvalError {}
@@ -0,0 +1,704 @@
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
+
+use prost::bytes::Bytes;
+use regex::Regex;
+
+use super::{
+    error::EvalError,
+    parse::{Expr, ExprKind, FieldInit, Literal, MacroKind},
+    time,
+    value::{format_duration, to_entry_key, to_map_key},
+    Activation, CelType, CelValue,
+};
+use crate::{DynamicMessage, MapKey, MessageDescriptor, ReflectMessage, Value};
+
+/// The result of evaluating a variable or a field selection.
+///
+/// Values are borrowed from the activation where possible, so that selecting a field only converts the
+/// selected value rather than copying each message it is selected from.
+enum Operand<'a> {
+    Value(Cow<'a, CelValue>),
+    Message(&'a DynamicMessage),
+}
+
+pub(super) struct Evaluator<'a> {
+    activation: &'a Activation,
+    locals: Vec<(&'a str, CelValue)>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(activation: &'a Activation) -> Self {
+        Evaluator {
+            activation,
+            locals: Vec::new(),
+        }
+    }
+
+    pub fn eval(&mut self, expr: &'a Expr) -> Result<CelValue, EvalError> {
+        match &expr.kind {
+            ExprKind::Literal(literal) => Ok(match literal {
+                Literal::Null => CelValue::Null,
+                Literal::Bool(value) => CelValue::Bool(*value),
+                Literal::Int(value) => CelValue::Int(*value),
+                Literal::Uint(value) => CelValue::Uint(*value),
+                Literal::Double(value) => CelValue::Double(*value),
+                Literal::String(value) => CelValue::String(value.clone()),
+                Literal::Bytes(value) => CelValue::Bytes(Bytes::copy_from_slice(value)),
+            }),
+            ExprKind::Type(ty) => Ok(CelValue::Type(ty.clone())),
+            ExprKind::Ident(_) | ExprKind::Select { .. } => {
+                self.eval_operand(expr).map(Operand::into_value)
+            }
+            ExprKind::Has { operand, field } => match self.eval_operand(operand)? {
+                Operand::Message(message) => has_field(message, field),
+                Operand::Value(value) => match &*value {
+                    CelValue::Message(message) => has_field(message, field),
+                    CelValue::Map(map) => Ok(CelValue::Bool(
+                        map.contains_key(&MapKey::String(field.clone())),
+                    )),
+                    _ => Err(no_overload("has")),
+                },
+            },
+            ExprKind::Call {
+                function,
+                target,
+                args,
+            } => match (function.as_str(), args.as_slice()) {
+                ("_&&_", [lhs, rhs]) => self.eval_logical(function, lhs, rhs, false),
+                ("_||_", [lhs, rhs]) => self.eval_logical(function, lhs, rhs, true),
+                ("_?_:_", [condition, then, otherwise]) => match self.eval(condition)? {
+                    CelValue::Bool(true) => self.eval(then),
+                    CelValue::Bool(false) => self.eval(otherwise),
+                    _ => Err(no_overload(function)),
+                },
+                _ => {
+                    let mut values = Vec::with_capacity(args.len() + 1);
+                    if let Some(target) = target {
+                        values.push(self.eval(target)?);
+                    }
+                    for arg in args {
+                        values.push(self.eval(arg)?);
+                    }
+                    call(function, values)
+                }
+            },
+            ExprKind::List(elements) => Ok(CelValue::List(
+                elements
+                    .iter()
+                    .map(|element| self.eval(element))
+                    .collect::<Result<_, _>>()?,
+            )),
+            ExprKind::Map(entries) => {
+                let mut map = HashMap::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let key = self.eval(key)?;
+                    let key = to_map_key(&key).ok_or_else(|| no_overload("{_:_}"))?;
+                    let value = self.eval(value)?;
+                    map.insert(key, value);
+                }
+                Ok(CelValue::Map(map))
+            }
+            ExprKind::Message { fields, desc, .. } => {
+                let desc = desc
+                    .as_ref()
+                    .expect("message type should be resolved by checker");
+                self.eval_message(desc, fields)
+            }
+            ExprKind::Comprehension {
+                kind,
+                range,
+                var,
+                args,
+            } => {
+                let items = match self.eval(range)? {
+                    CelValue::List(items) => items,
+                    CelValue::Map(map) => {
+                        let mut keys: Vec<MapKey> = map.into_keys().collect();
+                        keys.sort();
+                        keys.into_iter()
+                            .map(|key| CelValue::from(Value::from(key)))
+                            .collect()
+                    }
+                    _ => return Err(no_overload(kind.name())),
+                };
+                self.eval_comprehension(*kind, items, var, args)
+            }
+        }
+    }
+
+    /// Evaluates a variable or field selection, borrowing from the activation where possible.
+    fn eval_operand(&mut self, expr: &'a Expr) -> Result<Operand<'a>, EvalError> {
+        match &expr.kind {
+            ExprKind::Ident(name) => self.lookup(name),
+            ExprKind::Select { operand, field } => {
+                let operand = self.eval_operand(operand)?;
+                select(operand, field)
+            }
+            _ => self
+                .eval(expr)
+                .map(|value| Operand::Value(Cow::Owned(value))),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<Operand<'a>, EvalError> {
+        if let Some((_, value)) = self.locals.iter().rev().find(|(local, _)| *local == name) {
+            return Ok(Operand::Value(Cow::Owned(value.clone())));
+        }
+        let activation: &'a Activation = self.activation;
+        activation
+            .variables
+            .get(name)
+            .map(|value| Operand::Value(Cow::Borrowed(value)))
+            .ok_or_else(|| EvalError::UnboundVariable {
+                name: name.to_owned(),
+            })
+    }
+
+    /// Evaluates `&&` or `||`. As in CEL, the result is determined by either argument if possible, even if
+    /// the other argument is an error.
+    fn eval_logical(
+        &mut self,
+        function: &str,
+        lhs: &'a Expr,
+        rhs: &'a Expr,
+        short_circuit: bool,
+    ) -> Result<CelValue, EvalError> {
+        let lhs = match self.eval(lhs) {
+            Ok(CelValue::Bool(value)) if value == short_circuit => {
+                return Ok(CelValue::Bool(value))
+            }
+            Ok(CelValue::Bool(_)) => Ok(()),
+            Ok(_) => Err(no_overload(function)),
+            Err(err) => Err(err),
+        };
+        match self.eval(rhs) {
+            Ok(CelValue::Bool(value)) if value == short_circuit => Ok(CelValue::Bool(value)),
+            Ok(CelValue::Bool(value)) => lhs.map(|()| CelValue::Bool(value)),
+            Ok(_) => lhs.and(Err(no_overload(function))),
+            Err(err) => lhs.and(Err(err)),
+        }
+    }
+
+    fn eval_message(
+        &mut self,
+        desc: &MessageDescriptor,
+        fields: &'a [FieldInit],
+    ) -> Result<CelValue, EvalError> {
+        let mut message = DynamicMessage::new(desc.clone());
+        for (name, _, expr) in fields {
+            let field = desc
+                .get_field_by_name(name)
+                .ok_or_else(|| EvalError::NoSuchField {
+                    field_name: name.clone(),
+                })?;
+            let value = self.eval(expr)?;
+            if value == CelValue::Null && field.kind().as_message().is_some() {
+                continue;
+            }
+
+            let invalid_value = || EvalError::InvalidArgument {
+                function: desc.full_name().to_owned(),
+                message: format!("invalid value for field '{}'", name),
+            };
+            let kind = field.kind();
+            let value = if field.is_map() {
+                let entry = kind.as_message().expect("map entry must be a message");
+                let key_kind = entry.map_entry_key_field().kind();
+                let value_kind = entry.map_entry_value_field().kind();
+                match value {
+                    CelValue::Map(map) => Value::Map(
+                        map.into_iter()
+                            .map(|(key, value)| {
+                                Some((
+                                    to_entry_key(key, &key_kind)?,
+                                    value.into_value(&value_kind)?,
+                                ))
+                            })
+                            .collect::<Option<_>>()
+                            .ok_or_else(invalid_value)?,
+                    ),
+                    _ => return Err(invalid_value()),
+                }
+            } else if field.is_list() {
+                match value {
+                    CelValue::List(values) => Value::List(
+                        values
+                            .into_iter()
+                            .map(|value| value.into_value(&kind))
+                            .collect::<Option<_>>()
+                            .ok_or_else(invalid_value)?,
+                    ),
+                    _ => return Err(invalid_value()),
+                }
+            } else {
+                value.into_value(&kind).ok_or_else(invalid_value)?
+            };
+            message.set_field(&field, value);
+        }
+        Ok(CelValue::from_message(&message))
+    }
+
+    fn eval_comprehension(
+        &mut self,
+        kind: MacroKind,
+        items: Vec<CelValue>,
+        var: &'a str,
+        args: &'a [Expr],
+    ) -> Result<CelValue, EvalError> {
+        let function = kind.name();
+        let mut result = Vec::new();
+        let mut count = 0;
+        let mut error = None;
+
+        for item in items {
+            self.locals.push((var, item.clone()));
+            let value = match (kind, args) {
+                (MacroKind::Map, [transform]) => self.eval(transform).map(Some),
+                (MacroKind::Map, [filter, transform]) => match self.eval(filter) {
+                    Ok(CelValue::Bool(true)) => self.eval(transform).map(Some),
+                    Ok(CelValue::Bool(false)) => Ok(None),
+                    Ok(_) => Err(no_overload(function)),
+                    Err(err) => Err(err),
+                },
+                (_, [predicate]) => self.eval(predicate).map(Some),
+                _ => unreachable!("invalid macro arguments"),
+            };
+            self.locals.pop();
+
+            match (kind, value) {
+                (MacroKind::Map, Ok(Some(value))) => result.push(value),
+                (MacroKind::Map, Ok(None)) => (),
+                (MacroKind::Filter, Ok(Some(CelValue::Bool(true)))) => result.push(item),
+                (MacroKind::Filter, Ok(Some(CelValue::Bool(false)))) => (),
+                (MacroKind::All, Ok(Some(CelValue::Bool(false)))) => {
+                    return Ok(CelValue::Bool(false))
+                }
+                (MacroKind::Exists, Ok(Some(CelValue::Bool(true)))) => {
+                    return Ok(CelValue::Bool(true))
+                }
+                (MacroKind::All | MacroKind::Exists, Ok(Some(CelValue::Bool(_)))) => (),
+                (MacroKind::ExistsOne, Ok(Some(CelValue::Bool(value)))) => {
+                    count += usize::from(value)
+                }
+                (MacroKind::All | MacroKind::Exists, Err(err)) => {
+                    // Errors are ignored if another element determines the result.
+                    error.get_or_insert(err);
+                }
+                (_, Err(err)) => return Err(err),
+                (_, Ok(_)) => return Err(no_overload(function)),
+            }
+        }
+
+        if let Some(err) = error {
+            return Err(err);
+        }
+        Ok(match kind {
+            MacroKind::All => CelValue::Bool(true),
+            MacroKind::Exists => CelValue::Bool(false),
+            MacroKind::ExistsOne => CelValue::Bool(count == 1),
+            MacroKind::Map | MacroKind::Filter => CelValue::List(result),
+        })
+    }
+}
+
+impl Operand<'_> {
+    fn into_value(self) -> CelValue {
+        match self {
+            Operand::Value(value) => value.into_owned(),
+            Operand::Message(message) => CelValue::from_message(message),
+        }
+    }
+}
+
+fn select<'a>(operand: Operand<'a>, field: &str) -> Result<Operand<'a>, EvalError> {
+    match operand {
+        Operand::Message(message) | Operand::Value(Cow::Borrowed(CelValue::Message(message))) => {
+            select_field(message, field)
+        }
+        Operand::Value(Cow::Owned(CelValue::Message(message))) => select_field(&message, field)
+            .map(|value| Operand::Value(Cow::Owned(value.into_value()))),
+        Operand::Value(Cow::Borrowed(CelValue::Map(map))) => map
+            .get(&MapKey::String(field.to_owned()))
+            .map(|value| Operand::Value(Cow::Borrowed(value)))
+            .ok_or_else(|| no_such_key(field)),
+        Operand::Value(Cow::Owned(CelValue::Map(mut map))) => map
+            .remove(&MapKey::String(field.to_owned()))
+            .map(|value| Operand::Value(Cow::Owned(value)))
+            .ok_or_else(|| no_such_key(field)),
+        _ => Err(no_overload("_._")),
+    }
+}
+
+/// Selects a field of a message, borrowing the field's value if it is a message which does not need
+/// to be converted to another CEL type.
+fn select_field<'a>(message: &'a DynamicMessage, name: &str) -> Result<Operand<'a>, EvalError> {
+    let field = message
+        .descriptor()
+        .get_field_by_name(name)
+        .ok_or_else(|| EvalError::NoSuchField {
+            field_name: name.to_owned(),
+        })?;
+    if let Cow::Borrowed(Value::Message(nested)) = message.get_field(&field) {
+        if matches!(CelType::from(nested.descriptor()), CelType::Message(_)) {
+            return Ok(Operand::Message(nested));
+        }
+    }
+    Ok(Operand::Value(Cow::Owned(CelValue::from_field(
+        message, &field,
+    ))))
+}
+
+fn has_field(message: &DynamicMessage, name: &str) -> Result<CelValue, EvalError> {
+    match message.descriptor().get_field_by_name(name) {
+        Some(field) => Ok(CelValue::Bool(message.has_field(&field))),
+        None => Err(EvalError::NoSuchField {
+            field_name: name.to_owned(),
+        }),
+    }
+}
+
+fn no_such_key(field: &str) -> EvalError {
+    EvalError::NoSuchKey {
+        key: CelValue::String(field.to_owned()).to_string(),
+    }
+}
+
+fn call(function: &str, args: Vec<CelValue>) -> Result<CelValue, EvalError> {
+    use CelValue::*;
+
+    let bool_result = |result: Option<bool>| Ok(Bool(result.unwrap_or(false)));
+
+    match (function, args.as_slice()) {
+        ("!_", [Bool(value)]) => Ok(Bool(!value)),
+        ("-_", [Int(value)]) => value
+            .checked_neg()
+            .map(Int)
+            .ok_or_else(|| overflow(function)),
+        ("-_", [Double(value)]) => Ok(Double(-value)),
+        ("_==_", [lhs, rhs]) => Ok(Bool(equals(lhs, rhs))),
+        ("_!=_", [lhs, rhs]) => Ok(Bool(!equals(lhs, rhs))),
+        ("_<_", [lhs, rhs]) => bool_result(compare(function, lhs, rhs)?.map(Ordering::is_lt)),
+        ("_<=_", [lhs, rhs]) => bool_result(compare(function, lhs, rhs)?.map(Ordering::is_le)),
+        ("_>_", [lhs, rhs]) => bool_result(compare(function, lhs, rhs)?.map(Ordering::is_gt)),
+        ("_>=_", [lhs, rhs]) => bool_result(compare(function, lhs, rhs)?.map(Ordering::is_ge)),
+        ("@in", [elem, List(list)]) => Ok(Bool(list.iter().any(|item| equals(elem, item)))),
+        ("@in", [key, Map(map)]) => Ok(Bool(map_get(map, key).is_some())),
+        ("_[_]", [List(list), index]) => {
+            let index = match *index {
+                Int(index) => index,
+                Uint(index) => i64::try_from(index).unwrap_or(i64::MAX),
+                Double(index) if index.fract() == 0.0 => index as i64,
+                _ => return Err(no_overload(function)),
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|i| list.get(i))
+                .cloned()
+                .ok_or(EvalError::IndexOutOfRange {
+                    index,
+                    len: list.len(),
+                })
+        }
+        ("_[_]", [Map(map), key]) => {
+            map_get(map, key)
+                .cloned()
+                .ok_or_else(|| EvalError::NoSuchKey {
+                    key: key.to_string(),
+                })
+        }
+        ("_+_" | "_-_" | "_*_" | "_/_" | "_%_", [lhs, rhs]) => arithmetic(function, lhs, rhs),
+        ("size", [String(value)]) => Ok(Int(len(value.chars().count()))),
+        ("size", [Bytes(value)]) => Ok(Int(len(value.len()))),
+        ("size", [List(value)]) => Ok(Int(len(value.len()))),
+        ("size", [Map(value)]) => Ok(Int(len(value.len()))),
+        ("contains", [String(value), String(substring)]) => {
+            Ok(Bool(value.contains(substring.as_str())))
+        }
+        ("startsWith", [String(value), String(prefix)]) => {
+            Ok(Bool(value.starts_with(prefix.as_str())))
+        }
+        ("endsWith", [String(value), String(suffix)]) => Ok(Bool(value.ends_with(suffix.as_str()))),
+        ("matches", [String(value), String(pattern)]) => {
+            let regex = Regex::new(pattern).map_err(|err| invalid_argument(function, err))?;
+            Ok(Bool(regex.is_match(value)))
+        }
+        (
+            "int" | "uint" | "double" | "string" | "bytes" | "bool" | "timestamp" | "duration",
+            [value],
+        ) => convert(function, value),
+        ("type", [value]) => Ok(Type(value.cel_type())),
+        ("dyn", [value]) => Ok(value.clone()),
+        (_, [Timestamp(value), rest @ ..]) if rest.len() <= 1 => {
+            let offset = match rest {
+                [String(tz)] => time::parse_offset(tz).ok_or_else(|| {
+                    invalid_argument(function, format!("unsupported time zone '{}'", tz))
+                })?,
+                [] => 0,
+                _ => return Err(no_overload(function)),
+            };
+            time::timestamp_component(function, value, offset)?.ok_or_else(|| no_overload(function))
+        }
+        (_, [Duration(value)]) => {
+            time::duration_component(function, value).ok_or_else(|| no_overload(function))
+        }
+        _ => Err(no_overload(function)),
+    }
+}
+
+fn arithmetic(function: &str, lhs: &CelValue, rhs: &CelValue) -> Result<CelValue, EvalError> {
+    use CelValue::*;
+
+    let int_op = |lhs: i64, rhs: i64| -> Result<i64, EvalError> {
+        let result = match function {
+            "_+_" => lhs.checked_add(rhs),
+            "_-_" => lhs.checked_sub(rhs),
+            "_*_" => lhs.checked_mul(rhs),
+            "_/_" if rhs == 0 => return Err(EvalError::DivisionByZero),
+            "_/_" => lhs.checked_div(rhs),
+            "_%_" if rhs == 0 => return Err(EvalError::DivisionByZero),
+            "_%_" => lhs.checked_rem(rhs),
+            _ => unreachable!(),
+        };
+        result.ok_or_else(|| overflow(function))
+    };
+    let uint_op = |lhs: u64, rhs: u64| -> Result<u64, EvalError> {
+        let result = match function {
+            "_+_" => lhs.checked_add(rhs),
+            "_-_" => lhs.checked_sub(rhs),
+            "_*_" => lhs.checked_mul(rhs),
+            "_/_" | "_%_" if rhs == 0 => return Err(EvalError::DivisionByZero),
+            "_/_" => lhs.checked_div(rhs),
+            "_%_" => lhs.checked_rem(rhs),
+            _ => unreachable!(),
+        };
+        result.ok_or_else(|| overflow(function))
+    };
+
+    match (function, lhs, rhs) {
+        (_, Int(lhs), Int(rhs)) => int_op(*lhs, *rhs).map(Int),
+        (_, Uint(lhs), Uint(rhs)) => uint_op(*lhs, *rhs).map(Uint),
+        ("_+_", Double(lhs), Double(rhs)) => Ok(Double(lhs + rhs)),
+        ("_-_", Double(lhs), Double(rhs)) => Ok(Double(lhs - rhs)),
+        ("_*_", Double(lhs), Double(rhs)) => Ok(Double(lhs * rhs)),
+        ("_/_", Double(lhs), Double(rhs)) => Ok(Double(lhs / rhs)),
+        ("_+_", String(lhs), String(rhs)) => Ok(String(format!("{}{}", lhs, rhs))),
+        ("_+_", Bytes(lhs), Bytes(rhs)) => Ok(Bytes([lhs.as_ref(), rhs.as_ref()].concat().into())),
+        ("_+_", List(lhs), List(rhs)) => Ok(List(lhs.iter().chain(rhs).cloned().collect())),
+        ("_+_", Timestamp(ts), Duration(d)) | ("_+_", Duration(d), Timestamp(ts)) => {
+            time::timestamp_from_nanos(time::timestamp_nanos(ts) + time::duration_nanos(d))
+                .map(Timestamp)
+                .ok_or_else(|| overflow(function))
+        }
+        ("_-_", Timestamp(ts), Duration(d)) => {
+            time::timestamp_from_nanos(time::timestamp_nanos(ts) - time::duration_nanos(d))
+                .map(Timestamp)
+                .ok_or_else(|| overflow(function))
+        }
+        ("_-_", Timestamp(lhs), Timestamp(rhs)) => {
+            time::duration_from_nanos(time::timestamp_nanos(lhs) - time::timestamp_nanos(rhs))
+                .map(Duration)
+                .ok_or_else(|| overflow(function))
+        }
+        ("_+_" | "_-_", Duration(lhs), Duration(rhs)) => {
+            let rhs = time::duration_nanos(rhs);
+            let rhs = if function == "_+_" { rhs } else { -rhs };
+            time::duration_from_nanos(time::duration_nanos(lhs) + rhs)
+                .map(Duration)
+                .ok_or_else(|| overflow(function))
+        }
+        _ => Err(no_overload(function)),
+    }
+}
+
+fn convert(function: &str, value: &CelValue) -> Result<CelValue, EvalError> {
+    use CelValue::*;
+
+    match (function, value) {
+        ("int", Int(_))
+        | ("uint", Uint(_))
+        | ("double", Double(_))
+        | ("string", String(_))
+        | ("bytes", Bytes(_))
+        | ("bool", Bool(_))
+        | ("timestamp", Timestamp(_))
+        | ("duration", Duration(_)) => Ok(value.clone()),
+        ("int", Uint(value)) => i64::try_from(*value)
+            .map(Int)
+            .map_err(|_| overflow(function)),
+        ("int", Double(value)) => {
+            // The bounds of the range of i64, -2^63 and 2^63, are exactly representable as f64.
+            if value.is_finite() && *value >= i64::MIN as f64 && *value < -(i64::MIN as f64) {
+                Ok(Int(*value as i64))
+            } else {
+                Err(overflow(function))
+            }
+        }
+        ("int", String(value)) => value
+            .parse()
+            .map(Int)
+            .map_err(|err| invalid_argument(function, err)),
+        ("int", Timestamp(value)) => Ok(Int(value.seconds)),
+        ("uint", Int(value)) => u64::try_from(*value)
+            .map(Uint)
+            .map_err(|_| overflow(function)),
+        ("uint", Double(value)) => {
+            if value.is_finite() && *value > -1.0 && *value < u64::MAX as f64 {
+                Ok(Uint(*value as u64))
+            } else {
+                Err(overflow(function))
+            }
+        }
+        ("uint", String(value)) => value
+            .parse()
+            .map(Uint)
+            .map_err(|err| invalid_argument(function, err)),
+        ("double", Int(value)) => Ok(Double(*value as f64)),
+        ("double", Uint(value)) => Ok(Double(*value as f64)),
+        ("double", String(value)) => value
+            .parse()
+            .map(Double)
+            .map_err(|err| invalid_argument(function, err)),
+        ("string", Int(value)) => Ok(String(value.to_string())),
+        ("string", Uint(value)) => Ok(String(value.to_string())),
+        ("string", Double(value)) => Ok(String(value.to_string())),
+        ("string", Bool(value)) => Ok(String(value.to_string())),
+        ("string", Bytes(value)) => std::str::from_utf8(value)
+            .map(|value| String(value.to_owned()))
+            .map_err(|err| invalid_argument(function, err)),
+        ("string", Timestamp(value)) => Ok(String(value.to_string())),
+        ("string", Duration(value)) => Ok(String(format_duration(value))),
+        ("bytes", String(value)) => Ok(Bytes(value.clone().into())),
+        ("bool", String(value)) => match value.as_str() {
+            "true" | "True" | "TRUE" | "t" | "1" => Ok(Bool(true)),
+            "false" | "False" | "FALSE" | "f" | "0" => Ok(Bool(false)),
+            _ => Err(invalid_argument(
+                function,
+                format!("invalid bool '{}'", value),
+            )),
+        },
+        ("timestamp", String(value)) => time::parse_timestamp(value)
+            .map(Timestamp)
+            .ok_or_else(|| invalid_argument(function, format!("invalid timestamp '{}'", value))),
+        ("timestamp", Int(value)) => time::timestamp_from_nanos(i128::from(*value) * 1_000_000_000)
+            .map(Timestamp)
+            .ok_or_else(|| overflow(function)),
+        ("duration", String(value)) => time::parse_duration(value)
+            .map(Duration)
+            .ok_or_else(|| invalid_argument(function, format!("invalid duration '{}'", value))),
+        _ => Err(no_overload(function)),
+    }
+}
+
+/// Tests two values for equality. Numbers of different types are equal if they have the same value, and
+/// values of other different types are not equal.
+fn equals(lhs: &CelValue, rhs: &CelValue) -> bool {
+    use CelValue::*;
+
+    match (lhs, rhs) {
+        (Null, Null) => true,
+        (Bool(lhs), Bool(rhs)) => lhs == rhs,
+        (Int(_) | Uint(_) | Double(_), Int(_) | Uint(_) | Double(_)) => {
+            compare_numbers(lhs, rhs) == Some(Ordering::Equal)
+        }
+        (String(lhs), String(rhs)) => lhs == rhs,
+        (Bytes(lhs), Bytes(rhs)) => lhs == rhs,
+        (Duration(lhs), Duration(rhs)) => time::duration_nanos(lhs) == time::duration_nanos(rhs),
+        (Timestamp(lhs), Timestamp(rhs)) => {
+            time::timestamp_nanos(lhs) == time::timestamp_nanos(rhs)
+        }
+        (List(lhs), List(rhs)) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| equals(lhs, rhs))
+        }
+        (Map(lhs), Map(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .all(|(key, lhs)| rhs.get(key).is_some_and(|rhs| equals(lhs, rhs)))
+        }
+        (Message(lhs), Message(rhs)) => lhs == rhs,
+        (Type(lhs), Type(rhs)) => lhs == rhs,
+        _ => false,
+    }
+}
+
+/// Compares two values, returning `None` if they are unordered, such as when comparing with `NaN`.
+fn compare(function: &str, lhs: &CelValue, rhs: &CelValue) -> Result<Option<Ordering>, EvalError> {
+    use CelValue::*;
+
+    match (lhs, rhs) {
+        (Int(_) | Uint(_) | Double(_), Int(_) | Uint(_) | Double(_)) => {
+            Ok(compare_numbers(lhs, rhs))
+        }
+        (Bool(lhs), Bool(rhs)) => Ok(Some(lhs.cmp(rhs))),
+        (String(lhs), String(rhs)) => Ok(Some(lhs.cmp(rhs))),
+        (Bytes(lhs), Bytes(rhs)) => Ok(Some(lhs.cmp(rhs))),
+        (Duration(lhs), Duration(rhs)) => Ok(Some(
+            time::duration_nanos(lhs).cmp(&time::duration_nanos(rhs)),
+        )),
+        (Timestamp(lhs), Timestamp(rhs)) => Ok(Some(
+            time::timestamp_nanos(lhs).cmp(&time::timestamp_nanos(rhs)),
+        )),
+        _ => Err(no_overload(function)),
+    }
+}
+
+fn compare_numbers(lhs: &CelValue, rhs: &CelValue) -> Option<Ordering> {
+    use CelValue::*;
+
+    match (lhs, rhs) {
+        (&Int(lhs), &Int(rhs)) => Some(lhs.cmp(&rhs)),
+        (&Uint(lhs), &Uint(rhs)) => Some(lhs.cmp(&rhs)),
+        (&Int(lhs), &Uint(rhs)) => Some(i128::from(lhs).cmp(&i128::from(rhs))),
+        (&Uint(lhs), &Int(rhs)) => Some(i128::from(lhs).cmp(&i128::from(rhs))),
+        (&Double(lhs), &Double(rhs)) => lhs.partial_cmp(&rhs),
+        (&Double(lhs), &Int(rhs)) => lhs.partial_cmp(&(rhs as f64)),
+        (&Double(lhs), &Uint(rhs)) => lhs.partial_cmp(&(rhs as f64)),
+        (&Int(lhs), &Double(rhs)) => (lhs as f64).partial_cmp(&rhs),
+        (&Uint(lhs), &Double(rhs)) => (lhs as f64).partial_cmp(&rhs),
+        _ => None,
+    }
+}
+
+/// Looks up a key in a map, allowing numeric keys of different types with the same value.
+fn map_get<'m>(map: &'m HashMap<MapKey, CelValue>, key: &CelValue) -> Option<&'m CelValue> {
+    match *key {
+        CelValue::Int(value) => map
+            .get(&MapKey::I64(value))
+            .or_else(|| map.get(&MapKey::U64(u64::try_from(value).ok()?))),
+        CelValue::Uint(value) => map
+            .get(&MapKey::U64(value))
+            .or_else(|| map.get(&MapKey::I64(i64::try_from(value).ok()?))),
+        CelValue::Double(value) if value.fract() == 0.0 => {
+            map_get(map, &CelValue::Int(value as i64))
+                .filter(|_| (value as i64) as f64 == value)
+                .or_else(|| {
+                    map_get(map, &CelValue::Uint(value as u64))
+                        .filter(|_| (value as u64) as f64 == value)
+                })
+        }
+        _ => map.get(&to_map_key(key)?),
+    }
+}
+
+fn len(len: usize) -> i64 {
+    i64::try_from(len).unwrap_or(i64::MAX)
+}
+
+fn no_overload(function: &str) -> EvalError {
+    EvalError::NoMatchingOverload {
+        function: function.to_owned(),
+    }
+}
+
+fn overflow(function: &str) -> EvalError {
+    EvalError::Overflow {
+        function: function.to_owned(),
+    }
+}
+
+fn invalid_argument(function: &str, message: impl ToString) -> EvalError {
+    EvalError::InvalidArgument {
+        function: function.to_owned(),
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+
+use logos::{Lexer, Logos};
+
+use super::error::CompileErrorKind;
+
+#[derive(Debug, Clone, Logos, PartialEq)]
+#[logos(extras = TokenExtras)]
+#[logos(skip r"[\t\n\f\r ]+")]
+#[logos(skip r"//[^\n]*")]
+#[logos(subpattern exponent = r"[eE][+\-]?[0-9]+")]
+pub(super) enum Token<'a> {
+    #[regex("[A-Za-z_][A-Za-z0-9_]*")]
+    Ident(&'a str),
+    #[regex("[0-9]+", |lex| int(lex, 10, 0))]
+    #[regex("0[xX][0-9A-Fa-f]+", |lex| int(lex, 16, 2))]
+    IntLiteral(u64),
+    #[regex("[0-9]+[uU]", |lex| int(lex, 10, 0))]
+    #[regex("0[xX][0-9A-Fa-f]+[uU]", |lex| int(lex, 16, 2))]
+    UintLiteral(u64),
+    #[regex(r"[0-9]+\.[0-9]+(?&exponent)?", float)]
+    #[regex(r"[0-9]+(?&exponent)", float)]
+    #[regex(r"\.[0-9]+(?&exponent)?", float)]
+    DoubleLiteral(f64),
+    #[regex(r#"([rR][bB]?|[bB][rR]?)?("|')"#, quoted)]
+    #[regex(r#"([rR][bB]?|[bB][rR]?)?("""|''')"#, quoted)]
+    Quoted(Quoted),
+    #[token("(")]
+    LeftParen,
+    #[token(")")]
+    RightParen,
+    #[token("[")]
+    LeftBracket,
+    #[token("]")]
+    RightBracket,
+    #[token("{")]
+    LeftBrace,
+    #[token("}")]
+    RightBrace,
+    #[token(".")]
+    Dot,
+    #[token(",")]
+    Comma,
+    #[token(":")]
+    Colon,
+    #[token("?")]
+    Question,
+    #[token("+")]
+    Plus,
+    #[token("-")]
+    Minus,
+    #[token("*")]
+    Star,
+    #[token("/")]
+    Slash,
+    #[token("%")]
+    Percent,
+    #[token("!")]
+    Not,
+    #[token("==")]
+    Equal,
+    #[token("!=")]
+    NotEqual,
+    #[token("<")]
+    Less,
+    #[token("<=")]
+    LessEqual,
+    #[token(">")]
+    Greater,
+    #[token(">=")]
+    GreaterEqual,
+    #[token("&&")]
+    And,
+    #[token("||")]
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Quoted {
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Default)]
+pub(super) struct TokenExtras {
+    pub error: Option<CompileErrorKind>,
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(value) => write!(f, "{}", value),
+            Token::IntLiteral(value) => write!(f, "{}", value),
+            Token::UintLiteral(value) => write!(f, "{}u", value),
+            Token::DoubleLiteral(value) => {
+                if value.fract() == 0.0 {
+                    write!(f, "{:.1}", value)
+                } else {
+                    write!(f, "{}", value)
+                }
+            }
+            Token::Quoted(Quoted::String(value)) => write!(f, "{:?}", value),
+            Token::Quoted(Quoted::Bytes(value)) => {
+                write!(f, "b\"")?;
+                for &byte in value {
+                    write!(f, "{}", std::ascii::escape_default(byte))?;
+                }
+                write!(f, "\"")
+            }
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::LeftBracket => write!(f, "["),
+            Token::RightBracket => write!(f, "]"),
+            Token::LeftBrace => write!(f, "{{"),
+            Token::RightBrace => write!(f, "}}"),
+            Token::Dot => write!(f, "."),
+            Token::Comma => write!(f, ","),
+            Token::Colon => write!(f, ":"),
+            Token::Question => write!(f, "?"),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Not => write!(f, "!"),
+            Token::Equal => write!(f, "=="),
+            Token::NotEqual => write!(f, "!="),
+            Token::Less => write!(f, "<"),
+            Token::LessEqual => write!(f, "<="),
+            Token::Greater => write!(f, ">"),
+            Token::GreaterEqual => write!(f, ">="),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+        }
+    }
+}
+
+fn int<'a>(lex: &mut Lexer<'a, Token<'a>>, radix: u32, prefix_len: usize) -> Result<u64, ()> {
+    let digits = lex.slice()[prefix_len..].trim_end_matches(['u', 'U']);
+    u64::from_str_radix(digits, radix).map_err(|_| {
+        lex.extras.error = Some(CompileErrorKind::IntegerValueOutOfRange { span: lex.span() });
+    })
+}
+
+fn float<'a>(lex: &mut Lexer<'a, Token<'a>>) -> f64 {
+    lex.slice().parse().expect("failed to parse float")
+}
+
+enum Escaped {
+    Byte(u8),
+    Char(char),
+}
+
+fn quoted<'a>(lex: &mut Lexer<'a, Token<'a>>) -> Result<Quoted, ()> {
+    let slice = lex.slice();
+    let prefix_len = slice.trim_end_matches(['"', '\'']).len();
+    let (prefix, terminator) = slice.split_at(prefix_len);
+    let raw = prefix.contains(['r', 'R']);
+    let is_bytes = prefix.contains(['b', 'B']);
+
+    let remainder = lex.remainder();
+    let start = lex.span().end;
+    let mut value = Vec::new();
+    let mut pos = 0;
+    loop {
+        let rest = &remainder[pos..];
+        if rest.starts_with(terminator) {
+            pos += terminator.len();
+            break;
+        }
+
+        let ch = match rest.chars().next() {
+            Some(ch) if terminator.len() == 3 || (ch != '\n' && ch != '\r') => ch,
+            _ => {
+                lex.extras.error = Some(CompileErrorKind::UnterminatedString { span: lex.span() });
+                return Err(());
+            }
+        };
+
+        if ch == '\\' && !raw {
+            let (len, escaped) = match escape(rest, is_bytes) {
+                Some(escaped) => escaped,
+                None => {
+                    let end = rest[1..].chars().next().map_or(1, |ch| 1 + ch.len_utf8());
+                    lex.extras.error = Some(CompileErrorKind::InvalidStringEscape {
+                        span: start + pos..start + pos + end,
+                    });
+                    return Err(());
+                }
+            };
+            match escaped {
+                Escaped::Byte(byte) => value.push(byte),
+                Escaped::Char(ch) => {
+                    value.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes())
+                }
+            }
+            pos += len;
+        } else {
+            value.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            pos += ch.len_utf8();
+        }
+    }
+
+    lex.bump(pos);
+    if is_bytes {
+        Ok(Quoted::Bytes(value))
+    } else {
+        Ok(Quoted::String(
+            String::from_utf8(value).expect("string literal should be valid utf-8"),
+        ))
+    }
+}
+
+/// Parses an escape sequence at the start of `s`, returning its length and value.
+fn escape(s: &str, is_bytes: bool) -> Option<(usize, Escaped)> {
+    let bytes = s.as_bytes();
+    debug_assert_eq!(bytes[0], b'\\');
+
+    let code_point = |len: usize, radix: u32| -> Option<u32> {
+        let digits = s.get(2..2 + len)?;
+        if digits.chars().all(|ch| ch.is_digit(radix)) {
+            u32::from_str_radix(digits, radix).ok()
+        } else {
+            None
+        }
+    };
+
+    let ch = match *bytes.get(1)? {
+        b'a' => '\x07',
+        b'b' => '\x08',
+        b'f' => '\x0c',
+        b'n' => '\n',
+        b'r' => '\r',
+        b't' => '\t',
+        b'v' => '\x0b',
+        b'\\' => '\\',
+        b'\'' => '\'',
+        b'"' => '"',
+        b'`' => '`',
+        b'?' => '?',
+        b'x' | b'X' => {
+            let value = code_point(2, 16)?;
+            return Some((4, byte_or_char(value, is_bytes)?));
+        }
+        b'0'..=b'3' => {
+            let digits = s.get(1..4)?;
+            if !digits.chars().all(|ch| ch.is_digit(8)) {
+                return None;
+            }
+            let value = u32::from_str_radix(digits, 8).ok()?;
+            return Some((4, byte_or_char(value, is_bytes)?));
+        }
+        b'u' if !is_bytes => return Some((6, Escaped::Char(char::from_u32(code_point(4, 16)?)?))),
+        b'U' if !is_bytes => return Some((10, Escaped::Char(char::from_u32(code_point(8, 16)?)?))),
+        _ => return None,
+    };
+    Some((2, Escaped::Char(ch)))
+}
+
+fn byte_or_char(value: u32, is_bytes: bool) -> Option<Escaped> {
+    if is_bytes {
+        Some(Escaped::Byte(value.try_into().ok()?))
+    } else {
+        Some(Escaped::Char(char::from_u32(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_tokens() {
+        let source = r#"foo 42 0x2Au 1.5 2e3 .5 'a\n' "\x41\101é" r'\n' b"\xff" '''x'y''' // comment
+            <= != && ||"#;
+        let mut lexer = Token::lexer(source);
+
+        assert_eq!(lexer.next().unwrap(), Ok(Token::Ident("foo")));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::IntLiteral(42)));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::UintLiteral(42)));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::DoubleLiteral(1.5)));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::DoubleLiteral(2e3)));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::DoubleLiteral(0.5)));
+        assert_eq!(
+            lexer.next().unwrap(),
+            Ok(Token::Quoted(Quoted::String("a\n".to_owned())))
+        );
+        assert_eq!(
+            lexer.next().unwrap(),
+            Ok(Token::Quoted(Quoted::String("AAé".to_owned())))
+        );
+        assert_eq!(
+            lexer.next().unwrap(),
+            Ok(Token::Quoted(Quoted::String("\\n".to_owned())))
+        );
+        assert_eq!(
+            lexer.next().unwrap(),
+            Ok(Token::Quoted(Quoted::Bytes(vec![0xff])))
+        );
+        assert_eq!(
+            lexer.next().unwrap(),
+            Ok(Token::Quoted(Quoted::String("x'y".to_owned())))
+        );
+        assert_eq!(lexer.next().unwrap(), Ok(Token::LessEqual));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::NotEqual));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::And));
+        assert_eq!(lexer.next().unwrap(), Ok(Token::Or));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        let mut lexer = Token::lexer("'abc");
+        assert_eq!(lexer.next().unwrap(), Err(()));
+        assert_eq!(
+            lexer.extras.error,
+            Some(CompileErrorKind::UnterminatedString { span: 0..1 })
+        );
+
+        let mut lexer = Token::lexer(r"'a\qb'");
+        assert_eq!(lexer.next().unwrap(), Err(()));
+        assert_eq!(
+            lexer.extras.error,
+            Some(CompileErrorKind::InvalidStringEscape { span: 2..4 })
+        );
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let mut lexer = Token::lexer("99999999999999999999");
+        assert_eq!(lexer.next().unwrap(), Err(()));
+        assert_eq!(
+            lexer.extras.error,
+            Some(CompileErrorKind::IntegerValueOutOfRange { span: 0..20 })
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Evaluation of [Common Expression Language](https://github.com/google/cel-spec) (CEL) expressions
+//! against messages.
+//!
+//! Expressions are compiled against an [`Env`](crate::cel::Env), which declares the variables available
+//! to the expression and the [`DescriptorPool`](crate::DescriptorPool) used to resolve message types.
+//! Compilation type-checks the expression, so errors such as referencing a field which does not exist
+//! are reported before any message is evaluated. The resulting [`Program`](crate::cel::Program) can then
+//! be evaluated many times with different variable bindings.
+//!
+//! Messages are accessed through their [`MessageDescriptor`](crate::MessageDescriptor), so field
+//! selection, `has()` tests, and map and list indexing work for any [`DynamicMessage`](crate::DynamicMessage).
+//! Well-known types are converted to their CEL equivalents: `google.protobuf.Timestamp` and
+//! `google.protobuf.Duration` become `timestamp` and `duration` values, wrapper types become nullable
+//! primitives, and `google.protobuf.Struct`, `Value` and `ListValue` become maps, lists and dynamic values.
+//!
+//! # Limitations
+//!
+//! Only the standard functions and macros are supported; custom functions cannot be registered. Time zones
+//! passed to timestamp accessors must be `UTC` or a fixed offset such as `+05:30`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use prost_reflect::{DescriptorPool, DynamicMessage, cel::{Activation, CelType, CelValue, Env}};
+//! # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+//! let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+//! let env = Env::new(pool.clone()).variable("request", message_descriptor.clone());
+//!
+//! let program = env.compile("request.foo > 1 && has(request.nested)").unwrap();
+//! assert_eq!(*program.result_type(), CelType::Bool);
+//!
+//! let message = DynamicMessage::decode(message_descriptor, b"\x08\x02\x1a\x00".as_ref()).unwrap();
+//! let activation = Activation::new().bind("request", message);
+//! assert_eq!(program.evaluate(&activation).unwrap(), CelValue::Bool(true));
+//!
+//! assert!(env.compile("request.baz == 1").is_err());
+//! ```
+
+mod check;
+mod error;
+mod eval;
+mod lex;
+mod parse;
+mod time;
+mod value;
+
+pub use self::{
+    error::{CompileError, EvalError},
+    value::{CelType, CelValue},
+};
+
+use std::collections::HashMap;
+
+use self::{check::Checker, eval::Evaluator, parse::Parser};
+use crate::DescriptorPool;
+
+/// The declarations available to CEL expressions when they are compiled.
+#[derive(Debug, Clone)]
+pub struct Env {
+    pool: DescriptorPool,
+    variables: HashMap<String, CelType>,
+}
+
+/// A compiled and type-checked CEL expression.
+///
+/// Created by [`Env::compile()`].
+#[derive(Debug, Clone)]
+pub struct Program {
+    expr: parse::Expr,
+    result_type: CelType,
+}
+
+/// The values of variables used when evaluating a [`Program`].
+#[derive(Debug, Clone, Default)]
+pub struct Activation {
+    variables: HashMap<String, CelValue>,
+}
+
+impl Env {
+    /// Creates a new environment with no variables, which resolves message and enum types using `pool`.
+    pub fn new(pool: DescriptorPool) -> Self {
+        Env {
+            pool,
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Declares a variable with the given name and type.
+    ///
+    /// A [`MessageDescriptor`](crate::MessageDescriptor) may be passed as the type to declare a variable
+    /// holding a message.
+    pub fn variable(mut self, name: impl Into<String>, ty: impl Into<CelType>) -> Self {
+        self.variables.insert(name.into(), ty.into().normalize());
+        self
+    }
+
+    /// Parses and type-checks an expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is syntactically invalid, references an undeclared variable or
+    /// field, or is otherwise not well-typed.
+    pub fn compile(&self, source: &str) -> Result<Program, CompileError> {
+        let mut expr = Parser::new(source)
+            .parse()
+            .map_err(|kind| CompileError::new(kind, source))?;
+        let result_type = Checker::new(self)
+            .check(&mut expr)
+            .map_err(|kind| CompileError::new(kind, source))?;
+        Ok(Program { expr, result_type })
+    }
+
+    /// Resolves a fully-qualified enum value name, such as `package.MyEnum.VALUE`, to its number.
+    fn resolve_enum_value(&self, name: &str) -> Option<i32> {
+        let (enum_name, value_name) = name.rsplit_once('.')?;
+        let enum_desc = self.pool.get_enum_by_name(enum_name)?;
+        let value = enum_desc.get_value_by_name(value_name)?;
+        Some(value.number())
+    }
+
+    /// Resolves the name of a built-in type or message type.
+    fn resolve_type(&self, name: &str) -> Option<CelType> {
+        if let Some(ty) = CelType::builtin(name) {
+            return Some(ty);
+        }
+        let message_desc = self.pool.get_message_by_name(name)?;
+        Some(CelType::from(message_desc))
+    }
+}
+
+impl Program {
+    /// Gets the type of the value produced by this expression.
+    ///
+    /// This is [`CelType::Dyn`] if the type cannot be determined until the expression is evaluated.
+    pub fn result_type(&self) -> &CelType {
+        &self.result_type
+    }
+
+    /// Evaluates the expression with the given variable bindings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is not bound, or if evaluation fails, for example due to integer
+    /// overflow or indexing a list out of range.
+    pub fn evaluate(&self, activation: &Activation) -> Result<CelValue, EvalError> {
+        Evaluator::new(activation).eval(&self.expr)
+    }
+}
+
+impl Activation {
+    /// Creates a new, empty, set of variable bindings.
+    pub fn new() -> Self {
+        Activation::default()
+    }
+
+    /// Binds a value to the variable with the given name.
+    pub fn bind(mut self, name: impl Into<String>, value: impl Into<CelValue>) -> Self {
+        self.variables.insert(name.into(), value.into());
+        self
+    }
+}
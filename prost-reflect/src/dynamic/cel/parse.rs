@@ -0,0 +1,667 @@
+use logos::{Lexer, Logos, Span};
+
+use super::{
+    error::CompileErrorKind,
+    lex::{Quoted, Token},
+    CelType,
+};
+use crate::MessageDescriptor;
+
+/// The maximum height of the expression tree, which bounds the recursion of the checker and evaluator.
+const MAX_DEPTH: usize = 100;
+/// The maximum nesting of parentheses, brackets, unary operators and function arguments, which bounds the
+/// recursion of the parser.
+const MAX_NESTING: usize = 32;
+
+#[derive(Debug, Clone)]
+pub(super) struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum ExprKind {
+    Literal(Literal),
+    Ident(String),
+    Select {
+        operand: Box<Expr>,
+        field: String,
+    },
+    /// The `has()` macro, which tests for the presence of a field.
+    Has {
+        operand: Box<Expr>,
+        field: String,
+    },
+    /// A call to a function or operator. Operators use the names `_+_`, `!_`, `_[_]`, `_?_:_` etc.
+    Call {
+        function: String,
+        target: Option<Box<Expr>>,
+        args: Vec<Expr>,
+    },
+    List(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
+    Message {
+        type_name: String,
+        fields: Vec<FieldInit>,
+        /// The message type, resolved by the checker.
+        desc: Option<MessageDescriptor>,
+    },
+    /// One of the `all`, `exists`, `exists_one`, `map` or `filter` macros.
+    Comprehension {
+        kind: MacroKind,
+        range: Box<Expr>,
+        var: String,
+        args: Vec<Expr>,
+    },
+    /// A reference to a type, such as `int` or a message name, resolved by the checker.
+    Type(CelType),
+}
+
+/// A field initializer in a message construction expression, such as `foo: 1` in `Msg{foo: 1}`.
+pub(super) type FieldInit = (String, Span, Expr);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Literal {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Double(f64),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum MacroKind {
+    All,
+    Exists,
+    ExistsOne,
+    Map,
+    Filter,
+}
+
+pub(super) struct Parser<'a> {
+    lexer: Lexer<'a, Token<'a>>,
+    peek: Option<Result<(Token<'a>, Span), CompileErrorKind>>,
+    depth: usize,
+}
+
+impl Expr {
+    fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+
+    fn call(function: &str, args: Vec<Expr>, span: Span) -> Self {
+        Expr::new(
+            ExprKind::Call {
+                function: function.to_owned(),
+                target: None,
+                args,
+            },
+            span,
+        )
+    }
+
+    /// Returns the height of the expression tree, where a leaf node has height 1.
+    fn height(&self) -> usize {
+        let children: Vec<&Expr> = match &self.kind {
+            ExprKind::Literal(_) | ExprKind::Ident(_) | ExprKind::Type(_) => vec![],
+            ExprKind::Select { operand, .. } | ExprKind::Has { operand, .. } => vec![operand],
+            ExprKind::Call { target, args, .. } => {
+                target.as_deref().into_iter().chain(args).collect()
+            }
+            ExprKind::List(elements) => elements.iter().collect(),
+            ExprKind::Map(entries) => entries
+                .iter()
+                .flat_map(|(key, value)| [key, value])
+                .collect(),
+            ExprKind::Message { fields, .. } => fields.iter().map(|(_, _, expr)| expr).collect(),
+            ExprKind::Comprehension { range, args, .. } => {
+                std::iter::once(&**range).chain(args).collect()
+            }
+        };
+        1 + children.into_iter().map(Expr::height).max().unwrap_or(0)
+    }
+
+    /// Returns the qualified name represented by this expression, if it is a chain of identifiers.
+    pub fn qualified_name(&self) -> Option<String> {
+        match &self.kind {
+            ExprKind::Ident(name) => Some(name.clone()),
+            ExprKind::Select { operand, field } => {
+                let mut name = operand.qualified_name()?;
+                name.push('.');
+                name.push_str(field);
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl MacroKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(MacroKind::All),
+            "exists" => Some(MacroKind::Exists),
+            "exists_one" => Some(MacroKind::ExistsOne),
+            "map" => Some(MacroKind::Map),
+            "filter" => Some(MacroKind::Filter),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MacroKind::All => "all",
+            MacroKind::Exists => "exists",
+            MacroKind::ExistsOne => "exists_one",
+            MacroKind::Map => "map",
+            MacroKind::Filter => "filter",
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Token::lexer(input),
+            peek: None,
+            depth: 0,
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Expr, CompileErrorKind> {
+        let expr = self.parse_expr()?;
+        if self.peek()?.is_some() {
+            return self.unexpected_token("an operator or end of expression");
+        }
+        // Operator chains within nested expressions may still produce a tree which is too deep.
+        if expr.height() > MAX_DEPTH {
+            return Err(CompileErrorKind::NestingTooDeep { span: expr.span });
+        }
+        Ok(expr)
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, CompileErrorKind> {
+        self.enter()?;
+        let condition = self.parse_or()?;
+        let result = if let Some((Token::Question, _)) = self.peek()? {
+            self.bump();
+            let then = self.parse_or()?;
+            self.expect(Token::Colon, "':'")?;
+            let otherwise = self.parse_expr()?;
+            let span = join_span(&condition.span, &otherwise.span);
+            Expr::call("_?_:_", vec![condition, then, otherwise], span)
+        } else {
+            condition
+        };
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, CompileErrorKind> {
+        let mut lhs = self.parse_and()?;
+        let mut chain = 0;
+        while let Some((Token::Or, _)) = self.peek()? {
+            self.bump();
+            let rhs = self.parse_and()?;
+            lhs = self.binary("_||_", lhs, rhs, &mut chain)?;
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, CompileErrorKind> {
+        let mut lhs = self.parse_relation()?;
+        let mut chain = 0;
+        while let Some((Token::And, _)) = self.peek()? {
+            self.bump();
+            let rhs = self.parse_relation()?;
+            lhs = self.binary("_&&_", lhs, rhs, &mut chain)?;
+        }
+        Ok(lhs)
+    }
+
+    fn parse_relation(&mut self) -> Result<Expr, CompileErrorKind> {
+        let mut lhs = self.parse_addition()?;
+        let mut chain = 0;
+        loop {
+            let function = match self.peek()? {
+                Some((Token::Less, _)) => "_<_",
+                Some((Token::LessEqual, _)) => "_<=_",
+                Some((Token::Greater, _)) => "_>_",
+                Some((Token::GreaterEqual, _)) => "_>=_",
+                Some((Token::Equal, _)) => "_==_",
+                Some((Token::NotEqual, _)) => "_!=_",
+                Some((Token::Ident("in"), _)) => "@in",
+                _ => return Ok(lhs),
+            };
+            self.bump();
+            let rhs = self.parse_addition()?;
+            lhs = self.binary(function, lhs, rhs, &mut chain)?;
+        }
+    }
+
+    fn parse_addition(&mut self) -> Result<Expr, CompileErrorKind> {
+        let mut lhs = self.parse_multiplication()?;
+        let mut chain = 0;
+        loop {
+            let function = match self.peek()? {
+                Some((Token::Plus, _)) => "_+_",
+                Some((Token::Minus, _)) => "_-_",
+                _ => return Ok(lhs),
+            };
+            self.bump();
+            let rhs = self.parse_multiplication()?;
+            lhs = self.binary(function, lhs, rhs, &mut chain)?;
+        }
+    }
+
+    fn parse_multiplication(&mut self) -> Result<Expr, CompileErrorKind> {
+        let mut lhs = self.parse_unary()?;
+        let mut chain = 0;
+        loop {
+            let function = match self.peek()? {
+                Some((Token::Star, _)) => "_*_",
+                Some((Token::Slash, _)) => "_/_",
+                Some((Token::Percent, _)) => "_%_",
+                _ => return Ok(lhs),
+            };
+            self.bump();
+            let rhs = self.parse_unary()?;
+            lhs = self.binary(function, lhs, rhs, &mut chain)?;
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, CompileErrorKind> {
+        match self.peek()? {
+            Some((Token::Not, _)) => {
+                let start = self.bump();
+                self.enter()?;
+                let operand = self.parse_unary()?;
+                self.depth -= 1;
+                let span = join_span(&start, &operand.span);
+                Ok(Expr::call("!_", vec![operand], span))
+            }
+            Some((Token::Minus, _)) => {
+                let start = self.bump();
+                if let Some((Token::IntLiteral(value), span)) = self.peek()? {
+                    // Negative literals are parsed directly, so that the minimum value of int is representable.
+                    self.bump();
+                    let span = join_span(&start, &span);
+                    let value = i64::try_from(-i128::from(value)).map_err(|_| {
+                        CompileErrorKind::IntegerValueOutOfRange { span: span.clone() }
+                    })?;
+                    let literal = Expr::new(ExprKind::Literal(Literal::Int(value)), span);
+                    return self.parse_member_suffix(literal);
+                }
+
+                self.enter()?;
+                let operand = self.parse_unary()?;
+                self.depth -= 1;
+                let span = join_span(&start, &operand.span);
+                Ok(Expr::call("-_", vec![operand], span))
+            }
+            _ => {
+                let primary = self.parse_primary()?;
+                self.parse_member_suffix(primary)
+            }
+        }
+    }
+
+    fn parse_member_suffix(&mut self, mut expr: Expr) -> Result<Expr, CompileErrorKind> {
+        let mut chain = 0;
+        loop {
+            match self.peek()? {
+                Some((Token::Dot, _)) => {
+                    self.bump();
+                    let (name, name_span) = self.parse_ident()?;
+                    if let Some((Token::LeftParen, _)) = self.peek()? {
+                        self.bump();
+                        let (args, end) = self.parse_list(Token::RightParen, "')'")?;
+                        let span = join_span(&expr.span, &end);
+                        expr = self.method_call(expr, name, args, span)?;
+                    } else {
+                        let span = join_span(&expr.span, &name_span);
+                        expr = Expr::new(
+                            ExprKind::Select {
+                                operand: Box::new(expr),
+                                field: name,
+                            },
+                            span,
+                        );
+                    }
+                }
+                Some((Token::LeftBracket, _)) => {
+                    self.bump();
+                    let index = self.parse_expr()?;
+                    let end = self.expect(Token::RightBracket, "']'")?;
+                    let span = join_span(&expr.span, &end);
+                    expr = Expr::call("_[_]", vec![expr, index], span);
+                }
+                Some((Token::LeftBrace, _)) => match expr.qualified_name() {
+                    Some(type_name) => {
+                        self.bump();
+                        let (fields, end) = self.parse_field_inits()?;
+                        let span = join_span(&expr.span, &end);
+                        expr = Expr::new(
+                            ExprKind::Message {
+                                type_name,
+                                fields,
+                                desc: None,
+                            },
+                            span,
+                        );
+                    }
+                    None => return Ok(expr),
+                },
+                _ => return Ok(expr),
+            }
+
+            chain += 1;
+            self.check_depth(chain, &expr.span)?;
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, CompileErrorKind> {
+        match self.peek()? {
+            Some((Token::Ident(_), _)) => {
+                let (name, span) = self.parse_ident()?;
+                let literal = match name.as_str() {
+                    "true" => Some(Literal::Bool(true)),
+                    "false" => Some(Literal::Bool(false)),
+                    "null" => Some(Literal::Null),
+                    _ => None,
+                };
+                if let Some(literal) = literal {
+                    return Ok(Expr::new(ExprKind::Literal(literal), span));
+                }
+
+                if let Some((Token::LeftParen, _)) = self.peek()? {
+                    self.bump();
+                    let (args, end) = self.parse_list(Token::RightParen, "')'")?;
+                    let span = join_span(&span, &end);
+                    self.global_call(name, args, span)
+                } else {
+                    Ok(Expr::new(ExprKind::Ident(name), span))
+                }
+            }
+            Some((Token::Dot, _)) => {
+                // A leading dot marks a fully-qualified name.
+                let start = self.bump();
+                let (name, span) = self.parse_ident()?;
+                Ok(Expr::new(ExprKind::Ident(name), join_span(&start, &span)))
+            }
+            Some((Token::LeftParen, _)) => {
+                self.bump();
+                let expr = self.parse_expr()?;
+                self.expect(Token::RightParen, "')'")?;
+                Ok(expr)
+            }
+            Some((Token::LeftBracket, _)) => {
+                let start = self.bump();
+                let (elements, end) = self.parse_list(Token::RightBracket, "']'")?;
+                Ok(Expr::new(ExprKind::List(elements), join_span(&start, &end)))
+            }
+            Some((Token::LeftBrace, _)) => {
+                let start = self.bump();
+                let mut entries = Vec::new();
+                let end = loop {
+                    if let Some((Token::RightBrace, _)) = self.peek()? {
+                        break self.bump();
+                    }
+                    let key = self.parse_expr()?;
+                    self.expect(Token::Colon, "':'")?;
+                    let value = self.parse_expr()?;
+                    entries.push((key, value));
+                    match self.peek()? {
+                        Some((Token::Comma, _)) => {
+                            self.bump();
+                        }
+                        Some((Token::RightBrace, _)) => break self.bump(),
+                        _ => self.unexpected_token("',' or '}'")?,
+                    }
+                };
+                Ok(Expr::new(ExprKind::Map(entries), join_span(&start, &end)))
+            }
+            Some((Token::IntLiteral(value), span)) => {
+                self.bump();
+                let value = i64::try_from(value)
+                    .map_err(|_| CompileErrorKind::IntegerValueOutOfRange { span: span.clone() })?;
+                Ok(Expr::new(ExprKind::Literal(Literal::Int(value)), span))
+            }
+            Some((Token::UintLiteral(value), span)) => {
+                self.bump();
+                Ok(Expr::new(ExprKind::Literal(Literal::Uint(value)), span))
+            }
+            Some((Token::DoubleLiteral(value), span)) => {
+                self.bump();
+                Ok(Expr::new(ExprKind::Literal(Literal::Double(value)), span))
+            }
+            Some((Token::Quoted(quoted), span)) => {
+                self.bump();
+                let literal = match quoted {
+                    Quoted::String(value) => Literal::String(value),
+                    Quoted::Bytes(value) => Literal::Bytes(value),
+                };
+                Ok(Expr::new(ExprKind::Literal(literal), span))
+            }
+            _ => self.unexpected_token("an expression"),
+        }
+    }
+
+    fn parse_field_inits(&mut self) -> Result<(Vec<FieldInit>, Span), CompileErrorKind> {
+        let mut fields = Vec::new();
+        let end = loop {
+            if let Some((Token::RightBrace, _)) = self.peek()? {
+                break self.bump();
+            }
+            let (name, span) = self.parse_ident()?;
+            self.expect(Token::Colon, "':'")?;
+            let value = self.parse_expr()?;
+            fields.push((name, span, value));
+            match self.peek()? {
+                Some((Token::Comma, _)) => {
+                    self.bump();
+                }
+                Some((Token::RightBrace, _)) => break self.bump(),
+                _ => self.unexpected_token("',' or '}'")?,
+            }
+        };
+        Ok((fields, end))
+    }
+
+    /// Parses a comma-separated list of expressions, with an optional trailing comma, up to and including
+    /// the `terminator` token.
+    fn parse_list(
+        &mut self,
+        terminator: Token<'static>,
+        expected: &str,
+    ) -> Result<(Vec<Expr>, Span), CompileErrorKind> {
+        let mut exprs = Vec::new();
+        loop {
+            match self.peek()? {
+                Some((tok, _)) if tok == terminator => return Ok((exprs, self.bump())),
+                _ => exprs.push(self.parse_expr()?),
+            }
+            match self.peek()? {
+                Some((Token::Comma, _)) => {
+                    self.bump();
+                }
+                Some((tok, _)) if tok == terminator => return Ok((exprs, self.bump())),
+                _ => self.unexpected_token(format!("',' or {}", expected))?,
+            }
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<(String, Span), CompileErrorKind> {
+        match self.peek()? {
+            Some((Token::Ident(name), span)) if name != "in" => {
+                self.bump();
+                Ok((name.to_owned(), span))
+            }
+            _ => self.unexpected_token("an identifier"),
+        }
+    }
+
+    fn global_call(
+        &mut self,
+        function: String,
+        mut args: Vec<Expr>,
+        span: Span,
+    ) -> Result<Expr, CompileErrorKind> {
+        if function == "has" {
+            return match args.pop() {
+                Some(Expr {
+                    kind: ExprKind::Select { operand, field },
+                    ..
+                }) if args.is_empty() => Ok(Expr::new(ExprKind::Has { operand, field }, span)),
+                _ => Err(CompileErrorKind::InvalidMacroArgument {
+                    macro_name: function,
+                    span,
+                }),
+            };
+        }
+
+        Ok(Expr::new(
+            ExprKind::Call {
+                function,
+                target: None,
+                args,
+            },
+            span,
+        ))
+    }
+
+    fn method_call(
+        &mut self,
+        target: Expr,
+        function: String,
+        mut args: Vec<Expr>,
+        span: Span,
+    ) -> Result<Expr, CompileErrorKind> {
+        if let Some(kind) = MacroKind::from_name(&function) {
+            let arg_count_valid = match kind {
+                MacroKind::Map => args.len() == 2 || args.len() == 3,
+                _ => args.len() == 2,
+            };
+            let var = match args.first() {
+                Some(Expr {
+                    kind: ExprKind::Ident(var),
+                    ..
+                }) if arg_count_valid => var.clone(),
+                _ => {
+                    return Err(CompileErrorKind::InvalidMacroArgument {
+                        macro_name: function,
+                        span,
+                    })
+                }
+            };
+            args.remove(0);
+
+            return Ok(Expr::new(
+                ExprKind::Comprehension {
+                    kind,
+                    range: Box::new(target),
+                    var,
+                    args,
+                },
+                span,
+            ));
+        }
+
+        Ok(Expr::new(
+            ExprKind::Call {
+                function,
+                target: Some(Box::new(target)),
+                args,
+            },
+            span,
+        ))
+    }
+
+    fn binary(
+        &mut self,
+        function: &str,
+        lhs: Expr,
+        rhs: Expr,
+        chain: &mut usize,
+    ) -> Result<Expr, CompileErrorKind> {
+        let span = join_span(&lhs.span, &rhs.span);
+        *chain += 1;
+        self.check_depth(*chain, &span)?;
+        Ok(Expr::call(function, vec![lhs, rhs], span))
+    }
+
+    fn enter(&mut self) -> Result<(), CompileErrorKind> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            let span = match self.peek()? {
+                Some((_, span)) => span,
+                None => self.lexer.span(),
+            };
+            return Err(CompileErrorKind::NestingTooDeep { span });
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, chain: usize, span: &Span) -> Result<(), CompileErrorKind> {
+        if self.depth + chain > MAX_DEPTH {
+            Err(CompileErrorKind::NestingTooDeep { span: span.clone() })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn expect(&mut self, expected: Token<'static>, name: &str) -> Result<Span, CompileErrorKind> {
+        match self.peek()? {
+            Some((tok, _)) if tok == expected => Ok(self.bump()),
+            _ => self.unexpected_token(name),
+        }
+    }
+
+    fn bump(&mut self) -> Span {
+        let (_, span) = self
+            .peek
+            .take()
+            .expect("called bump without peek returning Some()")
+            .expect("called bump on invalid token");
+        span
+    }
+
+    fn peek(&mut self) -> Result<Option<(Token<'a>, Span)>, CompileErrorKind> {
+        if self.peek.is_none() {
+            self.peek = self.next();
+        }
+        self.peek.clone().transpose()
+    }
+
+    fn next(&mut self) -> Option<Result<(Token<'a>, Span), CompileErrorKind>> {
+        debug_assert!(self.peek.is_none());
+        match self.lexer.next() {
+            Some(Err(())) => Some(Err(self.lexer.extras.error.take().unwrap_or_else(|| {
+                CompileErrorKind::InvalidToken {
+                    span: self.lexer.span(),
+                }
+            }))),
+            Some(Ok(tok)) => Some(Ok((tok, self.lexer.span()))),
+            None => None,
+        }
+    }
+
+    fn unexpected_token<T>(&mut self, expected: impl ToString) -> Result<T, CompileErrorKind> {
+        match self.peek()? {
+            Some((found, span)) => Err(CompileErrorKind::UnexpectedToken {
+                expected: expected.to_string(),
+                found: found.to_string(),
+                span,
+            }),
+            None => Err(CompileErrorKind::UnexpectedEof {
+                expected: expected.to_string(),
+            }),
+        }
+    }
+}
+
+fn join_span(start: &Span, end: &Span) -> Span {
+    start.start..end.end
+}
@@ -0,0 +1,213 @@
+//! Arithmetic and calendar functions for the `google.protobuf.Timestamp` and `google.protobuf.Duration`
+//! types.
+
+use std::str::FromStr;
+
+use super::{CelValue, EvalError};
+use crate::dynamic::wkt::{check_duration, check_timestamp};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+pub(super) fn timestamp_nanos(timestamp: &prost_types::Timestamp) -> i128 {
+    i128::from(timestamp.seconds) * NANOS_PER_SECOND + i128::from(timestamp.nanos)
+}
+
+pub(super) fn duration_nanos(duration: &prost_types::Duration) -> i128 {
+    i128::from(duration.seconds) * NANOS_PER_SECOND + i128::from(duration.nanos)
+}
+
+/// Converts a number of nanoseconds since the unix epoch to a timestamp, returning `None` if it is out
+/// of the range of valid timestamps.
+pub(super) fn timestamp_from_nanos(nanos: i128) -> Option<prost_types::Timestamp> {
+    let timestamp = prost_types::Timestamp {
+        seconds: i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).ok()?,
+        nanos: nanos.rem_euclid(NANOS_PER_SECOND) as i32,
+    };
+    check_timestamp(&timestamp).ok()?;
+    Some(timestamp)
+}
+
+/// Converts a number of nanoseconds to a duration, returning `None` if it is out of the range of valid
+/// durations.
+pub(super) fn duration_from_nanos(nanos: i128) -> Option<prost_types::Duration> {
+    let duration = prost_types::Duration {
+        seconds: i64::try_from(nanos / NANOS_PER_SECOND).ok()?,
+        nanos: (nanos % NANOS_PER_SECOND) as i32,
+    };
+    check_duration(&duration).ok()?;
+    Some(duration)
+}
+
+/// Parses an RFC 3339 timestamp, such as `2023-01-01T00:00:00Z`.
+pub(super) fn parse_timestamp(s: &str) -> Option<prost_types::Timestamp> {
+    let timestamp = prost_types::Timestamp::from_str(s).ok()?;
+    check_timestamp(&timestamp).ok()?;
+    Some(timestamp)
+}
+
+/// Parses a duration as a sequence of decimal numbers with unit suffixes, such as `1h30m` or `-1.5s`.
+/// The supported units are `h`, `m`, `s`, `ms`, `us` and `ns`.
+pub(super) fn parse_duration(s: &str) -> Option<prost_types::Duration> {
+    let (negative, mut rest) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    if rest == "0" {
+        return Some(prost_types::Duration::default());
+    }
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total: i128 = 0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|ch: char| !ch.is_ascii_digit() && ch != '.')
+            .unwrap_or(rest.len());
+        let (number, suffix) = rest.split_at(number_len);
+        let unit_len = suffix
+            .find(|ch: char| ch.is_ascii_digit() || ch == '.')
+            .unwrap_or(suffix.len());
+        let (unit, remainder) = suffix.split_at(unit_len);
+
+        let unit_nanos: i128 = match unit {
+            "h" => 3600 * NANOS_PER_SECOND,
+            "m" => 60 * NANOS_PER_SECOND,
+            "s" => NANOS_PER_SECOND,
+            "ms" => 1_000_000,
+            "us" | "µs" => 1_000,
+            "ns" => 1,
+            _ => return None,
+        };
+
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if integer.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let integer: i128 = if integer.is_empty() {
+            0
+        } else {
+            integer.parse().ok()?
+        };
+        let mut value = integer.checked_mul(unit_nanos)?;
+        let mut scale = unit_nanos;
+        for digit in fraction.bytes() {
+            scale /= 10;
+            value = value.checked_add(i128::from(digit - b'0') * scale)?;
+        }
+
+        total = total.checked_add(value)?;
+        rest = remainder;
+    }
+
+    duration_from_nanos(if negative { -total } else { total })
+}
+
+/// Parses a time zone as a fixed offset from UTC in seconds. Only `UTC` and offsets such as `+05:30` are
+/// supported.
+pub(super) fn parse_offset(tz: &str) -> Option<i64> {
+    if matches!(tz, "UTC" | "Z" | "GMT" | "Etc/UTC") {
+        return Some(0);
+    }
+
+    let (sign, rest) = match tz.as_bytes().first()? {
+        b'+' => (1, &tz[1..]),
+        b'-' => (-1, &tz[1..]),
+        _ => (1, tz),
+    };
+    let (hours, minutes) = rest.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Evaluates a calendar accessor such as `getFullYear` on a timestamp, in the time zone with the given
+/// offset from UTC.
+///
+/// Returns `Ok(None)` if `function` is not a timestamp accessor, or an error if the timestamp cannot be
+/// adjusted by the offset without overflowing.
+pub(super) fn timestamp_component(
+    function: &str,
+    timestamp: &prost_types::Timestamp,
+    offset: i64,
+) -> Result<Option<CelValue>, EvalError> {
+    let seconds = timestamp
+        .seconds
+        .checked_add(offset)
+        .ok_or_else(|| EvalError::Overflow {
+            function: function.to_owned(),
+        })?;
+    let days = seconds.div_euclid(86_400);
+    let seconds_of_day = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    let value = match function {
+        "getFullYear" => year,
+        "getMonth" => i64::from(month) - 1,
+        "getDate" => i64::from(day),
+        "getDayOfMonth" => i64::from(day) - 1,
+        "getDayOfYear" => days - days_from_civil(year, 1, 1),
+        // 1970-01-01 was a Thursday.
+        "getDayOfWeek" => (days + 4).rem_euclid(7),
+        "getHours" => seconds_of_day / 3600,
+        "getMinutes" => seconds_of_day % 3600 / 60,
+        "getSeconds" => seconds_of_day % 60,
+        "getMilliseconds" => i64::from(timestamp.nanos / 1_000_000),
+        _ => return Ok(None),
+    };
+    Ok(Some(CelValue::Int(value)))
+}
+
+/// Evaluates an accessor such as `getHours` on a duration, which returns the total duration in the
+/// given unit.
+pub(super) fn duration_component(
+    function: &str,
+    duration: &prost_types::Duration,
+) -> Option<CelValue> {
+    let nanos = duration_nanos(duration);
+    let value = match function {
+        "getHours" => nanos / (3600 * NANOS_PER_SECOND),
+        "getMinutes" => nanos / (60 * NANOS_PER_SECOND),
+        "getSeconds" => nanos / NANOS_PER_SECOND,
+        "getMilliseconds" => nanos / 1_000_000,
+        _ => return None,
+    };
+    Some(CelValue::Int(value as i64))
+}
+
+/// Converts a number of days since the unix epoch to a (year, month, day) date in the proleptic
+/// Gregorian calendar.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a date in the proleptic Gregorian calendar to a number of days since the unix epoch.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
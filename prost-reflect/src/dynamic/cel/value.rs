@@ -0,0 +1,714 @@
+use std::{collections::HashMap, fmt};
+
+use prost::bytes::Bytes;
+
+use crate::{
+    dynamic::wkt::{check_duration, check_timestamp},
+    DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, ReflectMessage, TimeError,
+    Value,
+};
+
+/// The type of a CEL expression or value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "cel")))]
+pub enum CelType {
+    /// The type of `null`.
+    Null,
+    /// A boolean.
+    Bool,
+    /// A signed 64-bit integer. Protobuf enum values also have this type.
+    Int,
+    /// An unsigned 64-bit integer.
+    Uint,
+    /// A 64-bit floating point number.
+    Double,
+    /// A unicode string.
+    String,
+    /// A sequence of bytes.
+    Bytes,
+    /// A `google.protobuf.Duration`.
+    Duration,
+    /// A `google.protobuf.Timestamp`.
+    Timestamp,
+    /// A list whose elements have the given type.
+    List(Box<CelType>),
+    /// A map with the given key and value types.
+    Map(Box<CelType>, Box<CelType>),
+    /// A protobuf message.
+    ///
+    /// Well-known types with a special representation in CEL, such as `google.protobuf.Timestamp` and the wrapper
+    /// types, are converted to their CEL types when declaring variables. See the [`From`] implementation.
+    Message(MessageDescriptor),
+    /// A wrapper type such as `google.protobuf.Int64Value`, which is either `null` or a value of the given type.
+    Wrapper(Box<CelType>),
+    /// The type of a type, such as the result of `type(x)`.
+    Type,
+    /// A type which is only known at runtime, such as a field of a `google.protobuf.Struct`.
+    Dyn,
+}
+
+/// A value produced by, or provided as a variable to, a CEL expression.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "cel")))]
+pub enum CelValue {
+    /// The `null` value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// An unsigned integer.
+    Uint(u64),
+    /// A floating point number.
+    Double(f64),
+    /// A string.
+    String(String),
+    /// A sequence of bytes.
+    Bytes(Bytes),
+    /// A duration.
+    Duration(prost_types::Duration),
+    /// A point in time.
+    Timestamp(prost_types::Timestamp),
+    /// A list of values.
+    List(Vec<CelValue>),
+    /// A map of values.
+    ///
+    /// Keys are represented using [`MapKey::I64`], [`MapKey::U64`], [`MapKey::Bool`] or [`MapKey::String`].
+    Map(HashMap<MapKey, CelValue>),
+    /// A protobuf message.
+    Message(DynamicMessage),
+    /// A type.
+    Type(CelType),
+}
+
+impl CelType {
+    pub(super) fn for_kind(kind: &Kind) -> CelType {
+        match kind {
+            Kind::Double | Kind::Float => CelType::Double,
+            Kind::Int32
+            | Kind::Int64
+            | Kind::Sint32
+            | Kind::Sint64
+            | Kind::Sfixed32
+            | Kind::Sfixed64 => CelType::Int,
+            Kind::Uint32 | Kind::Uint64 | Kind::Fixed32 | Kind::Fixed64 => CelType::Uint,
+            Kind::Bool => CelType::Bool,
+            Kind::String => CelType::String,
+            Kind::Bytes => CelType::Bytes,
+            Kind::Message(desc) => CelType::from(desc.clone()),
+            Kind::Enum(_) => CelType::Int,
+        }
+    }
+
+    pub(super) fn for_field(field: &FieldDescriptor) -> CelType {
+        let kind = field.kind();
+        if field.is_map() {
+            let entry = kind.as_message().expect("map entry must be a message");
+            CelType::Map(
+                Box::new(CelType::for_kind(&entry.map_entry_key_field().kind())),
+                Box::new(CelType::for_kind(&entry.map_entry_value_field().kind())),
+            )
+        } else if field.is_list() {
+            CelType::List(Box::new(CelType::for_kind(&kind)))
+        } else {
+            CelType::for_kind(&kind)
+        }
+    }
+
+    /// Converts any well-known message types to their CEL representation.
+    pub(super) fn normalize(self) -> CelType {
+        match self {
+            CelType::Message(desc) => CelType::from(desc),
+            CelType::List(elem) => CelType::List(Box::new(elem.normalize())),
+            CelType::Map(key, value) => {
+                CelType::Map(Box::new(key.normalize()), Box::new(value.normalize()))
+            }
+            CelType::Wrapper(inner) => CelType::Wrapper(Box::new(inner.normalize())),
+            ty => ty,
+        }
+    }
+
+    /// Returns the type for a builtin type name such as `int` or `list`.
+    pub(super) fn builtin(name: &str) -> Option<CelType> {
+        match name {
+            "null_type" => Some(CelType::Null),
+            "bool" => Some(CelType::Bool),
+            "int" => Some(CelType::Int),
+            "uint" => Some(CelType::Uint),
+            "double" => Some(CelType::Double),
+            "string" => Some(CelType::String),
+            "bytes" => Some(CelType::Bytes),
+            "list" => Some(CelType::List(Box::new(CelType::Dyn))),
+            "map" => Some(CelType::Map(Box::new(CelType::Dyn), Box::new(CelType::Dyn))),
+            "type" => Some(CelType::Type),
+            _ => None,
+        }
+    }
+}
+
+impl From<MessageDescriptor> for CelType {
+    /// Returns the CEL type for values of the given message type.
+    ///
+    /// `google.protobuf.Timestamp` and `google.protobuf.Duration` map to [`CelType::Timestamp`] and
+    /// [`CelType::Duration`], wrapper types to [`CelType::Wrapper`], `google.protobuf.Struct` to a map from
+    /// strings to `dyn`, `google.protobuf.ListValue` to a list of `dyn`, and `google.protobuf.Value` and
+    /// `google.protobuf.Any` to `dyn`. Other messages map to [`CelType::Message`].
+    fn from(desc: MessageDescriptor) -> Self {
+        let wrapper = |ty| CelType::Wrapper(Box::new(ty));
+        match desc.full_name() {
+            "google.protobuf.Timestamp" => CelType::Timestamp,
+            "google.protobuf.Duration" => CelType::Duration,
+            "google.protobuf.BoolValue" => wrapper(CelType::Bool),
+            "google.protobuf.Int32Value" | "google.protobuf.Int64Value" => wrapper(CelType::Int),
+            "google.protobuf.UInt32Value" | "google.protobuf.UInt64Value" => wrapper(CelType::Uint),
+            "google.protobuf.FloatValue" | "google.protobuf.DoubleValue" => {
+                wrapper(CelType::Double)
+            }
+            "google.protobuf.StringValue" => wrapper(CelType::String),
+            "google.protobuf.BytesValue" => wrapper(CelType::Bytes),
+            "google.protobuf.Struct" => {
+                CelType::Map(Box::new(CelType::String), Box::new(CelType::Dyn))
+            }
+            "google.protobuf.ListValue" => CelType::List(Box::new(CelType::Dyn)),
+            "google.protobuf.Value" | "google.protobuf.Any" => CelType::Dyn,
+            _ => CelType::Message(desc),
+        }
+    }
+}
+
+impl CelValue {
+    /// Returns the runtime type of this value.
+    ///
+    /// The element types of lists and maps are not tracked at runtime, so they are reported as
+    /// [`CelType::Dyn`].
+    pub fn cel_type(&self) -> CelType {
+        match self {
+            CelValue::Null => CelType::Null,
+            CelValue::Bool(_) => CelType::Bool,
+            CelValue::Int(_) => CelType::Int,
+            CelValue::Uint(_) => CelType::Uint,
+            CelValue::Double(_) => CelType::Double,
+            CelValue::String(_) => CelType::String,
+            CelValue::Bytes(_) => CelType::Bytes,
+            CelValue::Duration(_) => CelType::Duration,
+            CelValue::Timestamp(_) => CelType::Timestamp,
+            CelValue::List(_) => CelType::List(Box::new(CelType::Dyn)),
+            CelValue::Map(_) => CelType::Map(Box::new(CelType::Dyn), Box::new(CelType::Dyn)),
+            CelValue::Message(message) => CelType::Message(message.descriptor()),
+            CelValue::Type(_) => CelType::Type,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::Bool`], or `None` otherwise.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            CelValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::Int`], or `None` otherwise.
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            CelValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::Uint`], or `None` otherwise.
+    pub fn as_uint(&self) -> Option<u64> {
+        match *self {
+            CelValue::Uint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::Double`], or `None` otherwise.
+    pub fn as_double(&self) -> Option<f64> {
+        match *self {
+            CelValue::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::String`], or `None` otherwise.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CelValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::List`], or `None` otherwise.
+    pub fn as_list(&self) -> Option<&[CelValue]> {
+        match self {
+            CelValue::List(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it is a [`CelValue::Message`], or `None` otherwise.
+    pub fn as_message(&self) -> Option<&DynamicMessage> {
+        match self {
+            CelValue::Message(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Converts the value of a field of `message` to its CEL representation.
+    pub(super) fn from_field(message: &DynamicMessage, field: &FieldDescriptor) -> CelValue {
+        if !field.is_list() && !field.is_map() && !message.has_field(field) {
+            if let Kind::Message(desc) = field.kind() {
+                if matches!(CelType::from(desc), CelType::Wrapper(_) | CelType::Dyn) {
+                    return CelValue::Null;
+                }
+            }
+        }
+
+        CelValue::from_value(&message.get_field(field))
+    }
+
+    pub(super) fn from_value(value: &Value) -> CelValue {
+        match value {
+            Value::Bool(value) => CelValue::Bool(*value),
+            Value::I32(value) => CelValue::Int((*value).into()),
+            Value::I64(value) => CelValue::Int(*value),
+            Value::U32(value) => CelValue::Uint((*value).into()),
+            Value::U64(value) => CelValue::Uint(*value),
+            Value::F32(value) => CelValue::Double((*value).into()),
+            Value::F64(value) => CelValue::Double(*value),
+            Value::String(value) => CelValue::String(value.clone()),
+            Value::Bytes(value) => CelValue::Bytes(value.clone()),
+            Value::EnumNumber(value) => CelValue::Int((*value).into()),
+            Value::Message(message) => CelValue::from_message(message),
+            Value::List(values) => {
+                CelValue::List(values.iter().map(CelValue::from_value).collect())
+            }
+            Value::Map(values) => CelValue::Map(
+                values
+                    .iter()
+                    .map(|(key, value)| (normalize_key(key), CelValue::from_value(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts a message to its CEL representation, unwrapping well-known types. Timestamps and durations
+    /// which are out of range are left as messages.
+    pub(super) fn from_message(message: &DynamicMessage) -> CelValue {
+        let unwrap_value = || {
+            message
+                .get_field_by_name("value")
+                .map_or(CelValue::Null, |value| CelValue::from_value(&value))
+        };
+
+        match message.descriptor().full_name() {
+            "google.protobuf.Timestamp" => match message.transcode_to::<prost_types::Timestamp>() {
+                Ok(timestamp) if check_timestamp(&timestamp).is_ok() => {
+                    CelValue::Timestamp(timestamp)
+                }
+                _ => CelValue::Message(message.clone()),
+            },
+            "google.protobuf.Duration" => match message.transcode_to::<prost_types::Duration>() {
+                Ok(duration) if check_duration(&duration).is_ok() => CelValue::Duration(duration),
+                _ => CelValue::Message(message.clone()),
+            },
+            "google.protobuf.BoolValue"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.DoubleValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue" => unwrap_value(),
+            "google.protobuf.Struct" => match message.transcode_to::<prost_types::Struct>() {
+                Ok(value) => from_json_struct(value),
+                Err(_) => CelValue::Message(message.clone()),
+            },
+            "google.protobuf.ListValue" => match message.transcode_to::<prost_types::ListValue>() {
+                Ok(value) => from_json_list(value),
+                Err(_) => CelValue::Message(message.clone()),
+            },
+            "google.protobuf.Value" => match message.transcode_to::<prost_types::Value>() {
+                Ok(value) => from_json(value),
+                Err(_) => CelValue::Message(message.clone()),
+            },
+            "google.protobuf.Any" => match message.unpack_any() {
+                Ok(unpacked) => CelValue::from_message(&unpacked),
+                Err(_) => CelValue::Message(message.clone()),
+            },
+            _ => CelValue::Message(message.clone()),
+        }
+    }
+
+    /// Converts this value to a protobuf value of the given kind, returning `None` if the types are
+    /// incompatible or the value is out of range.
+    pub(super) fn into_value(self, kind: &Kind) -> Option<Value> {
+        match (self, kind) {
+            (CelValue::Bool(value), Kind::Bool) => Some(Value::Bool(value)),
+            (CelValue::Int(value), Kind::Int32 | Kind::Sint32 | Kind::Sfixed32) => {
+                Some(Value::I32(value.try_into().ok()?))
+            }
+            (CelValue::Int(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => {
+                Some(Value::I64(value))
+            }
+            (CelValue::Int(value), Kind::Enum(_)) => {
+                Some(Value::EnumNumber(value.try_into().ok()?))
+            }
+            (CelValue::Uint(value), Kind::Uint32 | Kind::Fixed32) => {
+                Some(Value::U32(value.try_into().ok()?))
+            }
+            (CelValue::Uint(value), Kind::Uint64 | Kind::Fixed64) => Some(Value::U64(value)),
+            (CelValue::Double(value), Kind::Float) => Some(Value::F32(value as f32)),
+            (CelValue::Double(value), Kind::Double) => Some(Value::F64(value)),
+            (CelValue::String(value), Kind::String) => Some(Value::String(value)),
+            (CelValue::Bytes(value), Kind::Bytes) => Some(Value::Bytes(value)),
+            (value, Kind::Message(desc)) => value.into_message(desc).map(Value::Message),
+            _ => None,
+        }
+    }
+
+    fn into_message(self, desc: &MessageDescriptor) -> Option<DynamicMessage> {
+        let mut message = DynamicMessage::new(desc.clone());
+        match (desc.full_name(), self) {
+            (_, CelValue::Message(value)) if value.descriptor() == *desc => return Some(value),
+            ("google.protobuf.Timestamp", CelValue::Timestamp(value)) => {
+                message.transcode_from(&value).ok()?
+            }
+            ("google.protobuf.Duration", CelValue::Duration(value)) => {
+                message.transcode_from(&value).ok()?
+            }
+            ("google.protobuf.Struct", value @ CelValue::Map(_))
+            | ("google.protobuf.ListValue", value @ CelValue::List(_))
+            | ("google.protobuf.Value", value) => {
+                let json = value.into_json()?;
+                match desc.full_name() {
+                    "google.protobuf.Struct" => match json.kind? {
+                        prost_types::value::Kind::StructValue(value) => {
+                            message.transcode_from(&value).ok()?
+                        }
+                        _ => return None,
+                    },
+                    "google.protobuf.ListValue" => match json.kind? {
+                        prost_types::value::Kind::ListValue(value) => {
+                            message.transcode_from(&value).ok()?
+                        }
+                        _ => return None,
+                    },
+                    _ => message.transcode_from(&json).ok()?,
+                }
+            }
+            ("google.protobuf.Any", value) => {
+                let value = match value {
+                    CelValue::Message(value) => value,
+                    CelValue::Null => return None,
+                    value => {
+                        let kind = value.well_known_kind(desc)?;
+                        value.into_value(&kind)?.as_message()?.clone()
+                    }
+                };
+                return Some(value.pack_any());
+            }
+            (_, value) => {
+                // Wrapper types.
+                let field = desc.get_field_by_name("value")?;
+                if !matches!(CelType::from(desc.clone()), CelType::Wrapper(_)) {
+                    return None;
+                }
+                let value = value.into_value(&field.kind())?;
+                message.try_set_field(&field, value).ok()?;
+            }
+        }
+        Some(message)
+    }
+
+    /// Returns the well-known type used to pack this value into a `google.protobuf.Any`.
+    fn well_known_kind(&self, any_desc: &MessageDescriptor) -> Option<Kind> {
+        let name = match self {
+            CelValue::Bool(_) => "google.protobuf.BoolValue",
+            CelValue::Int(_) => "google.protobuf.Int64Value",
+            CelValue::Uint(_) => "google.protobuf.UInt64Value",
+            CelValue::Double(_) => "google.protobuf.DoubleValue",
+            CelValue::String(_) => "google.protobuf.StringValue",
+            CelValue::Bytes(_) => "google.protobuf.BytesValue",
+            CelValue::Duration(_) => "google.protobuf.Duration",
+            CelValue::Timestamp(_) => "google.protobuf.Timestamp",
+            CelValue::List(_) => "google.protobuf.ListValue",
+            CelValue::Map(_) => "google.protobuf.Struct",
+            _ => return None,
+        };
+        let pool = any_desc.parent_pool();
+        pool.get_message_by_name(name)
+            .or_else(|| crate::DescriptorPool::global().get_message_by_name(name))
+            .map(Kind::Message)
+    }
+
+    fn into_json(self) -> Option<prost_types::Value> {
+        use prost_types::value::Kind as JsonKind;
+
+        let kind = match self {
+            CelValue::Null => JsonKind::NullValue(0),
+            CelValue::Bool(value) => JsonKind::BoolValue(value),
+            CelValue::Int(value) => JsonKind::NumberValue(value as f64),
+            CelValue::Uint(value) => JsonKind::NumberValue(value as f64),
+            CelValue::Double(value) => JsonKind::NumberValue(value),
+            CelValue::String(value) => JsonKind::StringValue(value),
+            CelValue::List(values) => JsonKind::ListValue(prost_types::ListValue {
+                values: values
+                    .into_iter()
+                    .map(CelValue::into_json)
+                    .collect::<Option<_>>()?,
+            }),
+            CelValue::Map(values) => JsonKind::StructValue(prost_types::Struct {
+                fields: values
+                    .into_iter()
+                    .map(|(key, value)| match key {
+                        MapKey::String(key) => Some((key, value.into_json()?)),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            }),
+            _ => return None,
+        };
+        Some(prost_types::Value { kind: Some(kind) })
+    }
+}
+
+fn from_json(value: prost_types::Value) -> CelValue {
+    use prost_types::value::Kind as JsonKind;
+
+    match value.kind {
+        None | Some(JsonKind::NullValue(_)) => CelValue::Null,
+        Some(JsonKind::NumberValue(value)) => CelValue::Double(value),
+        Some(JsonKind::StringValue(value)) => CelValue::String(value),
+        Some(JsonKind::BoolValue(value)) => CelValue::Bool(value),
+        Some(JsonKind::StructValue(value)) => from_json_struct(value),
+        Some(JsonKind::ListValue(value)) => from_json_list(value),
+    }
+}
+
+fn from_json_struct(value: prost_types::Struct) -> CelValue {
+    CelValue::Map(
+        value
+            .fields
+            .into_iter()
+            .map(|(key, value)| (MapKey::String(key), from_json(value)))
+            .collect(),
+    )
+}
+
+fn from_json_list(value: prost_types::ListValue) -> CelValue {
+    CelValue::List(value.values.into_iter().map(from_json).collect())
+}
+
+/// Converts a map key to one of the representations used by [`CelValue::Map`].
+pub(super) fn normalize_key(key: &MapKey) -> MapKey {
+    match key {
+        MapKey::I32(value) => MapKey::I64((*value).into()),
+        MapKey::U32(value) => MapKey::U64((*value).into()),
+        key => key.clone(),
+    }
+}
+
+/// Converts a value to a map key, if it has a valid key type.
+pub(super) fn to_map_key(value: &CelValue) -> Option<MapKey> {
+    match value {
+        CelValue::Bool(value) => Some(MapKey::Bool(*value)),
+        CelValue::Int(value) => Some(MapKey::I64(*value)),
+        CelValue::Uint(value) => Some(MapKey::U64(*value)),
+        CelValue::String(value) => Some(MapKey::String(value.clone())),
+        _ => None,
+    }
+}
+
+/// Converts a map key to a value of the key field of a map entry.
+pub(super) fn to_entry_key(key: MapKey, kind: &Kind) -> Option<MapKey> {
+    match (key, kind) {
+        (MapKey::Bool(value), Kind::Bool) => Some(MapKey::Bool(value)),
+        (MapKey::I64(value), Kind::Int32 | Kind::Sint32 | Kind::Sfixed32) => {
+            Some(MapKey::I32(value.try_into().ok()?))
+        }
+        (MapKey::I64(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => {
+            Some(MapKey::I64(value))
+        }
+        (MapKey::U64(value), Kind::Uint32 | Kind::Fixed32) => {
+            Some(MapKey::U32(value.try_into().ok()?))
+        }
+        (MapKey::U64(value), Kind::Uint64 | Kind::Fixed64) => Some(MapKey::U64(value)),
+        (MapKey::String(value), Kind::String) => Some(MapKey::String(value)),
+        _ => None,
+    }
+}
+
+/// Formats a duration in the same format as the `string()` conversion, such as `1.5s`.
+pub(super) fn format_duration(duration: &prost_types::Duration) -> String {
+    let mut result = String::new();
+    if duration.seconds < 0 || duration.nanos < 0 {
+        result.push('-');
+    }
+    result.push_str(&duration.seconds.unsigned_abs().to_string());
+    if duration.nanos != 0 {
+        let nanos = format!("{:09}", duration.nanos.unsigned_abs());
+        result.push('.');
+        result.push_str(nanos.trim_end_matches('0'));
+    }
+    result.push('s');
+    result
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for CelValue {
+                fn from(value: $ty) -> Self {
+                    CelValue::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    bool => Bool,
+    i32 => Int,
+    i64 => Int,
+    u32 => Uint,
+    u64 => Uint,
+    f32 => Double,
+    f64 => Double,
+    String => String,
+    &str => String,
+    Bytes => Bytes,
+    Vec<u8> => Bytes,
+    Vec<CelValue> => List,
+    HashMap<MapKey, CelValue> => Map,
+}
+
+impl TryFrom<prost_types::Duration> for CelValue {
+    type Error = TimeError;
+
+    /// Converts a duration to a CEL value, returning an error if it is outside the range of valid
+    /// `google.protobuf.Duration` values.
+    fn try_from(value: prost_types::Duration) -> Result<Self, Self::Error> {
+        match check_duration(&value) {
+            Ok(()) => Ok(CelValue::Duration(value)),
+            Err(_) => Err(TimeError::OutOfRange {
+                message_name: "google.protobuf.Duration",
+            }),
+        }
+    }
+}
+
+impl TryFrom<prost_types::Timestamp> for CelValue {
+    type Error = TimeError;
+
+    /// Converts a timestamp to a CEL value, returning an error if it is outside the range of valid
+    /// `google.protobuf.Timestamp` values.
+    fn try_from(value: prost_types::Timestamp) -> Result<Self, Self::Error> {
+        match check_timestamp(&value) {
+            Ok(()) => Ok(CelValue::Timestamp(value)),
+            Err(_) => Err(TimeError::OutOfRange {
+                message_name: "google.protobuf.Timestamp",
+            }),
+        }
+    }
+}
+
+impl From<DynamicMessage> for CelValue {
+    /// Converts a message to a CEL value, converting well-known types such as `google.protobuf.Timestamp` and the
+    /// wrapper types to their CEL representation.
+    fn from(message: DynamicMessage) -> Self {
+        CelValue::from_message(&message)
+    }
+}
+
+impl From<Value> for CelValue {
+    fn from(value: Value) -> Self {
+        CelValue::from_value(&value)
+    }
+}
+
+impl fmt::Display for CelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CelType::Null => write!(f, "null_type"),
+            CelType::Bool => write!(f, "bool"),
+            CelType::Int => write!(f, "int"),
+            CelType::Uint => write!(f, "uint"),
+            CelType::Double => write!(f, "double"),
+            CelType::String => write!(f, "string"),
+            CelType::Bytes => write!(f, "bytes"),
+            CelType::Duration => write!(f, "google.protobuf.Duration"),
+            CelType::Timestamp => write!(f, "google.protobuf.Timestamp"),
+            CelType::List(elem) => write!(f, "list({})", elem),
+            CelType::Map(key, value) => write!(f, "map({}, {})", key, value),
+            CelType::Message(desc) => write!(f, "{}", desc.full_name()),
+            CelType::Wrapper(inner) => write!(f, "wrapper({})", inner),
+            CelType::Type => write!(f, "type"),
+            CelType::Dyn => write!(f, "dyn"),
+        }
+    }
+}
+
+impl fmt::Display for CelValue {
+    /// Formats the value using CEL literal syntax, where possible.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CelValue::Null => write!(f, "null"),
+            CelValue::Bool(value) => write!(f, "{}", value),
+            CelValue::Int(value) => write!(f, "{}", value),
+            CelValue::Uint(value) => write!(f, "{}u", value),
+            CelValue::Double(value) => {
+                if value.is_finite() && value.fract() == 0.0 {
+                    write!(f, "{:.1}", value)
+                } else {
+                    write!(f, "{}", value)
+                }
+            }
+            CelValue::String(value) => write!(f, "{:?}", value),
+            CelValue::Bytes(value) => {
+                write!(f, "b\"")?;
+                for &byte in value {
+                    write!(f, "{}", std::ascii::escape_default(byte))?;
+                }
+                write!(f, "\"")
+            }
+            CelValue::Duration(value) => write!(f, "duration(\"{}\")", format_duration(value)),
+            CelValue::Timestamp(value) => write!(f, "timestamp(\"{}\")", value),
+            CelValue::List(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            CelValue::Map(values) => {
+                let mut entries: Vec<_> = values.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.into_iter().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", CelValue::from(Value::from(key.clone())), value)?;
+                }
+                write!(f, "}}")
+            }
+            CelValue::Message(message) => {
+                write!(f, "{}{{", message.descriptor().full_name())?;
+                for (index, (field, value)) in message.fields().enumerate() {
+                    if index != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field.name(), CelValue::from_value(value))?;
+                }
+                write!(f, "}}")
+            }
+            CelValue::Type(ty) => write!(f, "{}", ty),
+        }
+    }
+}
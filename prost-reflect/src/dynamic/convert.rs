@@ -0,0 +1,427 @@
+use std::{collections::HashMap, fmt};
+
+use prost::{bytes::Bytes, Message};
+
+use crate::{
+    dynamic::{
+        diff::{index_path, join_path, key_path},
+        fields::ValueAndDescriptor,
+        FieldDescriptorLike,
+    },
+    DynamicMessage, Kind, MessageDescriptor, Value,
+};
+
+/// Options to control how [`DynamicMessage::convert_to()`] maps fields between message types.
+#[derive(Debug, Clone)]
+pub struct ConversionOptions {
+    match_by_name: bool,
+    preserve_unknown_fields: bool,
+}
+
+/// The result of converting a message with [`DynamicMessage::convert_to()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    message: DynamicMessage,
+    dropped: Vec<DroppedField>,
+}
+
+/// A value which could not be copied to the target message by [`DynamicMessage::convert_to()`].
+///
+/// The path uses field names of the source message separated by `.`, with list indices and map keys in square
+/// brackets, for example `nested.items[3].name` or `labels["env"]`. Extension fields are written as
+/// `[full.extension.name]` and unknown fields are identified by their field number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedField {
+    path: String,
+    value: Value,
+    reason: DropReason,
+}
+
+/// The reason a value was dropped during conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    /// The target message has no field matching the source field.
+    NotFound,
+    /// The target field has a type which cannot represent the value without loss.
+    Incompatible,
+    /// The value is an unknown field of the source message, and
+    /// [`preserve_unknown_fields`](ConversionOptions::preserve_unknown_fields) is not set.
+    NotPreserved,
+}
+
+impl ConversionOptions {
+    /// Creates a new instance of [`ConversionOptions`], with the default options.
+    pub const fn new() -> Self {
+        ConversionOptions {
+            match_by_name: false,
+            preserve_unknown_fields: true,
+        }
+    }
+
+    /// Whether to match fields by name instead of by number.
+    ///
+    /// When matching by name, extension fields are matched by their full name, or by their
+    /// unqualified name if the target message has no extension with the same full name, and enum
+    /// values are converted by name. When matching by number, enum values keep their number.
+    ///
+    /// The default value is `false`.
+    pub const fn match_by_name(mut self, yes: bool) -> Self {
+        self.match_by_name = yes;
+        self
+    }
+
+    /// Whether to copy unknown fields of the source message to the target message.
+    ///
+    /// When matching by number, unknown fields whose number corresponds to a field of the target message are
+    /// decoded into that field. When matching by name, unknown fields cannot be matched, and are reported as
+    /// dropped. If this is not set, unknown fields are reported as dropped with
+    /// [`DropReason::NotPreserved`].
+    ///
+    /// The default value is `true`.
+    pub const fn preserve_unknown_fields(mut self, yes: bool) -> Self {
+        self.preserve_unknown_fields = yes;
+        self
+    }
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Conversion {
+    /// Gets a reference to the converted message.
+    pub fn message(&self) -> &DynamicMessage {
+        &self.message
+    }
+
+    /// Consumes this conversion, returning the converted message.
+    pub fn into_message(self) -> DynamicMessage {
+        self.message
+    }
+
+    /// Gets the values of the source message which are not present in the converted message.
+    pub fn dropped_fields(&self) -> &[DroppedField] {
+        &self.dropped
+    }
+
+    /// Returns `true` if every value of the source message was copied to the converted message.
+    pub fn is_lossless(&self) -> bool {
+        self.dropped.is_empty()
+    }
+}
+
+impl DroppedField {
+    /// Returns the path of the dropped value in the source message.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the dropped value. Unknown fields are returned as their encoded bytes.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the reason the value was dropped.
+    pub fn reason(&self) -> DropReason {
+        self.reason
+    }
+}
+
+impl fmt::Display for DroppedField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            DropReason::NotFound => {
+                write!(f, "no matching field for {}: {}", self.path, self.value)
+            }
+            DropReason::Incompatible => {
+                write!(f, "incompatible value for {}: {}", self.path, self.value)
+            }
+            DropReason::NotPreserved => {
+                write!(
+                    f,
+                    "unknown field not preserved for {}: {}",
+                    self.path, self.value
+                )
+            }
+        }
+    }
+}
+
+impl DynamicMessage {
+    /// Converts this message to the message type `desc`, which may be from a different schema.
+    ///
+    /// Fields are matched by number, or by name if [`ConversionOptions::match_by_name`] is set, and
+    /// nested messages are converted recursively. Values are converted to the type of the target field if
+    /// this is lossless: integers and floats may be widened (for example, `int32` to `int64`, or `float` to
+    /// `double`), enums may be converted to and from `int32`, strings may be converted to `bytes`, and a
+    /// singular field may be converted to a repeated field.
+    ///
+    /// Values which cannot be converted are omitted from the result, and reported in
+    /// [`Conversion::dropped_fields()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # use prost_reflect::convert::ConversionOptions;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("foo", Value::I32(150));
+    ///
+    /// let empty = pool.get_message_by_name("google.protobuf.Empty").unwrap();
+    /// let conversion = dynamic_message.convert_to(&empty, &ConversionOptions::new());
+    /// assert!(!conversion.is_lossless());
+    /// assert_eq!(conversion.dropped_fields()[0].to_string(), "no matching field for foo: 150");
+    /// ```
+    pub fn convert_to(&self, desc: &MessageDescriptor, options: &ConversionOptions) -> Conversion {
+        let mut converter = Converter {
+            options,
+            dropped: Vec::new(),
+        };
+        let message = converter.convert_message("", self, desc);
+        Conversion {
+            message,
+            dropped: converter.dropped,
+        }
+    }
+}
+
+struct Converter<'a> {
+    options: &'a ConversionOptions,
+    dropped: Vec<DroppedField>,
+}
+
+impl Converter<'_> {
+    fn convert_message(
+        &mut self,
+        path: &str,
+        source: &DynamicMessage,
+        desc: &MessageDescriptor,
+    ) -> DynamicMessage {
+        let mut target = DynamicMessage::new(desc.clone());
+        for field in source.fields.iter(&source.desc, false, false) {
+            match field {
+                ValueAndDescriptor::Field(value, source_field) => {
+                    let path = join_path(path, source_field.name());
+                    let target_field = if self.options.match_by_name {
+                        desc.get_field_by_name(source_field.name())
+                    } else {
+                        desc.get_field(source_field.number())
+                    };
+                    match target_field {
+                        Some(target_field) => self.convert_field(
+                            path,
+                            &value,
+                            &source_field,
+                            &target_field,
+                            &mut target,
+                        ),
+                        None => self.drop(path, value.into_owned(), DropReason::NotFound),
+                    }
+                }
+                ValueAndDescriptor::Extension(value, source_extension) => {
+                    let path = join_path(path, &format!("[{}]", source_extension.full_name()));
+                    let target_extension = if self.options.match_by_name {
+                        // The extensions may be declared in different packages, so fall back to
+                        // matching the unqualified name.
+                        desc.get_extension_by_full_name(source_extension.full_name())
+                            .or_else(|| {
+                                desc.extensions()
+                                    .find(|ext| ext.name() == source_extension.name())
+                            })
+                    } else {
+                        desc.get_extension(source_extension.number())
+                    };
+                    match target_extension {
+                        Some(target_extension) => self.convert_field(
+                            path,
+                            &value,
+                            &source_extension,
+                            &target_extension,
+                            &mut target,
+                        ),
+                        None => self.drop(path, value.into_owned(), DropReason::NotFound),
+                    }
+                }
+                ValueAndDescriptor::Unknown(fields) => {
+                    for field in fields.iter() {
+                        let number = field.number();
+                        let path = join_path(path, &number.to_string());
+                        let mut bytes = Vec::with_capacity(field.encoded_len());
+                        field.encode(&mut bytes);
+                        let bytes = Bytes::from(bytes);
+                        if !self.options.preserve_unknown_fields {
+                            self.drop(path, Value::Bytes(bytes), DropReason::NotPreserved);
+                        } else if self.options.match_by_name {
+                            self.drop(path, Value::Bytes(bytes), DropReason::NotFound);
+                        } else if desc.get_field(number).is_some()
+                            || desc.get_extension(number).is_some()
+                        {
+                            if target.merge(bytes.clone()).is_err() {
+                                self.drop(path, Value::Bytes(bytes), DropReason::Incompatible);
+                            }
+                        } else {
+                            target.add_unknown_field(field.clone());
+                        }
+                    }
+                }
+            }
+        }
+        target
+    }
+
+    fn convert_field(
+        &mut self,
+        path: String,
+        value: &Value,
+        source: &impl FieldDescriptorLike,
+        target: &impl FieldDescriptorLike,
+        message: &mut DynamicMessage,
+    ) {
+        let converted = if target.is_map() {
+            self.convert_map(&path, value, source, target)
+        } else if target.is_list() {
+            self.convert_list(&path, value, source, target)
+        } else if source.is_list() || source.is_map() {
+            None
+        } else {
+            self.convert_singular(&path, value, &source.kind(), &target.kind())
+        };
+
+        match converted {
+            Some(converted) => message.fields.set(target, converted),
+            None => self.drop(path, value.clone(), DropReason::Incompatible),
+        }
+    }
+
+    fn convert_map(
+        &mut self,
+        path: &str,
+        value: &Value,
+        source: &impl FieldDescriptorLike,
+        target: &impl FieldDescriptorLike,
+    ) -> Option<Value> {
+        let (map, source_entry, target_entry) = match (value, source.kind(), target.kind()) {
+            (Value::Map(map), Kind::Message(source_entry), Kind::Message(target_entry))
+                if source.is_map() =>
+            {
+                (map, source_entry, target_entry)
+            }
+            _ => return None,
+        };
+
+        let source_key = source_entry.map_entry_key_field().kind();
+        let source_value = source_entry.map_entry_value_field().kind();
+        let target_key = target_entry.map_entry_key_field().kind();
+        let target_value = target_entry.map_entry_value_field().kind();
+
+        let mut result = HashMap::with_capacity(map.len());
+        for (key, value) in map {
+            let path = key_path(path, key);
+            let entry = self
+                .convert_singular(&path, &Value::from(key.clone()), &source_key, &target_key)
+                .and_then(Value::into_map_key)
+                .and_then(|key| {
+                    let value =
+                        self.convert_singular(&path, value, &source_value, &target_value)?;
+                    Some((key, value))
+                });
+            match entry {
+                Some((key, value)) => {
+                    result.insert(key, value);
+                }
+                None => self.drop(path, value.clone(), DropReason::Incompatible),
+            }
+        }
+        Some(Value::Map(result))
+    }
+
+    fn convert_list(
+        &mut self,
+        path: &str,
+        value: &Value,
+        source: &impl FieldDescriptorLike,
+        target: &impl FieldDescriptorLike,
+    ) -> Option<Value> {
+        let source_kind = source.kind();
+        let target_kind = target.kind();
+        if source.is_map() {
+            return None;
+        } else if !source.is_list() {
+            let value = self.convert_singular(path, value, &source_kind, &target_kind)?;
+            return Some(Value::List(vec![value]));
+        }
+
+        let mut result = Vec::new();
+        for (index, value) in value.as_list()?.iter().enumerate() {
+            let path = index_path(path, index);
+            match self.convert_singular(&path, value, &source_kind, &target_kind) {
+                Some(value) => result.push(value),
+                None => self.drop(path, value.clone(), DropReason::Incompatible),
+            }
+        }
+        Some(Value::List(result))
+    }
+
+    fn convert_singular(
+        &mut self,
+        path: &str,
+        value: &Value,
+        source: &Kind,
+        target: &Kind,
+    ) -> Option<Value> {
+        Some(match (value, target) {
+            (Value::Bool(value), Kind::Bool) => Value::Bool(*value),
+            (Value::I32(value), Kind::Int32 | Kind::Sint32 | Kind::Sfixed32) => Value::I32(*value),
+            (Value::I32(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => {
+                Value::I64((*value).into())
+            }
+            (Value::I32(value), Kind::Double) => Value::F64((*value).into()),
+            (Value::I32(value), Kind::Enum(_)) => Value::EnumNumber(*value),
+            (Value::I64(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => Value::I64(*value),
+            (Value::U32(value), Kind::Uint32 | Kind::Fixed32) => Value::U32(*value),
+            (Value::U32(value), Kind::Uint64 | Kind::Fixed64) => Value::U64((*value).into()),
+            (Value::U32(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => {
+                Value::I64((*value).into())
+            }
+            (Value::U32(value), Kind::Double) => Value::F64((*value).into()),
+            (Value::U64(value), Kind::Uint64 | Kind::Fixed64) => Value::U64(*value),
+            (Value::F32(value), Kind::Float) => Value::F32(*value),
+            (Value::F32(value), Kind::Double) => Value::F64((*value).into()),
+            (Value::F64(value), Kind::Double) => Value::F64(*value),
+            (Value::String(value), Kind::String) => Value::String(value.clone()),
+            (Value::String(value), Kind::Bytes) => Value::Bytes(value.clone().into()),
+            (Value::Bytes(value), Kind::Bytes) => Value::Bytes(value.clone()),
+            (Value::EnumNumber(value), Kind::Int32 | Kind::Sint32 | Kind::Sfixed32) => {
+                Value::I32(*value)
+            }
+            (Value::EnumNumber(value), Kind::Int64 | Kind::Sint64 | Kind::Sfixed64) => {
+                Value::I64((*value).into())
+            }
+            (Value::EnumNumber(value), Kind::Enum(target_enum)) => {
+                match (source, self.options.match_by_name) {
+                    (Kind::Enum(source_enum), true) => {
+                        let name = source_enum.get_value(*value)?;
+                        Value::EnumNumber(target_enum.get_value_by_name(name.name())?.number())
+                    }
+                    _ => Value::EnumNumber(*value),
+                }
+            }
+            (Value::Message(message), Kind::Message(target_message)) => {
+                Value::Message(self.convert_message(path, message, target_message))
+            }
+            _ => return None,
+        })
+    }
+
+    fn drop(&mut self, path: String, value: Value, reason: DropReason) {
+        self.dropped.push(DroppedField {
+            path,
+            value,
+            reason,
+        });
+    }
+}
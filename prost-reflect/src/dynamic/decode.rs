@@ -0,0 +1,344 @@
+use std::mem;
+
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, DecodeContext, WireType},
+    DecodeError,
+};
+
+use crate::{DynamicMessage, Kind, MessageDescriptor, UnknownField, UnknownFieldValue, Value};
+
+/// The options used by [`DynamicMessage::decode()`] and the [`Message`](prost::Message) implementation
+/// of [`DynamicMessage`].
+static DEFAULT_OPTIONS: DecodeOptions = DecodeOptions::new();
+
+/// Options to control decoding of messages from the protobuf binary format.
+///
+/// Used by [`DynamicMessage::decode_with_options()`] and [`DynamicMessage::merge_with_options()`].
+/// The default options match the behaviour of [`DynamicMessage::decode()`] and
+/// [`Message::merge()`](prost::Message::merge). The options apply to all nested messages.
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    recursion_limit: u32,
+    allocation_limit: usize,
+    discard_unknown_fields: bool,
+    deny_unknown_fields: bool,
+}
+
+impl DecodeOptions {
+    /// Creates a new instance of [`DecodeOptions`], with the default options.
+    pub const fn new() -> Self {
+        DecodeOptions {
+            recursion_limit: 100,
+            allocation_limit: usize::MAX,
+            discard_unknown_fields: false,
+            deny_unknown_fields: false,
+        }
+    }
+
+    /// The maximum depth of nested messages and groups, including map entries and unknown groups.
+    ///
+    /// Decoding fails if the input is nested more deeply than this. The default value is `100`,
+    /// which matches the limit used by `prost`.
+    pub const fn recursion_limit(mut self, limit: u32) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// The maximum number of bytes the decoded message may allocate.
+    ///
+    /// This is an approximate budget, covering the contents of strings and bytes fields, the elements of
+    /// repeated and map fields, and the contents of unknown fields. Each value is charged against the
+    /// budget using its length prefix or element count before any memory is allocated for it, so
+    /// decoding fails before a large allocation is made. By default, there is no limit.
+    pub const fn allocation_limit(mut self, limit: usize) -> Self {
+        self.allocation_limit = limit;
+        self
+    }
+
+    /// Whether to drop fields which are not in the message descriptor instead of storing them as
+    /// unknown fields.
+    ///
+    /// The default value is `false`.
+    pub const fn discard_unknown_fields(mut self, yes: bool) -> Self {
+        self.discard_unknown_fields = yes;
+        self
+    }
+
+    /// Whether to error during decoding when encountering fields which are not in the message descriptor.
+    ///
+    /// If set, this takes precedence over [`discard_unknown_fields`](Self::discard_unknown_fields).
+    /// The default value is `false`.
+    pub const fn deny_unknown_fields(mut self, yes: bool) -> Self {
+        self.deny_unknown_fields = yes;
+        self
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicMessage {
+    /// Decodes an instance of the message type described by `desc` from `buf`, using the limits
+    /// specified by `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DecodeOptions, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let options = DecodeOptions::new().recursion_limit(1);
+    ///
+    /// let dynamic_message = DynamicMessage::decode_with_options(message_descriptor.clone(), b"\x08\x96\x01".as_ref(), &options).unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    ///
+    /// let nested = b"\x1a\x02\x1a\x00";
+    /// assert!(DynamicMessage::decode_with_options(message_descriptor, nested.as_ref(), &options).is_err());
+    /// ```
+    pub fn decode_with_options<B>(
+        desc: MessageDescriptor,
+        buf: B,
+        options: &DecodeOptions,
+    ) -> Result<Self, DecodeError>
+    where
+        B: Buf,
+    {
+        let mut message = DynamicMessage::new(desc);
+        message.merge_with_options(buf, options)?;
+        Ok(message)
+    }
+
+    /// Merges fields from `buf` into this message, using the limits specified by `options`.
+    ///
+    /// If an error is returned, the message may have been partially updated.
+    pub fn merge_with_options<B>(
+        &mut self,
+        mut buf: B,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        self.merge_with_state(&mut buf, &mut DecodeState::new(options))
+    }
+}
+
+/// The state of a single decoding operation, used to enforce the limits in a [`DecodeOptions`].
+pub(super) struct DecodeState<'a> {
+    options: &'a DecodeOptions,
+    depth: u32,
+    allocated: usize,
+}
+
+impl<'a> DecodeState<'a> {
+    pub(super) fn new(options: &'a DecodeOptions) -> Self {
+        DecodeState {
+            options,
+            depth: 0,
+            allocated: 0,
+        }
+    }
+
+    /// Enters a nested message or group, checking the recursion limit.
+    pub(super) fn enter(&mut self) -> Result<(), DecodeError> {
+        if self.depth >= self.options.recursion_limit {
+            return Err(DecodeError::new("recursion limit reached"));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(super) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Charges `size` bytes against the allocation limit. This must be called before the memory is
+    /// allocated.
+    pub(super) fn allocate(&mut self, size: usize) -> Result<(), DecodeError> {
+        self.allocated = self.allocated.saturating_add(size);
+        if self.allocated > self.options.allocation_limit {
+            return Err(DecodeError::new("allocation limit exceeded"));
+        }
+        Ok(())
+    }
+
+    /// Charges the space needed for the elements of a packed repeated field of type `kind`, whose
+    /// encoded length is `len`, and returns the number of elements charged.
+    ///
+    /// Varint elements can only be counted if the encoded data is contiguous. Otherwise only the
+    /// elements in the current chunk are charged, and the caller must charge any further elements
+    /// as they are decoded.
+    pub(super) fn allocate_packed<B>(
+        &mut self,
+        kind: &Kind,
+        buf: &B,
+        len: usize,
+    ) -> Result<usize, DecodeError>
+    where
+        B: Buf,
+    {
+        let count = match kind.wire_type() {
+            WireType::ThirtyTwoBit => len / 4,
+            WireType::SixtyFourBit => len / 8,
+            _ => {
+                let chunk = buf.chunk();
+                chunk[..len.min(chunk.len())]
+                    .iter()
+                    .filter(|&&byte| byte < 0x80)
+                    .count()
+            }
+        };
+        self.allocate(count.saturating_mul(mem::size_of::<Value>()))?;
+        Ok(count)
+    }
+
+    pub(super) fn merge_string<B>(
+        &mut self,
+        wire_type: WireType,
+        value: &mut String,
+        buf: &mut B,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = decode_len(buf)?;
+        self.allocate(len)?;
+
+        let mut bytes = vec![0; len];
+        buf.copy_to_slice(&mut bytes);
+        *value = String::from_utf8(bytes)
+            .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded"))?;
+        Ok(())
+    }
+
+    pub(super) fn merge_bytes<B>(
+        &mut self,
+        wire_type: WireType,
+        value: &mut Bytes,
+        buf: &mut B,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = decode_len(buf)?;
+        self.allocate(len)?;
+
+        *value = buf.copy_to_bytes(len);
+        Ok(())
+    }
+
+    /// Decodes an unknown field of `message`, returning `None` if it should be discarded.
+    pub(super) fn decode_unknown<B>(
+        &mut self,
+        message: &MessageDescriptor,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+    ) -> Result<Option<UnknownField>, DecodeError>
+    where
+        B: Buf,
+    {
+        if self.options.deny_unknown_fields {
+            return Err(DecodeError::new(format!(
+                "unknown field {} in message '{}'",
+                number,
+                message.full_name()
+            )));
+        }
+        self.decode_unknown_value(number, wire_type, buf)
+    }
+
+    fn decode_unknown_value<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+    ) -> Result<Option<UnknownField>, DecodeError>
+    where
+        B: Buf,
+    {
+        let discard = self.options.discard_unknown_fields;
+        if !discard {
+            self.allocate(mem::size_of::<UnknownField>())?;
+        }
+
+        match wire_type {
+            WireType::LengthDelimited => {
+                let len = decode_len(buf)?;
+                if discard {
+                    buf.advance(len);
+                    Ok(None)
+                } else {
+                    self.allocate(len)?;
+                    Ok(Some(UnknownField::new(
+                        number,
+                        UnknownFieldValue::LengthDelimited(buf.copy_to_bytes(len)),
+                    )))
+                }
+            }
+            WireType::StartGroup => {
+                self.enter()?;
+                let mut fields = Vec::new();
+                loop {
+                    let (field_number, field_wire_type) = encoding::decode_key(buf)?;
+                    if field_wire_type == WireType::EndGroup {
+                        if field_number != number {
+                            return Err(DecodeError::new("unexpected end group tag"));
+                        }
+                        break;
+                    }
+                    fields.extend(self.decode_unknown_value(field_number, field_wire_type, buf)?);
+                }
+                self.exit();
+                Ok((!discard).then(|| UnknownField::group(number, fields)))
+            }
+            _ => {
+                let field =
+                    UnknownField::decode_value(number, wire_type, buf, DecodeContext::default())?;
+                Ok((!discard).then_some(field))
+            }
+        }
+    }
+}
+
+/// Returns an error if the recursion limit tracked by `ctx` has been reached.
+///
+/// [`DecodeContext`] does not expose its remaining depth, so this skips an empty varint field, which
+/// only fails if the limit has been reached.
+pub(super) fn check_recursion_limit(ctx: DecodeContext) -> Result<(), DecodeError> {
+    encoding::skip_field(WireType::Varint, 1, &mut [0u8].as_ref(), ctx)
+}
+
+impl Default for DecodeState<'static> {
+    fn default() -> Self {
+        DecodeState::new(&DEFAULT_OPTIONS)
+    }
+}
+
+pub(super) fn check_wire_type(expected: WireType, actual: WireType) -> Result<(), DecodeError> {
+    if expected != actual {
+        return Err(DecodeError::new(format!(
+            "invalid wire type: {:?} (expected {:?})",
+            actual, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Decodes a length prefix, checking that `buf` contains at least that many bytes.
+pub(super) fn decode_len<B>(buf: &mut B) -> Result<usize, DecodeError>
+where
+    B: Buf,
+{
+    let len = encoding::decode_varint(buf)?;
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(len as usize)
+}
@@ -0,0 +1,377 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use prost::{DecodeError, Message};
+
+use crate::{DynamicMessage, MessageDescriptor};
+
+/// The default maximum size of a single message, in bytes.
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// The maximum length of an encoded varint.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Reads a sequence of length-delimited messages from an [`io::Read`] implementation.
+///
+/// Each message is preceded by its length, encoded as a varint. This is the format written by
+/// `writeDelimitedTo` in the Java protobuf library, and by [`Message::encode_length_delimited()`].
+///
+/// Lengths are read one byte at a time, so wrapping unbuffered readers such as files in an
+/// [`io::BufReader`] is recommended.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, delimited::DelimitedReader};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let bytes = b"\x03\x08\x96\x01\x02\x08\x01".as_ref();
+/// let messages = DelimitedReader::new(bytes, message_descriptor)
+///     .map(|message| message.map(|message| message.to_string()))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(messages, ["foo:150", "foo:1"]);
+/// ```
+#[derive(Debug)]
+pub struct DelimitedReader<R> {
+    reader: R,
+    desc: MessageDescriptor,
+    max_message_size: usize,
+    done: bool,
+}
+
+/// Writes a sequence of length-delimited messages to an [`io::Write`] implementation.
+///
+/// See [`DelimitedReader`] for a description of the format.
+///
+/// # Examples
+///
+/// ```
+/// # use prost::Message;
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, delimited::DelimitedWriter};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+///
+/// let mut writer = DelimitedWriter::new(Vec::new());
+/// writer.write(&message).unwrap();
+/// writer.write(&message).unwrap();
+/// assert_eq!(writer.into_inner(), b"\x03\x08\x96\x01\x03\x08\x96\x01");
+/// ```
+#[derive(Debug)]
+pub struct DelimitedWriter<W> {
+    writer: W,
+    max_message_size: usize,
+}
+
+/// An error returned when reading or writing length-delimited messages.
+#[derive(Debug)]
+pub enum DelimitedError {
+    /// An I/O error occurred, or the input ended in the middle of a message.
+    Io(io::Error),
+    /// A message could not be decoded.
+    Decode(DecodeError),
+    /// The length prefix of a message is not a valid varint.
+    InvalidLength,
+    /// A message exceeded the maximum allowed size.
+    MessageTooLarge {
+        /// The size of the message, in bytes.
+        size: u64,
+        /// The maximum allowed size, in bytes.
+        max_size: usize,
+    },
+}
+
+impl<R> DelimitedReader<R>
+where
+    R: Read,
+{
+    /// Creates a new reader which decodes messages of type `desc` from `reader`.
+    ///
+    /// The maximum message size defaults to 64 MiB.
+    pub fn new(reader: R, desc: MessageDescriptor) -> Self {
+        DelimitedReader {
+            reader,
+            desc,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            done: false,
+        }
+    }
+
+    /// Sets the maximum size of a single message, in bytes.
+    ///
+    /// Reading a message whose length prefix exceeds this size returns a
+    /// [`DelimitedError::MessageTooLarge`] error, without reading the message body.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Reads the next message, returning `None` if the input is at the end of the stream.
+    pub fn read_message(&mut self) -> Result<Option<DynamicMessage>, DelimitedError> {
+        let len = match read_length(&mut self.reader)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        check_size(len, self.max_message_size)?;
+
+        let mut buf = Vec::with_capacity(len as usize);
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut buf)
+            .map_err(DelimitedError::Io)?;
+        if buf.len() as u64 != len {
+            return Err(DelimitedError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+
+        DynamicMessage::decode(self.desc.clone(), buf.as_slice())
+            .map(Some)
+            .map_err(DelimitedError::Decode)
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this `DelimitedReader`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> Iterator for DelimitedReader<R>
+where
+    R: Read,
+{
+    type Item = Result<DynamicMessage, DelimitedError>;
+
+    /// Reads the next message. After an error is returned, the iterator is fused and always returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_message().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<W> DelimitedWriter<W>
+where
+    W: Write,
+{
+    /// Creates a new writer which encodes messages to `writer`.
+    ///
+    /// The maximum message size defaults to 64 MiB.
+    pub fn new(writer: W) -> Self {
+        DelimitedWriter {
+            writer,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Sets the maximum size of a single message, in bytes.
+    ///
+    /// Writing a larger message returns a [`DelimitedError::MessageTooLarge`] error, and nothing is
+    /// written to the underlying writer.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Writes a message, preceded by its length.
+    pub fn write(&mut self, message: &DynamicMessage) -> Result<(), DelimitedError> {
+        check_size(message.encoded_len() as u64, self.max_message_size)?;
+
+        self.writer
+            .write_all(&message.encode_length_delimited_to_vec())
+            .map_err(DelimitedError::Io)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), DelimitedError> {
+        self.writer.flush().map_err(DelimitedError::Io)
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Unwraps this `DelimitedWriter`, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl fmt::Display for DelimitedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DelimitedError::Io(_) => write!(f, "failed to read or write message"),
+            DelimitedError::Decode(_) => write!(f, "failed to decode message"),
+            DelimitedError::InvalidLength => write!(f, "invalid message length prefix"),
+            DelimitedError::MessageTooLarge { size, max_size } => write!(
+                f,
+                "message of {} bytes exceeds the maximum size of {} bytes",
+                size, max_size
+            ),
+        }
+    }
+}
+
+impl Error for DelimitedError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DelimitedError::Io(err) => Some(err),
+            DelimitedError::Decode(err) => Some(err),
+            DelimitedError::InvalidLength | DelimitedError::MessageTooLarge { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for DelimitedError {
+    fn from(err: io::Error) -> Self {
+        DelimitedError::Io(err)
+    }
+}
+
+/// Reads a varint length prefix, returning `None` if the reader is at the end of the stream.
+fn read_length(reader: &mut impl Read) -> Result<Option<u64>, DelimitedError> {
+    let mut value = 0u64;
+    for index in 0..MAX_VARINT_LEN {
+        let mut byte = [0u8];
+        if let Err(err) = reader.read_exact(&mut byte) {
+            return if index == 0 && err.kind() == io::ErrorKind::UnexpectedEof {
+                Ok(None)
+            } else {
+                Err(DelimitedError::Io(err))
+            };
+        }
+
+        value |= u64::from(byte[0] & 0x7f) << (index * 7);
+        if byte[0] < 0x80 {
+            return Ok(Some(value));
+        }
+    }
+
+    Err(DelimitedError::InvalidLength)
+}
+
+fn check_size(size: u64, max_size: usize) -> Result<(), DelimitedError> {
+    if size > max_size as u64 {
+        Err(DelimitedError::MessageTooLarge { size, max_size })
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+pub use self::codec::DelimitedCodec;
+
+#[cfg(feature = "tokio-util")]
+mod codec {
+    use prost::{
+        bytes::{Buf, BytesMut},
+        encoding::decode_varint,
+        Message,
+    };
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::{check_size, DelimitedError, DEFAULT_MAX_MESSAGE_SIZE, MAX_VARINT_LEN};
+    use crate::{DynamicMessage, MessageDescriptor};
+
+    /// A [`tokio_util::codec`] implementation for length-delimited messages.
+    ///
+    /// See [`DelimitedReader`](super::DelimitedReader) for a description of the format.
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+    #[derive(Debug, Clone)]
+    pub struct DelimitedCodec {
+        desc: MessageDescriptor,
+        max_message_size: usize,
+    }
+
+    impl DelimitedCodec {
+        /// Creates a new codec which decodes messages of type `desc`.
+        ///
+        /// The maximum message size defaults to 64 MiB.
+        pub fn new(desc: MessageDescriptor) -> Self {
+            DelimitedCodec {
+                desc,
+                max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            }
+        }
+
+        /// Sets the maximum size of a single message, in bytes, for both decoding and encoding.
+        pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+            self.max_message_size = max_message_size;
+            self
+        }
+    }
+
+    impl Decoder for DelimitedCodec {
+        type Item = DynamicMessage;
+        type Error = DelimitedError;
+
+        fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+            let header_len = match src.iter().take(MAX_VARINT_LEN).position(|&b| b < 0x80) {
+                Some(index) => index + 1,
+                None if src.len() < MAX_VARINT_LEN => return Ok(None),
+                None => return Err(DelimitedError::InvalidLength),
+            };
+
+            let len = decode_varint(&mut &src[..header_len])
+                .map_err(|_| DelimitedError::InvalidLength)?;
+            check_size(len, self.max_message_size)?;
+
+            let len = len as usize;
+            if src.len() < header_len + len {
+                src.reserve(header_len + len - src.len());
+                return Ok(None);
+            }
+
+            src.advance(header_len);
+            let message = src.split_to(len).freeze();
+            DynamicMessage::decode(self.desc.clone(), message)
+                .map(Some)
+                .map_err(DelimitedError::Decode)
+        }
+    }
+
+    impl Encoder<&DynamicMessage> for DelimitedCodec {
+        type Error = DelimitedError;
+
+        fn encode(&mut self, item: &DynamicMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+            check_size(item.encoded_len() as u64, self.max_message_size)?;
+
+            dst.reserve(item.encoded_len() + MAX_VARINT_LEN);
+            item.encode_length_delimited(dst)
+                .expect("buffer has sufficient capacity");
+            Ok(())
+        }
+    }
+
+    impl Encoder<DynamicMessage> for DelimitedCodec {
+        type Error = DelimitedError;
+
+        fn encode(&mut self, item: DynamicMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+            Encoder::<&DynamicMessage>::encode(self, &item, dst)
+        }
+    }
+}
@@ -0,0 +1,589 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::{self, Write},
+};
+
+use prost::bytes::{Bytes, BytesMut};
+
+use crate::{
+    dynamic::{fmt_string, FieldDescriptorLike},
+    DynamicMessage, FieldDescriptor, MapKey, UnknownField, Value,
+};
+
+/// Options to control how [`DynamicMessage::diff()`] compares messages.
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    ignore_unknown_fields: bool,
+    float_fraction: f64,
+    float_margin: f64,
+    treat_nan_as_equal: bool,
+    repeated_fields: HashMap<String, RepeatedFieldComparison>,
+    ignored_paths: HashSet<String>,
+}
+
+/// Controls how the elements of a repeated field are compared by [`DynamicMessage::diff()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepeatedFieldComparison {
+    /// Elements are compared by their position in the list. This is the default.
+    List,
+    /// Elements are compared ignoring their order. Each element is matched with an equal element of the other list.
+    Set,
+    /// Elements are messages which are matched by the value of the given field, ignoring their order.
+    ///
+    /// Matched elements are compared recursively, so a change to any other field of an element is reported as a
+    /// change within that element.
+    Map(FieldDescriptor),
+}
+
+/// A single difference between two messages, as reported by [`DynamicMessage::diff()`].
+///
+/// The path of a difference uses field names separated by `.`, with list indices and map keys in square
+/// brackets, for example `nested.items[3].name` or `labels["env"]`. Extension fields are written as
+/// `[full.extension.name]` and unknown fields are identified by their field number.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// A field, list element or map entry which is only present in the new message.
+    Added {
+        /// The path to the added value.
+        path: String,
+        /// The added value.
+        value: Value,
+    },
+    /// A field, list element or map entry which is only present in the old message.
+    Removed {
+        /// The path to the removed value.
+        path: String,
+        /// The removed value.
+        value: Value,
+    },
+    /// A value which is present in both messages, but is not equal.
+    Changed {
+        /// The path to the changed value.
+        path: String,
+        /// The value in the old message.
+        old: Value,
+        /// The value in the new message.
+        new: Value,
+    },
+}
+
+impl DiffOptions {
+    /// Creates a new instance of [`DiffOptions`], with the default options chosen to compare messages exactly.
+    pub fn new() -> Self {
+        DiffOptions {
+            ignore_unknown_fields: false,
+            float_fraction: 0.0,
+            float_margin: 0.0,
+            treat_nan_as_equal: false,
+            repeated_fields: HashMap::new(),
+            ignored_paths: HashSet::new(),
+        }
+    }
+
+    /// Whether to ignore unknown fields when comparing messages.
+    ///
+    /// If `false`, unknown fields are compared by field number, and differences are reported with a
+    /// [`Value::Bytes`] containing the encoded unknown fields with that number.
+    ///
+    /// The default value is `false`.
+    pub fn ignore_unknown_fields(mut self, yes: bool) -> Self {
+        self.ignore_unknown_fields = yes;
+        self
+    }
+
+    /// Sets the tolerance used when comparing `float` and `double` values.
+    ///
+    /// Two values `a` and `b` are considered equal if `|a - b| <= margin`, or if
+    /// `|a - b| <= fraction * max(|a|, |b|)`.
+    ///
+    /// The default is to compare floating point values exactly.
+    pub fn float_tolerance(mut self, fraction: f64, margin: f64) -> Self {
+        self.float_fraction = fraction;
+        self.float_margin = margin;
+        self
+    }
+
+    /// Whether `NaN` values should be considered equal to each other.
+    ///
+    /// The default value is `false`.
+    pub fn treat_nan_as_equal(mut self, yes: bool) -> Self {
+        self.treat_nan_as_equal = yes;
+        self
+    }
+
+    /// Sets how the elements of the repeated field `field` are compared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `field` is not a list field, or if `comparison` is [`RepeatedFieldComparison::Map`] and the
+    /// key field is not a field of the element message type of `field`.
+    pub fn repeated_field_comparison(
+        mut self,
+        field: &FieldDescriptor,
+        comparison: RepeatedFieldComparison,
+    ) -> Self {
+        assert!(
+            field.is_list(),
+            "field '{}' is not a list field",
+            field.full_name()
+        );
+        if let RepeatedFieldComparison::Map(key_field) = &comparison {
+            assert!(
+                field.kind().as_message() == Some(key_field.parent_message()),
+                "field '{}' is not a field of the element type of '{}'",
+                key_field.full_name(),
+                field.full_name()
+            );
+        }
+
+        self.repeated_fields
+            .insert(field.full_name().to_owned(), comparison);
+        self
+    }
+
+    /// Ignores the field at `path`, and all of its sub-fields, when comparing messages.
+    ///
+    /// The path uses field names separated by `.`, without any list indices or map keys, for example
+    /// `nested.items.name`. Extension fields are written as `[full.extension.name]`.
+    pub fn ignore_path(mut self, path: impl Into<String>) -> Self {
+        self.ignored_paths.insert(path.into());
+        self
+    }
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Difference {
+    /// Returns the path to the value which differs.
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Added { path, .. }
+            | Difference::Removed { path, .. }
+            | Difference::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Added { path, value } => write!(f, "added {}: {}", path, value),
+            Difference::Removed { path, value } => write!(f, "removed {}: {}", path, value),
+            Difference::Changed { path, old, new } => {
+                write!(f, "changed {}: {} -> {}", path, old, new)
+            }
+        }
+    }
+}
+
+impl DynamicMessage {
+    /// Compares this message with `other`, returning a list of the differences between them.
+    ///
+    /// This message is treated as the old value, and `other` as the new value. The differences in fields
+    /// are listed in the order the fields are declared in the message, followed by extension fields
+    /// ordered by field number, and then unknown fields. If the messages have different types, a single
+    /// [`Difference::Changed`] with an empty path is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value, diff::{DiffOptions, Difference}};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let old = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let new = DynamicMessage::decode(message_descriptor, b"\x08\x02\x1a\x02\x08\x03".as_ref()).unwrap();
+    ///
+    /// let differences = old.diff(&new, &DiffOptions::new());
+    /// assert_eq!(differences[0], Difference::Changed { path: "foo".to_owned(), old: Value::I32(1), new: Value::I32(2) });
+    /// assert_eq!(differences[1].to_string(), "changed nested.foo: 0 -> 3");
+    /// assert_eq!(differences[2].to_string(), "removed nested.bar: 66");
+    /// ```
+    pub fn diff(&self, other: &DynamicMessage, options: &DiffOptions) -> Vec<Difference> {
+        let mut differ = Differ {
+            options,
+            differences: Vec::new(),
+        };
+        differ.diff_message("", "", self, other);
+        differ.differences
+    }
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    differences: Vec<Difference>,
+}
+
+impl Differ<'_> {
+    fn diff_message(
+        &mut self,
+        path: &str,
+        name_path: &str,
+        old: &DynamicMessage,
+        new: &DynamicMessage,
+    ) {
+        if old.desc != new.desc {
+            self.differences.push(Difference::Changed {
+                path: path.to_owned(),
+                old: Value::Message(old.clone()),
+                new: Value::Message(new.clone()),
+            });
+            return;
+        }
+
+        for field in old.desc.fields() {
+            self.diff_field(
+                path,
+                name_path,
+                field.name(),
+                field.full_name(),
+                &field,
+                old,
+                new,
+            );
+        }
+
+        let mut extensions: Vec<_> = old.extensions().map(|(desc, _)| desc).collect();
+        for (desc, _) in new.extensions() {
+            if !extensions.contains(&desc) {
+                extensions.push(desc);
+            }
+        }
+        extensions.sort_by_key(|desc| desc.number());
+        for extension in extensions {
+            let name = format!("[{}]", extension.full_name());
+            self.diff_field(
+                path,
+                name_path,
+                &name,
+                extension.full_name(),
+                &extension,
+                old,
+                new,
+            );
+        }
+
+        if !self.options.ignore_unknown_fields {
+            self.diff_unknown_fields(path, name_path, old, new);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn diff_field(
+        &mut self,
+        path: &str,
+        name_path: &str,
+        name: &str,
+        full_name: &str,
+        desc: &impl FieldDescriptorLike,
+        old: &DynamicMessage,
+        new: &DynamicMessage,
+    ) {
+        let name_path = join_path(name_path, name);
+        if self.options.ignored_paths.contains(&name_path) {
+            return;
+        }
+        let path = join_path(path, name);
+
+        let old_value = old.fields.get(desc);
+        let new_value = new.fields.get(desc);
+        if desc.is_map() {
+            self.diff_map(
+                &path,
+                &name_path,
+                old_value.as_map().unwrap(),
+                new_value.as_map().unwrap(),
+            );
+        } else if desc.is_list() {
+            let comparison = self
+                .options
+                .repeated_fields
+                .get(full_name)
+                .unwrap_or(&RepeatedFieldComparison::List);
+            self.diff_list(
+                &path,
+                &name_path,
+                comparison,
+                old_value.as_list().unwrap(),
+                new_value.as_list().unwrap(),
+            );
+        } else if desc.supports_presence() {
+            match (old.fields.has(desc), new.fields.has(desc)) {
+                (false, false) => (),
+                (true, false) => self.differences.push(Difference::Removed {
+                    path,
+                    value: old_value.into_owned(),
+                }),
+                (false, true) => self.differences.push(Difference::Added {
+                    path,
+                    value: new_value.into_owned(),
+                }),
+                (true, true) => self.diff_value(&path, &name_path, &old_value, &new_value),
+            }
+        } else {
+            self.diff_value(&path, &name_path, &old_value, &new_value);
+        }
+    }
+
+    fn diff_list(
+        &mut self,
+        path: &str,
+        name_path: &str,
+        comparison: &RepeatedFieldComparison,
+        old: &[Value],
+        new: &[Value],
+    ) {
+        match comparison {
+            RepeatedFieldComparison::List => {
+                for (index, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                    self.diff_value(&index_path(path, index), name_path, old_value, new_value);
+                }
+                self.removed_elements(path, old.iter().enumerate().skip(new.len()));
+                self.added_elements(path, new.iter().enumerate().skip(old.len()));
+            }
+            RepeatedFieldComparison::Set => {
+                let mut matched = vec![false; new.len()];
+                let mut removed = Vec::new();
+                for (old_index, old_value) in old.iter().enumerate() {
+                    let found = new.iter().enumerate().position(|(new_index, new_value)| {
+                        !matched[new_index] && self.values_equal(name_path, old_value, new_value)
+                    });
+                    match found {
+                        Some(new_index) => matched[new_index] = true,
+                        None => removed.push((old_index, old_value)),
+                    }
+                }
+                self.removed_elements(path, removed.into_iter());
+                self.added_elements(
+                    path,
+                    new.iter().enumerate().filter(|&(index, _)| !matched[index]),
+                );
+            }
+            RepeatedFieldComparison::Map(key_field) => {
+                let key = |value: &Value| {
+                    value
+                        .as_message()
+                        .map(|message| message.get_field(key_field).into_owned())
+                };
+
+                let mut matched = vec![false; new.len()];
+                let mut removed = Vec::new();
+                for (old_index, old_value) in old.iter().enumerate() {
+                    let old_key = key(old_value);
+                    let found = new.iter().enumerate().position(|(new_index, new_value)| {
+                        !matched[new_index] && key(new_value) == old_key
+                    });
+                    match found {
+                        Some(new_index) => {
+                            matched[new_index] = true;
+                            self.diff_value(
+                                &index_path(path, new_index),
+                                name_path,
+                                old_value,
+                                &new[new_index],
+                            );
+                        }
+                        None => removed.push((old_index, old_value)),
+                    }
+                }
+                self.removed_elements(path, removed.into_iter());
+                self.added_elements(
+                    path,
+                    new.iter().enumerate().filter(|&(index, _)| !matched[index]),
+                );
+            }
+        }
+    }
+
+    fn removed_elements<'b>(
+        &mut self,
+        path: &str,
+        values: impl Iterator<Item = (usize, &'b Value)>,
+    ) {
+        for (index, value) in values {
+            self.differences.push(Difference::Removed {
+                path: index_path(path, index),
+                value: value.clone(),
+            });
+        }
+    }
+
+    fn added_elements<'b>(&mut self, path: &str, values: impl Iterator<Item = (usize, &'b Value)>) {
+        for (index, value) in values {
+            self.differences.push(Difference::Added {
+                path: index_path(path, index),
+                value: value.clone(),
+            });
+        }
+    }
+
+    fn diff_map(
+        &mut self,
+        path: &str,
+        name_path: &str,
+        old: &HashMap<MapKey, Value>,
+        new: &HashMap<MapKey, Value>,
+    ) {
+        let keys: BTreeSet<&MapKey> = old.keys().chain(new.keys()).collect();
+        for key in keys {
+            let path = key_path(path, key);
+            match (old.get(key), new.get(key)) {
+                (Some(old_value), Some(new_value)) => {
+                    self.diff_value(&path, name_path, old_value, new_value)
+                }
+                (Some(old_value), None) => self.differences.push(Difference::Removed {
+                    path,
+                    value: old_value.clone(),
+                }),
+                (None, Some(new_value)) => self.differences.push(Difference::Added {
+                    path,
+                    value: new_value.clone(),
+                }),
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    fn diff_value(&mut self, path: &str, name_path: &str, old: &Value, new: &Value) {
+        match (old, new) {
+            (Value::Message(old), Value::Message(new)) => {
+                self.diff_message(path, name_path, old, new)
+            }
+            _ => {
+                if !self.values_equal(name_path, old, new) {
+                    self.differences.push(Difference::Changed {
+                        path: path.to_owned(),
+                        old: old.clone(),
+                        new: new.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn values_equal(&self, name_path: &str, old: &Value, new: &Value) -> bool {
+        match (old, new) {
+            (Value::F32(old), Value::F32(new)) => self.floats_equal(*old as f64, *new as f64),
+            (Value::F64(old), Value::F64(new)) => self.floats_equal(*old, *new),
+            (Value::Message(old), Value::Message(new)) => {
+                let mut differ = Differ {
+                    options: self.options,
+                    differences: Vec::new(),
+                };
+                differ.diff_message("", name_path, old, new);
+                differ.differences.is_empty()
+            }
+            _ => old == new,
+        }
+    }
+
+    fn floats_equal(&self, old: f64, new: f64) -> bool {
+        if old.is_nan() || new.is_nan() {
+            return old.is_nan() && new.is_nan() && self.options.treat_nan_as_equal;
+        }
+        if old == new {
+            return true;
+        }
+
+        let difference = (old - new).abs();
+        difference <= self.options.float_margin
+            || difference <= self.options.float_fraction * old.abs().max(new.abs())
+    }
+
+    fn diff_unknown_fields(
+        &mut self,
+        path: &str,
+        name_path: &str,
+        old: &DynamicMessage,
+        new: &DynamicMessage,
+    ) {
+        let old_fields = group_unknown_fields(old);
+        let new_fields = group_unknown_fields(new);
+
+        let numbers: BTreeSet<u32> = old_fields
+            .keys()
+            .chain(new_fields.keys())
+            .copied()
+            .collect();
+        for number in numbers {
+            let name = number.to_string();
+            if self
+                .options
+                .ignored_paths
+                .contains(&join_path(name_path, &name))
+            {
+                continue;
+            }
+
+            let path = join_path(path, &name);
+            match (old_fields.get(&number), new_fields.get(&number)) {
+                (Some(old_fields), Some(new_fields)) => {
+                    if old_fields != new_fields {
+                        self.differences.push(Difference::Changed {
+                            path,
+                            old: encode_unknown_fields(old_fields),
+                            new: encode_unknown_fields(new_fields),
+                        });
+                    }
+                }
+                (Some(old_fields), None) => self.differences.push(Difference::Removed {
+                    path,
+                    value: encode_unknown_fields(old_fields),
+                }),
+                (None, Some(new_fields)) => self.differences.push(Difference::Added {
+                    path,
+                    value: encode_unknown_fields(new_fields),
+                }),
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+}
+
+fn group_unknown_fields(message: &DynamicMessage) -> BTreeMap<u32, Vec<&UnknownField>> {
+    let mut result: BTreeMap<u32, Vec<&UnknownField>> = BTreeMap::new();
+    for field in message.unknown_fields() {
+        result.entry(field.number()).or_default().push(field);
+    }
+    result
+}
+
+fn encode_unknown_fields(fields: &[&UnknownField]) -> Value {
+    let mut buf = BytesMut::new();
+    for field in fields {
+        field.encode(&mut buf);
+    }
+    Value::Bytes(Bytes::from(buf))
+}
+
+pub(super) fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+pub(super) fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+pub(super) fn key_path(path: &str, key: &MapKey) -> String {
+    let mut result = String::with_capacity(path.len() + 2);
+    result.push_str(path);
+    result.push('[');
+    match key {
+        MapKey::Bool(value) => write!(result, "{}", value),
+        MapKey::I32(value) => write!(result, "{}", value),
+        MapKey::I64(value) => write!(result, "{}", value),
+        MapKey::U32(value) => write!(result, "{}", value),
+        MapKey::U64(value) => write!(result, "{}", value),
+        MapKey::String(value) => fmt_string(&mut result, value.as_bytes()),
+    }
+    .expect("writing to string cannot fail");
+    result.push(']');
+    result
+}
@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use prost::bytes::Bytes;
+
+use crate::{
+    DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, MethodDescriptor,
+    ServiceDescriptor, Value,
+};
+
+/// Options to control the messages produced by [`DynamicMessage::example()`].
+#[derive(Debug, Clone)]
+pub struct ExampleOptions {
+    seed: u64,
+    max_depth: u32,
+    now: Option<SystemTime>,
+}
+
+/// An example request and response for a method, as produced by [`service()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodExample {
+    method: MethodDescriptor,
+    request: DynamicMessage,
+    response: DynamicMessage,
+}
+
+/// Returns an example request and response for each method of a service.
+///
+/// The examples are generated in the order the methods are defined, using a single sequence of random
+/// values, so the result is deterministic for a given seed.
+pub fn service(service: &ServiceDescriptor, options: &ExampleOptions) -> Vec<MethodExample> {
+    let mut generator = Generator::new(options);
+    service
+        .methods()
+        .map(|method| {
+            let request = generator.message(&method.input(), 0);
+            let response = generator.message(&method.output(), 0);
+            MethodExample {
+                method,
+                request,
+                response,
+            }
+        })
+        .collect()
+}
+
+impl ExampleOptions {
+    /// Creates a new instance of [`ExampleOptions`], with the default options.
+    pub const fn new() -> Self {
+        ExampleOptions {
+            seed: 0,
+            max_depth: 4,
+            now: None,
+        }
+    }
+
+    /// The seed for the random values in the example.
+    ///
+    /// Examples generated with the same seed, descriptor and [`now`](Self::now) are identical. The
+    /// default value is `0`.
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The maximum depth of nested messages to generate.
+    ///
+    /// Message fields nested more deeply than this are left unset, which stops the generation of
+    /// recursive message types. The default value is `4`.
+    pub const fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The current time, which `google.protobuf.Timestamp` values are generated relative to.
+    ///
+    /// By default, the system clock is used.
+    pub const fn now(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+}
+
+impl Default for ExampleOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MethodExample {
+    /// Gets the method this is an example of.
+    pub fn method(&self) -> &MethodDescriptor {
+        &self.method
+    }
+
+    /// Gets the example request message.
+    pub fn request(&self) -> &DynamicMessage {
+        &self.request
+    }
+
+    /// Gets the example response message.
+    pub fn response(&self) -> &DynamicMessage {
+        &self.response
+    }
+}
+
+impl DynamicMessage {
+    /// Creates a plausible example message of type `desc`, for use in documentation or mock services.
+    ///
+    /// Every field is set once: repeated fields have a single element, map fields have a single entry, and
+    /// only the first field of each oneof is set. Values are chosen based on the field type and the words of
+    /// the field name, separated by `_`, so that for example `home_email` is an email address but
+    /// `page_size` is not an age:
+    ///
+    /// - string fields named like `email`, `id`, `uuid`, `url` or `phone` get values in the matching format,
+    ///   and fields ending in `name` get a person's name.
+    /// - numeric fields get small positive values, or values in the expected range for names like `age`,
+    ///   `year`, `latitude` or `ratio`.
+    /// - enum fields are set to the first value with a non-zero number.
+    /// - `google.protobuf.Timestamp` fields are set to a time in the 30 days before
+    ///   [`ExampleOptions::now`], and `google.protobuf.Duration` fields to at most an hour.
+    ///
+    /// Fields of type `google.protobuf.Any` are left unset, since their payload type cannot be chosen from
+    /// the descriptor. Messages nested more deeply than [`ExampleOptions::max_depth`] are also left unset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # use prost_reflect::example::ExampleOptions;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let options = ExampleOptions::new().seed(42);
+    /// let example = DynamicMessage::example(message_descriptor.clone(), &options);
+    /// assert!(example.has_field_by_name("foo"));
+    /// assert_eq!(example, DynamicMessage::example(message_descriptor, &options));
+    /// ```
+    pub fn example(desc: MessageDescriptor, options: &ExampleOptions) -> DynamicMessage {
+        Generator::new(options).message(&desc, 0)
+    }
+}
+
+struct Generator<'a> {
+    options: &'a ExampleOptions,
+    rng: SplitMix64,
+    now: i64,
+}
+
+impl<'a> Generator<'a> {
+    fn new(options: &'a ExampleOptions) -> Self {
+        let now = options.now.unwrap_or_else(SystemTime::now);
+        let now = match now.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        };
+        Generator {
+            options,
+            rng: SplitMix64(options.seed),
+            now,
+        }
+    }
+
+    fn message(&mut self, desc: &MessageDescriptor, depth: u32) -> DynamicMessage {
+        let mut message = DynamicMessage::new(desc.clone());
+        for field_desc in desc.fields() {
+            if let Some(oneof_desc) = field_desc.containing_oneof() {
+                if oneof_desc.fields().next().as_ref() != Some(&field_desc) {
+                    continue;
+                }
+            }
+
+            if let Some(value) = self.field(&field_desc, depth) {
+                message.set_field(&field_desc, value);
+            }
+        }
+        message
+    }
+
+    fn field(&mut self, field_desc: &FieldDescriptor, depth: u32) -> Option<Value> {
+        let name = field_desc.name();
+        if field_desc.is_map() {
+            let entry_desc = match field_desc.kind() {
+                Kind::Message(entry_desc) => entry_desc,
+                _ => unreachable!(),
+            };
+            let value = self.value(&entry_desc.map_entry_value_field().kind(), name, depth)?;
+            let key = self.map_key(&entry_desc.map_entry_key_field().kind(), name);
+            Some(Value::Map(HashMap::from([(key, value)])))
+        } else if field_desc.is_list() {
+            let value = self.value(&field_desc.kind(), name, depth)?;
+            Some(Value::List(vec![value]))
+        } else {
+            self.value(&field_desc.kind(), name, depth)
+        }
+    }
+
+    /// Returns an example value of the given kind, for a field named `name`, or `None` if it should be left unset.
+    fn value(&mut self, kind: &Kind, name: &str, depth: u32) -> Option<Value> {
+        let name = name.to_ascii_lowercase();
+        let value = match kind {
+            Kind::Double => Value::F64(self.float(&name)),
+            Kind::Float => Value::F32(self.float(&name) as f32),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(self.int(&name) as i32),
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(self.int(&name) as i64),
+            Kind::Uint32 | Kind::Fixed32 => Value::U32(self.int(&name) as u32),
+            Kind::Uint64 | Kind::Fixed64 => Value::U64(self.int(&name)),
+            Kind::Bool => Value::Bool(true),
+            Kind::String => Value::String(self.string(&name)),
+            Kind::Bytes => Value::Bytes(Bytes::from(self.rng.next().to_be_bytes().to_vec())),
+            Kind::Enum(enum_desc) => {
+                let value = enum_desc
+                    .values()
+                    .find(|value| value.number() != 0)
+                    .unwrap_or_else(|| enum_desc.default_value());
+                Value::EnumNumber(value.number())
+            }
+            Kind::Message(message_desc) => {
+                if depth >= self.options.max_depth {
+                    return None;
+                }
+                Value::Message(self.nested_message(message_desc, &name, depth + 1)?)
+            }
+        };
+        Some(value)
+    }
+
+    fn nested_message(
+        &mut self,
+        desc: &MessageDescriptor,
+        name: &str,
+        depth: u32,
+    ) -> Option<DynamicMessage> {
+        let mut message = DynamicMessage::new(desc.clone());
+        match desc.full_name() {
+            "google.protobuf.Any" => return None,
+            "google.protobuf.Timestamp" => {
+                let seconds = self.now - self.rng.range(0, 30 * 24 * 60 * 60) as i64;
+                message.set_field_by_name("seconds", Value::I64(seconds));
+            }
+            "google.protobuf.Duration" => {
+                let seconds = self.rng.range(1, 60 * 60) as i64;
+                message.set_field_by_name("seconds", Value::I64(seconds));
+            }
+            "google.protobuf.FieldMask" => {
+                message
+                    .set_field_by_name("paths", Value::List(vec![Value::String(name.to_owned())]));
+            }
+            "google.protobuf.Value" => {
+                message.set_field_by_name("string_value", Value::String(self.string(name)));
+            }
+            "google.protobuf.Struct" => {
+                let field_desc = desc.get_field_by_name("fields")?;
+                let value_desc = match field_desc.kind() {
+                    Kind::Message(entry_desc) => entry_desc.map_entry_value_field().kind(),
+                    _ => return None,
+                };
+                let value = self.value(&value_desc, name, depth - 1)?;
+                message.set_field(
+                    &field_desc,
+                    Value::Map(HashMap::from([(MapKey::String(name.to_owned()), value)])),
+                );
+            }
+            "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue" => {
+                // Wrappers take the name of the field containing them, rather than their own `value` field.
+                let field_desc = desc.get_field_by_name("value")?;
+                let value = self.value(&field_desc.kind(), name, depth)?;
+                message.set_field(&field_desc, value);
+            }
+            _ => return Some(self.message(desc, depth)),
+        }
+        Some(message)
+    }
+
+    fn map_key(&mut self, kind: &Kind, name: &str) -> MapKey {
+        match kind {
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                MapKey::I32(self.rng.range(1, 100) as i32)
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                MapKey::I64(self.rng.range(1, 100) as i64)
+            }
+            Kind::Uint32 | Kind::Fixed32 => MapKey::U32(self.rng.range(1, 100) as u32),
+            Kind::Uint64 | Kind::Fixed64 => MapKey::U64(self.rng.range(1, 100)),
+            Kind::Bool => MapKey::Bool(true),
+            Kind::String => MapKey::String(format!("{}_key", name.to_ascii_lowercase())),
+            _ => panic!("invalid type for map key"),
+        }
+    }
+
+    fn string(&mut self, name: &str) -> String {
+        const FIRST_NAMES: &[&str] = &["Alice", "Bob", "Carol", "Dave", "Erin", "Frank"];
+        const LAST_NAMES: &[&str] = &["Smith", "Jones", "Taylor", "Brown", "Wilson", "Evans"];
+
+        if has_word(name, "email") {
+            let first = self.rng.choose(FIRST_NAMES).to_ascii_lowercase();
+            let last = self.rng.choose(LAST_NAMES).to_ascii_lowercase();
+            format!("{}.{}@example.com", first, last)
+        } else if has_word(name, "uuid") || name == "id" || name.ends_with("_id") {
+            self.uuid()
+        } else if has_word(name, "url") || has_word(name, "uri") || has_word(name, "link") {
+            format!("https://example.com/{}/{}", name, self.rng.range(1, 1000))
+        } else if has_word(name, "phone") {
+            format!("+1-555-01{:02}", self.rng.range(0, 99))
+        } else if name.ends_with("name") {
+            let first = self.rng.choose(FIRST_NAMES);
+            let last = self.rng.choose(LAST_NAMES);
+            if has_word(name, "first") || has_word(name, "given") {
+                first.to_owned()
+            } else if has_word(name, "last")
+                || has_word(name, "family")
+                || has_word(name, "surname")
+            {
+                last.to_owned()
+            } else if has_word(name, "user") || has_word(name, "username") {
+                format!("{}{}", first.to_ascii_lowercase(), self.rng.range(1, 99))
+            } else {
+                format!("{} {}", first, last)
+            }
+        } else {
+            format!("example {}", name.replace('_', " "))
+        }
+    }
+
+    fn uuid(&mut self) -> String {
+        let high = self.rng.next();
+        let low = self.rng.next();
+        // Set the version and variant bits of a random (version 4) UUID.
+        let high = (high & !0xf000) | 0x4000;
+        let low = (low & !(0b11 << 62)) | (0b10 << 62);
+
+        let mut uuid = String::with_capacity(36);
+        write!(
+            uuid,
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff,
+        )
+        .unwrap();
+        uuid
+    }
+
+    fn int(&mut self, name: &str) -> u64 {
+        if has_word(name, "year") {
+            (1970 + self.now / 31_556_952) as u64
+        } else if has_word(name, "age") {
+            self.rng.range(18, 80)
+        } else if has_word(name, "port") {
+            8080
+        } else if has_word(name, "count")
+            || has_word(name, "size")
+            || has_word(name, "quantity")
+            || has_word(name, "total")
+        {
+            self.rng.range(1, 10)
+        } else {
+            self.rng.range(1, 100)
+        }
+    }
+
+    fn float(&mut self, name: &str) -> f64 {
+        let (min, max, scale) = if has_word(name, "lat") || has_word(name, "latitude") {
+            (-90, 90, 10_000.0)
+        } else if has_word(name, "lon") || has_word(name, "lng") || has_word(name, "longitude") {
+            (-180, 180, 10_000.0)
+        } else if has_word(name, "ratio") || has_word(name, "rate") || has_word(name, "fraction") {
+            (0, 1, 100.0)
+        } else {
+            (1, 100, 100.0)
+        };
+        let range = (max - min) as f64 * scale;
+        min as f64 + self.rng.range(0, range as u64) as f64 / scale
+    }
+}
+
+/// A small, fast random number generator, so that examples are reproducible without extra dependencies.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value between `min` and `max` inclusive.
+    fn range(&mut self, min: u64, max: u64) -> u64 {
+        min + self.next() % (max - min + 1)
+    }
+
+    fn choose<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.range(0, items.len() as u64 - 1) as usize]
+    }
+}
+
+/// Returns `true` if `word` is one of the `_`-separated parts of the field name `name`.
+fn has_word(name: &str, word: &str) -> bool {
+    name.split('_').any(|part| part == word)
+}
@@ -0,0 +1,483 @@
+use std::{collections::BTreeMap, error::Error, fmt};
+
+use prost_types::FieldMask;
+
+use crate::{DynamicMessage, FieldDescriptor, Kind, MessageDescriptor, Value};
+
+/// Options to control how [`DynamicMessage::merge_with_field_mask()`] merges fields.
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    replace_message_fields: bool,
+    replace_repeated_fields: bool,
+}
+
+/// An error returned when a [`FieldMask`] is not valid for a message type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldMaskError {
+    /// A path in the mask references a field which does not exist.
+    FieldNotFound {
+        /// The path containing the invalid field name.
+        path: String,
+        /// The full name of the message type which was expected to contain the field.
+        message_name: String,
+        /// The name of the field which was not found.
+        field_name: String,
+    },
+    /// A path in the mask has a sub-path on a field which is not a singular message.
+    ///
+    /// Only the last component of a path may refer to a repeated, map or scalar field.
+    InvalidSubPath {
+        /// The invalid path.
+        path: String,
+        /// The full name of the field which cannot have sub-paths.
+        field_name: String,
+    },
+    /// The source and destination messages passed to [`DynamicMessage::merge_with_field_mask()`] have different types.
+    MessageTypeMismatch {
+        /// The full name of the destination message type.
+        expected: String,
+        /// The full name of the source message type.
+        actual: String,
+    },
+}
+
+/// Returns an error if any path in `mask` does not refer to a field of `desc`.
+///
+/// Each component of a path is a field name, as defined in the protobuf source file. All
+/// components except the last must refer to singular message fields.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, field_mask};
+/// # use prost_types::FieldMask;
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mask = FieldMask { paths: vec!["foo".to_owned(), "nested.bar".to_owned()] };
+/// assert!(field_mask::validate(&message_descriptor, &mask).is_ok());
+///
+/// let mask = FieldMask { paths: vec!["foo.bar".to_owned()] };
+/// assert!(field_mask::validate(&message_descriptor, &mask).is_err());
+/// ```
+pub fn validate(desc: &MessageDescriptor, mask: &FieldMask) -> Result<(), FieldMaskError> {
+    for path in &mask.paths {
+        validate_path(desc, path)?;
+    }
+    Ok(())
+}
+
+/// Returns the canonical form of `mask`.
+///
+/// The paths of the result are sorted and deduplicated, and any path covered by another path
+/// in the mask is removed.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::field_mask;
+/// # use prost_types::FieldMask;
+/// let mask = FieldMask { paths: vec!["foo.bar".to_owned(), "baz".to_owned(), "foo".to_owned()] };
+/// assert_eq!(field_mask::normalize(&mask).paths, ["baz", "foo"]);
+/// ```
+pub fn normalize(mask: &FieldMask) -> FieldMask {
+    let mut tree = FieldMaskTree::default();
+    tree.add_paths(&mask.paths);
+    tree.to_field_mask()
+}
+
+/// Returns a normalized mask containing every path covered by either `a` or `b`.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::field_mask;
+/// # use prost_types::FieldMask;
+/// let a = FieldMask { paths: vec!["foo.bar".to_owned(), "baz".to_owned()] };
+/// let b = FieldMask { paths: vec!["foo".to_owned()] };
+/// assert_eq!(field_mask::union(&a, &b).paths, ["baz", "foo"]);
+/// ```
+pub fn union(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    let mut tree = FieldMaskTree::default();
+    tree.add_paths(&a.paths);
+    tree.add_paths(&b.paths);
+    tree.to_field_mask()
+}
+
+/// Returns a normalized mask containing only the paths covered by both `a` and `b`.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::field_mask;
+/// # use prost_types::FieldMask;
+/// let a = FieldMask { paths: vec!["foo.bar".to_owned(), "baz".to_owned()] };
+/// let b = FieldMask { paths: vec!["foo".to_owned(), "qux".to_owned()] };
+/// assert_eq!(field_mask::intersect(&a, &b).paths, ["foo.bar"]);
+/// ```
+pub fn intersect(a: &FieldMask, b: &FieldMask) -> FieldMask {
+    let mut tree = FieldMaskTree::default();
+    tree.add_paths(&a.paths);
+
+    let mut result = FieldMaskTree::default();
+    for path in &b.paths {
+        tree.intersect_path(path, &mut result);
+    }
+    result.to_field_mask()
+}
+
+impl DynamicMessage {
+    /// Clears all fields of this message which are not covered by `mask`.
+    ///
+    /// Unknown fields and extensions are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value};
+    /// # use prost_types::FieldMask;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x04\x08\x01\x10\x42".as_ref()).unwrap();
+    /// dynamic_message.trim(&FieldMask { paths: vec!["nested.bar".to_owned()] }).unwrap();
+    /// assert_eq!(dynamic_message.to_string(), "nested{bar:66}");
+    /// ```
+    pub fn trim(&mut self, mask: &FieldMask) -> Result<(), FieldMaskError> {
+        validate(&self.desc, mask)?;
+
+        let mut tree = FieldMaskTree::default();
+        tree.add_paths(&mask.paths);
+        tree.trim(self);
+        Ok(())
+    }
+
+    /// Merges the fields covered by `mask` from `source` into this message.
+    ///
+    /// Singular fields covered by the mask which are not set in `source` are cleared in this message.
+    /// Message fields are merged recursively, and repeated and map fields are appended to, unless
+    /// [`replace_message_fields`](MergeOptions::replace_message_fields) or
+    /// [`replace_repeated_fields`](MergeOptions::replace_repeated_fields) are set, in which case they
+    /// are overwritten by, or cleared if not set in, `source`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, Value, field_mask::MergeOptions};
+    /// # use prost_types::FieldMask;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let mut dynamic_message = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x01\x10\x02".as_ref()).unwrap();
+    /// let source = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    ///
+    /// let mask = FieldMask { paths: vec!["foo".to_owned(), "bar".to_owned(), "nested".to_owned()] };
+    /// dynamic_message.merge_with_field_mask(&source, &mask, &MergeOptions::new()).unwrap();
+    /// assert_eq!(dynamic_message.to_string(), "foo:150,nested{bar:66}");
+    /// ```
+    pub fn merge_with_field_mask(
+        &mut self,
+        source: &DynamicMessage,
+        mask: &FieldMask,
+        options: &MergeOptions,
+    ) -> Result<(), FieldMaskError> {
+        if self.desc != source.desc {
+            return Err(FieldMaskError::MessageTypeMismatch {
+                expected: self.desc.full_name().to_owned(),
+                actual: source.desc.full_name().to_owned(),
+            });
+        }
+        validate(&self.desc, mask)?;
+
+        let mut tree = FieldMaskTree::default();
+        tree.add_paths(&mask.paths);
+        tree.merge(self, source, options);
+        Ok(())
+    }
+}
+
+impl MergeOptions {
+    /// Creates a new instance of [`MergeOptions`], with the default options.
+    pub const fn new() -> Self {
+        MergeOptions {
+            replace_message_fields: false,
+            replace_repeated_fields: false,
+        }
+    }
+
+    /// Whether to replace message fields covered by the mask, instead of merging them.
+    ///
+    /// If `false`, message fields set in the source are recursively merged into the destination message. If `true`,
+    /// the destination field is overwritten with the source value, or cleared if it is not set in the source.
+    ///
+    /// The default value is `false`.
+    pub const fn replace_message_fields(mut self, yes: bool) -> Self {
+        self.replace_message_fields = yes;
+        self
+    }
+
+    /// Whether to replace repeated and map fields covered by the mask, instead of appending to them.
+    ///
+    /// If `false`, elements of the source field are appended to the destination field, with map entries
+    /// overwriting any existing entry with the same key. If `true`, the destination field is overwritten
+    /// with the source value.
+    ///
+    /// The default value is `false`.
+    pub const fn replace_repeated_fields(mut self, yes: bool) -> Self {
+        self.replace_repeated_fields = yes;
+        self
+    }
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMaskError::FieldNotFound {
+                path,
+                message_name,
+                field_name,
+            } => write!(
+                f,
+                "invalid field mask path '{}': message '{}' has no field named '{}'",
+                path, message_name, field_name
+            ),
+            FieldMaskError::InvalidSubPath { path, field_name } => write!(
+                f,
+                "invalid field mask path '{}': field '{}' is not a singular message field",
+                path, field_name
+            ),
+            FieldMaskError::MessageTypeMismatch { expected, actual } => write!(
+                f,
+                "expected a message of type '{}', but found '{}'",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for FieldMaskError {}
+
+fn validate_path(desc: &MessageDescriptor, path: &str) -> Result<(), FieldMaskError> {
+    let mut desc = desc.clone();
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let field = desc
+            .get_field_by_name(part)
+            .ok_or_else(|| FieldMaskError::FieldNotFound {
+                path: path.to_owned(),
+                message_name: desc.full_name().to_owned(),
+                field_name: part.to_owned(),
+            })?;
+
+        if parts.peek().is_some() {
+            desc = match field.kind() {
+                Kind::Message(message_desc) if !field.is_list() && !field.is_map() => message_desc,
+                _ => {
+                    return Err(FieldMaskError::InvalidSubPath {
+                        path: path.to_owned(),
+                        field_name: field.full_name().to_owned(),
+                    })
+                }
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// A tree representation of a field mask. A node with no children covers all of its sub-fields.
+#[derive(Debug, Default)]
+struct FieldMaskTree {
+    children: BTreeMap<String, FieldMaskTree>,
+}
+
+impl FieldMaskTree {
+    fn add_paths(&mut self, paths: &[String]) {
+        for path in paths {
+            self.add_path(path);
+        }
+    }
+
+    fn add_path(&mut self, path: &str) {
+        if path.is_empty() {
+            return;
+        }
+
+        let mut node = self;
+        for part in path.split('.') {
+            let is_new = !node.children.contains_key(part);
+            node = node.children.entry(part.to_owned()).or_default();
+            if !is_new && node.children.is_empty() {
+                // An existing path already covers this one.
+                return;
+            }
+        }
+        node.children.clear();
+    }
+
+    fn intersect_path(&self, path: &str, result: &mut FieldMaskTree) {
+        if path.is_empty() {
+            return;
+        }
+
+        let mut node = self;
+        let mut prefix = String::new();
+        for part in path.split('.') {
+            node = match node.children.get(part) {
+                Some(child) => child,
+                None => return,
+            };
+
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(part);
+
+            if node.children.is_empty() {
+                result.add_path(path);
+                return;
+            }
+        }
+
+        node.collect_paths(&mut prefix, &mut |p| result.add_path(p));
+    }
+
+    fn collect_paths(&self, prefix: &mut String, f: &mut impl FnMut(&str)) {
+        if self.children.is_empty() {
+            if !prefix.is_empty() {
+                f(prefix);
+            }
+            return;
+        }
+
+        for (name, child) in &self.children {
+            let len = prefix.len();
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(name);
+            child.collect_paths(prefix, f);
+            prefix.truncate(len);
+        }
+    }
+
+    fn to_field_mask(&self) -> FieldMask {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut String::new(), &mut |path| paths.push(path.to_owned()));
+        FieldMask { paths }
+    }
+
+    fn trim(&self, message: &mut DynamicMessage) {
+        let desc = message.desc.clone();
+        for field in desc.fields() {
+            match self.children.get(field.name()) {
+                None => message.clear_field(&field),
+                Some(child) if child.children.is_empty() => (),
+                Some(child) => {
+                    if message.has_field(&field) {
+                        if let Value::Message(nested) = message.get_field_mut(&field) {
+                            child.trim(nested);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge(&self, dst: &mut DynamicMessage, src: &DynamicMessage, options: &MergeOptions) {
+        for (name, child) in &self.children {
+            let field = dst
+                .desc
+                .get_field_by_name(name)
+                .expect("field mask should be validated");
+
+            if child.children.is_empty() {
+                merge_field(dst, src, &field, options);
+            } else if src.has_field(&field) {
+                let src_value = src.get_field(&field);
+                if let (Value::Message(dst_nested), Value::Message(src_nested)) =
+                    (dst.get_field_mut(&field), src_value.as_ref())
+                {
+                    child.merge(dst_nested, src_nested, options);
+                }
+            } else if dst.has_field(&field) {
+                // Clear the fields covered by the mask in the destination.
+                if let Value::Message(dst_nested) = dst.get_field_mut(&field) {
+                    let src_nested = DynamicMessage::new(dst_nested.desc.clone());
+                    child.merge(dst_nested, &src_nested, options);
+                }
+            }
+        }
+    }
+}
+
+fn merge_field(
+    dst: &mut DynamicMessage,
+    src: &DynamicMessage,
+    field: &FieldDescriptor,
+    options: &MergeOptions,
+) {
+    if !src.has_field(field) {
+        if field.is_list() || field.is_map() {
+            if options.replace_repeated_fields {
+                dst.clear_field(field);
+            }
+        } else if field.kind().as_message().is_none() || options.replace_message_fields {
+            dst.clear_field(field);
+        }
+        return;
+    }
+
+    let src_value = src.get_field(field);
+    match (dst.get_field_mut(field), src_value.as_ref()) {
+        (Value::List(dst_list), Value::List(src_list)) if !options.replace_repeated_fields => {
+            dst_list.extend(src_list.iter().cloned());
+        }
+        (Value::Map(dst_map), Value::Map(src_map)) if !options.replace_repeated_fields => {
+            dst_map.extend(src_map.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        (Value::Message(dst_nested), Value::Message(src_nested))
+            if !options.replace_message_fields =>
+        {
+            merge_message(dst_nested, src_nested);
+        }
+        (dst_value, src_value) => *dst_value = src_value.clone(),
+    }
+}
+
+/// Merges `src` into `dst`, following the standard protobuf merge semantics.
+fn merge_message(dst: &mut DynamicMessage, src: &DynamicMessage) {
+    for (field, src_value) in src.fields() {
+        match (dst.get_field_mut(&field), src_value) {
+            (Value::List(dst_list), Value::List(src_list)) => {
+                dst_list.extend(src_list.iter().cloned());
+            }
+            (Value::Map(dst_map), Value::Map(src_map)) => {
+                dst_map.extend(src_map.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            (Value::Message(dst_nested), Value::Message(src_nested)) => {
+                merge_message(dst_nested, src_nested);
+            }
+            (dst_value, src_value) => *dst_value = src_value.clone(),
+        }
+    }
+
+    for (extension, src_value) in src.extensions() {
+        match (dst.get_extension_mut(&extension), src_value) {
+            (Value::List(dst_list), Value::List(src_list)) => {
+                dst_list.extend(src_list.iter().cloned());
+            }
+            (Value::Message(dst_nested), Value::Message(src_nested)) => {
+                merge_message(dst_nested, src_nested);
+            }
+            (dst_value, src_value) => *dst_value = src_value.clone(),
+        }
+    }
+
+    for unknown in src.unknown_fields() {
+        dst.fields.add_unknown(unknown.number(), unknown.clone());
+    }
+}
@@ -29,6 +29,7 @@ pub(crate) trait FieldDescriptorLike: fmt::Debug {
     fn is_map(&self) -> bool;
     fn is_packed(&self) -> bool;
     fn is_packable(&self) -> bool;
+    fn encoded_options(&self) -> &[u8];
     fn has(&self, value: &Value) -> bool {
         self.supports_presence() || !self.is_default_value(value)
     }
@@ -351,6 +352,17 @@ impl DynamicMessageFieldSet {
             })
     }
 
+    /// Retains only the unknown fields for which `f` returns `true`, passing each a mutable reference.
+    pub(super) fn retain_unknown(&mut self, mut f: impl FnMut(&mut UnknownField) -> bool) {
+        self.fields.retain(|_, value| match value {
+            ValueOrUnknown::Unknown(unknowns) => {
+                unknowns.retain_mut(&mut f);
+                !unknowns.is_empty()
+            }
+            ValueOrUnknown::Taken | ValueOrUnknown::Value(_) => true,
+        });
+    }
+
     pub(super) fn clear_all(&mut self) {
         self.fields.clear();
     }
@@ -432,6 +444,10 @@ impl FieldDescriptorLike for FieldDescriptor {
     fn is_packable(&self) -> bool {
         self.is_packable()
     }
+
+    fn encoded_options(&self) -> &[u8] {
+        self.encoded_options()
+    }
 }
 
 impl FieldDescriptorLike for ExtensionDescriptor {
@@ -487,4 +503,8 @@ impl FieldDescriptorLike for ExtensionDescriptor {
     fn is_packable(&self) -> bool {
         self.is_packable()
     }
+
+    fn encoded_options(&self) -> &[u8] {
+        self.encoded_options()
+    }
 }
@@ -1,7 +1,9 @@
+use std::mem;
+
 use prost::{
     bytes::{Buf, BufMut},
     encoding::{DecodeContext, WireType},
-    DecodeError, Message,
+    DecodeError, EncodeError, Message,
 };
 
 use crate::{
@@ -10,8 +12,8 @@ use crate::{
 };
 
 use super::{
+    decode::{check_recursion_limit, check_wire_type, decode_len, DecodeState},
     fields::{FieldDescriptorLike, ValueAndDescriptor},
-    unknown::UnknownField,
 };
 
 impl Message for DynamicMessage {
@@ -20,15 +22,7 @@ impl Message for DynamicMessage {
         Self: Sized,
     {
         for field in self.fields.iter(&self.desc, false, false) {
-            match field {
-                ValueAndDescriptor::Field(value, field_desc) => {
-                    value.encode_field(&field_desc, buf)
-                }
-                ValueAndDescriptor::Extension(value, extension_desc) => {
-                    value.encode_field(&extension_desc, buf)
-                }
-                ValueAndDescriptor::Unknown(unknowns) => unknowns.encode_raw(buf),
-            }
+            field.encode(buf, false);
         }
     }
 
@@ -42,21 +36,8 @@ impl Message for DynamicMessage {
     where
         Self: Sized,
     {
-        if let Some(field_desc) = self.desc.get_field(number) {
-            self.get_field_mut(&field_desc)
-                .merge_field(&field_desc, wire_type, buf, ctx)
-        } else if let Some(extension_desc) = self.desc.get_extension(number) {
-            self.get_extension_mut(&extension_desc).merge_field(
-                &extension_desc,
-                wire_type,
-                buf,
-                ctx,
-            )
-        } else {
-            let field = UnknownField::decode_value(number, wire_type, buf, ctx)?;
-            self.fields.add_unknown(number, field);
-            Ok(())
-        }
+        check_recursion_limit(ctx)?;
+        self.merge_field_with_state(number, wire_type, buf, &mut DecodeState::default())
     }
 
     fn encoded_len(&self) -> usize {
@@ -80,9 +61,191 @@ impl Message for DynamicMessage {
     }
 }
 
-impl Value {
-    pub(super) fn encode_field<B>(&self, field_desc: &impl FieldDescriptorLike, buf: &mut B)
+impl DynamicMessage {
+    /// Encodes the message to a buffer, using a deterministic encoding.
+    ///
+    /// Unlike [`Message::encode()`], the output of this method does not depend on the iteration order
+    /// of map fields: map entries are written in order of their keys, and all fields, extensions and
+    /// unknown fields are written in order of their field number. Nested messages are also encoded
+    /// deterministically.
+    ///
+    /// Note that the deterministic encoding of a message is only guaranteed to be stable for a single
+    /// version of its message definition, and should not be treated as a canonical form across
+    /// languages or implementations.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x1a\x02\x10\x42\x08\x96\x01".as_ref()).unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// dynamic_message.encode_deterministic(&mut buf).unwrap();
+    /// assert_eq!(buf, b"\x08\x96\x01\x1a\x02\x10\x42");
+    /// ```
+    pub fn encode_deterministic(&self, buf: &mut impl BufMut) -> Result<(), EncodeError> {
+        if self.encoded_len() > buf.remaining_mut() {
+            // The buffer is too small: Message::encode returns the appropriate error without writing anything.
+            return self.encode(buf);
+        }
+
+        self.encode_raw_deterministic(buf);
+        Ok(())
+    }
+
+    /// Encodes the message to a newly allocated buffer, using a deterministic encoding.
+    ///
+    /// See [`encode_deterministic()`](DynamicMessage::encode_deterministic) for details of the encoding.
+    pub fn encode_deterministic_to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_len());
+        self.encode_raw_deterministic(&mut buf);
+        buf
+    }
+
+    fn encode_raw_deterministic(&self, buf: &mut impl BufMut) {
+        let mut fields: Vec<_> = self.fields.iter(&self.desc, false, false).collect();
+        fields.sort_by_key(|field| field.number());
+        for field in fields {
+            field.encode(buf, true);
+        }
+    }
+}
+
+impl ValueAndDescriptor<'_> {
+    fn number(&self) -> u32 {
+        match self {
+            ValueAndDescriptor::Field(_, field_desc) => field_desc.number(),
+            ValueAndDescriptor::Extension(_, extension_desc) => extension_desc.number(),
+            ValueAndDescriptor::Unknown(unknowns) => {
+                unknowns.iter().next().map_or(0, |unknown| unknown.number())
+            }
+        }
+    }
+
+    fn encode(&self, buf: &mut impl BufMut, deterministic: bool) {
+        match self {
+            ValueAndDescriptor::Field(value, field_desc) => {
+                value.encode_field(field_desc, buf, deterministic)
+            }
+            ValueAndDescriptor::Extension(value, extension_desc) => {
+                value.encode_field(extension_desc, buf, deterministic)
+            }
+            ValueAndDescriptor::Unknown(unknowns) => unknowns.encode_raw(buf),
+        }
+    }
+}
+
+impl DynamicMessage {
+    pub(super) fn merge_with_state<B>(
+        &mut self,
+        buf: &mut B,
+        state: &mut DecodeState,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        while buf.has_remaining() {
+            let (number, wire_type) = prost::encoding::decode_key(buf)?;
+            self.merge_field_with_state(number, wire_type, buf, state)?;
+        }
+        Ok(())
+    }
+
+    fn merge_field_with_state<B>(
+        &mut self,
+        number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        state: &mut DecodeState,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        if let Some(field_desc) = self.desc.get_field(number) {
+            self.get_field_mut(&field_desc)
+                .merge_field(&field_desc, wire_type, buf, state)
+        } else if let Some(extension_desc) = self.desc.get_extension(number) {
+            self.get_extension_mut(&extension_desc).merge_field(
+                &extension_desc,
+                wire_type,
+                buf,
+                state,
+            )
+        } else {
+            if let Some(field) = state.decode_unknown(&self.desc, number, wire_type, buf)? {
+                self.fields.add_unknown(number, field);
+            }
+            Ok(())
+        }
+    }
+
+    fn merge_length_delimited<B>(
+        &mut self,
+        wire_type: WireType,
+        buf: &mut B,
+        state: &mut DecodeState,
+    ) -> Result<(), DecodeError>
+    where
+        B: Buf,
+    {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        let len = decode_len(buf)?;
+        let limit = buf.remaining() - len;
+
+        state.enter()?;
+        while buf.remaining() > limit {
+            let (number, wire_type) = prost::encoding::decode_key(buf)?;
+            self.merge_field_with_state(number, wire_type, buf, state)?;
+        }
+        state.exit();
+
+        if buf.remaining() != limit {
+            return Err(DecodeError::new("delimited length exceeded"));
+        }
+        Ok(())
+    }
+
+    fn merge_group<B>(
+        &mut self,
+        group_number: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        state: &mut DecodeState,
+    ) -> Result<(), DecodeError>
     where
+        B: Buf,
+    {
+        check_wire_type(WireType::StartGroup, wire_type)?;
+
+        state.enter()?;
+        loop {
+            let (number, wire_type) = prost::encoding::decode_key(buf)?;
+            if wire_type == WireType::EndGroup {
+                if number != group_number {
+                    return Err(DecodeError::new("unexpected end group tag"));
+                }
+                break;
+            }
+            self.merge_field_with_state(number, wire_type, buf, state)?;
+        }
+        state.exit();
+
+        Ok(())
+    }
+}
+
+impl Value {
+    pub(super) fn encode_field<B>(
+        &self,
+        field_desc: &impl FieldDescriptorLike,
+        buf: &mut B,
+        deterministic: bool,
+    ) where
         B: BufMut,
     {
         if !field_desc.supports_presence() && field_desc.is_default_value(self) {
@@ -132,7 +295,17 @@ impl Value {
                 prost::encoding::int32::encode(number, value, buf)
             }
             (Value::Message(message), Kind::Message(_)) => {
-                if field_desc.is_group() {
+                if deterministic {
+                    if field_desc.is_group() {
+                        prost::encoding::encode_key(number, WireType::StartGroup, buf);
+                        message.encode_raw_deterministic(buf);
+                        prost::encoding::encode_key(number, WireType::EndGroup, buf);
+                    } else {
+                        prost::encoding::encode_key(number, WireType::LengthDelimited, buf);
+                        prost::encoding::encode_varint(message.encoded_len() as u64, buf);
+                        message.encode_raw_deterministic(buf);
+                    }
+                } else if field_desc.is_group() {
                     prost::encoding::group::encode(number, message, buf)
                 } else {
                     prost::encoding::message::encode(number, message, buf)
@@ -245,7 +418,7 @@ impl Value {
                     }
                 } else {
                     for value in values {
-                        value.encode_field(field_desc, buf);
+                        value.encode_field(field_desc, buf, deterministic);
                     }
                 }
             }
@@ -253,14 +426,26 @@ impl Value {
                 let key_desc = map_entry.get_field(MAP_ENTRY_KEY_NUMBER).unwrap();
                 let value_desc = map_entry.get_field(MAP_ENTRY_VALUE_NUMBER).unwrap();
 
-                for (key, value) in values {
+                let encode_entry = |key: &MapKey, value: &Value, buf: &mut B| {
                     let len = key.encoded_len(&key_desc) + value.encoded_len(&value_desc);
 
                     prost::encoding::encode_key(number, WireType::LengthDelimited, buf);
                     prost::encoding::encode_varint(len as u64, buf);
 
                     key.encode_field(&key_desc, buf);
-                    value.encode_field(&value_desc, buf);
+                    value.encode_field(&value_desc, buf, deterministic);
+                };
+
+                if deterministic {
+                    let mut entries: Vec<_> = values.iter().collect();
+                    entries.sort_unstable_by_key(|&(key, _)| key);
+                    for (key, value) in entries {
+                        encode_entry(key, value, buf);
+                    }
+                } else {
+                    for (key, value) in values {
+                        encode_entry(key, value, buf);
+                    }
                 }
             }
             (value, ty) => panic!(
@@ -275,11 +460,12 @@ impl Value {
         field_desc: &impl FieldDescriptorLike,
         wire_type: WireType,
         buf: &mut B,
-        ctx: DecodeContext,
+        state: &mut DecodeState,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
     {
+        let ctx = DecodeContext::default();
         match (self, field_desc.kind()) {
             (Value::Bool(value), Kind::Bool) => {
                 prost::encoding::bool::merge(wire_type, value, buf, ctx)
@@ -320,33 +506,42 @@ impl Value {
             (Value::F64(value), Kind::Double) => {
                 prost::encoding::double::merge(wire_type, value, buf, ctx)
             }
-            (Value::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
-            }
-            (Value::Bytes(value), Kind::Bytes) => {
-                prost::encoding::bytes::merge(wire_type, value, buf, ctx)
-            }
+            (Value::String(value), Kind::String) => state.merge_string(wire_type, value, buf),
+            (Value::Bytes(value), Kind::Bytes) => state.merge_bytes(wire_type, value, buf),
             (Value::EnumNumber(value), Kind::Enum(_)) => {
                 prost::encoding::int32::merge(wire_type, value, buf, ctx)
             }
             (Value::Message(message), Kind::Message(_)) => {
                 if field_desc.is_group() {
-                    prost::encoding::group::merge(field_desc.number(), wire_type, message, buf, ctx)
+                    message.merge_group(field_desc.number(), wire_type, buf, state)
                 } else {
-                    prost::encoding::message::merge(wire_type, message, buf, ctx)
+                    message.merge_length_delimited(wire_type, buf, state)
                 }
             }
             (Value::List(values), field_kind) if field_desc.is_list() => {
                 if wire_type == WireType::LengthDelimited && field_desc.is_packable() {
-                    prost::encoding::merge_loop(values, buf, ctx, |values, buf, ctx| {
+                    let len = decode_len(buf)?;
+                    let count = state.allocate_packed(&field_kind, buf, len)?;
+                    values.reserve(count);
+
+                    let limit = buf.remaining() - len;
+                    let start = values.len();
+                    while buf.remaining() > limit {
+                        if values.len() - start >= count {
+                            state.allocate(mem::size_of::<Value>())?;
+                        }
                         let mut value = Value::default_value(&field_kind);
-                        value.merge_field(field_desc, field_kind.wire_type(), buf, ctx)?;
+                        value.merge_field(field_desc, field_kind.wire_type(), buf, state)?;
                         values.push(value);
-                        Ok(())
-                    })
+                    }
+                    if buf.remaining() != limit {
+                        return Err(DecodeError::new("delimited length exceeded"));
+                    }
+                    Ok(())
                 } else {
+                    state.allocate(mem::size_of::<Value>())?;
                     let mut value = Value::default_value(&field_kind);
-                    value.merge_field(field_desc, wire_type, buf, ctx)?;
+                    value.merge_field(field_desc, wire_type, buf, state)?;
                     values.push(value);
                     Ok(())
                 }
@@ -355,23 +550,30 @@ impl Value {
                 let key_desc = map_entry.get_field(MAP_ENTRY_KEY_NUMBER).unwrap();
                 let value_desc = map_entry.get_field(MAP_ENTRY_VALUE_NUMBER).unwrap();
 
+                check_wire_type(WireType::LengthDelimited, wire_type)?;
+                let len = decode_len(buf)?;
+                let limit = buf.remaining() - len;
+                state.allocate(mem::size_of::<MapKey>() + mem::size_of::<Value>())?;
+
                 let mut key = MapKey::default_value(&key_desc.kind());
                 let mut value = Value::default_value_for_field(&value_desc);
-                prost::encoding::merge_loop(
-                    &mut (&mut key, &mut value),
-                    buf,
-                    ctx,
-                    |(key, value), buf, ctx| {
-                        let (number, wire_type) = prost::encoding::decode_key(buf)?;
-                        match number {
-                            MAP_ENTRY_KEY_NUMBER => key.merge_field(&key_desc, wire_type, buf, ctx),
-                            MAP_ENTRY_VALUE_NUMBER => {
-                                value.merge_field(&value_desc, wire_type, buf, ctx)
-                            }
-                            _ => prost::encoding::skip_field(wire_type, number, buf, ctx),
+                state.enter()?;
+                while buf.remaining() > limit {
+                    let (number, wire_type) = prost::encoding::decode_key(buf)?;
+                    match number {
+                        MAP_ENTRY_KEY_NUMBER => {
+                            key.merge_field(&key_desc, wire_type, buf, state)?
+                        }
+                        MAP_ENTRY_VALUE_NUMBER => {
+                            value.merge_field(&value_desc, wire_type, buf, state)?
                         }
-                    },
-                )?;
+                        _ => prost::encoding::skip_field(wire_type, number, buf, ctx.clone())?,
+                    }
+                }
+                state.exit();
+                if buf.remaining() != limit {
+                    return Err(DecodeError::new("delimited length exceeded"));
+                }
                 values.insert(key, value);
 
                 Ok(())
@@ -591,16 +793,17 @@ impl MapKey {
         }
     }
 
-    fn merge_field<B>(
+    pub(super) fn merge_field<B>(
         &mut self,
         field_desc: &FieldDescriptor,
         wire_type: WireType,
         buf: &mut B,
-        ctx: DecodeContext,
+        state: &mut DecodeState,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
     {
+        let ctx = DecodeContext::default();
         match (self, field_desc.kind()) {
             (MapKey::Bool(value), Kind::Bool) => {
                 prost::encoding::bool::merge(wire_type, value, buf, ctx)
@@ -635,9 +838,7 @@ impl MapKey {
             (MapKey::U64(value), Kind::Fixed64) => {
                 prost::encoding::fixed64::merge(wire_type, value, buf, ctx)
             }
-            (MapKey::String(value), Kind::String) => {
-                prost::encoding::string::merge(wire_type, value, buf, ctx)
-            }
+            (MapKey::String(value), Kind::String) => state.merge_string(wire_type, value, buf),
             (value, ty) => panic!(
                 "mismatch between DynamicMessage value {:?} and type {:?}",
                 value, ty
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use prost::{
+    encoding::{self, DecodeContext, WireType},
+    DecodeError, Message,
+};
+
+use crate::{
+    dynamic::{decode::DecodeState, FieldDescriptorLike},
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, MessageDescriptor, Value,
+};
+
+/// A read-only view of an encoded protobuf message, which decodes field values only when they are accessed.
+///
+/// Constructing a [`DynamicMessageRef`] scans the encoded bytes once to find the location of each field,
+/// without decoding any values or allocating strings or nested messages. Field values are then decoded
+/// on demand by [`get_field()`](DynamicMessageRef::get_field), and nested messages can be accessed as views
+/// using [`get_message()`](DynamicMessageRef::get_message). This is useful when only a few fields of a
+/// large message are needed.
+///
+/// Field values are decoded with the same semantics as [`DynamicMessage::decode()`]: repeated occurrences of
+/// a singular field are merged, and setting a member of a oneof clears any earlier members.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessageRef, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let bytes = b"\x08\x96\x01\x1a\x02\x10\x42";
+/// let message = DynamicMessageRef::new(message_descriptor.clone(), bytes).unwrap();
+///
+/// let foo = message_descriptor.get_field_by_name("foo").unwrap();
+/// assert_eq!(message.get_field(&foo).unwrap(), Value::I32(150));
+///
+/// let nested = message_descriptor.get_field_by_name("nested").unwrap();
+/// let bar = message_descriptor.get_field_by_name("bar").unwrap();
+/// let nested_message = message.get_message(&nested).unwrap().unwrap();
+/// assert_eq!(nested_message.get_field(&bar).unwrap(), Value::I32(66));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynamicMessageRef<'a> {
+    desc: MessageDescriptor,
+    fields: BTreeMap<u32, Vec<Occurrence<'a>>>,
+}
+
+/// The location of a single occurrence of a field in the encoded message.
+#[derive(Debug, Clone, Copy)]
+struct Occurrence<'a> {
+    /// The position of this occurrence relative to all other fields in the message.
+    position: usize,
+    wire_type: WireType,
+    /// The encoded value, excluding the key. For groups, this includes the end group tag.
+    bytes: &'a [u8],
+}
+
+impl<'a> DynamicMessageRef<'a> {
+    /// Creates a view of the message of type `desc` encoded in `buf`.
+    ///
+    /// An error is returned if `buf` is not a structurally valid protobuf message. The contents of
+    /// individual fields are not validated until they are accessed.
+    pub fn new(desc: MessageDescriptor, buf: &'a [u8]) -> Result<Self, DecodeError> {
+        Self::from_parts(desc, &[buf])
+    }
+
+    fn from_parts(desc: MessageDescriptor, parts: &[&'a [u8]]) -> Result<Self, DecodeError> {
+        let mut fields: BTreeMap<u32, Vec<Occurrence<'a>>> = BTreeMap::new();
+        let mut position = 0;
+        for &part in parts {
+            let mut buf = part;
+            while !buf.is_empty() {
+                let (number, wire_type) = encoding::decode_key(&mut buf)?;
+                let value = buf;
+                encoding::skip_field(wire_type, number, &mut buf, DecodeContext::default())?;
+
+                fields.entry(number).or_default().push(Occurrence {
+                    position,
+                    wire_type,
+                    bytes: &value[..value.len() - buf.len()],
+                });
+                position += 1;
+            }
+        }
+
+        Ok(DynamicMessageRef { desc, fields })
+    }
+
+    /// Gets a reference to the message descriptor describing the type of this message.
+    pub fn descriptor(&self) -> MessageDescriptor {
+        self.desc.clone()
+    }
+
+    /// Returns `true` if the encoded message contains a value for the given field.
+    ///
+    /// Unlike [`DynamicMessage::has_field()`], this does not decode the field, so it returns `true` if the
+    /// field is present in the encoded bytes even if it is set to its default value.
+    pub fn has_field(&self, field_desc: &FieldDescriptor) -> bool {
+        self.occurrences(field_desc).next().is_some()
+    }
+
+    /// Decodes the value of the given field, or returns the default value if it is not set.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `field_desc` is not a field of this message type.
+    pub fn get_field(&self, field_desc: &FieldDescriptor) -> Result<Value, DecodeError> {
+        assert_eq!(field_desc.parent_message(), &self.desc);
+        self.decode_field(field_desc)
+    }
+
+    /// Decodes the value of the field with the given number, or returns the default value if it is not set.
+    ///
+    /// Returns `None` if the message has no field with the given number.
+    pub fn get_field_by_number(&self, number: u32) -> Option<Result<Value, DecodeError>> {
+        self.desc
+            .get_field(number)
+            .map(|field_desc| self.decode_field(&field_desc))
+    }
+
+    /// Decodes the value of the field with the given name, or returns the default value if it is not set.
+    ///
+    /// Returns `None` if the message has no field with the given name.
+    pub fn get_field_by_name(&self, name: &str) -> Option<Result<Value, DecodeError>> {
+        self.desc
+            .get_field_by_name(name)
+            .map(|field_desc| self.decode_field(&field_desc))
+    }
+
+    /// Returns `true` if the encoded message contains a value for the given extension field.
+    pub fn has_extension(&self, extension_desc: &ExtensionDescriptor) -> bool {
+        self.occurrences(extension_desc).next().is_some()
+    }
+
+    /// Decodes the value of the given extension field, or returns the default value if it is not set.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `extension_desc` does not extend this message type.
+    pub fn get_extension(
+        &self,
+        extension_desc: &ExtensionDescriptor,
+    ) -> Result<Value, DecodeError> {
+        assert_eq!(extension_desc.containing_message(), self.desc);
+        self.decode_field(extension_desc)
+    }
+
+    /// Returns a view of the singular message field `field_desc`, or `None` if it is not set.
+    ///
+    /// The nested message is not decoded. If the field occurs multiple times in the encoded message,
+    /// the returned view merges all occurrences.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `field_desc` is not a singular message field of this message type.
+    pub fn get_message(
+        &self,
+        field_desc: &FieldDescriptor,
+    ) -> Result<Option<DynamicMessageRef<'a>>, DecodeError> {
+        assert_eq!(field_desc.parent_message(), &self.desc);
+        let desc = match field_desc.kind().as_message() {
+            Some(desc) if !field_desc.is_list() && !field_desc.is_map() => desc.clone(),
+            _ => panic!(
+                "field '{}' is not a singular message field",
+                field_desc.full_name()
+            ),
+        };
+
+        let parts = self
+            .occurrences(field_desc)
+            .map(|occurrence| occurrence.message_bytes(field_desc.number()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.is_empty() {
+            return Ok(None);
+        }
+
+        DynamicMessageRef::from_parts(desc, &parts).map(Some)
+    }
+
+    /// Returns views of each element of the repeated message field `field_desc`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `field_desc` is not a repeated message field of this message type.
+    pub fn get_message_list(
+        &self,
+        field_desc: &FieldDescriptor,
+    ) -> Result<Vec<DynamicMessageRef<'a>>, DecodeError> {
+        assert_eq!(field_desc.parent_message(), &self.desc);
+        let desc = match field_desc.kind().as_message() {
+            Some(desc) if field_desc.is_list() => desc.clone(),
+            _ => panic!(
+                "field '{}' is not a repeated message field",
+                field_desc.full_name()
+            ),
+        };
+
+        self.occurrences(field_desc)
+            .map(|occurrence| {
+                let bytes = occurrence.message_bytes(field_desc.number())?;
+                DynamicMessageRef::new(desc.clone(), bytes)
+            })
+            .collect()
+    }
+
+    /// Decodes all fields of this message into a [`DynamicMessage`].
+    pub fn to_dynamic_message(&self) -> Result<DynamicMessage, DecodeError> {
+        let mut occurrences: Vec<(u32, &Occurrence<'a>)> = self
+            .fields
+            .iter()
+            .flat_map(|(&number, occurrences)| {
+                occurrences
+                    .iter()
+                    .map(move |occurrence| (number, occurrence))
+            })
+            .collect();
+        occurrences.sort_by_key(|(_, occurrence)| occurrence.position);
+
+        let mut message = DynamicMessage::new(self.desc.clone());
+        for (number, occurrence) in occurrences {
+            let mut buf = occurrence.bytes;
+            message.merge_field(
+                number,
+                occurrence.wire_type,
+                &mut buf,
+                DecodeContext::default(),
+            )?;
+        }
+        Ok(message)
+    }
+
+    fn decode_field(&self, desc: &impl FieldDescriptorLike) -> Result<Value, DecodeError> {
+        let mut value = desc.default_value();
+        for occurrence in self.occurrences(desc) {
+            let mut buf = occurrence.bytes;
+            value.merge_field(
+                desc,
+                occurrence.wire_type,
+                &mut buf,
+                &mut DecodeState::default(),
+            )?;
+        }
+        Ok(value)
+    }
+
+    /// Returns the occurrences of a field which contribute to its value.
+    fn occurrences<'b>(
+        &'b self,
+        desc: &impl FieldDescriptorLike,
+    ) -> impl Iterator<Item = &'b Occurrence<'a>> + 'b {
+        // Setting a member of a oneof clears any other members, so ignore occurrences before the last
+        // occurrence of a different member.
+        let cleared_before = desc.containing_oneof().and_then(|oneof| {
+            oneof
+                .fields()
+                .filter(|field| field.number() != desc.number())
+                .filter_map(|field| self.fields.get(&field.number())?.last())
+                .map(|occurrence| occurrence.position)
+                .max()
+        });
+
+        self.fields
+            .get(&desc.number())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(move |occurrence| {
+                cleared_before.map_or(true, |position| occurrence.position > position)
+            })
+    }
+}
+
+impl<'a> Occurrence<'a> {
+    /// Returns the encoded bytes of the message contained in this occurrence.
+    fn message_bytes(&self, number: u32) -> Result<&'a [u8], DecodeError> {
+        match self.wire_type {
+            WireType::LengthDelimited => {
+                let mut buf = self.bytes;
+                let len = encoding::decode_varint(&mut buf)?;
+                Ok(&buf[..len as usize])
+            }
+            WireType::StartGroup => Ok(&self.bytes[..self.bytes.len() - encoding::key_len(number)]),
+            wire_type => Err(DecodeError::new(format!(
+                "invalid wire type: {:?} (expected LengthDelimited)",
+                wire_type
+            ))),
+        }
+    }
+}
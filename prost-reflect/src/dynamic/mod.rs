@@ -6,19 +6,88 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
 pub mod text_format;
 
+/// Packing and unpacking of messages in the [`Any`](prost_types::Any) well-known type.
+///
+/// See [`DynamicMessage::pack_any()`] and [`DynamicMessage::unpack_any()`] for the main entry points of this
+/// module, and [`TypeResolver`](any::TypeResolver) for customizing how type URLs are resolved.
+pub mod any;
+/// Generation of arbitrary messages for property-based testing with [`proptest`].
+///
+/// See [`message()`](arbitrary::message) and [`message_with_options()`](arbitrary::message_with_options) for the
+/// main entry points of this module.
+#[cfg(feature = "proptest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub mod arbitrary;
+mod canonical;
+#[cfg(feature = "cel")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cel")))]
+pub mod cel;
+/// Conversion of messages between different message types, such as versions of a schema.
+///
+/// See [`DynamicMessage::convert_to()`] for the main entry point of this module.
+pub mod convert;
+mod decode;
+/// Reading and writing streams of length-delimited messages.
+///
+/// This module contains a reader and writer for sequences of messages which are each prefixed by their
+/// length. With the `tokio-util` feature enabled, it also contains a codec for use with asynchronous IO.
+pub mod delimited;
+/// Comparison of messages, reporting the differences between them as a list of field paths and values.
+///
+/// See [`DynamicMessage::diff()`] for the main entry point of this module.
+pub mod diff;
+/// Generation of plausible example messages, for documentation and mock services.
+///
+/// See [`DynamicMessage::example()`] for the main entry point of this module.
+pub mod example;
+/// Utilities for working with the [`FieldMask`](prost_types::FieldMask) well-known type.
+///
+/// This module contains functions for validating and combining field masks. See also the associated functions
+/// [`DynamicMessage::trim()`] and [`DynamicMessage::merge_with_field_mask()`].
+pub mod field_mask;
 mod fields;
 mod message;
+mod message_ref;
+mod path;
+/// Decoding of messages without a schema, equivalent to `protoc --decode_raw`.
+///
+/// See [`decode_raw()`](raw::decode_raw) for the main entry point of this module.
+pub mod raw;
+mod redact;
+mod reparse;
 #[cfg(feature = "serde")]
 mod serde;
 #[cfg(not(feature = "text-format"))]
 mod text_format;
+mod timestamp;
 mod unknown;
+/// Validation of messages against [protovalidate](https://github.com/bufbuild/protovalidate) constraints.
+///
+/// See [`DynamicMessage::validate()`] for the main entry point of this module, and [`Validator`](validate::Validator)
+/// to check many messages of the same type.
+#[cfg(feature = "validate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "validate")))]
+pub mod validate;
+/// Depth-first traversal of the contents of messages, with visitors which may inspect or modify values.
+///
+/// See [`DynamicMessage::visit()`] and [`DynamicMessage::visit_mut()`] for the main entry points of this module.
+pub mod visit;
+mod wkt;
 
 use std::{borrow::Cow, collections::HashMap, error::Error, fmt};
 
+pub use self::canonical::{CanonicalDynamicMessage, CanonicalOptions, CanonicalValue};
+pub use self::decode::DecodeOptions;
+pub use self::message_ref::DynamicMessageRef;
+pub use self::path::{FieldPath, FieldPathError, FieldPathSegment};
+pub use self::redact::RedactOptions;
 #[cfg(feature = "serde")]
-pub use self::serde::{DeserializeOptions, SerializeOptions};
-pub use self::unknown::UnknownField;
+pub use self::serde::{
+    DeserializeError, DeserializeOptions, DeserializePathSegment, MessageDeserializer,
+    MessageSerializer, SerializeError, SerializeOptions,
+};
+pub use self::timestamp::TimeError;
+pub use self::unknown::{UnknownField, UnknownFieldSet, UnknownFieldValue};
 
 pub(crate) use self::fields::FieldDescriptorLike;
 
@@ -548,6 +617,38 @@ impl DynamicMessage {
         self.fields.iter_unknown()
     }
 
+    /// Adds an unknown field to the message.
+    ///
+    /// The field is stored after any existing unknown fields with the same number, and is written when
+    /// the message is encoded.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the message descriptor has a field or extension with the same number as `field`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, UnknownField};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("google.protobuf.Empty").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.add_unknown_field(UnknownField::varint(1, 150));
+    /// assert_eq!(dynamic_message.unknown_fields().count(), 1);
+    /// assert_eq!(dynamic_message.encode_to_vec(), b"\x08\x96\x01");
+    /// ```
+    pub fn add_unknown_field(&mut self, field: UnknownField) {
+        let number = field.number();
+        assert!(
+            self.desc.get_field(number).is_none() && self.desc.get_extension(number).is_none(),
+            "message '{}' has a field with number {}",
+            self.desc.full_name(),
+            number
+        );
+        self.fields.add_unknown(number, field);
+    }
+
     /// Clears all unknown fields from the message and returns an iterator yielding the values.
     ///
     /// If the iterator is dropped before completing the iteration, it is unspecified how many fields are removed.
@@ -1156,6 +1257,8 @@ pub(crate) fn fmt_string(f: &mut impl fmt::Write, bytes: &[u8]) -> fmt::Result {
 impl fmt::Display for DynamicMessage {
     /// Formats this message using the protobuf text format.
     ///
+    /// Fields marked with the `debug_redact` option are replaced with `[REDACTED]`. See [`RedactOptions`].
+    ///
     /// # Examples
     ///
     /// ```
@@ -1170,8 +1273,10 @@ impl fmt::Display for DynamicMessage {
     /// assert_eq!(format!("{:#}", dynamic_message), "foo: 150\nnested {\n  bar: 66\n}");
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        text_format::Writer::new(text_format::FormatOptions::new().pretty(f.alternate()), f)
-            .fmt_message(self)
+        let options = text_format::FormatOptions::new()
+            .pretty(f.alternate())
+            .redact(RedactOptions::new());
+        text_format::Writer::new(options, f).fmt_message(self)
     }
 }
 
@@ -1190,8 +1295,10 @@ impl fmt::Display for Value {
     /// assert_eq!(format!("{:#}", Value::Map(HashMap::from_iter([(MapKey::I32(1), Value::U32(2))]))), "[{\n  key: 1\n  value: 2\n}]");
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        text_format::Writer::new(text_format::FormatOptions::new().pretty(f.alternate()), f)
-            .fmt_value(self, None)
+        let options = text_format::FormatOptions::new()
+            .pretty(f.alternate())
+            .redact(RedactOptions::new());
+        text_format::Writer::new(options, f).fmt_value(self, None)
     }
 }
 
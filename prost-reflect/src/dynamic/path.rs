@@ -0,0 +1,784 @@
+use std::{borrow::Cow, error::Error, fmt};
+
+use crate::{
+    dynamic::{
+        diff::{index_path, join_path, key_path},
+        fields::FieldDescriptorLike,
+    },
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value,
+};
+
+/// A path to a value nested within a message, such as `a.b[3].c`.
+///
+/// A path is a sequence of field names separated by `.`. Elements of repeated fields are selected using an
+/// index in square brackets (`items[0]`), and entries of map fields using a key in square brackets
+/// (`labels["env"]`, `counts[-1]`, `flags[true]`). String keys must be quoted, using the same escapes as the
+/// protobuf text format. Extensions are referenced by their full name in square brackets, in place of a
+/// field name (`[my.package.ext].x` or `a.[my.package.ext]`).
+///
+/// A path is parsed against a [`MessageDescriptor`], so it is known to be valid for that message type. See
+/// [`DynamicMessage::get_path()`] and [`DynamicMessage::set_path()`] for how paths are applied to messages.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, FieldPath, Value};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let path = FieldPath::parse(&message_descriptor, "nested.foo").unwrap();
+///
+/// let mut message = DynamicMessage::new(message_descriptor.clone());
+/// message.set_path(&path, Value::I32(5)).unwrap();
+/// assert_eq!(message.get_path(&path).unwrap().as_ref(), &Value::I32(5));
+///
+/// assert!(FieldPath::parse(&message_descriptor, "foo.bar").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPath {
+    desc: MessageDescriptor,
+    segments: Vec<FieldPathSegment>,
+}
+
+/// A single step of a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldPathSegment {
+    /// A field of the current message.
+    Field(FieldDescriptor),
+    /// An extension field of the current message.
+    Extension(ExtensionDescriptor),
+    /// An element of the current repeated field.
+    Index(usize),
+    /// An entry of the current map field.
+    Key(MapKey),
+}
+
+/// Error type returned when parsing or applying a [`FieldPath`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldPathError {
+    /// The path could not be parsed.
+    InvalidSyntax {
+        /// The byte offset in the path at which the error occurred.
+        position: usize,
+        /// A description of what was expected at this position.
+        expected: String,
+    },
+    /// A field name in the path does not exist in the message.
+    FieldNotFound {
+        /// The full name of the message.
+        message: String,
+        /// The name of the field which was not found.
+        name: String,
+    },
+    /// An extension name in the path does not extend the message.
+    ExtensionNotFound {
+        /// The full name of the message.
+        message: String,
+        /// The name of the extension which was not found.
+        name: String,
+    },
+    /// A field was referenced within a value which is not a message.
+    NotAMessage {
+        /// The path to the value.
+        path: String,
+    },
+    /// An index or key was applied to a field which is not repeated.
+    NotIndexable {
+        /// The path to the field.
+        path: String,
+    },
+    /// A map key could not be parsed as the key type of the map.
+    InvalidMapKey {
+        /// The path to the map field.
+        path: String,
+        /// The key, as written in the path.
+        key: String,
+    },
+    /// An index was out of bounds for a repeated field.
+    IndexOutOfBounds {
+        /// The path to the repeated field.
+        path: String,
+        /// The index.
+        index: usize,
+        /// The length of the repeated field.
+        len: usize,
+    },
+    /// A value could not be set because its type does not match the type at the path.
+    InvalidType {
+        /// The path at which the value was to be set.
+        path: String,
+        /// The invalid value.
+        value: Value,
+    },
+}
+
+impl FieldPath {
+    /// Parses a path to a value within the message type described by `desc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is not syntactically valid, or if it does not describe a value within
+    /// messages of type `desc`.
+    pub fn parse(desc: &MessageDescriptor, path: &str) -> Result<Self, FieldPathError> {
+        Parser {
+            input: path,
+            pos: 0,
+        }
+        .parse(desc)
+    }
+
+    /// Gets the message type this path was parsed against.
+    pub fn message_descriptor(&self) -> &MessageDescriptor {
+        &self.desc
+    }
+
+    /// Gets the steps of this path. This is never empty, and the first segment is always a field or
+    /// extension.
+    pub fn segments(&self) -> &[FieldPathSegment] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for FieldPath {
+    /// Formats this path in the syntax accepted by [`FieldPath::parse()`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&segments_path(&self.segments))
+    }
+}
+
+impl DynamicMessage {
+    /// Gets the value at `path` within this message.
+    ///
+    /// Fields which are not set, including any intermediate messages, are treated as having their default
+    /// value. Returns `None` if an index in the path is out of bounds, or if a key is not present in a map.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `path` was not parsed against the type of this message.
+    pub fn get_path(&self, path: &FieldPath) -> Option<Cow<'_, Value>> {
+        self.check_path(path);
+
+        let (first, rest) = path.segments.split_first().unwrap();
+        let mut value = match first {
+            FieldPathSegment::Field(field_desc) => self.get_field(field_desc),
+            FieldPathSegment::Extension(extension_desc) => self.get_extension(extension_desc),
+            FieldPathSegment::Index(_) | FieldPathSegment::Key(_) => unreachable!(),
+        };
+        for segment in rest {
+            value = match value {
+                Cow::Borrowed(value) => get_segment(value, segment)?,
+                Cow::Owned(value) => Cow::Owned(get_segment(&value, segment)?.into_owned()),
+            };
+        }
+        Some(value)
+    }
+
+    /// Gets a mutable reference to the value at `path` within this message.
+    ///
+    /// Like [`get_field_mut()`](DynamicMessage::get_field_mut), fields along the path which are not set
+    /// are populated with their default value, which may clear other fields in the same oneof. Returns
+    /// `None` if an index in the path is out of bounds, or if a key is not present in a map.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `path` was not parsed against the type of this message.
+    pub fn get_path_mut(&mut self, path: &FieldPath) -> Option<&mut Value> {
+        self.check_path(path);
+        self.navigate_mut(&path.segments, false).ok()
+    }
+
+    /// Sets the value at `path` within this message.
+    ///
+    /// Any intermediate messages which are not set are created, as are the entries for any map keys in the
+    /// path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an index in the path is out of bounds, or if the type of `value` is not valid
+    /// for the value at `path`. If an error is returned, intermediate messages may have been created.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `path` was not parsed against the type of this message.
+    pub fn set_path(&mut self, path: &FieldPath, value: Value) -> Result<(), FieldPathError> {
+        self.check_path(path);
+
+        let (last, prefix) = path.segments.split_last().unwrap();
+        let is_valid = match last {
+            FieldPathSegment::Field(field_desc) => value.is_valid_for_field(field_desc),
+            FieldPathSegment::Extension(extension_desc) => {
+                value.is_valid_for_extension(extension_desc)
+            }
+            FieldPathSegment::Index(_) | FieldPathSegment::Key(_) => {
+                value.is_valid(&element_kind(prefix.last().unwrap()))
+            }
+        };
+        if !is_valid {
+            return Err(FieldPathError::InvalidType {
+                path: path.to_string(),
+                value,
+            });
+        }
+
+        match last {
+            FieldPathSegment::Field(field_desc) => self
+                .parent_message_mut(prefix)?
+                .set_field(field_desc, value),
+            FieldPathSegment::Extension(extension_desc) => self
+                .parent_message_mut(prefix)?
+                .set_extension(extension_desc, value),
+            FieldPathSegment::Index(index) => {
+                let list = self
+                    .navigate_mut(prefix, true)
+                    .map_err(|err| err.into_error(prefix))?
+                    .as_list_mut()
+                    .unwrap();
+                let len = list.len();
+                match list.get_mut(*index) {
+                    Some(element) => *element = value,
+                    None => {
+                        return Err(FieldPathError::IndexOutOfBounds {
+                            path: segments_path(prefix),
+                            index: *index,
+                            len,
+                        })
+                    }
+                }
+            }
+            FieldPathSegment::Key(key) => {
+                self.navigate_mut(prefix, true)
+                    .map_err(|err| err.into_error(prefix))?
+                    .as_map_mut()
+                    .unwrap()
+                    .insert(key.clone(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Clears the value at `path` within this message.
+    ///
+    /// If the path refers to a field, it is cleared as if by [`clear_field()`](DynamicMessage::clear_field).
+    /// If it refers to an element of a repeated field, the element is removed, and if it refers to an
+    /// entry of a map field, the entry is removed. If any intermediate value in the path is not set, this
+    /// method does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This method may panic if `path` was not parsed against the type of this message.
+    pub fn clear_path(&mut self, path: &FieldPath) {
+        self.check_path(path);
+
+        let (last, prefix) = path.segments.split_last().unwrap();
+        if !self.contains_path(prefix) {
+            return;
+        }
+
+        match last {
+            FieldPathSegment::Field(field_desc) => {
+                if let Ok(message) = self.parent_message_mut(prefix) {
+                    message.clear_field(field_desc);
+                }
+            }
+            FieldPathSegment::Extension(extension_desc) => {
+                if let Ok(message) = self.parent_message_mut(prefix) {
+                    message.clear_extension(extension_desc);
+                }
+            }
+            FieldPathSegment::Index(index) => {
+                if let Ok(Value::List(list)) = self.navigate_mut(prefix, false) {
+                    if *index < list.len() {
+                        list.remove(*index);
+                    }
+                }
+            }
+            FieldPathSegment::Key(key) => {
+                if let Ok(Value::Map(map)) = self.navigate_mut(prefix, false) {
+                    map.remove(key);
+                }
+            }
+        }
+    }
+
+    fn check_path(&self, path: &FieldPath) {
+        assert_eq!(
+            path.desc.full_name(),
+            self.desc.full_name(),
+            "field path is for a different message type"
+        );
+    }
+
+    fn parent_message_mut(
+        &mut self,
+        prefix: &[FieldPathSegment],
+    ) -> Result<&mut DynamicMessage, FieldPathError> {
+        if prefix.is_empty() {
+            Ok(self)
+        } else {
+            let value = self
+                .navigate_mut(prefix, true)
+                .map_err(|err| err.into_error(prefix))?;
+            Ok(value.as_message_mut().unwrap())
+        }
+    }
+
+    fn navigate_mut(
+        &mut self,
+        segments: &[FieldPathSegment],
+        insert_keys: bool,
+    ) -> Result<&mut Value, NavigateError> {
+        let (first, rest) = segments.split_first().unwrap();
+        let mut value = match first {
+            FieldPathSegment::Field(field_desc) => self.get_field_mut(field_desc),
+            FieldPathSegment::Extension(extension_desc) => self.get_extension_mut(extension_desc),
+            FieldPathSegment::Index(_) | FieldPathSegment::Key(_) => unreachable!(),
+        };
+        for (i, segment) in rest.iter().enumerate() {
+            value = match segment {
+                FieldPathSegment::Field(field_desc) => {
+                    value.as_message_mut().unwrap().get_field_mut(field_desc)
+                }
+                FieldPathSegment::Extension(extension_desc) => value
+                    .as_message_mut()
+                    .unwrap()
+                    .get_extension_mut(extension_desc),
+                FieldPathSegment::Index(index) => {
+                    let list = value.as_list_mut().unwrap();
+                    let len = list.len();
+                    match list.get_mut(*index) {
+                        Some(element) => element,
+                        None => {
+                            return Err(NavigateError::IndexOutOfBounds {
+                                position: i + 1,
+                                len,
+                            })
+                        }
+                    }
+                }
+                FieldPathSegment::Key(key) => {
+                    let map = value.as_map_mut().unwrap();
+                    if insert_keys {
+                        let kind = element_kind(&segments[i]);
+                        map.entry(key.clone())
+                            .or_insert_with(|| Value::default_value(&kind))
+                    } else {
+                        match map.get_mut(key) {
+                            Some(value) => value,
+                            None => return Err(NavigateError::KeyNotFound),
+                        }
+                    }
+                }
+            };
+        }
+        Ok(value)
+    }
+
+    fn contains_path(&self, segments: &[FieldPathSegment]) -> bool {
+        let mut current: Option<&Value> = None;
+        for segment in segments {
+            let next = match (current, segment) {
+                (None, FieldPathSegment::Field(field_desc)) => self
+                    .fields
+                    .has(field_desc)
+                    .then(|| self.fields.get(field_desc)),
+                (None, FieldPathSegment::Extension(extension_desc)) => self
+                    .fields
+                    .has(extension_desc)
+                    .then(|| self.fields.get(extension_desc)),
+                (Some(value), segment) => get_existing_segment(value, segment),
+                (None, _) => None,
+            };
+            current = match next {
+                Some(Cow::Borrowed(value)) => Some(value),
+                _ => return false,
+            };
+        }
+        true
+    }
+}
+
+/// The reason a path could not be followed through a message.
+enum NavigateError {
+    IndexOutOfBounds { position: usize, len: usize },
+    KeyNotFound,
+}
+
+impl NavigateError {
+    fn into_error(self, segments: &[FieldPathSegment]) -> FieldPathError {
+        match self {
+            NavigateError::IndexOutOfBounds { position, len } => {
+                let index = match &segments[position] {
+                    FieldPathSegment::Index(index) => *index,
+                    _ => unreachable!(),
+                };
+                FieldPathError::IndexOutOfBounds {
+                    path: segments_path(&segments[..position]),
+                    index,
+                    len,
+                }
+            }
+            NavigateError::KeyNotFound => unreachable!("keys are inserted when setting a path"),
+        }
+    }
+}
+
+fn get_segment<'a>(value: &'a Value, segment: &FieldPathSegment) -> Option<Cow<'a, Value>> {
+    match segment {
+        FieldPathSegment::Field(field_desc) => Some(value.as_message()?.get_field(field_desc)),
+        FieldPathSegment::Extension(extension_desc) => {
+            Some(value.as_message()?.get_extension(extension_desc))
+        }
+        FieldPathSegment::Index(index) => value.as_list()?.get(*index).map(Cow::Borrowed),
+        FieldPathSegment::Key(key) => value.as_map()?.get(key).map(Cow::Borrowed),
+    }
+}
+
+fn get_existing_segment<'a>(
+    value: &'a Value,
+    segment: &FieldPathSegment,
+) -> Option<Cow<'a, Value>> {
+    match segment {
+        FieldPathSegment::Field(field_desc) => {
+            let message = value.as_message()?;
+            message
+                .fields
+                .has(field_desc)
+                .then(|| message.fields.get(field_desc))
+        }
+        FieldPathSegment::Extension(extension_desc) => {
+            let message = value.as_message()?;
+            message
+                .fields
+                .has(extension_desc)
+                .then(|| message.fields.get(extension_desc))
+        }
+        FieldPathSegment::Index(_) | FieldPathSegment::Key(_) => get_segment(value, segment),
+    }
+}
+
+/// Gets the kind of the elements of the list or map field referenced by `segment`.
+fn element_kind(segment: &FieldPathSegment) -> Kind {
+    match segment {
+        FieldPathSegment::Field(field_desc) => field_element_kind(field_desc),
+        FieldPathSegment::Extension(extension_desc) => field_element_kind(extension_desc),
+        FieldPathSegment::Index(_) | FieldPathSegment::Key(_) => unreachable!(),
+    }
+}
+
+fn field_element_kind(field_desc: &impl FieldDescriptorLike) -> Kind {
+    let kind = field_desc.kind();
+    if field_desc.is_map() {
+        kind.as_message().unwrap().map_entry_value_field().kind()
+    } else {
+        kind
+    }
+}
+
+fn segments_path(segments: &[FieldPathSegment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        path = match segment {
+            FieldPathSegment::Field(field_desc) => join_path(&path, field_desc.name()),
+            FieldPathSegment::Extension(extension_desc) => {
+                join_path(&path, &format!("[{}]", extension_desc.full_name()))
+            }
+            FieldPathSegment::Index(index) => index_path(&path, *index),
+            FieldPathSegment::Key(key) => key_path(&path, key),
+        };
+    }
+    path
+}
+
+/// The type of the value reached by a prefix of a path.
+enum Target {
+    Message(MessageDescriptor),
+    List(Kind),
+    Map(Kind, Kind),
+    Scalar,
+}
+
+impl Target {
+    fn for_field(field_desc: &impl FieldDescriptorLike) -> Self {
+        let kind = field_desc.kind();
+        if field_desc.is_map() {
+            let entry = kind.as_message().unwrap();
+            Target::Map(
+                entry.map_entry_key_field().kind(),
+                entry.map_entry_value_field().kind(),
+            )
+        } else if field_desc.is_list() {
+            Target::List(kind)
+        } else {
+            Target::for_kind(kind)
+        }
+    }
+
+    fn for_kind(kind: Kind) -> Self {
+        match kind {
+            Kind::Message(message_desc) => Target::Message(message_desc),
+            _ => Target::Scalar,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self, desc: &MessageDescriptor) -> Result<FieldPath, FieldPathError> {
+        let mut segments = Vec::new();
+        let mut path = String::new();
+        let mut message_desc = desc.clone();
+        loop {
+            let mut target = if self.eat('[') {
+                let name =
+                    self.take_while(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '.');
+                if name.is_empty() {
+                    return Err(self.syntax_error("an extension name"));
+                }
+                self.expect(']')?;
+                let extension_desc = match message_desc
+                    .get_extension_by_full_name(name.strip_prefix('.').unwrap_or(name))
+                {
+                    Some(extension_desc) => extension_desc,
+                    None => {
+                        return Err(FieldPathError::ExtensionNotFound {
+                            message: message_desc.full_name().to_owned(),
+                            name: name.to_owned(),
+                        })
+                    }
+                };
+                path = join_path(&path, &format!("[{}]", extension_desc.full_name()));
+                let target = Target::for_field(&extension_desc);
+                segments.push(FieldPathSegment::Extension(extension_desc));
+                target
+            } else {
+                let name = self.take_while(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+                if name.is_empty() {
+                    return Err(self.syntax_error("a field name"));
+                }
+                let field_desc = match message_desc.get_field_by_name(name) {
+                    Some(field_desc) => field_desc,
+                    None => {
+                        return Err(FieldPathError::FieldNotFound {
+                            message: message_desc.full_name().to_owned(),
+                            name: name.to_owned(),
+                        })
+                    }
+                };
+                path = join_path(&path, field_desc.name());
+                let target = Target::for_field(&field_desc);
+                segments.push(FieldPathSegment::Field(field_desc));
+                target
+            };
+
+            while self.peek() == Some('[') {
+                target = match target {
+                    Target::List(kind) => {
+                        self.pos += 1;
+                        let index = self
+                            .take_while(|ch| ch.is_ascii_digit())
+                            .parse()
+                            .map_err(|_| self.syntax_error("an index"))?;
+                        self.expect(']')?;
+                        path = index_path(&path, index);
+                        segments.push(FieldPathSegment::Index(index));
+                        Target::for_kind(kind)
+                    }
+                    Target::Map(key_kind, value_kind) => {
+                        self.pos += 1;
+                        let key = self.parse_key(&key_kind, &path)?;
+                        self.expect(']')?;
+                        path = key_path(&path, &key);
+                        segments.push(FieldPathSegment::Key(key));
+                        Target::for_kind(value_kind)
+                    }
+                    Target::Message(_) | Target::Scalar => {
+                        return Err(FieldPathError::NotIndexable { path })
+                    }
+                };
+            }
+
+            match self.peek() {
+                None => break,
+                Some('.') => {
+                    self.pos += 1;
+                    match target {
+                        Target::Message(desc) => message_desc = desc,
+                        Target::List(_) | Target::Map(_, _) | Target::Scalar => {
+                            return Err(FieldPathError::NotAMessage { path })
+                        }
+                    }
+                }
+                Some(_) => return Err(self.syntax_error("'.' or '['")),
+            }
+        }
+
+        Ok(FieldPath {
+            desc: desc.clone(),
+            segments,
+        })
+    }
+
+    fn parse_key(&mut self, kind: &Kind, path: &str) -> Result<MapKey, FieldPathError> {
+        let start = self.pos;
+        let key = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let value = self.parse_string(quote)?;
+                match (kind, String::from_utf8(value)) {
+                    (Kind::String, Ok(value)) => Some(MapKey::String(value)),
+                    _ => None,
+                }
+            }
+            _ => {
+                let value = self.take_while(|ch| ch != ']');
+                match kind {
+                    Kind::Bool => value.parse().ok().map(MapKey::Bool),
+                    Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                        value.parse().ok().map(MapKey::I32)
+                    }
+                    Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                        value.parse().ok().map(MapKey::I64)
+                    }
+                    Kind::Uint32 | Kind::Fixed32 => value.parse().ok().map(MapKey::U32),
+                    Kind::Uint64 | Kind::Fixed64 => value.parse().ok().map(MapKey::U64),
+                    _ => None,
+                }
+            }
+        };
+
+        key.ok_or_else(|| FieldPathError::InvalidMapKey {
+            path: path.to_owned(),
+            key: self.input[start..self.pos].to_owned(),
+        })
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<Vec<u8>, FieldPathError> {
+        let mut value = Vec::new();
+        loop {
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                None => return Err(self.syntax_error("the end of the string")),
+            };
+            self.pos += ch.len_utf8();
+            match ch {
+                ch if ch == quote => return Ok(value),
+                '\\' => {
+                    let escape = self.peek().ok_or_else(|| self.syntax_error("an escape"))?;
+                    self.pos += escape.len_utf8();
+                    match escape {
+                        'n' => value.push(b'\n'),
+                        'r' => value.push(b'\r'),
+                        't' => value.push(b'\t'),
+                        '\\' | '\'' | '"' => value.push(escape as u8),
+                        'x' => {
+                            let digits = self.take_max(2, |ch| ch.is_ascii_hexdigit());
+                            let byte = u8::from_str_radix(digits, 16)
+                                .map_err(|_| self.syntax_error("a hex escape"))?;
+                            value.push(byte);
+                        }
+                        '0'..='7' => {
+                            self.pos -= 1;
+                            let digits = self.take_max(3, |ch| ('0'..='7').contains(&ch));
+                            let byte = u8::from_str_radix(digits, 8)
+                                .map_err(|_| self.syntax_error("an octal escape"))?;
+                            value.push(byte);
+                        }
+                        _ => {
+                            self.pos -= escape.len_utf8();
+                            return Err(self.syntax_error("an escape"));
+                        }
+                    }
+                }
+                ch => {
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), FieldPathError> {
+        if self.eat(ch) {
+            Ok(())
+        } else {
+            Err(self.syntax_error(&format!("'{}'", ch)))
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        self.take_max(usize::MAX, f)
+    }
+
+    fn take_max(&mut self, max: usize, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        for ch in self.input[start..].chars().take(max) {
+            if !f(ch) {
+                break;
+            }
+            self.pos += ch.len_utf8();
+        }
+        &self.input[start..self.pos]
+    }
+
+    fn syntax_error(&self, expected: &str) -> FieldPathError {
+        FieldPathError::InvalidSyntax {
+            position: self.pos,
+            expected: expected.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for FieldPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldPathError::InvalidSyntax { position, expected } => {
+                write!(
+                    f,
+                    "invalid field path: expected {} at position {}",
+                    expected, position
+                )
+            }
+            FieldPathError::FieldNotFound { message, name } => {
+                write!(f, "message '{}' has no field named '{}'", message, name)
+            }
+            FieldPathError::ExtensionNotFound { message, name } => {
+                write!(f, "message '{}' has no extension named '{}'", message, name)
+            }
+            FieldPathError::NotAMessage { path } => {
+                write!(f, "'{}' is not a message", path)
+            }
+            FieldPathError::NotIndexable { path } => {
+                write!(f, "'{}' is not a repeated or map field", path)
+            }
+            FieldPathError::InvalidMapKey { path, key } => {
+                write!(f, "invalid key '{}' for map field '{}'", key, path)
+            }
+            FieldPathError::IndexOutOfBounds { path, index, len } => write!(
+                f,
+                "index {} is out of bounds for '{}' with length {}",
+                index, path, len
+            ),
+            FieldPathError::InvalidType { path, value } => {
+                write!(f, "invalid value '{}' for '{}'", value, path)
+            }
+        }
+    }
+}
+
+impl Error for FieldPathError {}
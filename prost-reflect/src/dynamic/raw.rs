@@ -0,0 +1,216 @@
+use std::fmt;
+
+use prost::{
+    bytes::{Buf, Bytes},
+    encoding::{self, WireType},
+    DecodeError, Message,
+};
+
+use crate::dynamic::{
+    text_format,
+    unknown::{UnknownField, UnknownFieldSet, UnknownFieldValue},
+};
+
+/// The maximum depth of length-delimited fields which will be interpreted as nested messages.
+const RECURSION_LIMIT: u32 = 100;
+
+/// Decodes a protobuf message without a schema, in the same way as `protoc --decode_raw`.
+///
+/// The wire format does not include type information, so the contents of length-delimited fields are
+/// guessed: see [`RawValue`] for details. The result may be printed in the text format using its
+/// [`Display`](fmt::Display) implementation.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::raw::{decode_raw, RawValue};
+/// let message = decode_raw(b"\x08\x96\x01\x12\x05hello\x1a\x02\x10\x42".as_ref()).unwrap();
+/// assert_eq!(message.fields()[0].value(), &RawValue::Varint(150));
+/// assert_eq!(message.fields()[1].value(), &RawValue::String("hello".to_owned()));
+/// assert_eq!(format!("{}", message), r#"1:150,2:"hello",3{2:66}"#);
+/// assert_eq!(format!("{:#}", message), "1: 150\n2: \"hello\"\n3 {\n  2: 66\n}");
+/// ```
+pub fn decode_raw<B>(buf: B) -> Result<RawMessage, DecodeError>
+where
+    B: Buf,
+{
+    let set = UnknownFieldSet::decode(buf)?;
+    Ok(RawMessage::from_unknown_fields(set, 0))
+}
+
+/// A protobuf message decoded without a schema, returned by [`decode_raw()`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RawMessage {
+    fields: Vec<RawField>,
+}
+
+/// A single field of a [`RawMessage`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawField {
+    number: u32,
+    value: RawValue,
+}
+
+/// The value of a field in a [`RawMessage`].
+///
+/// Length-delimited fields are interpreted using the first of the following rules which applies:
+///
+/// * If the value is a valid, non-empty protobuf message, it is decoded as [`RawValue::Message`].
+/// * If the value is valid UTF-8 and contains no control characters other than whitespace, it is decoded as
+///   [`RawValue::String`].
+/// * If the value consists entirely of varints, it is decoded as a packed repeated field ([`RawValue::Packed`]).
+/// * Otherwise, it is returned as [`RawValue::Bytes`].
+///
+/// These rules are heuristics, and may guess wrongly. In particular, packed repeated fields with fixed-size
+/// elements cannot be distinguished from other bytes, and short strings may also be valid messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValue {
+    /// A field with the `Varint` wire type.
+    Varint(u64),
+    /// A field with the `ThirtyTwoBit` wire type.
+    Fixed32(u32),
+    /// A field with the `SixtyFourBit` wire type.
+    Fixed64(u64),
+    /// A length-delimited field which appears to contain a UTF-8 string.
+    String(String),
+    /// A length-delimited field which appears to contain a packed list of varints.
+    Packed(Vec<u64>),
+    /// A length-delimited field whose contents could not be interpreted.
+    Bytes(Bytes),
+    /// A length-delimited field which appears to contain a nested message.
+    Message(RawMessage),
+    /// A field with the group wire type.
+    Group(RawMessage),
+}
+
+impl RawMessage {
+    /// Gets the fields of this message, in the order they were decoded.
+    pub fn fields(&self) -> &[RawField] {
+        &self.fields
+    }
+
+    /// Consumes this message, returning its fields in the order they were decoded.
+    pub fn into_fields(self) -> Vec<RawField> {
+        self.fields
+    }
+
+    /// Gets an iterator over all occurrences of the field with the given number.
+    pub fn get(&self, number: u32) -> impl Iterator<Item = &RawField> {
+        self.fields
+            .iter()
+            .filter(move |field| field.number == number)
+    }
+
+    fn from_unknown_fields(set: UnknownFieldSet, depth: u32) -> Self {
+        RawMessage {
+            fields: set
+                .into_iter()
+                .map(|field| RawField::from_unknown_field(field, depth))
+                .collect(),
+        }
+    }
+}
+
+impl RawField {
+    /// The number of this field.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// The wire type of this field.
+    pub fn wire_type(&self) -> WireType {
+        match &self.value {
+            RawValue::Varint(_) => WireType::Varint,
+            RawValue::Fixed32(_) => WireType::ThirtyTwoBit,
+            RawValue::Fixed64(_) => WireType::SixtyFourBit,
+            RawValue::String(_)
+            | RawValue::Packed(_)
+            | RawValue::Bytes(_)
+            | RawValue::Message(_) => WireType::LengthDelimited,
+            RawValue::Group(_) => WireType::StartGroup,
+        }
+    }
+
+    /// Gets a reference to the interpreted value of this field.
+    pub fn value(&self) -> &RawValue {
+        &self.value
+    }
+
+    /// Consumes this field, returning its interpreted value.
+    pub fn into_value(self) -> RawValue {
+        self.value
+    }
+
+    fn from_unknown_field(field: UnknownField, depth: u32) -> Self {
+        let number = field.number();
+        let value = match field.into_value() {
+            UnknownFieldValue::Varint(value) => RawValue::Varint(value),
+            UnknownFieldValue::ThirtyTwoBit(value) => RawValue::Fixed32(u32::from_le_bytes(value)),
+            UnknownFieldValue::SixtyFourBit(value) => RawValue::Fixed64(u64::from_le_bytes(value)),
+            UnknownFieldValue::LengthDelimited(bytes) => RawValue::from_bytes(bytes, depth),
+            UnknownFieldValue::Group(set) => {
+                RawValue::Group(RawMessage::from_unknown_fields(set, depth + 1))
+            }
+        };
+        RawField { number, value }
+    }
+}
+
+impl RawValue {
+    fn from_bytes(bytes: Bytes, depth: u32) -> Self {
+        if !bytes.is_empty() && depth < RECURSION_LIMIT {
+            if let Ok(set) = UnknownFieldSet::decode(bytes.clone()) {
+                return RawValue::Message(RawMessage::from_unknown_fields(set, depth + 1));
+            }
+        }
+
+        if let Ok(string) = std::str::from_utf8(&bytes) {
+            if !string
+                .chars()
+                .any(|ch| ch.is_control() && !ch.is_whitespace())
+            {
+                return RawValue::String(string.to_owned());
+            }
+        }
+
+        if let Some(values) = decode_packed_varints(&bytes) {
+            return RawValue::Packed(values);
+        }
+
+        RawValue::Bytes(bytes)
+    }
+}
+
+fn decode_packed_varints(mut buf: &[u8]) -> Option<Vec<u64>> {
+    let mut values = Vec::new();
+    while !buf.is_empty() {
+        values.push(encoding::decode_varint(&mut buf).ok()?);
+    }
+    Some(values)
+}
+
+impl From<UnknownFieldSet> for RawMessage {
+    /// Interprets a set of unknown fields using the same rules as [`decode_raw()`].
+    fn from(set: UnknownFieldSet) -> Self {
+        RawMessage::from_unknown_fields(set, 0)
+    }
+}
+
+impl fmt::Display for RawMessage {
+    /// Formats this message using the protobuf text format, with field numbers in place of field names.
+    ///
+    /// The alternate format specifier may be used to indent the output, matching the output of
+    /// `protoc --decode_raw`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text_format::Writer::new(text_format::FormatOptions::new().pretty(f.alternate()), f)
+            .fmt_raw_message(self)
+    }
+}
+
+impl fmt::Display for RawField {
+    /// Formats this field using the protobuf text format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        text_format::Writer::new(text_format::FormatOptions::new().pretty(f.alternate()), f)
+            .fmt_raw_field(self)
+    }
+}
@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+
+use prost::{
+    bytes::Bytes,
+    encoding::{self, DecodeContext},
+    Message,
+};
+
+use crate::{
+    dynamic::{
+        fields::FieldDescriptorLike,
+        unknown::{UnknownFieldSet, UnknownFieldValue},
+        visit::{ControlMut, VisitorMut},
+    },
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, FieldPathSegment, Kind, Value,
+};
+
+/// The field number of the `debug_redact` option in `google.protobuf.FieldOptions`.
+const DEBUG_REDACT_NUMBER: u32 = 16;
+
+/// Options to control which fields are treated as sensitive, and how they are redacted.
+///
+/// Used by [`DynamicMessage::redact()`], and by the text format and JSON serialization options. By default,
+/// fields marked with the `debug_redact` field option are redacted.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, RedactOptions};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let mut message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+/// // No fields of `package.MyMessage` are marked as sensitive.
+/// message.redact(&RedactOptions::new());
+/// assert_eq!(message.to_string(), "foo:150");
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactOptions {
+    debug_redact: bool,
+    extensions: Vec<ExtensionDescriptor>,
+    placeholder: Cow<'static, str>,
+}
+
+impl RedactOptions {
+    /// Creates a new instance of [`RedactOptions`], with the default options.
+    pub const fn new() -> Self {
+        RedactOptions {
+            debug_redact: true,
+            extensions: Vec::new(),
+            placeholder: Cow::Borrowed("[REDACTED]"),
+        }
+    }
+
+    /// Whether to redact fields which have the `debug_redact` option set to `true`.
+    ///
+    /// The default value is `true`.
+    pub const fn debug_redact(mut self, yes: bool) -> Self {
+        self.debug_redact = yes;
+        self
+    }
+
+    /// Adds a custom option which marks fields as sensitive.
+    ///
+    /// A field is redacted if this option is set to a value other than its default. For example, a `bool`
+    /// option marks fields as sensitive if set to `true`, and an enum option marks fields as sensitive if set
+    /// to any value other than the first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `extension` does not extend `google.protobuf.FieldOptions`.
+    pub fn option_extension(mut self, extension: ExtensionDescriptor) -> Self {
+        assert_eq!(
+            extension.containing_message().full_name(),
+            "google.protobuf.FieldOptions",
+            "extension '{}' is not a field option",
+            extension.full_name()
+        );
+        self.extensions.push(extension);
+        self
+    }
+
+    /// The text which replaces the value of redacted fields.
+    ///
+    /// The default value is `[REDACTED]`.
+    pub fn placeholder(mut self, placeholder: impl Into<Cow<'static, str>>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Returns `true` if the field is marked as sensitive by these options.
+    pub fn is_field_redacted(&self, field_desc: &FieldDescriptor) -> bool {
+        self.is_redacted(field_desc)
+    }
+
+    /// Returns `true` if the extension field is marked as sensitive by these options.
+    pub fn is_extension_redacted(&self, extension_desc: &ExtensionDescriptor) -> bool {
+        self.is_redacted(extension_desc)
+    }
+
+    pub(super) fn placeholder_str(&self) -> &str {
+        &self.placeholder
+    }
+
+    pub(super) fn is_redacted(&self, field_desc: &impl FieldDescriptorLike) -> bool {
+        let encoded = field_desc.encoded_options();
+        if encoded.is_empty() {
+            return false;
+        }
+
+        if self.debug_redact && has_debug_redact(encoded) {
+            return true;
+        }
+
+        self.extensions.iter().any(|extension| {
+            let option = find_option(encoded, extension.number());
+            if option.is_empty() {
+                return false;
+            }
+
+            match DynamicMessage::decode(extension.containing_message(), option.as_slice()) {
+                Ok(options) => {
+                    options.has_extension(extension)
+                        && !extension.is_default_value(&options.get_extension(extension))
+                }
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DynamicMessage {
+    /// Redacts all fields of this message, and of any nested messages, which are marked as sensitive by
+    /// `options`.
+    ///
+    /// Singular `string` and `bytes` fields are replaced with the [placeholder](RedactOptions::placeholder)
+    /// text. All other sensitive fields, including nested messages, are cleared.
+    pub fn redact(&mut self, options: &RedactOptions) {
+        self.visit_mut(&mut Redactor { options });
+    }
+}
+
+struct Redactor<'a> {
+    options: &'a RedactOptions,
+}
+
+impl Redactor<'_> {
+    fn redact(&self, field_desc: &impl FieldDescriptorLike, value: &mut Value) -> ControlMut {
+        if !self.options.is_redacted(field_desc) {
+            return ControlMut::Continue;
+        }
+
+        if field_desc.is_list() || field_desc.is_map() {
+            return ControlMut::Remove;
+        }
+
+        match field_desc.kind() {
+            Kind::String => {
+                *value = Value::String(self.options.placeholder.clone().into_owned());
+                ControlMut::Skip
+            }
+            Kind::Bytes => {
+                *value = Value::Bytes(Bytes::from(self.options.placeholder.clone().into_owned()));
+                ControlMut::Skip
+            }
+            _ => ControlMut::Remove,
+        }
+    }
+}
+
+impl VisitorMut for Redactor<'_> {
+    fn visit_field_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        field_desc: &FieldDescriptor,
+        value: &mut Value,
+    ) -> ControlMut {
+        self.redact(field_desc, value)
+    }
+
+    fn visit_extension_mut(
+        &mut self,
+        _: &[FieldPathSegment],
+        extension_desc: &ExtensionDescriptor,
+        value: &mut Value,
+    ) -> ControlMut {
+        self.redact(extension_desc, value)
+    }
+}
+
+fn has_debug_redact(encoded: &[u8]) -> bool {
+    let fields = match UnknownFieldSet::decode(find_option(encoded, DEBUG_REDACT_NUMBER).as_slice())
+    {
+        Ok(fields) => fields,
+        Err(_) => return false,
+    };
+    let value = fields
+        .iter()
+        .filter(|field| field.number() == DEBUG_REDACT_NUMBER)
+        .last()
+        .map(|field| field.value());
+    matches!(value, Some(UnknownFieldValue::Varint(value)) if *value != 0)
+}
+
+/// Returns the encoded occurrences of the field `number` in the encoded options, without decoding any
+/// other fields.
+fn find_option(encoded: &[u8], number: u32) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut buf = encoded;
+    while !buf.is_empty() {
+        let start = buf;
+        let (field_number, wire_type) = match encoding::decode_key(&mut buf) {
+            Ok(key) => key,
+            Err(_) => return Vec::new(),
+        };
+        if encoding::skip_field(wire_type, field_number, &mut buf, DecodeContext::default())
+            .is_err()
+        {
+            return Vec::new();
+        }
+        if field_number == number {
+            result.extend_from_slice(&start[..start.len() - buf.len()]);
+        }
+    }
+    result
+}
@@ -0,0 +1,70 @@
+use prost::{DecodeError, Message};
+
+use crate::{DescriptorPool, DynamicMessage, MessageDescriptor};
+
+impl DynamicMessage {
+    /// Decodes any unknown fields which correspond to a field or extension of this message's current
+    /// descriptor, including in nested messages.
+    ///
+    /// This is equivalent to calling [`reparse_unknown_fields_with()`](DynamicMessage::reparse_unknown_fields_with)
+    /// with the parent pool of [`descriptor()`](crate::ReflectMessage::descriptor). Nested messages are converted to
+    /// the types defined by this message's descriptor, so this picks up fields and extensions of nested
+    /// messages which were created from an older definition of their type and then set on this message.
+    ///
+    /// It cannot pick up fields or extensions which were added to a pool after this message's descriptor was
+    /// obtained, because the descriptor refers to the state of its pool at that time. Use
+    /// [`reparse_unknown_fields_with()`](DynamicMessage::reparse_unknown_fields_with) with the updated pool
+    /// instead.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the existing fields cannot be decoded using the current definition. If an
+    /// error is returned, the message is unchanged.
+    pub fn reparse_unknown_fields(&mut self) -> Result<(), DecodeError> {
+        self.set_descriptor(self.desc.clone())
+    }
+
+    /// Decodes any unknown fields which correspond to a field or extension defined in `pool`, including in
+    /// nested messages.
+    ///
+    /// A message's descriptor refers to the state of its pool when the message was created, so extensions
+    /// or fields added to a pool afterwards are not visible to it. This method switches the message to the
+    /// version of its type in `pool`, using [`set_descriptor()`](DynamicMessage::set_descriptor). To pick up
+    /// extensions registered in the global pool, pass [`DescriptorPool::global()`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `pool` does not contain a message with the same full name as this message,
+    /// or if the existing fields cannot be decoded using the new definition. If an error is returned, the
+    /// message is unchanged.
+    pub fn reparse_unknown_fields_with(
+        &mut self,
+        pool: &DescriptorPool,
+    ) -> Result<(), DecodeError> {
+        match pool.get_message_by_name(self.desc.full_name()) {
+            Some(desc) => self.set_descriptor(desc),
+            None => Err(DecodeError::new(format!(
+                "message '{}' not found in descriptor pool",
+                self.desc.full_name()
+            ))),
+        }
+    }
+
+    /// Changes the type of this message to `desc`, reinterpreting its contents.
+    ///
+    /// This is typically used to move a message onto an updated definition of the same type, for example
+    /// from a [`DescriptorPool`] which has been reloaded. The message is converted as if it were encoded
+    /// with its current descriptor and decoded with the new one: fields are matched by number, nested
+    /// messages are converted to the types defined by the new descriptor, fields which are no longer defined
+    /// become unknown fields, and unknown fields which match a field or extension of the new type are decoded.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if a field cannot be decoded as the type of the corresponding field in `desc`,
+    /// for example because its wire type has changed. If an error is returned, the message is unchanged.
+    pub fn set_descriptor(&mut self, desc: MessageDescriptor) -> Result<(), DecodeError> {
+        let bytes = self.encode_to_vec();
+        *self = DynamicMessage::decode(desc, bytes.as_slice())?;
+        Ok(())
+    }
+}
@@ -4,15 +4,16 @@ use prost::bytes::Bytes;
 use serde::de::{DeserializeSeed, Deserializer, Error, IgnoredAny, MapAccess, SeqAccess, Visitor};
 
 use crate::{
-    dynamic::{serde::DeserializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{DynamicMessage, MapKey, Value},
     EnumDescriptor, Kind, MessageDescriptor, ReflectMessage,
 };
 
 use super::{
-    deserialize_enum, deserialize_message, FieldDescriptorSeed, OptionalFieldDescriptorSeed,
+    deserialize_enum, deserialize_message, Context, FieldDescriptorSeed,
+    OptionalFieldDescriptorSeed,
 };
 
-pub struct KindSeed<'a>(pub &'a Kind, pub &'a DeserializeOptions);
+pub struct KindSeed<'a>(pub &'a Kind, pub Context<'a>);
 
 impl<'de> DeserializeSeed<'de> for KindSeed<'_> {
     type Value = Value;
@@ -49,8 +50,8 @@ impl<'de> DeserializeSeed<'de> for KindSeed<'_> {
     }
 }
 
-pub struct ListVisitor<'a>(pub &'a Kind, pub &'a DeserializeOptions);
-pub struct MapVisitor<'a>(pub &'a Kind, pub &'a DeserializeOptions);
+pub struct ListVisitor<'a>(pub &'a Kind, pub Context<'a>);
+pub struct MapVisitor<'a>(pub &'a Kind, pub Context<'a>);
 pub struct DoubleVisitor;
 pub struct FloatVisitor;
 pub struct Int32Visitor;
@@ -60,8 +61,8 @@ pub struct Uint64Visitor;
 pub struct StringVisitor;
 pub struct BoolVisitor;
 pub struct BytesVisitor;
-pub struct MessageVisitor<'a>(pub &'a MessageDescriptor, pub &'a DeserializeOptions);
-pub struct MessageVisitorInner<'a>(pub &'a mut DynamicMessage, pub &'a DeserializeOptions);
+pub struct MessageVisitor<'a>(pub &'a MessageDescriptor, pub Context<'a>);
+pub struct MessageVisitorInner<'a>(pub &'a mut DynamicMessage, pub Context<'a>);
 pub struct EnumVisitor<'a>(pub &'a EnumDescriptor);
 
 impl<'de> Visitor<'de> for ListVisitor<'_> {
@@ -78,7 +79,10 @@ impl<'de> Visitor<'de> for ListVisitor<'_> {
     {
         let mut result = Vec::with_capacity(seq.size_hint().unwrap_or(0));
 
-        while let Some(value) = seq.next_element_seed(KindSeed(self.0, self.1))? {
+        while let Some(value) = self.1.track.index(
+            result.len(),
+            seq.next_element_seed(KindSeed(self.0, self.1)),
+        )? {
             result.push(value)
         }
 
@@ -104,27 +108,15 @@ impl<'de> Visitor<'de> for MapVisitor<'_> {
         let value_desc = map_entry_message.map_entry_value_field();
 
         while let Some(key_str) = map.next_key::<Cow<str>>()? {
-            let key = match key_kind {
-                Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
-                    MapKey::I32(i32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
-                    MapKey::I64(i64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint32 | Kind::Fixed32 => {
-                    MapKey::U32(u32::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Uint64 | Kind::Fixed64 => {
-                    MapKey::U64(u64::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::Bool => {
-                    MapKey::Bool(bool::from_str(key_str.as_ref()).map_err(Error::custom)?)
-                }
-                Kind::String => MapKey::String(key_str.into_owned()),
-                _ => unreachable!("invalid type for map key"),
-            };
+            let key = self.1.track.key(
+                &key_str,
+                parse_map_key(&key_kind, Cow::Borrowed(key_str.as_ref())),
+            )?;
 
-            let value = map.next_value_seed(FieldDescriptorSeed(&value_desc, self.1))?;
+            let value = self.1.track.key(
+                &key_str,
+                map.next_value_seed(FieldDescriptorSeed(&value_desc, self.1)),
+            )?;
 
             result.insert(key, value);
         }
@@ -133,6 +125,30 @@ impl<'de> Visitor<'de> for MapVisitor<'_> {
     }
 }
 
+/// Parses a map key of type `kind` from its JSON representation as a string.
+pub fn parse_map_key<E>(kind: &Kind, key_str: Cow<str>) -> Result<MapKey, E>
+where
+    E: Error,
+{
+    Ok(match kind {
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            MapKey::I32(i32::from_str(key_str.as_ref()).map_err(Error::custom)?)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            MapKey::I64(i64::from_str(key_str.as_ref()).map_err(Error::custom)?)
+        }
+        Kind::Uint32 | Kind::Fixed32 => {
+            MapKey::U32(u32::from_str(key_str.as_ref()).map_err(Error::custom)?)
+        }
+        Kind::Uint64 | Kind::Fixed64 => {
+            MapKey::U64(u64::from_str(key_str.as_ref()).map_err(Error::custom)?)
+        }
+        Kind::Bool => MapKey::Bool(bool::from_str(key_str.as_ref()).map_err(Error::custom)?),
+        Kind::String => MapKey::String(key_str.into_owned()),
+        _ => unreachable!("invalid type for map key"),
+    })
+}
+
 impl Visitor<'_> for DoubleVisitor {
     type Value = f64;
 
@@ -550,21 +566,26 @@ impl<'de> Visitor<'de> for MessageVisitorInner<'_> {
         A: MapAccess<'de>,
     {
         let desc = self.0.descriptor();
+        let track = self.1.track;
         while let Some(key) = map.next_key::<Cow<str>>()? {
             if let Some(field) = desc
                 .get_field_by_json_name(key.as_ref())
                 .or_else(|| desc.get_field_by_name(key.as_ref()))
             {
-                if let Some(value) =
-                    map.next_value_seed(OptionalFieldDescriptorSeed(&field, self.1))?
-                {
+                if let Some(value) = track.field(
+                    &key,
+                    map.next_value_seed(OptionalFieldDescriptorSeed(&field, self.1)),
+                )? {
                     if let Some(oneof_desc) = field.containing_oneof() {
                         for oneof_field in oneof_desc.fields() {
                             if self.0.has_field(&oneof_field) {
-                                return Err(Error::custom(format!(
-                                    "multiple fields provided for oneof '{}'",
-                                    oneof_desc.name()
-                                )));
+                                return track.field(
+                                    &key,
+                                    Err(Error::custom(format!(
+                                        "multiple fields provided for oneof '{}'",
+                                        oneof_desc.name()
+                                    ))),
+                                );
                             }
                         }
                     }
@@ -572,15 +593,16 @@ impl<'de> Visitor<'de> for MessageVisitorInner<'_> {
                     self.0.set_field(&field, value);
                 }
             } else if let Some(extension_desc) = desc.get_extension_by_json_name(key.as_ref()) {
-                if let Some(value) =
-                    map.next_value_seed(OptionalFieldDescriptorSeed(&extension_desc, self.1))?
-                {
+                if let Some(value) = track.field(
+                    &key,
+                    map.next_value_seed(OptionalFieldDescriptorSeed(&extension_desc, self.1)),
+                )? {
                     self.0.set_extension(&extension_desc, value);
                 }
-            } else if self.1.deny_unknown_fields {
+            } else if self.1.options.deny_unknown_fields {
                 return Err(Error::custom(format!("unrecognized field name '{}'", key)));
             } else {
-                let _ = map.next_value::<IgnoredAny>()?;
+                let _ = track.field(&key, map.next_value::<IgnoredAny>())?;
             }
         }
 
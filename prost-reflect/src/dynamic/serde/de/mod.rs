@@ -1,6 +1,13 @@
 mod kind;
+mod path;
 mod wkt;
 
+pub use self::path::{DeserializeError, DeserializePathSegment};
+pub(super) use self::{
+    kind::{parse_map_key, KindSeed},
+    path::Track,
+};
+
 use std::fmt;
 
 use prost::Message;
@@ -11,17 +18,44 @@ use crate::{
     EnumDescriptor, Kind, MessageDescriptor,
 };
 
-pub(super) fn deserialize_message<'de, D>(
+/// The state shared by the visitors deserializing a message.
+#[derive(Clone, Copy)]
+pub(super) struct Context<'a> {
+    pub options: &'a DeserializeOptions,
+    pub track: &'a Track,
+}
+
+pub(super) fn deserialize_with_path<'de, D>(
     desc: &MessageDescriptor,
     deserializer: D,
     options: &DeserializeOptions,
+) -> Result<DynamicMessage, DeserializeError<D::Error>>
+where
+    D: Deserializer<'de>,
+{
+    let track = Track::default();
+    deserialize_message(
+        desc,
+        deserializer,
+        Context {
+            options,
+            track: &track,
+        },
+    )
+    .map_err(|error| DeserializeError::new(track, error))
+}
+
+fn deserialize_message<'de, D>(
+    desc: &MessageDescriptor,
+    deserializer: D,
+    cx: Context<'_>,
 ) -> Result<DynamicMessage, D::Error>
 where
     D: Deserializer<'de>,
 {
     match desc.full_name() {
         "google.protobuf.Any" => deserializer
-            .deserialize_any(wkt::GoogleProtobufAnyVisitor(desc.parent_pool(), options))
+            .deserialize_any(wkt::GoogleProtobufAnyVisitor(desc.parent_pool(), cx))
             .and_then(|timestamp| make_message(desc, timestamp)),
         "google.protobuf.Timestamp" => deserializer
             .deserialize_str(wkt::GoogleProtobufTimestampVisitor)
@@ -60,18 +94,18 @@ where
             .deserialize_str(wkt::GoogleProtobufFieldMaskVisitor)
             .and_then(|field_mask| make_message(desc, field_mask)),
         "google.protobuf.Struct" => deserializer
-            .deserialize_map(wkt::GoogleProtobufStructVisitor)
+            .deserialize_map(wkt::GoogleProtobufStructVisitor(cx.track))
             .and_then(|value| make_message(desc, value)),
         "google.protobuf.ListValue" => deserializer
-            .deserialize_seq(wkt::GoogleProtobufListVisitor)
+            .deserialize_seq(wkt::GoogleProtobufListVisitor(cx.track))
             .and_then(|list| make_message(desc, list)),
         "google.protobuf.Value" => deserializer
-            .deserialize_any(wkt::GoogleProtobufValueVisitor)
+            .deserialize_any(wkt::GoogleProtobufValueVisitor(cx.track))
             .and_then(|value| make_message(desc, value)),
         "google.protobuf.Empty" => deserializer
             .deserialize_map(wkt::GoogleProtobufEmptyVisitor)
             .and_then(|empty| make_message(desc, empty)),
-        _ => deserializer.deserialize_map(kind::MessageVisitor(desc, options)),
+        _ => deserializer.deserialize_map(kind::MessageVisitor(desc, cx)),
     }
 }
 
@@ -85,7 +119,7 @@ where
     }
 }
 
-struct MessageSeed<'a>(&'a MessageDescriptor, &'a DeserializeOptions);
+struct MessageSeed<'a>(&'a MessageDescriptor, Context<'a>);
 
 impl<'de> DeserializeSeed<'de> for MessageSeed<'_> {
     type Value = DynamicMessage;
@@ -98,7 +132,7 @@ impl<'de> DeserializeSeed<'de> for MessageSeed<'_> {
     }
 }
 
-struct FieldDescriptorSeed<'a, T>(&'a T, &'a DeserializeOptions);
+struct FieldDescriptorSeed<'a, T>(&'a T, Context<'a>);
 
 impl<'de, T> DeserializeSeed<'de> for FieldDescriptorSeed<'_, T>
 where
@@ -124,7 +158,7 @@ where
     }
 }
 
-struct OptionalFieldDescriptorSeed<'a, T>(&'a T, &'a DeserializeOptions);
+struct OptionalFieldDescriptorSeed<'a, T>(&'a T, Context<'a>);
 
 impl<'de, T> DeserializeSeed<'de> for OptionalFieldDescriptorSeed<'_, T>
 where
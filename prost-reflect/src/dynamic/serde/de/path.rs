@@ -0,0 +1,129 @@
+use std::{cell::RefCell, error::Error, fmt};
+
+use crate::dynamic::{
+    diff::{index_path, join_path, key_path},
+    MapKey,
+};
+
+/// The error type returned by [`DynamicMessage::deserialize_with_path()`](crate::DynamicMessage::deserialize_with_path),
+/// which includes the path to the value which could not be deserialized.
+///
+/// The [`Display`](fmt::Display) implementation prefixes the message of the underlying error with the
+/// path, for example `at $.items[3].price.units: invalid type: string "x", expected a 64-bit signed
+/// integer or decimal string`.
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct DeserializeError<E> {
+    path: Vec<DeserializePathSegment>,
+    error: E,
+}
+
+/// A single step of the path to a value which could not be deserialized.
+///
+/// Used by [`DeserializeError::path()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub enum DeserializePathSegment {
+    /// A field of a message, using the name as it appeared in the input, which may be either the JSON
+    /// name or the proto name of the field.
+    Field(String),
+    /// An element of a repeated field or `google.protobuf.ListValue`.
+    Index(usize),
+    /// An entry of a map field or `google.protobuf.Struct`, using the key as it appeared in the input.
+    Key(String),
+}
+
+/// Records the path to the value being deserialized when an error occurs.
+///
+/// Each nested value adds its segment as the error is propagated to its parent, so the path is built in
+/// reverse.
+#[derive(Debug, Default)]
+pub struct Track {
+    path: RefCell<Vec<DeserializePathSegment>>,
+}
+
+impl<E> DeserializeError<E> {
+    pub(crate) fn new(track: Track, error: E) -> Self {
+        let mut path = track.path.into_inner();
+        path.reverse();
+        DeserializeError { path, error }
+    }
+
+    /// The path to the value which could not be deserialized, or an empty slice if the error applies to
+    /// the whole message.
+    pub fn path(&self) -> &[DeserializePathSegment] {
+        &self.path
+    }
+
+    /// Formats the path to the value which could not be deserialized as a
+    /// [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) expression, such as `$.items[3].labels["env"]`.
+    pub fn json_path(&self) -> String {
+        self.path
+            .iter()
+            .fold("$".to_owned(), |path, segment| match segment {
+                DeserializePathSegment::Field(name) => join_path(&path, name),
+                DeserializePathSegment::Index(index) => index_path(&path, *index),
+                DeserializePathSegment::Key(key) => key_path(&path, &MapKey::String(key.clone())),
+            })
+    }
+
+    /// Gets a reference to the underlying error.
+    pub fn inner(&self) -> &E {
+        &self.error
+    }
+
+    /// Gets the underlying error, discarding the path.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E> fmt::Display for DeserializeError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.error)
+        } else {
+            write!(f, "at {}: {}", self.json_path(), self.error)
+        }
+    }
+}
+
+impl<E> Error for DeserializeError<E>
+where
+    E: Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl Track {
+    /// Records the field `name` as part of the path to the error, if `result` is an error.
+    pub fn field<T, E>(&self, name: &str, result: Result<T, E>) -> Result<T, E> {
+        self.push(result, || DeserializePathSegment::Field(name.to_owned()))
+    }
+
+    /// Records the list element `index` as part of the path to the error, if `result` is an error.
+    pub fn index<T, E>(&self, index: usize, result: Result<T, E>) -> Result<T, E> {
+        self.push(result, || DeserializePathSegment::Index(index))
+    }
+
+    /// Records the map entry `key` as part of the path to the error, if `result` is an error.
+    pub fn key<T, E>(&self, key: &str, result: Result<T, E>) -> Result<T, E> {
+        self.push(result, || DeserializePathSegment::Key(key.to_owned()))
+    }
+
+    fn push<T, E>(
+        &self,
+        result: Result<T, E>,
+        segment: impl FnOnce() -> DeserializePathSegment,
+    ) -> Result<T, E> {
+        if result.is_err() {
+            self.path.borrow_mut().push(segment());
+        }
+        result
+    }
+}
@@ -11,27 +11,27 @@ use serde::de::{
 };
 
 use crate::{
+    any::resolve_type_url,
     descriptor::{GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN},
     dynamic::{
         serde::{
             case::camel_case_to_snake_case, check_duration, check_timestamp, is_well_known_type,
-            DeserializeOptions,
         },
         DynamicMessage,
     },
     DescriptorPool,
 };
 
-use super::{deserialize_message, kind::MessageVisitorInner, MessageSeed};
+use super::{deserialize_message, kind::MessageVisitorInner, Context, MessageSeed, Track};
 
-pub struct GoogleProtobufAnyVisitor<'a>(pub &'a DescriptorPool, pub &'a DeserializeOptions);
+pub struct GoogleProtobufAnyVisitor<'a>(pub &'a DescriptorPool, pub Context<'a>);
 pub struct GoogleProtobufNullVisitor;
 pub struct GoogleProtobufTimestampVisitor;
 pub struct GoogleProtobufDurationVisitor;
 pub struct GoogleProtobufFieldMaskVisitor;
-pub struct GoogleProtobufListVisitor;
-pub struct GoogleProtobufStructVisitor;
-pub struct GoogleProtobufValueVisitor;
+pub struct GoogleProtobufListVisitor<'a>(pub &'a Track);
+pub struct GoogleProtobufStructVisitor<'a>(pub &'a Track);
+pub struct GoogleProtobufValueVisitor<'a>(pub &'a Track);
 pub struct GoogleProtobufEmptyVisitor;
 
 impl<'de> Visitor<'de> for GoogleProtobufAnyVisitor<'_> {
@@ -45,48 +45,57 @@ impl<'de> Visitor<'de> for GoogleProtobufAnyVisitor<'_> {
     where
         A: MapAccess<'de>,
     {
+        let track = self.1.track;
         let mut buffered_entries = HashMap::new();
 
         let type_url = loop {
             match map.next_key::<Cow<str>>()? {
                 Some(key) if key == "@type" => {
-                    break map.next_value::<String>()?;
+                    break track.field(&key, map.next_value::<String>())?;
                 }
                 Some(key) => {
-                    let value: serde_value::Value = map.next_value()?;
+                    let value: serde_value::Value = track.field(&key, map.next_value())?;
                     buffered_entries.insert(key, value);
                 }
                 None => return Err(Error::custom("expected '@type' field")),
             }
         };
 
-        if let Some(message_name) = type_url
-            .strip_prefix(GOOGLE_APIS_DOMAIN)
-            .or_else(|| type_url.strip_prefix(GOOGLE_PROD_DOMAIN))
+        if self.1.options.type_resolver.is_some()
+            || type_url.starts_with(GOOGLE_APIS_DOMAIN)
+            || type_url.starts_with(GOOGLE_PROD_DOMAIN)
         {
-            let message_desc = self
-                .0
-                .get_message_by_name(message_name)
-                .ok_or_else(|| Error::custom(format!("message '{}' not found", message_name)))?;
-
-            let payload_message = if is_well_known_type(message_name) {
+            let message_desc =
+                resolve_type_url(self.1.options.type_resolver.as_ref(), self.0, &type_url)
+                    .ok_or_else(|| {
+                        Error::custom(format!(
+                            "message type not found for type url '{}'",
+                            type_url
+                        ))
+                    })?;
+
+            let payload_message = if is_well_known_type(message_desc.full_name()) {
                 let payload_message = match buffered_entries.remove("value") {
-                    Some(value) => {
-                        deserialize_message(&message_desc, value, self.1).map_err(Error::custom)?
-                    }
+                    Some(value) => track.field(
+                        "value",
+                        deserialize_message(&message_desc, value, self.1).map_err(Error::custom),
+                    )?,
                     None => loop {
                         match map.next_key::<Cow<str>>()? {
                             Some(key) if key == "value" => {
-                                break map.next_value_seed(MessageSeed(&message_desc, self.1))?
+                                break track.field(
+                                    &key,
+                                    map.next_value_seed(MessageSeed(&message_desc, self.1)),
+                                )?
                             }
                             Some(key) => {
-                                if self.1.deny_unknown_fields {
+                                if self.1.options.deny_unknown_fields {
                                     return Err(Error::custom(format!(
                                         "unrecognized field name '{}'",
                                         key
                                     )));
                                 } else {
-                                    let _ = map.next_value::<IgnoredAny>()?;
+                                    let _ = track.field(&key, map.next_value::<IgnoredAny>())?;
                                 }
                             }
                             None => return Err(Error::custom("expected '@type' field")),
@@ -94,7 +103,7 @@ impl<'de> Visitor<'de> for GoogleProtobufAnyVisitor<'_> {
                     },
                 };
 
-                if self.1.deny_unknown_fields {
+                if self.1.options.deny_unknown_fields {
                     if let Some(key) = buffered_entries.keys().next() {
                         return Err(Error::custom(format!("unrecognized field name '{}'", key)));
                     }
@@ -233,7 +242,7 @@ impl Visitor<'_> for GoogleProtobufFieldMaskVisitor {
     }
 }
 
-impl<'de> DeserializeSeed<'de> for GoogleProtobufValueVisitor {
+impl<'de> DeserializeSeed<'de> for GoogleProtobufValueVisitor<'_> {
     type Value = prost_types::Value;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
@@ -244,7 +253,7 @@ impl<'de> DeserializeSeed<'de> for GoogleProtobufValueVisitor {
     }
 }
 
-impl<'de> Visitor<'de> for GoogleProtobufListVisitor {
+impl<'de> Visitor<'de> for GoogleProtobufListVisitor<'_> {
     type Value = prost_types::ListValue;
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -252,7 +261,10 @@ impl<'de> Visitor<'de> for GoogleProtobufListVisitor {
         A: SeqAccess<'de>,
     {
         let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
-        while let Some(value) = seq.next_element_seed(GoogleProtobufValueVisitor)? {
+        while let Some(value) = self.0.index(
+            values.len(),
+            seq.next_element_seed(GoogleProtobufValueVisitor(self.0)),
+        )? {
             values.push(value);
         }
         Ok(prost_types::ListValue { values })
@@ -263,7 +275,7 @@ impl<'de> Visitor<'de> for GoogleProtobufListVisitor {
     }
 }
 
-impl<'de> Visitor<'de> for GoogleProtobufStructVisitor {
+impl<'de> Visitor<'de> for GoogleProtobufStructVisitor<'_> {
     type Value = prost_types::Struct;
 
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
@@ -272,7 +284,10 @@ impl<'de> Visitor<'de> for GoogleProtobufStructVisitor {
     {
         let mut fields = BTreeMap::new();
         while let Some(key) = map.next_key::<String>()? {
-            let value = map.next_value_seed(GoogleProtobufValueVisitor)?;
+            let value = self.0.key(
+                &key,
+                map.next_value_seed(GoogleProtobufValueVisitor(self.0)),
+            )?;
             fields.insert(key, value);
         }
         Ok(prost_types::Struct { fields })
@@ -283,7 +298,7 @@ impl<'de> Visitor<'de> for GoogleProtobufStructVisitor {
     }
 }
 
-impl<'de> Visitor<'de> for GoogleProtobufValueVisitor {
+impl<'de> Visitor<'de> for GoogleProtobufValueVisitor<'_> {
     type Value = prost_types::Value;
 
     fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -352,7 +367,7 @@ impl<'de> Visitor<'de> for GoogleProtobufValueVisitor {
     where
         A: SeqAccess<'de>,
     {
-        GoogleProtobufListVisitor
+        GoogleProtobufListVisitor(self.0)
             .visit_seq(seq)
             .map(|l| prost_types::Value {
                 kind: Some(prost_types::value::Kind::ListValue(l)),
@@ -363,7 +378,7 @@ impl<'de> Visitor<'de> for GoogleProtobufValueVisitor {
     where
         A: MapAccess<'de>,
     {
-        GoogleProtobufStructVisitor
+        GoogleProtobufStructVisitor(self.0)
             .visit_map(map)
             .map(|s| prost_types::Value {
                 kind: Some(prost_types::value::Kind::StructValue(s)),
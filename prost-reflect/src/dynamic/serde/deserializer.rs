@@ -0,0 +1,705 @@
+use std::borrow::Cow;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::de::{
+    value::{CowStrDeserializer, Error},
+    DeserializeSeed, Deserializer, Error as _, IntoDeserializer, MapAccess, SeqAccess, Visitor,
+};
+
+use crate::{
+    dynamic::{
+        any::{SharedTypeResolver, TypeResolver},
+        fields::ValueAndDescriptor,
+        serde::{
+            case::snake_case_to_camel_case, check_duration, check_timestamp, is_well_known_type,
+            time_field_aliases,
+        },
+    },
+    DynamicMessage, FieldDescriptor, Kind, MapKey, ReflectMessage, Value,
+};
+
+/// A [`Deserializer`] which reads the contents of a [`DynamicMessage`], so that it can be converted into
+/// any type implementing [`Deserialize`](serde::Deserialize) without an intermediate format such as JSON.
+///
+/// Created by [`DynamicMessage::into_deserializer()`] or [`MessageDeserializer::new()`].
+///
+/// Messages are deserialized as maps, in the same form as the [canonical JSON encoding], except that
+/// numbers, including 64-bit integers, are passed to the visitor as numbers. `bytes` values are base64
+/// strings, unless the visitor requests bytes or a sequence of `u8`. When deserializing a struct, each
+/// field is given the name of the struct field matching either its proto name or its JSON name. Fields
+/// which do not support presence are always included, while fields which do, such as message fields, are
+/// only included if they are set, and so may be deserialized into an `Option`. Extension fields and
+/// unknown fields are ignored.
+///
+/// Well-known types follow the JSON conventions: `google.protobuf.Timestamp` and `google.protobuf.Duration`
+/// values are strings, unless a struct is requested, in which case they may also be deserialized into
+/// [`SystemTime`](std::time::SystemTime) and [`Duration`](std::time::Duration). Wrapper types are
+/// deserialized as their contained value, and `google.protobuf.Struct`, `Value` and `ListValue` as maps,
+/// dynamic values and sequences. `google.protobuf.Any` values are deserialized as the fields
+/// of the contained message along with an `@type` entry, using the resolver set by
+/// [`MessageDeserializer::type_resolver()`] to find the message type.
+///
+/// [canonical JSON encoding]: https://developers.google.com/protocol-buffers/docs/proto3#json
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct MessageDeserializer<'a> {
+    message: Cow<'a, DynamicMessage>,
+    options: DeserializerOptions,
+}
+
+/// A [`Deserializer`] for a single field value, which may be a default value.
+struct ValueDeserializer<'a> {
+    value: Cow<'a, Value>,
+    kind: Kind,
+    options: DeserializerOptions,
+}
+
+/// The options of a [`MessageDeserializer`], which also apply to the values it contains.
+#[derive(Debug, Clone, Default)]
+struct DeserializerOptions {
+    use_proto_field_name: bool,
+    type_resolver: Option<SharedTypeResolver>,
+    integral_numbers: bool,
+}
+
+struct KeyDeserializer<'a>(&'a MapKey);
+
+/// The name, value and type of a field to deserialize.
+type FieldEntry<'a> = (Cow<'static, str>, Cow<'a, Value>, Kind);
+
+/// The type requested by the [`Visitor`], which determines how some values are represented.
+#[derive(Debug, Clone, Copy)]
+enum Hint {
+    Any,
+    Number,
+    Str,
+    Bytes,
+    Option,
+    Unit,
+    NewtypeStruct,
+    Seq,
+    Map,
+    Struct(&'static [&'static str]),
+    Enum,
+}
+
+struct SeqDeserializer<I>(I);
+
+struct MapDeserializer<I, V> {
+    entries: I,
+    value: Option<V>,
+}
+
+impl DynamicMessage {
+    /// Converts this message into a [`Deserializer`], which can be used to deserialize it into any type
+    /// implementing [`Deserialize`](serde::Deserialize).
+    ///
+    /// See [`MessageDeserializer`] for details of how messages are represented.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool};
+    /// # use serde1 as serde;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// use serde::Deserialize;
+    ///
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let map = HashMap::<String, i32>::deserialize(dynamic_message.into_deserializer()).unwrap();
+    /// assert_eq!(map, HashMap::from([("foo".to_owned(), 150)]));
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn into_deserializer(self) -> MessageDeserializer<'static> {
+        MessageDeserializer {
+            message: Cow::Owned(self),
+            options: DeserializerOptions::default(),
+        }
+    }
+}
+
+impl<'a> MessageDeserializer<'a> {
+    /// Creates a new [`MessageDeserializer`] which reads from a borrowed message.
+    pub fn new(message: &'a DynamicMessage) -> Self {
+        MessageDeserializer {
+            message: Cow::Borrowed(message),
+            options: DeserializerOptions::default(),
+        }
+    }
+
+    /// Whether to use the proto field name instead of the lowerCamelCase name as the keys of maps.
+    ///
+    /// This only applies when deserializing into a type other than a struct, such as a map or a
+    /// self-describing value. Struct fields are matched using either name regardless of this option.
+    ///
+    /// The default value is `false`.
+    pub fn use_proto_field_name(mut self, yes: bool) -> Self {
+        self.options.use_proto_field_name = yes;
+        self
+    }
+
+    /// The resolver used to find the message types of `google.protobuf.Any` values from their type URL.
+    ///
+    /// By default, types are looked up in the descriptor pool of the `Any` message, and only the
+    /// `type.googleapis.com` and `type.googleprod.com` domains are supported.
+    pub fn type_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        self.options.type_resolver = Some(SharedTypeResolver::new(resolver));
+        self
+    }
+
+    /// Passes floating point numbers with no fractional part to the visitor as integers, so that values
+    /// read from a `google.protobuf.Struct` can be deserialized into enum fields as in JSON.
+    pub(crate) fn integral_numbers(mut self) -> Self {
+        self.options.integral_numbers = true;
+        self
+    }
+
+    fn nested<'b>(&self, message: &'b DynamicMessage) -> MessageDeserializer<'b> {
+        MessageDeserializer {
+            message: Cow::Borrowed(message),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Gets a deserializer for a field of a well-known type.
+    fn field(&self, name: &str) -> ValueDeserializer<'_> {
+        let field = self
+            .message
+            .descriptor()
+            .get_field_by_name(name)
+            .expect("well-known type has an unexpected definition");
+        ValueDeserializer {
+            value: self.message.get_field(&field),
+            kind: field.kind(),
+            options: self.options.clone(),
+        }
+    }
+
+    fn deserialize_hint<'de, V>(self, hint: Hint, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        let message_desc = self.message.descriptor();
+        match (message_desc.full_name(), hint) {
+            (
+                "google.protobuf.FloatValue"
+                | "google.protobuf.DoubleValue"
+                | "google.protobuf.Int32Value"
+                | "google.protobuf.Int64Value"
+                | "google.protobuf.UInt32Value"
+                | "google.protobuf.UInt64Value"
+                | "google.protobuf.BoolValue"
+                | "google.protobuf.StringValue"
+                | "google.protobuf.BytesValue",
+                _,
+            ) => self.field("value").deserialize_hint(hint, visitor),
+            ("google.protobuf.Struct", _) => self.field("fields").deserialize_hint(hint, visitor),
+            ("google.protobuf.ListValue", _) => {
+                self.field("values").deserialize_hint(hint, visitor)
+            }
+            ("google.protobuf.Value", _) => {
+                let kind = [
+                    "null_value",
+                    "number_value",
+                    "string_value",
+                    "bool_value",
+                    "struct_value",
+                    "list_value",
+                ]
+                .into_iter()
+                .find(|name| self.message.has_field_by_name(name));
+                match (kind, hint) {
+                    (Some(name), _) => self.field(name).deserialize_hint(hint, visitor),
+                    (None, Hint::Option) => visitor.visit_none(),
+                    (None, _) => visitor.visit_unit(),
+                }
+            }
+            (_, Hint::Option) => visitor.visit_some(self),
+            (_, Hint::NewtypeStruct) => visitor.visit_newtype_struct(self),
+            ("google.protobuf.Empty", Hint::Unit) => visitor.visit_unit(),
+            ("google.protobuf.Timestamp", hint) if !is_map_hint(hint) => {
+                let timestamp: prost_types::Timestamp =
+                    self.message.transcode_to().map_err(Error::custom)?;
+                check_timestamp(&timestamp).map_err(Error::custom)?;
+                visitor.visit_string(timestamp.to_string())
+            }
+            ("google.protobuf.Duration", hint) if !is_map_hint(hint) => {
+                let duration: prost_types::Duration =
+                    self.message.transcode_to().map_err(Error::custom)?;
+                check_duration(&duration).map_err(Error::custom)?;
+                visitor.visit_string(duration.to_string())
+            }
+            ("google.protobuf.Timestamp", hint) => {
+                let timestamp: prost_types::Timestamp =
+                    self.message.transcode_to().map_err(Error::custom)?;
+                check_timestamp(&timestamp).map_err(Error::custom)?;
+                let entries = self.field_entries(hint);
+                visitor.visit_map(self.fields_deserializer(entries))
+            }
+            ("google.protobuf.Duration", hint) => {
+                let duration: prost_types::Duration =
+                    self.message.transcode_to().map_err(Error::custom)?;
+                check_duration(&duration).map_err(Error::custom)?;
+                let entries = self.field_entries(hint);
+                visitor.visit_map(self.fields_deserializer(entries))
+            }
+            ("google.protobuf.FieldMask", Hint::Seq) => {
+                self.field("paths").deserialize_hint(hint, visitor)
+            }
+            ("google.protobuf.FieldMask", hint) if !is_map_hint(hint) => {
+                let field_mask: prost_types::FieldMask =
+                    self.message.transcode_to().map_err(Error::custom)?;
+                let mut result = String::new();
+                for (i, path) in field_mask.paths.iter().enumerate() {
+                    if i > 0 {
+                        result.push(',');
+                    }
+                    for (j, part) in path.split('.').enumerate() {
+                        if j > 0 {
+                            result.push('.');
+                        }
+                        snake_case_to_camel_case(&mut result, part).map_err(|()| {
+                            Error::custom("cannot roundtrip field name through camelcase")
+                        })?;
+                    }
+                }
+                visitor.visit_string(result)
+            }
+            ("google.protobuf.Any", _) => {
+                let payload = match &self.options.type_resolver {
+                    Some(resolver) => self.message.unpack_any_with_resolver(resolver.get()),
+                    None => self.message.unpack_any(),
+                }
+                .map_err(Error::custom)?;
+                let type_url = self
+                    .message
+                    .get_field_by_name("type_url")
+                    .and_then(|value| value.as_str().map(ToOwned::to_owned))
+                    .unwrap_or_default();
+
+                let mut entries = vec![(
+                    Cow::Borrowed("@type"),
+                    Cow::Owned(Value::String(type_url)),
+                    Kind::String,
+                )];
+                let payload_desc = payload.descriptor();
+                if is_well_known_type(payload_desc.full_name()) {
+                    entries.push((
+                        Cow::Borrowed("value"),
+                        Cow::Owned(Value::Message(payload)),
+                        Kind::Message(payload_desc),
+                    ));
+                    visitor.visit_map(self.fields_deserializer(entries))
+                } else {
+                    let nested = self.nested(&payload);
+                    entries.extend(nested.field_entries(hint));
+                    visitor.visit_map(nested.fields_deserializer(entries))
+                }
+            }
+            (_, hint) => {
+                let entries = self.field_entries(hint);
+                visitor.visit_map(self.fields_deserializer(entries))
+            }
+        }
+    }
+
+    /// Gets the names, values and types of the fields to deserialize. If the visitor expects a struct, the
+    /// names are chosen to match its field names where possible.
+    fn field_entries(&self, hint: Hint) -> Vec<FieldEntry<'_>> {
+        let struct_fields = match hint {
+            Hint::Struct(fields) => fields,
+            _ => &[],
+        };
+
+        self.message
+            .fields
+            .iter(&self.message.desc, true, false)
+            .filter_map(|field| match field {
+                ValueAndDescriptor::Field(value, field_desc) => {
+                    let name = self.field_name(&field_desc, struct_fields);
+                    Some((name, value, field_desc.kind()))
+                }
+                ValueAndDescriptor::Extension(..) | ValueAndDescriptor::Unknown(_) => None,
+            })
+            .collect()
+    }
+
+    fn field_name(
+        &self,
+        field_desc: &FieldDescriptor,
+        struct_fields: &'static [&'static str],
+    ) -> Cow<'static, str> {
+        let candidates = [field_desc.name(), field_desc.json_name()];
+        let aliases = time_field_aliases(field_desc);
+        if let Some(name) = struct_fields
+            .iter()
+            .find(|name| candidates.contains(name) || aliases.contains(name))
+        {
+            return Cow::Borrowed(name);
+        }
+
+        if self.options.use_proto_field_name {
+            Cow::Owned(field_desc.name().to_owned())
+        } else {
+            Cow::Owned(field_desc.json_name().to_owned())
+        }
+    }
+
+    fn fields_deserializer<'b>(
+        &self,
+        entries: Vec<FieldEntry<'b>>,
+    ) -> MapDeserializer<
+        impl Iterator<Item = (CowStrDeserializer<'static, Error>, ValueDeserializer<'b>)>,
+        ValueDeserializer<'b>,
+    > {
+        let options = self.options.clone();
+        MapDeserializer::new(entries.into_iter().map(move |(name, value, kind)| {
+            (
+                name.into_deserializer(),
+                ValueDeserializer {
+                    value,
+                    kind,
+                    options: options.clone(),
+                },
+            )
+        }))
+    }
+}
+
+/// Returns `true` if the visitor expects a map or struct, in which case well-known types which are usually
+/// represented as strings are deserialized as their fields instead.
+fn is_map_hint(hint: Hint) -> bool {
+    matches!(hint, Hint::Map | Hint::Struct(_))
+}
+
+impl ValueDeserializer<'_> {
+    fn deserialize_hint<'de, V>(self, hint: Hint, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let (Value::Message(message), Kind::Message(_)) = (self.value.as_ref(), &self.kind) {
+            return MessageDeserializer {
+                message: Cow::Borrowed(message),
+                options: self.options.clone(),
+            }
+            .deserialize_hint(hint, visitor);
+        }
+
+        if let (Value::EnumNumber(_), Kind::Enum(enum_desc)) = (self.value.as_ref(), &self.kind) {
+            if enum_desc.full_name() == "google.protobuf.NullValue" {
+                return match hint {
+                    Hint::Option => visitor.visit_none(),
+                    _ => visitor.visit_unit(),
+                };
+            }
+        }
+
+        match hint {
+            Hint::Option => return visitor.visit_some(self),
+            Hint::NewtypeStruct => return visitor.visit_newtype_struct(self),
+            _ => (),
+        }
+
+        match (self.value.as_ref(), hint) {
+            (Value::Bool(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::Bool(value), _) => visitor.visit_bool(*value),
+            (Value::I32(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::I32(value), _) => visitor.visit_i32(*value),
+            (Value::I64(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::I64(value), _) => visitor.visit_i64(*value),
+            (Value::U32(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::U32(value), _) => visitor.visit_u32(*value),
+            (Value::U64(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::U64(value), _) => visitor.visit_u64(*value),
+            (Value::F32(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::F32(value), _) => visitor.visit_f32(*value),
+            (Value::F64(value), Hint::Str) => visitor.visit_string(value.to_string()),
+            (Value::F64(value), _) if self.options.integral_numbers && value.fract() == 0.0 => {
+                if *value >= i64::MIN as f64 && *value < i64::MAX as f64 {
+                    visitor.visit_i64(*value as i64)
+                } else if *value >= 0.0 && *value < u64::MAX as f64 {
+                    visitor.visit_u64(*value as u64)
+                } else {
+                    visitor.visit_f64(*value)
+                }
+            }
+            (Value::F64(value), _) => visitor.visit_f64(*value),
+            (Value::String(value), Hint::Bytes) => visitor.visit_bytes(value.as_bytes()),
+            (Value::String(value), Hint::Enum) => {
+                visitor.visit_enum(value.as_str().into_deserializer())
+            }
+            (Value::String(value), _) => visitor.visit_str(value),
+            (Value::Bytes(value), Hint::Seq) => visitor.visit_seq(SeqDeserializer(
+                value.iter().map(|&byte| byte.into_deserializer()),
+            )),
+            (Value::Bytes(value), Hint::Bytes) => visitor.visit_bytes(value),
+            (Value::Bytes(value), _) => visitor.visit_string(BASE64_STANDARD.encode(value)),
+            (&Value::EnumNumber(number), hint) => {
+                let enum_desc = match &self.kind {
+                    Kind::Enum(enum_desc) => enum_desc,
+                    _ => panic!(
+                        "mismatch between DynamicMessage value {:?} and type {:?}",
+                        self.value, self.kind
+                    ),
+                };
+                match (enum_desc.get_value(number), hint) {
+                    (_, Hint::Number) => visitor.visit_i32(number),
+                    (Some(value), Hint::Enum) => {
+                        visitor.visit_enum(value.name().into_deserializer())
+                    }
+                    (None, Hint::Enum) => Err(Error::custom(format!(
+                        "unknown value {} for enum '{}'",
+                        number,
+                        enum_desc.full_name()
+                    ))),
+                    (Some(value), _) => visitor.visit_str(value.name()),
+                    (None, _) => visitor.visit_i32(number),
+                }
+            }
+            (Value::List(values), _) => {
+                let kind = &self.kind;
+                visitor.visit_seq(SeqDeserializer(values.iter().map(|value| {
+                    ValueDeserializer {
+                        value: Cow::Borrowed(value),
+                        kind: kind.clone(),
+                        options: self.options.clone(),
+                    }
+                })))
+            }
+            (Value::Map(values), _) => {
+                let value_kind = match &self.kind {
+                    Kind::Message(message) if message.is_map_entry() => {
+                        message.map_entry_value_field().kind()
+                    }
+                    _ => panic!(
+                        "mismatch between DynamicMessage value {:?} and type {:?}",
+                        self.value, self.kind
+                    ),
+                };
+                visitor.visit_map(MapDeserializer::new(values.iter().map(|(key, value)| {
+                    (
+                        KeyDeserializer(key),
+                        ValueDeserializer {
+                            value: Cow::Borrowed(value),
+                            kind: value_kind.clone(),
+                            options: self.options.clone(),
+                        },
+                    )
+                })))
+            }
+            (Value::Message(_), _) => panic!(
+                "mismatch between DynamicMessage value {:?} and type {:?}",
+                self.value, self.kind
+            ),
+        }
+    }
+}
+
+/// Implements the methods of [`Deserializer`] by passing the corresponding [`Hint`] to `deserialize_hint`.
+macro_rules! deserialize_with_hint {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $hint:expr,)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                $(let _ = $arg;)*
+                self.deserialize_hint($hint, visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! impl_deserializer {
+    ($ty:ty) => {
+        impl<'de> Deserializer<'de> for $ty {
+            type Error = Error;
+
+            deserialize_with_hint! {
+                deserialize_any() => Hint::Any,
+                deserialize_bool() => Hint::Any,
+                deserialize_i8() => Hint::Number,
+                deserialize_i16() => Hint::Number,
+                deserialize_i32() => Hint::Number,
+                deserialize_i64() => Hint::Number,
+                deserialize_u8() => Hint::Number,
+                deserialize_u16() => Hint::Number,
+                deserialize_u32() => Hint::Number,
+                deserialize_u64() => Hint::Number,
+                deserialize_f32() => Hint::Number,
+                deserialize_f64() => Hint::Number,
+                deserialize_char() => Hint::Str,
+                deserialize_str() => Hint::Str,
+                deserialize_string() => Hint::Str,
+                deserialize_bytes() => Hint::Bytes,
+                deserialize_byte_buf() => Hint::Bytes,
+                deserialize_option() => Hint::Option,
+                deserialize_unit() => Hint::Unit,
+                deserialize_unit_struct(name: &'static str) => Hint::Unit,
+                deserialize_newtype_struct(name: &'static str) => Hint::NewtypeStruct,
+                deserialize_seq() => Hint::Seq,
+                deserialize_tuple(len: usize) => Hint::Seq,
+                deserialize_tuple_struct(name: &'static str, len: usize) => Hint::Seq,
+                deserialize_map() => Hint::Map,
+                deserialize_struct(
+                    name: &'static str,
+                    fields: &'static [&'static str]
+                ) => Hint::Struct(fields),
+                deserialize_enum(
+                    name: &'static str,
+                    variants: &'static [&'static str]
+                ) => Hint::Enum,
+                deserialize_identifier() => Hint::Str,
+                deserialize_ignored_any() => Hint::Any,
+            }
+        }
+    };
+}
+
+impl_deserializer!(MessageDeserializer<'_>);
+impl_deserializer!(ValueDeserializer<'_>);
+
+impl<'de> Deserializer<'de> for KeyDeserializer<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            MapKey::Bool(value) => visitor.visit_bool(*value),
+            MapKey::I32(value) => visitor.visit_i32(*value),
+            MapKey::I64(value) => visitor.visit_i64(*value),
+            MapKey::U32(value) => visitor.visit_u32(*value),
+            MapKey::U64(value) => visitor.visit_u64(*value),
+            MapKey::String(value) => visitor.visit_str(value),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            MapKey::Bool(value) => visitor.visit_string(value.to_string()),
+            MapKey::I32(value) => visitor.visit_string(value.to_string()),
+            MapKey::I64(value) => visitor.visit_string(value.to_string()),
+            MapKey::U32(value) => visitor.visit_string(value.to_string()),
+            MapKey::U64(value) => visitor.visit_string(value.to_string()),
+            MapKey::String(value) => visitor.visit_str(value),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf option unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum ignored_any
+    }
+}
+
+impl<'de, I, D> SeqAccess<'de> for SeqDeserializer<I>
+where
+    I: Iterator<Item = D>,
+    D: Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.0.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.0.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+impl<I, V> MapDeserializer<I, V> {
+    fn new(entries: I) -> Self {
+        MapDeserializer {
+            entries,
+            value: None,
+        }
+    }
+}
+
+impl<'de, I, K, V> MapAccess<'de> for MapDeserializer<I, V>
+where
+    I: Iterator<Item = (K, V)>,
+    K: Deserializer<'de, Error = Error>,
+    V: Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_key_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.entries.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for DynamicMessage {
+    type Deserializer = MessageDeserializer<'static>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        DynamicMessage::into_deserializer(self)
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for &'a DynamicMessage {
+    type Deserializer = MessageDeserializer<'a>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        MessageDeserializer::new(self)
+    }
+}
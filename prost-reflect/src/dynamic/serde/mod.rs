@@ -1,13 +1,26 @@
 mod case;
 mod de;
+mod deserializer;
 mod ser;
+mod serializer;
+
+pub use self::{
+    de::{DeserializeError, DeserializePathSegment},
+    deserializer::MessageDeserializer,
+    serializer::{MessageSerializer, SerializeError},
+};
 
 use serde::{
-    de::{DeserializeSeed, Deserializer},
+    de::{value::Error, DeserializeSeed, Deserializer},
     ser::{Serialize, Serializer},
 };
 
-use crate::{DynamicMessage, MessageDescriptor};
+use crate::{
+    any::{SharedTypeResolver, TypeResolver},
+    DynamicMessage, FieldDescriptor, MessageDescriptor, RedactOptions, ReflectMessage,
+};
+
+use super::wkt::{check_duration, check_timestamp};
 
 /// Options to control serialization of messages.
 ///
@@ -19,15 +32,22 @@ pub struct SerializeOptions {
     use_enum_numbers: bool,
     use_proto_field_name: bool,
     skip_default_fields: bool,
+    redact: Option<RedactOptions>,
+    max_string_len: usize,
+    max_collection_len: usize,
+    max_depth: usize,
+    max_output_len: usize,
+    type_resolver: Option<SharedTypeResolver>,
 }
 
 /// Options to control deserialization of messages.
 ///
-/// Used by [`DynamicMessage::deserialize_with_options()`].
+/// Used by [`DynamicMessage::deserialize_with_options()`] and [`MessageSerializer::with_options()`].
 #[derive(Debug, Clone)]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 pub struct DeserializeOptions {
     deny_unknown_fields: bool,
+    type_resolver: Option<SharedTypeResolver>,
 }
 
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
@@ -145,6 +165,9 @@ impl DynamicMessage {
     /// Deserialize an instance of the message type described by `desc` from `deserializer`, using
     /// the encoding specified by `options`.
     ///
+    /// Use [`deserialize_with_path()`](DynamicMessage::deserialize_with_path) to also get the path to the
+    /// value which could not be deserialized.
+    ///
     /// # Examples
     ///
     /// ```
@@ -170,7 +193,131 @@ impl DynamicMessage {
     where
         D: Deserializer<'de>,
     {
-        de::deserialize_message(&desc, deserializer, options)
+        Self::deserialize_with_path(desc, deserializer, options)
+            .map_err(DeserializeError::into_inner)
+    }
+
+    /// Deserialize an instance of the message type described by `desc` from `deserializer`, using
+    /// the encoding specified by `options`, and report the path to the value which caused any error.
+    ///
+    /// The error returned by `deserializer` is left unchanged, and can be retrieved using
+    /// [`DeserializeError::into_inner()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost::Message;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, DeserializeOptions, DeserializePathSegment};
+    /// # use serde1 as serde;
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let json = r#"{ "nested": { "foo": "abc" } }"#;
+    /// let mut deserializer = serde_json::de::Deserializer::from_str(json);
+    /// let err = DynamicMessage::deserialize_with_path(message_descriptor, &mut deserializer, &DeserializeOptions::new()).unwrap_err();
+    ///
+    /// assert_eq!(err.path(), &[
+    ///     DeserializePathSegment::Field("nested".to_owned()),
+    ///     DeserializePathSegment::Field("foo".to_owned()),
+    /// ]);
+    /// assert_eq!(err.json_path(), "$.nested.foo");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn deserialize_with_path<'de, D>(
+        desc: MessageDescriptor,
+        deserializer: D,
+        options: &DeserializeOptions,
+    ) -> Result<Self, DeserializeError<D::Error>>
+    where
+        D: Deserializer<'de>,
+    {
+        de::deserialize_with_path(&desc, deserializer, options)
+    }
+
+    /// Converts this message into a `google.protobuf.Struct` message, following the
+    /// [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json).
+    ///
+    /// The conversion does not produce JSON text, and the result can be converted back into a message of
+    /// the original type without loss using [`from_struct()`](DynamicMessage::from_struct). If the
+    /// descriptor pool of this message contains the `google.protobuf.Struct` type, it is used for the
+    /// result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this message is not represented as a JSON object, for example if it is a
+    /// `google.protobuf.Timestamp`, or if a `google.protobuf.Any` field cannot be resolved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, ReflectMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor.clone(), b"\x08\x96\x01".as_ref()).unwrap();
+    /// let struct_message = dynamic_message.to_struct().unwrap();
+    /// assert_eq!(struct_message.descriptor().full_name(), "google.protobuf.Struct");
+    ///
+    /// let roundtripped = DynamicMessage::from_struct(message_descriptor, &struct_message).unwrap();
+    /// assert_eq!(roundtripped, dynamic_message);
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_struct(&self) -> Result<DynamicMessage, SerializeError> {
+        self.to_struct_with_options(&Default::default())
+    }
+
+    /// Converts this message into a `google.protobuf.Struct` message, using the encoding specified by
+    /// `options`.
+    ///
+    /// See [`to_struct()`](DynamicMessage::to_struct) for details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn to_struct_with_options(
+        &self,
+        options: &SerializeOptions,
+    ) -> Result<DynamicMessage, SerializeError> {
+        let struct_desc = self
+            .desc
+            .parent_pool()
+            .get_message_by_name("google.protobuf.Struct")
+            .unwrap_or_else(|| prost_types::Struct::default().descriptor());
+        self.serialize_with_options(MessageSerializer::new(struct_desc), options)
+    }
+
+    /// Creates a message of the type described by `desc` from a `google.protobuf.Struct` or
+    /// `google.protobuf.Value` message, following the
+    /// [canonical JSON encoding](https://developers.google.com/protocol-buffers/docs/proto3#json).
+    ///
+    /// This is the inverse of [`to_struct()`](DynamicMessage::to_struct). Numbers may be given as either
+    /// `number_value` or `string_value` fields, so 64-bit integers are converted without loss of precision
+    /// if they are represented as strings.
+    ///
+    /// Numbers with no fractional part may also be used for enum fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` does not match the message type. The error includes the path to the
+    /// value which could not be converted.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn from_struct(
+        desc: MessageDescriptor,
+        value: &DynamicMessage,
+    ) -> Result<Self, DeserializeError<Error>> {
+        Self::from_struct_with_options(desc, value, &Default::default())
+    }
+
+    /// Creates a message of the type described by `desc` from a `google.protobuf.Struct` or
+    /// `google.protobuf.Value` message, using the encoding specified by `options`.
+    ///
+    /// See [`from_struct()`](DynamicMessage::from_struct) for details.
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn from_struct_with_options(
+        desc: MessageDescriptor,
+        value: &DynamicMessage,
+        options: &DeserializeOptions,
+    ) -> Result<Self, DeserializeError<Error>> {
+        Self::deserialize_with_path(
+            desc,
+            MessageDeserializer::new(value).integral_numbers(),
+            options,
+        )
     }
 }
 
@@ -180,6 +327,7 @@ impl DeserializeOptions {
     pub const fn new() -> Self {
         DeserializeOptions {
             deny_unknown_fields: true,
+            type_resolver: None,
         }
     }
 
@@ -190,6 +338,18 @@ impl DeserializeOptions {
         self.deny_unknown_fields = yes;
         self
     }
+
+    /// The resolver used to find the message types of `google.protobuf.Any` values from their `@type` URL.
+    ///
+    /// By default, types are looked up in the descriptor pool of the `Any` message, and only the
+    /// `type.googleapis.com` and `type.googleprod.com` domains are supported.
+    pub fn type_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        self.type_resolver = Some(SharedTypeResolver::new(resolver));
+        self
+    }
 }
 
 impl Default for DeserializeOptions {
@@ -207,6 +367,12 @@ impl SerializeOptions {
             use_enum_numbers: false,
             use_proto_field_name: false,
             skip_default_fields: true,
+            redact: None,
+            max_string_len: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_depth: usize::MAX,
+            max_output_len: usize::MAX,
+            type_resolver: None,
         }
     }
 
@@ -251,6 +417,86 @@ impl SerializeOptions {
         self.skip_default_fields = yes;
         self
     }
+
+    /// Replaces the values of sensitive fields with a placeholder string, as determined by `options`.
+    ///
+    /// By default, no fields are redacted.
+    pub fn redact(mut self, options: RedactOptions) -> Self {
+        self.redact = Some(options);
+        self
+    }
+
+    /// The maximum number of bytes of a `string` or `bytes` value to serialize.
+    ///
+    /// Longer values are cut off, and followed by the length of the original value, for example
+    /// `"abc...(10 bytes)"`. For `bytes` values, the limit applies before base64 encoding.
+    ///
+    /// The default value is `usize::MAX`.
+    pub const fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    /// The maximum number of elements of a repeated or map field to serialize.
+    ///
+    /// Further elements are replaced by a count of the elements omitted. For repeated fields this is an extra
+    /// element such as `"...(3 more)"`, and for map fields an extra entry such as `"...": "(3 more)"`.
+    ///
+    /// The default value is `usize::MAX`.
+    pub const fn max_collection_len(mut self, len: usize) -> Self {
+        self.max_collection_len = len;
+        self
+    }
+
+    /// The maximum depth of nested messages to serialize.
+    ///
+    /// Messages nested more deeply are serialized as the string `"{...}"`. A depth of `0` serializes only the
+    /// fields of the top-level message.
+    ///
+    /// The default value is `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, SerializeOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let mut serializer = serde_json::Serializer::new(vec![]);
+    /// let options = SerializeOptions::new().max_depth(0);
+    /// dynamic_message.serialize_with_options(&mut serializer, &options).unwrap();
+    /// assert_eq!(serializer.into_inner(), br#"{"foo":150,"nested":"{...}"}"#);
+    /// ```
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The approximate maximum number of bytes to serialize in total.
+    ///
+    /// The size of the output is estimated from the field names and values serialized, not including punctuation
+    /// or whitespace. A string or bytes value which does not fit in the remaining budget is shortened, as for
+    /// [`max_string_len`](Self::max_string_len). Once this limit is reached, no further fields or elements are
+    /// serialized, and a final `"...": "(truncated)"` entry or `"...(truncated)"` element is added. The output
+    /// remains valid JSON.
+    ///
+    /// The default value is `usize::MAX`.
+    pub const fn max_output_len(mut self, len: usize) -> Self {
+        self.max_output_len = len;
+        self
+    }
+
+    /// The resolver used to find the message types of `google.protobuf.Any` values from their type URL.
+    ///
+    /// By default, types are looked up in the descriptor pool of the `Any` message, and only the
+    /// `type.googleapis.com` and `type.googleprod.com` domains are supported.
+    pub fn type_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        self.type_resolver = Some(SharedTypeResolver::new(resolver));
+        self
+    }
 }
 
 impl Default for SerializeOptions {
@@ -259,12 +505,6 @@ impl Default for SerializeOptions {
     }
 }
 
-const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
-const MAX_DURATION_NANOS: u32 = 999_999_999;
-
-const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
-const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
-
 fn is_well_known_type(full_name: &str) -> bool {
     matches!(
         full_name,
@@ -288,20 +528,13 @@ fn is_well_known_type(full_name: &str) -> bool {
     )
 }
 
-fn check_duration(duration: &prost_types::Duration) -> Result<(), &'static str> {
-    if duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS
-        || duration.nanos.unsigned_abs() > MAX_DURATION_NANOS
-    {
-        Err("duration out of range")
-    } else {
-        Ok(())
-    }
-}
-
-fn check_timestamp(timestamp: &prost_types::Timestamp) -> Result<(), &'static str> {
-    if timestamp.seconds < MIN_TIMESTAMP_SECONDS || MAX_TIMESTAMP_SECONDS < timestamp.seconds {
-        Err("timestamp out of range")
-    } else {
-        Ok(())
+/// Alternative names for the fields of `Timestamp` and `Duration`, matching the serde representation of
+/// [`SystemTime`](std::time::SystemTime) and [`Duration`](std::time::Duration).
+fn time_field_aliases(field_desc: &FieldDescriptor) -> &'static [&'static str] {
+    match field_desc.full_name() {
+        "google.protobuf.Timestamp.seconds" => &["secs_since_epoch"],
+        "google.protobuf.Timestamp.nanos" => &["nanos_since_epoch"],
+        "google.protobuf.Duration.seconds" => &["secs"],
+        _ => &[],
     }
 }
@@ -1,18 +1,39 @@
 mod wkt;
 
+use std::{
+    cell::Cell,
+    fmt::{self, Write},
+};
+
 use base64::{display::Base64Display, prelude::BASE64_STANDARD};
 
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use crate::{
     descriptor::Kind,
-    dynamic::{fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, MapKey, Value},
+    dynamic::{
+        fields::ValueAndDescriptor, serde::SerializeOptions, DynamicMessage, FieldDescriptorLike,
+        MapKey, Value,
+    },
     ReflectMessage,
 };
 
+/// The marker used as a map key, or list element prefix, when entries are omitted.
+const OMITTED_MARKER: &str = "...";
+
 struct SerializeWrapper<'a, T> {
     value: &'a T,
     options: &'a SerializeOptions,
+    state: &'a SerializeState,
+}
+
+/// Tracks the nesting depth and remaining output budget while serializing a message.
+struct SerializeState {
+    depth: Cell<usize>,
+    limited: bool,
+    remaining: Cell<usize>,
+    overrun: Cell<usize>,
+    truncated: Cell<bool>,
 }
 
 pub(super) fn serialize_message<S>(
@@ -26,23 +47,75 @@ where
     SerializeWrapper {
         value: message,
         options,
+        state: &SerializeState::new(options),
     }
     .serialize(serializer)
 }
 
+impl SerializeState {
+    fn new(options: &SerializeOptions) -> Self {
+        SerializeState {
+            depth: Cell::new(0),
+            limited: options.max_output_len != usize::MAX,
+            remaining: Cell::new(options.max_output_len),
+            overrun: Cell::new(0),
+            truncated: Cell::new(false),
+        }
+    }
+
+    /// Deducts the estimated length `len` from the output budget. Returns `false` if the budget was already
+    /// exhausted, in which case nothing further should be serialized.
+    ///
+    /// If the estimate exceeds the remaining budget, the excess is recorded so that a string or bytes value
+    /// serialized next can be shortened to fit. The estimate is only computed if the output length is
+    /// limited.
+    fn consume(&self, len: impl FnOnce() -> usize) -> bool {
+        if !self.limited {
+            return true;
+        }
+
+        let remaining = self.remaining.get();
+        let len = len();
+        self.overrun.set(len.saturating_sub(remaining));
+        self.remaining.set(remaining.saturating_sub(len));
+        remaining != 0
+    }
+
+    /// Returns the number of bytes by which the last estimate exceeded the output budget, and resets it.
+    fn take_overrun(&self) -> usize {
+        self.overrun.replace(0)
+    }
+
+    /// Returns `true` the first time it is called, so that only the innermost list or map where the budget
+    /// ran out includes a marker.
+    fn mark_truncated(&self) -> bool {
+        !self.truncated.replace(true)
+    }
+}
+
 impl Serialize for SerializeWrapper<'_, DynamicMessage> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        let depth = self.state.depth.get();
+        if depth > self.options.max_depth {
+            return serializer.serialize_str("{...}");
+        }
+
+        self.state.depth.set(depth + 1);
         let message_desc = self.value.descriptor();
-        if let Some(serialize) = wkt::get_well_known_type_serializer(message_desc.full_name()) {
-            serialize(self.value, serializer, self.options)
+        let result = if let Some(serialize) =
+            wkt::get_well_known_type_serializer(message_desc.full_name())
+        {
+            serialize(self.value, serializer, self.options, self.state)
         } else {
             let mut map = serializer.serialize_map(None)?;
-            serialize_dynamic_message_fields(&mut map, self.value, self.options)?;
+            serialize_dynamic_message_fields(&mut map, self.value, self.options, self.state)?;
             map.end()
-        }
+        };
+        self.state.depth.set(depth);
+        result
     }
 }
 
@@ -50,6 +123,7 @@ fn serialize_dynamic_message_fields<S>(
     map: &mut S,
     value: &DynamicMessage,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<(), S::Error>
 where
     S: SerializeMap,
@@ -59,21 +133,42 @@ where
         .iter(&value.desc, !options.skip_default_fields, false);
 
     for field in fields {
-        let (name, value, ref kind) = match field {
+        let (name, value, ref kind, redacted) = match field {
             ValueAndDescriptor::Field(value, ref field_desc) => {
                 let name = if options.use_proto_field_name {
                     field_desc.name()
                 } else {
                     field_desc.json_name()
                 };
-                (name, value, field_desc.kind())
-            }
-            ValueAndDescriptor::Extension(value, ref extension_desc) => {
-                (extension_desc.json_name(), value, extension_desc.kind())
+                (
+                    name,
+                    value,
+                    field_desc.kind(),
+                    is_redacted(field_desc, options),
+                )
             }
+            ValueAndDescriptor::Extension(value, ref extension_desc) => (
+                extension_desc.json_name(),
+                value,
+                extension_desc.kind(),
+                is_redacted(extension_desc, options),
+            ),
             ValueAndDescriptor::Unknown(_) => continue,
         };
 
+        if !state.consume(|| name.len() + estimate_len(&value, options)) {
+            if state.mark_truncated() {
+                map.serialize_entry(OMITTED_MARKER, "(truncated)")?;
+            }
+            break;
+        }
+
+        if redacted {
+            let placeholder = options.redact.as_ref().unwrap().placeholder_str();
+            map.serialize_entry(name, placeholder)?;
+            continue;
+        }
+
         map.serialize_entry(
             name,
             &SerializeWrapper {
@@ -82,6 +177,7 @@ where
                     kind,
                 },
                 options,
+                state,
             },
         )?;
     }
@@ -89,6 +185,204 @@ where
     Ok(())
 }
 
+/// Estimates the number of bytes needed to serialize `value`, not including the contents of messages,
+/// lists or maps.
+fn estimate_len(value: &Value, options: &SerializeOptions) -> usize {
+    match value {
+        Value::Bool(value) => bool_len(*value),
+        Value::I32(value) => int_len((*value).into()),
+        Value::I64(value) => int_len(*value),
+        Value::U32(value) => uint_len((*value).into()),
+        Value::U64(value) => uint_len(*value),
+        Value::F32(value) => display_len(value),
+        Value::F64(value) => display_len(value),
+        Value::EnumNumber(value) => int_len((*value).into()),
+        Value::String(value) => value.len().min(options.max_string_len),
+        Value::Bytes(value) => base64_len(value.len().min(options.max_string_len)),
+        Value::Message(_) | Value::List(_) | Value::Map(_) => 0,
+    }
+}
+
+fn key_len(key: &MapKey) -> usize {
+    match key {
+        MapKey::Bool(value) => bool_len(*value),
+        MapKey::I32(value) => int_len((*value).into()),
+        MapKey::I64(value) => int_len(*value),
+        MapKey::U32(value) => uint_len((*value).into()),
+        MapKey::U64(value) => uint_len(*value),
+        MapKey::String(value) => value.len(),
+    }
+}
+
+fn bool_len(value: bool) -> usize {
+    if value {
+        4
+    } else {
+        5
+    }
+}
+
+fn int_len(value: i64) -> usize {
+    uint_len(value.unsigned_abs()) + usize::from(value < 0)
+}
+
+fn uint_len(value: u64) -> usize {
+    value
+        .checked_ilog10()
+        .map_or(1, |digits| digits as usize + 1)
+}
+
+fn base64_len(len: usize) -> usize {
+    (len + 2) / 3 * 4
+}
+
+fn display_len(value: &impl fmt::Display) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    write!(counter, "{}", value).expect("writing to counter cannot fail");
+    counter.0
+}
+
+/// Serializes a list, stopping once `max_collection_len` elements or the output budget are exhausted.
+fn serialize_limited_seq<S, T>(
+    serializer: S,
+    values: impl ExactSizeIterator<Item = T>,
+    options: &SerializeOptions,
+    state: &SerializeState,
+    estimate_len: impl Fn(&T) -> usize,
+    mut serialize_element: impl FnMut(&mut S::SerializeSeq, T) -> Result<(), S::Error>,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let len = values.len();
+    let mut list = serializer.serialize_seq(size_hint(len, options))?;
+    for (index, value) in values.enumerate() {
+        if index == options.max_collection_len {
+            list.serialize_element(&format_args!("{}({} more)", OMITTED_MARKER, len - index))?;
+            break;
+        }
+        if !state.consume(|| estimate_len(&value) + 1) {
+            if state.mark_truncated() {
+                list.serialize_element(&format_args!("{}(truncated)", OMITTED_MARKER))?;
+            }
+            break;
+        }
+        serialize_element(&mut list, value)?;
+    }
+    list.end()
+}
+
+/// Serializes a map, stopping once `max_collection_len` entries or the output budget are exhausted.
+fn serialize_limited_map<S, T>(
+    serializer: S,
+    entries: impl ExactSizeIterator<Item = T>,
+    options: &SerializeOptions,
+    state: &SerializeState,
+    estimate_len: impl Fn(&T) -> usize,
+    mut serialize_entry: impl FnMut(&mut S::SerializeMap, T) -> Result<(), S::Error>,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let len = entries.len();
+    let mut map = serializer.serialize_map(size_hint(len, options))?;
+    for (index, entry) in entries.enumerate() {
+        if index == options.max_collection_len {
+            map.serialize_entry(OMITTED_MARKER, &format_args!("({} more)", len - index))?;
+            break;
+        }
+        if !state.consume(|| estimate_len(&entry) + 1) {
+            if state.mark_truncated() {
+                map.serialize_entry(OMITTED_MARKER, "(truncated)")?;
+            }
+            break;
+        }
+        serialize_entry(&mut map, entry)?;
+    }
+    map.end()
+}
+
+/// The length of a list or map is only known in advance if no limits apply.
+fn size_hint(len: usize, options: &SerializeOptions) -> Option<usize> {
+    if len <= options.max_collection_len && options.max_output_len == usize::MAX {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Serializes a string, truncating it to `max_string_len` bytes, or fewer if its estimated length did
+/// not fit in the output budget.
+fn serialize_limited_str<S>(
+    serializer: S,
+    value: &str,
+    options: &SerializeOptions,
+    state: &SerializeState,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let limit = value
+        .len()
+        .min(options.max_string_len)
+        .saturating_sub(state.take_overrun());
+    if value.len() > limit {
+        let mut end = limit;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        serializer.collect_str(&format_args!(
+            "{}{}({} bytes)",
+            &value[..end],
+            OMITTED_MARKER,
+            value.len()
+        ))
+    } else {
+        serializer.serialize_str(value)
+    }
+}
+
+/// Serializes a bytes value, truncating it to `max_string_len` bytes, or fewer if its estimated encoded
+/// length did not fit in the output budget.
+fn serialize_limited_bytes<S>(
+    serializer: S,
+    value: &[u8],
+    options: &SerializeOptions,
+    state: &SerializeState,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut limit = value.len().min(options.max_string_len);
+    let overrun = state.take_overrun();
+    if overrun > 0 {
+        limit = limit.min(base64_len(limit).saturating_sub(overrun) / 4 * 3);
+    }
+    if value.len() > limit {
+        serializer.collect_str(&format_args!(
+            "{}{}({} bytes)",
+            Base64Display::new(&value[..limit], &BASE64_STANDARD),
+            OMITTED_MARKER,
+            value.len()
+        ))
+    } else {
+        serializer.collect_str(&Base64Display::new(value, &BASE64_STANDARD))
+    }
+}
+
+fn is_redacted(field_desc: &impl FieldDescriptorLike, options: &SerializeOptions) -> bool {
+    matches!(&options.redact, Some(redact) if redact.is_redacted(field_desc))
+}
+
 struct ValueAndKind<'a> {
     value: &'a Value,
     kind: &'a Kind,
@@ -141,9 +435,11 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
                     serializer.serialize_str("NaN")
                 }
             }
-            Value::String(value) => serializer.serialize_str(value),
+            Value::String(value) => {
+                serialize_limited_str(serializer, value, self.options, self.state)
+            }
             Value::Bytes(value) => {
-                serializer.collect_str(&Base64Display::new(value, &BASE64_STANDARD))
+                serialize_limited_bytes(serializer, value, self.options, self.state)
             }
             Value::EnumNumber(number) => {
                 let enum_ty = match self.value.kind {
@@ -164,20 +460,29 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
                     serializer.serialize_i32(*number)
                 }
             }
-            Value::Message(message) => message.serialize_with_options(serializer, self.options),
-            Value::List(values) => {
-                let mut list = serializer.serialize_seq(Some(values.len()))?;
-                for value in values {
+            Value::Message(message) => SerializeWrapper {
+                value: message,
+                options: self.options,
+                state: self.state,
+            }
+            .serialize(serializer),
+            Value::List(values) => serialize_limited_seq(
+                serializer,
+                values.iter(),
+                self.options,
+                self.state,
+                |value| estimate_len(value, self.options),
+                |list, value| {
                     list.serialize_element(&SerializeWrapper {
                         value: &ValueAndKind {
                             value,
                             kind: self.value.kind,
                         },
                         options: self.options,
-                    })?;
-                }
-                list.end()
-            }
+                        state: self.state,
+                    })
+                },
+            ),
             Value::Map(values) => {
                 let value_kind = match self.value.kind {
                     Kind::Message(message) if message.is_map_entry() => {
@@ -189,23 +494,30 @@ impl<'a> Serialize for SerializeWrapper<'a, ValueAndKind<'a>> {
                     ),
                 };
 
-                let mut map = serializer.serialize_map(Some(values.len()))?;
-                for (key, value) in values {
-                    map.serialize_entry(
-                        &SerializeWrapper {
-                            value: key,
-                            options: self.options,
-                        },
-                        &SerializeWrapper {
-                            value: &ValueAndKind {
-                                value,
-                                kind: &value_kind,
+                serialize_limited_map(
+                    serializer,
+                    values.iter(),
+                    self.options,
+                    self.state,
+                    |(key, value)| key_len(key) + estimate_len(value, self.options),
+                    |map, (key, value)| {
+                        map.serialize_entry(
+                            &SerializeWrapper {
+                                value: key,
+                                options: self.options,
+                                state: self.state,
                             },
-                            options: self.options,
-                        },
-                    )?;
-                }
-                map.end()
+                            &SerializeWrapper {
+                                value: &ValueAndKind {
+                                    value,
+                                    kind: &value_kind,
+                                },
+                                options: self.options,
+                                state: self.state,
+                            },
+                        )
+                    },
+                )
             }
         }
     }
@@ -1,8 +1,9 @@
-use base64::{display::Base64Display, prelude::BASE64_STANDARD};
 use prost::{DecodeError, Message};
 use serde::ser::{Error, Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use crate::{
+    any::resolve_type_url,
+    descriptor::GOOGLE_APIS_DOMAIN,
     dynamic::{
         serde::{
             case::snake_case_to_camel_case, check_duration, check_timestamp, is_well_known_type,
@@ -13,11 +14,15 @@ use crate::{
     ReflectMessage,
 };
 
-use super::{serialize_dynamic_message_fields, SerializeWrapper};
+use super::{
+    base64_len, display_len, serialize_dynamic_message_fields, serialize_limited_bytes,
+    serialize_limited_map, serialize_limited_seq, serialize_limited_str, SerializeState,
+    SerializeWrapper,
+};
 
 #[allow(type_alias_bounds)]
 type WellKnownTypeSerializer<S: Serializer> =
-    fn(&DynamicMessage, S, &SerializeOptions) -> Result<S::Ok, S::Error>;
+    fn(&DynamicMessage, S, &SerializeOptions, &SerializeState) -> Result<S::Ok, S::Error>;
 
 pub fn get_well_known_type_serializer<S>(full_name: &str) -> Option<WellKnownTypeSerializer<S>>
 where
@@ -52,25 +57,32 @@ fn serialize_any<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: prost_types::Any = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    if let Some(message_name) = raw.type_url.strip_prefix("type.googleapis.com/") {
-        let message_desc = msg
-            .descriptor()
-            .parent_pool()
-            .get_message_by_name(message_name)
-            .ok_or_else(|| Error::custom(format!("message '{}' not found", message_name)))?;
+    if options.type_resolver.is_some() || raw.type_url.starts_with(GOOGLE_APIS_DOMAIN) {
+        let message_desc = resolve_type_url(
+            options.type_resolver.as_ref(),
+            msg.descriptor().parent_pool(),
+            &raw.type_url,
+        )
+        .ok_or_else(|| {
+            Error::custom(format!(
+                "message type not found for type url '{}'",
+                raw.type_url
+            ))
+        })?;
 
         let mut payload_message = DynamicMessage::new(message_desc);
         payload_message
             .merge(raw.value.as_ref())
             .map_err(decode_to_ser_err)?;
 
-        if is_well_known_type(message_name) {
+        if is_well_known_type(payload_message.descriptor().full_name()) {
             let mut map = serializer.serialize_map(Some(2))?;
             map.serialize_entry("@type", &raw.type_url)?;
             map.serialize_entry(
@@ -78,13 +90,14 @@ where
                 &SerializeWrapper {
                     value: &payload_message,
                     options,
+                    state,
                 },
             )?;
             map.end()
         } else {
             let mut map = serializer.serialize_map(None)?;
             map.serialize_entry("@type", &raw.type_url)?;
-            serialize_dynamic_message_fields(&mut map, &payload_message, options)?;
+            serialize_dynamic_message_fields(&mut map, &payload_message, options, state)?;
             map.end()
         }
     } else {
@@ -99,6 +112,7 @@ fn serialize_timestamp<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -114,6 +128,7 @@ fn serialize_duration<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -129,6 +144,7 @@ fn serialize_float<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -142,6 +158,7 @@ fn serialize_double<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -155,6 +172,7 @@ fn serialize_int32<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -168,6 +186,7 @@ fn serialize_int64<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -185,6 +204,7 @@ fn serialize_uint32<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -198,6 +218,7 @@ fn serialize_uint64<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -215,6 +236,7 @@ fn serialize_bool<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -227,33 +249,38 @@ where
 fn serialize_string<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: String = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.serialize_str(&raw)
+    state.consume(|| raw.len().min(options.max_string_len));
+    serialize_limited_str(serializer, &raw, options, state)
 }
 
 fn serialize_bytes<S>(
     msg: &DynamicMessage,
     serializer: S,
-    _options: &SerializeOptions,
+    options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: Vec<u8> = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serializer.collect_str(&Base64Display::new(&raw, &BASE64_STANDARD))
+    state.consume(|| base64_len(raw.len().min(options.max_string_len)));
+    serialize_limited_bytes(serializer, &raw, options, state)
 }
 
 fn serialize_field_mask<S>(
     msg: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -284,6 +311,7 @@ fn serialize_empty<S>(
     _: &DynamicMessage,
     serializer: S,
     _options: &SerializeOptions,
+    _state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -295,39 +323,42 @@ fn serialize_value<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: prost_types::Value = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serialize_value_inner(&raw, serializer, options)
+    serialize_value_inner(&raw, serializer, options, state)
 }
 
 fn serialize_struct<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: prost_types::Struct = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serialize_struct_inner(&raw, serializer, options)
+    serialize_struct_inner(&raw, serializer, options, state)
 }
 
 fn serialize_list<S>(
     msg: &DynamicMessage,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     let raw: prost_types::ListValue = msg.transcode_to().map_err(decode_to_ser_err)?;
 
-    serialize_list_inner(&raw, serializer, options)
+    serialize_list_inner(&raw, serializer, options, state)
 }
 
 impl Serialize for SerializeWrapper<'_, prost_types::Value> {
@@ -335,7 +366,7 @@ impl Serialize for SerializeWrapper<'_, prost_types::Value> {
     where
         S: Serializer,
     {
-        serialize_value_inner(self.value, serializer, self.options)
+        serialize_value_inner(self.value, serializer, self.options, self.state)
     }
 }
 
@@ -343,6 +374,7 @@ fn serialize_value_inner<S>(
     raw: &prost_types::Value,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -359,12 +391,14 @@ where
                 ))
             }
         }
-        Some(prost_types::value::Kind::StringValue(value)) => serializer.serialize_str(value),
+        Some(prost_types::value::Kind::StringValue(value)) => {
+            serialize_limited_str(serializer, value, options, state)
+        }
         Some(prost_types::value::Kind::ListValue(value)) => {
-            serialize_list_inner(value, serializer, options)
+            serialize_list_inner(value, serializer, options, state)
         }
         Some(prost_types::value::Kind::StructValue(value)) => {
-            serialize_struct_inner(value, serializer, options)
+            serialize_struct_inner(value, serializer, options, state)
         }
     }
 }
@@ -373,30 +407,68 @@ fn serialize_struct_inner<S>(
     raw: &prost_types::Struct,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let mut map = serializer.serialize_map(Some(raw.fields.len()))?;
-    for (key, value) in &raw.fields {
-        map.serialize_entry(key, &SerializeWrapper { value, options })?;
-    }
-    map.end()
+    serialize_limited_map(
+        serializer,
+        raw.fields.iter(),
+        options,
+        state,
+        |(key, value)| key.len() + estimate_len(value, options),
+        |map, (key, value)| {
+            map.serialize_entry(
+                key,
+                &SerializeWrapper {
+                    value,
+                    options,
+                    state,
+                },
+            )
+        },
+    )
 }
 
 fn serialize_list_inner<S>(
     raw: &prost_types::ListValue,
     serializer: S,
     options: &SerializeOptions,
+    state: &SerializeState,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let mut list = serializer.serialize_seq(Some(raw.values.len()))?;
-    for value in &raw.values {
-        list.serialize_element(&SerializeWrapper { value, options })?;
+    serialize_limited_seq(
+        serializer,
+        raw.values.iter(),
+        options,
+        state,
+        |value| estimate_len(value, options),
+        |list, value| {
+            list.serialize_element(&SerializeWrapper {
+                value,
+                options,
+                state,
+            })
+        },
+    )
+}
+
+/// Estimates the number of bytes needed to serialize `value`, not including the contents of structs or lists.
+fn estimate_len(value: &prost_types::Value, options: &SerializeOptions) -> usize {
+    match &value.kind {
+        None | Some(prost_types::value::Kind::NullValue(_)) => 4,
+        Some(prost_types::value::Kind::BoolValue(value)) => display_len(value),
+        Some(prost_types::value::Kind::NumberValue(value)) => display_len(value),
+        Some(prost_types::value::Kind::StringValue(value)) => {
+            value.len().min(options.max_string_len)
+        }
+        Some(prost_types::value::Kind::ListValue(_) | prost_types::value::Kind::StructValue(_)) => {
+            0
+        }
     }
-    list.end()
 }
 
 fn decode_to_ser_err<E>(err: DecodeError) -> E
@@ -0,0 +1,1436 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+};
+
+use serde::{
+    de::{value::Error as ValueError, DeserializeSeed, Deserializer, IntoDeserializer, Unexpected},
+    ser::{
+        self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+        SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+        Serializer,
+    },
+};
+
+use crate::{
+    dynamic::{
+        diff::{index_path, join_path, key_path},
+        fields::FieldDescriptorLike,
+        serde::{
+            check_duration, check_timestamp,
+            de::{deserialize_with_path, parse_map_key, Context, KindSeed, Track},
+            time_field_aliases, DeserializeOptions,
+        },
+    },
+    DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value,
+};
+
+/// The options used by [`MessageSerializer::new()`].
+static DEFAULT_OPTIONS: DeserializeOptions = DeserializeOptions::new();
+
+/// A [`Serializer`] which builds a [`DynamicMessage`] of a given type from any value implementing
+/// [`Serialize`], without an intermediate format such as JSON.
+///
+/// Created by [`MessageSerializer::new()`] or [`MessageSerializer::with_options()`], and used by
+/// [`DynamicMessage::from_serialize()`].
+///
+/// Values are interpreted in the same way as by [`DynamicMessage::deserialize()`], following the
+/// [canonical JSON encoding]. Structs and maps are matched to message fields by either their JSON name or
+/// their proto name, sequences populate repeated fields, maps populate map fields, and enums are matched by
+/// name or number. `None` leaves a field unset. Enum variants with data, such as those used to represent a
+/// oneof, are treated as a map with a single entry named after the variant.
+///
+/// Well-known types may be serialized from the same values as their JSON representation. In addition,
+/// `google.protobuf.Timestamp` and `google.protobuf.Duration` may be serialized from a
+/// [`SystemTime`](std::time::SystemTime) or [`Duration`](std::time::Duration), and
+/// `google.protobuf.FieldMask` from a sequence of paths.
+///
+/// [canonical JSON encoding]: https://developers.google.com/protocol-buffers/docs/proto3#json
+#[derive(Debug, Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct MessageSerializer<'a> {
+    desc: MessageDescriptor,
+    options: &'a DeserializeOptions,
+}
+
+/// The state of a [`MessageSerializer`] while serializing a compound value, such as a struct or sequence.
+#[derive(Debug)]
+pub struct Compound<'a> {
+    desc: MessageDescriptor,
+    compound: ValueCompound<'a>,
+}
+
+/// The error type returned by [`MessageSerializer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct SerializeError {
+    path: String,
+    message: String,
+}
+
+/// A [`Serializer`] for the value of a field.
+///
+/// If the serialized value is `None`, the field should be left unset.
+struct ValueSerializer<'a> {
+    target: Target,
+    options: &'a DeserializeOptions,
+}
+
+/// The type of value expected by a [`ValueSerializer`].
+#[derive(Debug, Clone)]
+enum Target {
+    Singular(Kind),
+    List(Kind),
+    Map(Kind, Kind),
+}
+
+/// The kinds of compound or binary value which may populate a field of a well-known type instead of
+/// the type itself.
+#[derive(Debug, Clone, Copy)]
+enum Category {
+    Bytes,
+    Seq,
+    Map,
+}
+
+/// A step in wrapping a value into its parent, for well-known types and enum variants.
+#[derive(Debug, Clone)]
+enum Wrap {
+    Field(FieldDescriptor),
+    Key(MapKey),
+}
+
+#[derive(Debug)]
+struct ValueCompound<'a> {
+    state: State,
+    wrap: Vec<Wrap>,
+    variant: Option<&'static str>,
+    options: &'a DeserializeOptions,
+}
+
+#[derive(Debug)]
+enum State {
+    List {
+        kind: Kind,
+        values: Vec<Value>,
+    },
+    Bytes(Vec<u8>),
+    Map {
+        key_kind: Kind,
+        value_kind: Kind,
+        entries: HashMap<MapKey, Value>,
+        key: Option<MapKey>,
+    },
+    Message {
+        message: DynamicMessage,
+        key: Option<String>,
+    },
+    Any {
+        desc: MessageDescriptor,
+        entries: BTreeMap<serde_value::Value, serde_value::Value>,
+        key: Option<serde_value::Value>,
+    },
+}
+
+/// A [`Serializer`] for map keys and field names, which converts them to strings.
+struct KeySerializer;
+
+impl DynamicMessage {
+    /// Creates a message of the type described by `desc` from any value implementing [`Serialize`].
+    ///
+    /// See [`MessageSerializer`] for details of how values are converted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value does not match the message type. The error includes the path to the
+    /// value which could not be converted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let value = HashMap::from([("foo", 150)]);
+    /// let dynamic_message = DynamicMessage::from_serialize(message_descriptor.clone(), &value).unwrap();
+    /// assert_eq!(dynamic_message.get_field_by_name("foo").unwrap().as_ref(), &Value::I32(150));
+    ///
+    /// let value = HashMap::from([("foo", "bar")]);
+    /// let err = DynamicMessage::from_serialize(message_descriptor, &value).unwrap_err();
+    /// assert_eq!(err.path(), "foo");
+    /// ```
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn from_serialize<T>(desc: MessageDescriptor, value: &T) -> Result<Self, SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(MessageSerializer::new(desc))
+    }
+}
+
+impl MessageSerializer<'static> {
+    /// Creates a new [`MessageSerializer`] which builds messages of the type described by `desc`.
+    pub fn new(desc: MessageDescriptor) -> Self {
+        MessageSerializer {
+            desc,
+            options: &DEFAULT_OPTIONS,
+        }
+    }
+}
+
+impl<'a> MessageSerializer<'a> {
+    /// Creates a new [`MessageSerializer`] which builds messages of the type described by `desc`, using the
+    /// given options.
+    ///
+    /// The [`deny_unknown_fields`](DeserializeOptions::deny_unknown_fields) option determines whether
+    /// struct fields and map entries which do not match a message field are an error or are ignored, and
+    /// the [`type_resolver`](DeserializeOptions::type_resolver) is used to find the types of
+    /// `google.protobuf.Any` values.
+    pub fn with_options(desc: MessageDescriptor, options: &'a DeserializeOptions) -> Self {
+        MessageSerializer { desc, options }
+    }
+
+    fn value_serializer(&self) -> ValueSerializer<'a> {
+        ValueSerializer::new(
+            Target::Singular(Kind::Message(self.desc.clone())),
+            self.options,
+        )
+    }
+
+    fn compound(&self, compound: ValueCompound<'a>) -> Compound<'a> {
+        Compound {
+            desc: self.desc.clone(),
+            compound,
+        }
+    }
+}
+
+/// Converts the result of serializing a message value, which is `None` if no fields were set.
+fn into_message(desc: &MessageDescriptor, value: Option<Value>) -> DynamicMessage {
+    match value {
+        Some(Value::Message(message)) => message,
+        None => DynamicMessage::new(desc.clone()),
+        Some(value) => panic!(
+            "mismatch between serialized value {:?} and type {}",
+            value,
+            desc.full_name()
+        ),
+    }
+}
+
+impl SerializeError {
+    fn new(message: impl fmt::Display) -> Self {
+        SerializeError {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The path to the value which could not be converted, such as `items[3].name`, or an empty string if
+    /// the error applies to the whole message.
+    ///
+    /// The path uses the names of struct fields and the keys of maps as they were serialized, which may be
+    /// either the JSON name or proto name of a field.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// A description of the error, without the path.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn at_field(self, name: &str) -> Self {
+        self.prepend(name.to_owned())
+    }
+
+    fn at_index(self, index: usize) -> Self {
+        self.prepend(index_path("", index))
+    }
+
+    fn at_key(self, key: &MapKey) -> Self {
+        self.prepend(key_path("", key))
+    }
+
+    fn in_variant(self, variant: Option<&str>) -> Self {
+        match variant {
+            Some(variant) => self.at_field(variant),
+            None => self,
+        }
+    }
+
+    fn prepend(mut self, segment: String) -> Self {
+        self.path = if self.path.is_empty() {
+            segment
+        } else if self.path.starts_with('[') {
+            segment + &self.path
+        } else {
+            join_path(&segment, &self.path)
+        };
+        self
+    }
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl Error for SerializeError {}
+
+impl ser::Error for SerializeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        SerializeError::new(msg)
+    }
+}
+
+impl From<ValueError> for SerializeError {
+    fn from(err: ValueError) -> Self {
+        SerializeError::new(err)
+    }
+}
+
+impl Target {
+    fn new(field_desc: &impl FieldDescriptorLike) -> Self {
+        let kind = field_desc.kind();
+        if field_desc.is_map() {
+            let entry_desc = kind
+                .as_message()
+                .expect("map field must have a message type");
+            Target::Map(
+                entry_desc.map_entry_key_field().kind(),
+                entry_desc.map_entry_value_field().kind(),
+            )
+        } else if field_desc.is_list() {
+            Target::List(kind)
+        } else {
+            Target::Singular(kind)
+        }
+    }
+
+    fn invalid_type(&self, unexpected: Unexpected) -> SerializeError {
+        SerializeError::new(format!("invalid type: {}, expected {}", unexpected, self))
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Singular(kind) => fmt_kind(f, kind),
+            Target::List(kind) => {
+                f.write_str("repeated ")?;
+                fmt_kind(f, kind)
+            }
+            Target::Map(key_kind, value_kind) => {
+                f.write_str("map<")?;
+                fmt_kind(f, key_kind)?;
+                f.write_str(", ")?;
+                fmt_kind(f, value_kind)?;
+                f.write_str(">")
+            }
+        }
+    }
+}
+
+fn fmt_kind(f: &mut fmt::Formatter<'_>, kind: &Kind) -> fmt::Result {
+    match kind {
+        Kind::Double => f.write_str("double"),
+        Kind::Float => f.write_str("float"),
+        Kind::Int32 => f.write_str("int32"),
+        Kind::Int64 => f.write_str("int64"),
+        Kind::Uint32 => f.write_str("uint32"),
+        Kind::Uint64 => f.write_str("uint64"),
+        Kind::Sint32 => f.write_str("sint32"),
+        Kind::Sint64 => f.write_str("sint64"),
+        Kind::Fixed32 => f.write_str("fixed32"),
+        Kind::Fixed64 => f.write_str("fixed64"),
+        Kind::Sfixed32 => f.write_str("sfixed32"),
+        Kind::Sfixed64 => f.write_str("sfixed64"),
+        Kind::Bool => f.write_str("bool"),
+        Kind::String => f.write_str("string"),
+        Kind::Bytes => f.write_str("bytes"),
+        Kind::Message(message_desc) => write!(f, "message '{}'", message_desc.full_name()),
+        Kind::Enum(enum_desc) => write!(f, "enum '{}'", enum_desc.full_name()),
+    }
+}
+
+/// Gets the field of a well-known type which is populated by a value of the given category, for example
+/// the `values` field of `google.protobuf.ListValue` for a sequence.
+fn well_known_type_field(desc: &MessageDescriptor, category: Category) -> Option<FieldDescriptor> {
+    let name = match (desc.full_name(), category) {
+        ("google.protobuf.BytesValue", Category::Bytes | Category::Seq) => "value",
+        ("google.protobuf.ListValue", Category::Seq) => "values",
+        ("google.protobuf.FieldMask", Category::Seq) => "paths",
+        ("google.protobuf.Struct", Category::Map) => "fields",
+        ("google.protobuf.Value", Category::Seq) => "list_value",
+        ("google.protobuf.Value", Category::Map) => "struct_value",
+        _ => return None,
+    };
+    desc.get_field_by_name(name)
+}
+
+/// Finds the field of a message with the given JSON name or proto name.
+fn find_field(desc: &MessageDescriptor, name: &str) -> Option<FieldDescriptor> {
+    desc.get_field_by_json_name(name)
+        .or_else(|| desc.get_field_by_name(name))
+        .or_else(|| {
+            desc.fields()
+                .find(|field_desc| time_field_aliases(field_desc).contains(&name))
+        })
+}
+
+/// Applies `wrap` to a value, innermost step first.
+fn wrap_value(value: Option<Value>, wrap: &[Wrap]) -> Option<Value> {
+    let mut value = value?;
+    for step in wrap.iter().rev() {
+        value = match step {
+            Wrap::Field(field_desc) => {
+                let mut message = DynamicMessage::new(field_desc.parent_message().clone());
+                message.set_field(field_desc, value);
+                Value::Message(message)
+            }
+            Wrap::Key(key) => Value::Map(HashMap::from([(key.clone(), value)])),
+        };
+    }
+    Some(value)
+}
+
+/// Checks that `google.protobuf.Timestamp` and `google.protobuf.Duration` values built from their fields
+/// are within the allowed range.
+fn check_message(message: &DynamicMessage) -> Result<(), SerializeError> {
+    match message.desc.full_name() {
+        "google.protobuf.Timestamp" => {
+            let timestamp: prost_types::Timestamp =
+                message.transcode_to().map_err(SerializeError::new)?;
+            check_timestamp(&timestamp).map_err(SerializeError::new)
+        }
+        "google.protobuf.Duration" => {
+            let duration: prost_types::Duration =
+                message.transcode_to().map_err(SerializeError::new)?;
+            check_duration(&duration).map_err(SerializeError::new)
+        }
+        _ => Ok(()),
+    }
+}
+
+impl<'a> ValueSerializer<'a> {
+    fn new(target: Target, options: &'a DeserializeOptions) -> Self {
+        ValueSerializer { target, options }
+    }
+
+    /// Descends into the field of a well-known type populated by values of the given category.
+    fn resolve(mut self, category: Category) -> (Self, Vec<Wrap>) {
+        let mut wrap = Vec::new();
+        while let Target::Singular(Kind::Message(message_desc)) = &self.target {
+            match well_known_type_field(message_desc, category) {
+                Some(field_desc) => {
+                    self.target = Target::new(&field_desc);
+                    wrap.push(Wrap::Field(field_desc));
+                }
+                None => break,
+            }
+        }
+        (self, wrap)
+    }
+
+    /// Gets the serializer for the contents of an enum variant with data, which is treated as a map with a
+    /// single entry.
+    fn variant(
+        self,
+        variant: &'static str,
+        unexpected: Unexpected,
+    ) -> Result<(Self, Vec<Wrap>), SerializeError> {
+        let (serializer, mut wrap) = self.resolve(Category::Map);
+        let target = match &serializer.target {
+            Target::Singular(Kind::Message(message_desc)) => {
+                let field_desc = find_field(message_desc, variant).ok_or_else(|| {
+                    SerializeError::new(format!("unrecognized field name '{}'", variant))
+                })?;
+                let target = Target::new(&field_desc);
+                wrap.push(Wrap::Field(field_desc));
+                target
+            }
+            Target::Map(key_kind, value_kind) => {
+                wrap.push(Wrap::Key(parse_map_key::<ValueError>(
+                    key_kind,
+                    Cow::Borrowed(variant),
+                )?));
+                Target::Singular(value_kind.clone())
+            }
+            target => return Err(target.invalid_type(unexpected)),
+        };
+        Ok((ValueSerializer::new(target, serializer.options), wrap))
+    }
+
+    /// Converts a primitive value using the rules for deserializing JSON.
+    fn serialize_primitive<'de, D>(
+        self,
+        deserializer: D,
+        unexpected: Unexpected,
+    ) -> Result<Option<Value>, SerializeError>
+    where
+        D: Deserializer<'de, Error = ValueError>,
+    {
+        match &self.target {
+            Target::Singular(kind) => {
+                let track = Track::default();
+                let cx = Context {
+                    options: self.options,
+                    track: &track,
+                };
+                Ok(Some(KindSeed(kind, cx).deserialize(deserializer)?))
+            }
+            target => Err(target.invalid_type(unexpected)),
+        }
+    }
+
+    fn compound(
+        self,
+        category: Category,
+        unexpected: Unexpected,
+    ) -> Result<ValueCompound<'a>, SerializeError> {
+        let (serializer, wrap) = self.resolve(category);
+        let state = match (serializer.target, category) {
+            (Target::List(kind), Category::Seq) => State::List {
+                kind,
+                values: Vec::new(),
+            },
+            (Target::Singular(Kind::Bytes), Category::Seq) => State::Bytes(Vec::new()),
+            (Target::Map(key_kind, value_kind), Category::Map) => State::Map {
+                key_kind,
+                value_kind,
+                entries: HashMap::new(),
+                key: None,
+            },
+            (Target::Singular(Kind::Message(desc)), Category::Map)
+                if desc.full_name() == "google.protobuf.Any" =>
+            {
+                State::Any {
+                    desc,
+                    entries: BTreeMap::new(),
+                    key: None,
+                }
+            }
+            (Target::Singular(Kind::Message(desc)), Category::Map) => State::Message {
+                message: DynamicMessage::new(desc),
+                key: None,
+            },
+            (target, _) => return Err(target.invalid_type(unexpected)),
+        };
+
+        Ok(ValueCompound {
+            state,
+            wrap,
+            variant: None,
+            options: serializer.options,
+        })
+    }
+
+    fn variant_compound(
+        self,
+        variant: &'static str,
+        category: Category,
+        unexpected: Unexpected,
+    ) -> Result<ValueCompound<'a>, SerializeError> {
+        let (serializer, wrap) = self.variant(variant, unexpected)?;
+        let mut compound = serializer
+            .compound(category, unexpected)
+            .map_err(|err| err.at_field(variant))?;
+        compound.wrap.splice(0..0, wrap);
+        compound.variant = Some(variant);
+        Ok(compound)
+    }
+}
+
+macro_rules! serialize_primitive {
+    ($($method:ident($ty:ty) => $unexpected:expr,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                #[allow(clippy::redundant_closure_call)]
+                let unexpected = ($unexpected)(v);
+                self.serialize_primitive(v.into_deserializer(), unexpected)
+            }
+        )*
+    };
+}
+
+impl<'a> Serializer for ValueSerializer<'a> {
+    type Ok = Option<Value>;
+    type Error = SerializeError;
+
+    type SerializeSeq = ValueCompound<'a>;
+    type SerializeTuple = ValueCompound<'a>;
+    type SerializeTupleStruct = ValueCompound<'a>;
+    type SerializeTupleVariant = ValueCompound<'a>;
+    type SerializeMap = ValueCompound<'a>;
+    type SerializeStruct = ValueCompound<'a>;
+    type SerializeStructVariant = ValueCompound<'a>;
+
+    serialize_primitive! {
+        serialize_bool(bool) => Unexpected::Bool,
+        serialize_i8(i8) => |v| Unexpected::Signed(i64::from(v)),
+        serialize_i16(i16) => |v| Unexpected::Signed(i64::from(v)),
+        serialize_i32(i32) => |v| Unexpected::Signed(i64::from(v)),
+        serialize_i64(i64) => Unexpected::Signed,
+        serialize_u8(u8) => |v| Unexpected::Unsigned(u64::from(v)),
+        serialize_u16(u16) => |v| Unexpected::Unsigned(u64::from(v)),
+        serialize_u32(u32) => |v| Unexpected::Unsigned(u64::from(v)),
+        serialize_u64(u64) => Unexpected::Unsigned,
+        serialize_f32(f32) => |v| Unexpected::Float(f64::from(v)),
+        serialize_f64(f64) => Unexpected::Float,
+        serialize_char(char) => Unexpected::Char,
+        serialize_str(&str) => Unexpected::Str,
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        if let Ok(v) = i64::try_from(v) {
+            self.serialize_i64(v)
+        } else if let Ok(v) = u64::try_from(v) {
+            self.serialize_u64(v)
+        } else {
+            Err(SerializeError::new(format!(
+                "integer {} is out of range",
+                v
+            )))
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        match u64::try_from(v) {
+            Ok(v) => self.serialize_u64(v),
+            Err(_) => Err(SerializeError::new(format!(
+                "integer {} is out of range",
+                v
+            ))),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let (serializer, wrap) = self.resolve(Category::Bytes);
+        match &serializer.target {
+            Target::Singular(Kind::Bytes) => {
+                Ok(wrap_value(Some(Value::Bytes(v.to_vec().into())), &wrap))
+            }
+            target => Err(target.invalid_type(Unexpected::Bytes(v))),
+        }
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        match &self.target {
+            Target::Singular(Kind::Message(message_desc))
+                if message_desc.full_name() == "google.protobuf.Value" =>
+            {
+                self.serialize_primitive(().into_deserializer(), Unexpected::Option)
+            }
+            Target::Singular(Kind::Enum(enum_desc))
+                if enum_desc.full_name() == "google.protobuf.NullValue" =>
+            {
+                self.serialize_primitive(().into_deserializer(), Unexpected::Option)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        match &self.target {
+            Target::Singular(Kind::Message(message_desc))
+                if message_desc.full_name() == "google.protobuf.Empty" =>
+            {
+                Ok(Some(Value::Message(DynamicMessage::new(
+                    message_desc.clone(),
+                ))))
+            }
+            _ => self.serialize_none(),
+        }
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_primitive(variant.into_deserializer(), Unexpected::UnitVariant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let (serializer, wrap) = self.variant(variant, Unexpected::NewtypeVariant)?;
+        let value = value
+            .serialize(serializer)
+            .map_err(|err| err.at_field(variant))?;
+        Ok(wrap_value(value, &wrap))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.compound(Category::Seq, Unexpected::Seq)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.compound(Category::Seq, Unexpected::Seq)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.compound(Category::Seq, Unexpected::Seq)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.variant_compound(variant, Category::Seq, Unexpected::TupleVariant)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.compound(Category::Map, Unexpected::Map)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.compound(Category::Map, Unexpected::Map)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        self.variant_compound(variant, Category::Map, Unexpected::StructVariant)
+    }
+}
+
+impl ValueCompound<'_> {
+    fn element<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let variant = self.variant;
+        self.push_element(value)
+            .map_err(|err| err.in_variant(variant))
+    }
+
+    fn key<T>(&mut self, key: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let variant = self.variant;
+        self.push_key(key).map_err(|err| err.in_variant(variant))
+    }
+
+    fn value<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let variant = self.variant;
+        self.push_value(value)
+            .map_err(|err| err.in_variant(variant))
+    }
+
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let variant = self.variant;
+        let result = match &mut self.state {
+            State::Message { message, .. } => set_message_field(message, key, value, self.options),
+            _ => self.push_key(key).and_then(|()| self.push_value(value)),
+        };
+        result.map_err(|err| err.in_variant(variant))
+    }
+
+    fn push_element<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match &mut self.state {
+            State::List { kind, values } => {
+                let index = values.len();
+                let target = Target::Singular(kind.clone());
+                match value.serialize(ValueSerializer::new(target.clone(), self.options)) {
+                    Ok(Some(value)) => {
+                        values.push(value);
+                        Ok(())
+                    }
+                    Ok(None) => Err(target.invalid_type(Unexpected::Option).at_index(index)),
+                    Err(err) => Err(err.at_index(index)),
+                }
+            }
+            State::Bytes(bytes) => {
+                let index = bytes.len();
+                let byte = match value.serialize(ValueSerializer::new(
+                    Target::Singular(Kind::Uint32),
+                    self.options,
+                )) {
+                    Ok(Some(Value::U32(byte))) => u8::try_from(byte).map_err(|_| {
+                        SerializeError::new(format!("value {} is out of range for a byte", byte))
+                    }),
+                    Ok(_) => Err(SerializeError::new("expected a byte")),
+                    Err(err) => Err(err),
+                };
+                bytes.push(byte.map_err(|err| err.at_index(index))?);
+                Ok(())
+            }
+            _ => unreachable!("sequence element serialized for a map"),
+        }
+    }
+
+    fn push_key<T>(&mut self, key: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match &mut self.state {
+            State::Map {
+                key_kind,
+                key: pending,
+                ..
+            } => {
+                let key = key.serialize(KeySerializer)?;
+                *pending = Some(parse_map_key::<ValueError>(key_kind, Cow::Owned(key))?);
+            }
+            State::Message { key: pending, .. } => {
+                *pending = Some(key.serialize(KeySerializer)?);
+            }
+            State::Any { key: pending, .. } => {
+                *pending = Some(serde_value::to_value(key).map_err(SerializeError::new)?);
+            }
+            _ => unreachable!("map key serialized for a sequence"),
+        }
+        Ok(())
+    }
+
+    fn push_value<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match &mut self.state {
+            State::Map {
+                value_kind,
+                entries,
+                key,
+                ..
+            } => {
+                let key = key
+                    .take()
+                    .expect("serialize_value called before serialize_key");
+                let target = Target::Singular(value_kind.clone());
+                match value.serialize(ValueSerializer::new(target.clone(), self.options)) {
+                    Ok(Some(value)) => {
+                        entries.insert(key, value);
+                        Ok(())
+                    }
+                    Ok(None) => Err(target.invalid_type(Unexpected::Option).at_key(&key)),
+                    Err(err) => Err(err.at_key(&key)),
+                }
+            }
+            State::Message { message, key } => {
+                let key = key
+                    .take()
+                    .expect("serialize_value called before serialize_key");
+                set_message_field(message, &key, value, self.options)
+            }
+            State::Any { entries, key, .. } => {
+                let key = key
+                    .take()
+                    .expect("serialize_value called before serialize_key");
+                entries.insert(
+                    key,
+                    serde_value::to_value(value).map_err(SerializeError::new)?,
+                );
+                Ok(())
+            }
+            _ => unreachable!("map value serialized for a sequence"),
+        }
+    }
+
+    fn finish(self) -> Result<Option<Value>, SerializeError> {
+        let variant = self.variant;
+        self.finish_inner().map_err(|err| err.in_variant(variant))
+    }
+
+    fn finish_inner(self) -> Result<Option<Value>, SerializeError> {
+        let value = match self.state {
+            State::List { values, .. } => Value::List(values),
+            State::Bytes(bytes) => Value::Bytes(bytes.into()),
+            State::Map { entries, .. } => Value::Map(entries),
+            State::Message { message, .. } => {
+                check_message(&message)?;
+                Value::Message(message)
+            }
+            State::Any { desc, entries, .. } => Value::Message(
+                deserialize_with_path(&desc, serde_value::Value::Map(entries), self.options)
+                    .map_err(SerializeError::new)?,
+            ),
+        };
+        Ok(wrap_value(Some(value), &self.wrap))
+    }
+}
+
+/// Sets the field of `message` named `name` to `value`.
+fn set_message_field<T>(
+    message: &mut DynamicMessage,
+    name: &str,
+    value: &T,
+    options: &DeserializeOptions,
+) -> Result<(), SerializeError>
+where
+    T: Serialize + ?Sized,
+{
+    let desc = message.desc.clone();
+    if let Some(field_desc) = find_field(&desc, name) {
+        let value = value
+            .serialize(ValueSerializer::new(Target::new(&field_desc), options))
+            .map_err(|err| err.at_field(name))?;
+        if let Some(value) = value {
+            if let Some(oneof_desc) = field_desc.containing_oneof() {
+                if oneof_desc
+                    .fields()
+                    .any(|oneof_field| message.has_field(&oneof_field))
+                {
+                    return Err(SerializeError::new(format!(
+                        "multiple fields provided for oneof '{}'",
+                        oneof_desc.name()
+                    )));
+                }
+            }
+            message.set_field(&field_desc, value);
+        }
+    } else if let Some(extension_desc) = desc.get_extension_by_json_name(name) {
+        let value = value
+            .serialize(ValueSerializer::new(Target::new(&extension_desc), options))
+            .map_err(|err| err.at_field(name))?;
+        if let Some(value) = value {
+            message.set_extension(&extension_desc, value);
+        }
+    } else if options.deny_unknown_fields {
+        return Err(SerializeError::new(format!(
+            "unrecognized field name '{}'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Implements the compound serializer traits for `$ty`, in terms of the `element`, `key`, `value`, `field`
+/// and `finish` methods.
+macro_rules! impl_compound {
+    ($ty:ty, $ok:ty, $finish:path) => {
+        impl SerializeSeq for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.element(value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeTuple for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.element(value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeTupleStruct for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.element(value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeTupleVariant for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.element(value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeMap for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.key(key)
+            }
+
+            fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.value(value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeStruct for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_field<T>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.field(key, value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+
+        impl SerializeStructVariant for $ty {
+            type Ok = $ok;
+            type Error = SerializeError;
+
+            fn serialize_field<T>(
+                &mut self,
+                key: &'static str,
+                value: &T,
+            ) -> Result<(), Self::Error>
+            where
+                T: Serialize + ?Sized,
+            {
+                self.field(key, value)
+            }
+
+            fn end(self) -> Result<Self::Ok, Self::Error> {
+                $finish(self)
+            }
+        }
+    };
+}
+
+impl_compound!(ValueCompound<'_>, Option<Value>, ValueCompound::finish);
+impl_compound!(Compound<'_>, DynamicMessage, Compound::finish);
+
+impl Compound<'_> {
+    fn element<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.element(value)
+    }
+
+    fn key<T>(&mut self, key: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.key(key)
+    }
+
+    fn value<T>(&mut self, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.value(value)
+    }
+
+    fn field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerializeError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.field(key, value)
+    }
+
+    fn finish(self) -> Result<DynamicMessage, SerializeError> {
+        let value = self.compound.finish()?;
+        Ok(into_message(&self.desc, value))
+    }
+}
+
+/// Implements the methods of [`Serializer`] for [`MessageSerializer`] by converting the result of the
+/// corresponding method of [`ValueSerializer`].
+macro_rules! forward_to_value_serializer {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<Self::Ok, Self::Error> {
+                let value = self.value_serializer().$method($($arg),*)?;
+                Ok(into_message(&self.desc, value))
+            }
+        )*
+    };
+}
+
+impl<'a> Serializer for MessageSerializer<'a> {
+    type Ok = DynamicMessage;
+    type Error = SerializeError;
+
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    forward_to_value_serializer! {
+        serialize_bool(v: bool),
+        serialize_i8(v: i8),
+        serialize_i16(v: i16),
+        serialize_i32(v: i32),
+        serialize_i64(v: i64),
+        serialize_i128(v: i128),
+        serialize_u8(v: u8),
+        serialize_u16(v: u16),
+        serialize_u32(v: u32),
+        serialize_u64(v: u64),
+        serialize_u128(v: u128),
+        serialize_f32(v: f32),
+        serialize_f64(v: f64),
+        serialize_char(v: char),
+        serialize_str(v: &str),
+        serialize_bytes(v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(name: &'static str),
+        serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str),
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = self.value_serializer().serialize_newtype_variant(
+            name,
+            variant_index,
+            variant,
+            value,
+        )?;
+        Ok(into_message(&self.desc, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        let compound = self.value_serializer().serialize_seq(len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        let compound = self.value_serializer().serialize_tuple(len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        let compound = self.value_serializer().serialize_tuple_struct(name, len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let compound =
+            self.value_serializer()
+                .serialize_tuple_variant(name, variant_index, variant, len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let compound = self.value_serializer().serialize_map(len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        let compound = self.value_serializer().serialize_struct(name, len)?;
+        Ok(self.compound(compound))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let compound =
+            self.value_serializer()
+                .serialize_struct_variant(name, variant_index, variant, len)?;
+        Ok(self.compound(compound))
+    }
+}
+
+/// Implements the methods of [`Serializer`] for [`KeySerializer`] which convert a value to a string.
+macro_rules! serialize_key_to_string {
+    ($($method:ident($ty:ty),)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+impl Serializer for KeySerializer {
+    type Ok = String;
+    type Error = SerializeError;
+
+    type SerializeSeq = Impossible<String, SerializeError>;
+    type SerializeTuple = Impossible<String, SerializeError>;
+    type SerializeTupleStruct = Impossible<String, SerializeError>;
+    type SerializeTupleVariant = Impossible<String, SerializeError>;
+    type SerializeMap = Impossible<String, SerializeError>;
+    type SerializeStruct = Impossible<String, SerializeError>;
+    type SerializeStructVariant = Impossible<String, SerializeError>;
+
+    serialize_key_to_string! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_char(char),
+        serialize_str(&str),
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(key_error())
+    }
+}
+
+fn key_error() -> SerializeError {
+    SerializeError::new("map key must be a string, integer or boolean")
+}
@@ -1,12 +1,11 @@
 use std::fmt::{self, Write};
 
-use prost::Message;
-
 use crate::{
-    descriptor::{GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN},
+    any::resolve_type_url,
     dynamic::{
-        fields::ValueAndDescriptor,
+        fields::{FieldDescriptorLike, ValueAndDescriptor},
         fmt_string,
+        raw::{RawField, RawMessage, RawValue},
         text_format::FormatOptions,
         unknown::{UnknownField, UnknownFieldSet, UnknownFieldValue},
     },
@@ -15,8 +14,16 @@ use crate::{
 
 pub(in crate::dynamic) struct Writer<'a, W> {
     options: FormatOptions,
-    f: &'a mut W,
+    f: LimitedWriter<'a, W>,
     indent_level: u32,
+    depth: usize,
+}
+
+/// Wraps the output of a [`Writer`], cutting it off once `max_output_len` bytes have been written.
+struct LimitedWriter<'a, W> {
+    inner: &'a mut W,
+    remaining: usize,
+    truncated: bool,
 }
 
 impl<'a, W> Writer<'a, W>
@@ -24,16 +31,22 @@ where
     W: Write,
 {
     pub fn new(options: FormatOptions, f: &'a mut W) -> Self {
+        let remaining = options.max_output_len;
         Writer {
             options,
-            f,
+            f: LimitedWriter {
+                inner: f,
+                remaining,
+                truncated: false,
+            },
             indent_level: 0,
+            depth: 0,
         }
     }
 
     pub fn fmt_message(&mut self, message: &DynamicMessage) -> fmt::Result {
         if self.options.expand_any {
-            if let Some((type_url, body)) = as_any(message) {
+            if let Some((type_url, body)) = as_any(message, &self.options) {
                 self.f.write_char('[')?;
                 self.f.write_str(&type_url)?;
                 self.f.write_str("]")?;
@@ -89,6 +102,7 @@ where
                 }
                 write!(self.f, "{}", value)
             }
+            Value::Message(_) if self.depth >= self.options.max_depth => self.f.write_str("{...}"),
             Value::Message(message) => {
                 let mut fields = message.fields.iter(
                     &message.desc,
@@ -104,13 +118,13 @@ where
                     self.f.write_char('{')?;
                     self.indent_level += 2;
                     self.fmt_newline()?;
-                    self.fmt_message(message)?;
+                    self.fmt_nested_message(message)?;
                     self.indent_level -= 2;
                     self.fmt_newline()?;
                     self.f.write_char('}')
                 } else {
                     self.f.write_char('{')?;
-                    self.fmt_message(message)?;
+                    self.fmt_nested_message(message)?;
                     self.f.write_char('}')
                 }
             }
@@ -146,6 +160,13 @@ where
         }
     }
 
+    fn fmt_nested_message(&mut self, message: &DynamicMessage) -> fmt::Result {
+        self.depth += 1;
+        self.fmt_message(message)?;
+        self.depth -= 1;
+        Ok(())
+    }
+
     fn fmt_map_key(&mut self, value: &MapKey) -> fmt::Result {
         match value {
             MapKey::Bool(value) => write!(self.f, "{}", value),
@@ -165,10 +186,16 @@ where
                 } else {
                     write!(self.f, "{}", desc.name())?;
                 }
+                if self.is_redacted(&desc) {
+                    return self.fmt_redacted();
+                }
                 self.fmt_field_value(&value, Some(&desc.kind()))
             }
             ValueAndDescriptor::Extension(value, desc) => {
                 write!(self.f, "[{}]", desc.full_name())?;
+                if self.is_redacted(&desc) {
+                    return self.fmt_redacted();
+                }
                 self.fmt_field_value(&value, Some(&desc.kind()))
             }
             ValueAndDescriptor::Unknown(values) => {
@@ -177,6 +204,17 @@ where
         }
     }
 
+    fn is_redacted(&self, desc: &impl FieldDescriptorLike) -> bool {
+        matches!(&self.options.redact, Some(redact) if redact.is_redacted(desc))
+    }
+
+    fn fmt_redacted(&mut self) -> fmt::Result {
+        self.f.write_char(':')?;
+        self.fmt_padding()?;
+        let placeholder = self.options.redact.as_ref().unwrap().placeholder_str();
+        self.f.write_str(placeholder)
+    }
+
     fn fmt_field_value(&mut self, value: &Value, kind: Option<&Kind>) -> fmt::Result {
         if !matches!(value, Value::Message(_)) {
             self.f.write_char(':')?;
@@ -205,7 +243,7 @@ where
             }
             UnknownFieldValue::LengthDelimited(bytes) => {
                 if !bytes.is_empty() {
-                    if let Ok(set) = UnknownFieldSet::decode(bytes.clone()) {
+                    if let Some(set) = field.value().decode_message() {
                         self.fmt_padding()?;
                         return self.fmt_unknown_field_set(&set);
                     }
@@ -240,8 +278,56 @@ where
         }
     }
 
+    pub fn fmt_raw_message(&mut self, message: &RawMessage) -> fmt::Result {
+        self.fmt_delimited(message.fields().iter(), Writer::fmt_raw_field)
+    }
+
+    pub fn fmt_raw_field(&mut self, field: &RawField) -> fmt::Result {
+        write!(self.f, "{}", field.number())?;
+        match field.value() {
+            RawValue::Message(message) | RawValue::Group(message) => {
+                self.fmt_padding()?;
+                if message.fields().is_empty() {
+                    self.f.write_str("{}")
+                } else if self.options.pretty {
+                    self.f.write_char('{')?;
+                    self.indent_level += 2;
+                    self.fmt_newline()?;
+                    self.fmt_raw_message(message)?;
+                    self.indent_level -= 2;
+                    self.fmt_newline()?;
+                    self.f.write_char('}')
+                } else {
+                    self.f.write_char('{')?;
+                    self.fmt_raw_message(message)?;
+                    self.f.write_char('}')
+                }
+            }
+            value => {
+                self.f.write_char(':')?;
+                self.fmt_padding()?;
+                match value {
+                    RawValue::Varint(int) => write!(self.f, "{}", int),
+                    RawValue::Fixed32(int) => write!(self.f, "0x{:08x}", int),
+                    RawValue::Fixed64(int) => write!(self.f, "0x{:016x}", int),
+                    RawValue::String(string) => self.fmt_string(string.as_bytes()),
+                    RawValue::Bytes(bytes) => self.fmt_string(bytes),
+                    RawValue::Packed(values) => {
+                        self.fmt_list(values.iter(), |this, int| write!(this.f, "{}", int))
+                    }
+                    RawValue::Message(_) | RawValue::Group(_) => unreachable!(),
+                }
+            }
+        }
+    }
+
     fn fmt_string(&mut self, bytes: &[u8]) -> fmt::Result {
-        fmt_string(&mut self.f, bytes)
+        if bytes.len() > self.options.max_string_len {
+            fmt_string(&mut self.f, &bytes[..self.options.max_string_len])?;
+            write!(self.f, "...({} bytes)", bytes.len())
+        } else {
+            fmt_string(&mut self.f, bytes)
+        }
     }
 
     fn fmt_delimited<T>(
@@ -253,6 +339,9 @@ where
             f(self, first)?;
         }
         for item in iter {
+            if self.f.truncated {
+                break;
+            }
             if self.options.pretty {
                 self.fmt_newline()?;
             } else {
@@ -270,13 +359,30 @@ where
         f: impl Fn(&mut Self, I) -> fmt::Result,
     ) -> fmt::Result {
         self.f.write_char('[')?;
-        if let Some(first) = iter.next() {
-            f(self, first)?;
-        }
-        for item in iter {
-            self.f.write_char(',')?;
-            self.fmt_padding()?;
+        let mut count = 0;
+        let mut omitted = 0;
+        for item in iter.by_ref() {
+            if self.f.truncated {
+                return Ok(());
+            }
+            if count == self.options.max_collection_len {
+                omitted = 1;
+                break;
+            }
+            if count != 0 {
+                self.f.write_char(',')?;
+                self.fmt_padding()?;
+            }
             f(self, item)?;
+            count += 1;
+        }
+        if omitted != 0 {
+            omitted += iter.count();
+            if count != 0 {
+                self.f.write_char(',')?;
+                self.fmt_padding()?;
+            }
+            write!(self.f, "...({} more)", omitted)?;
         }
         self.f.write_char(']')
     }
@@ -297,21 +403,43 @@ where
     }
 }
 
-fn as_any(message: &DynamicMessage) -> Option<(String, DynamicMessage)> {
+impl<W> Write for LimitedWriter<'_, W>
+where
+    W: Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.truncated {
+            return Ok(());
+        }
+
+        if s.len() <= self.remaining {
+            self.remaining -= s.len();
+            return self.inner.write_str(s);
+        }
+
+        let mut end = self.remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.inner.write_str(&s[..end])?;
+        self.inner.write_str("...(truncated)")?;
+        self.remaining = 0;
+        self.truncated = true;
+        Ok(())
+    }
+}
+
+fn as_any(message: &DynamicMessage, options: &FormatOptions) -> Option<(String, DynamicMessage)> {
     if message.desc.full_name() != "google.protobuf.Any" {
         return None;
     }
 
     let any = message.transcode_to::<prost_types::Any>().ok()?;
-    let message_name = any
-        .type_url
-        .strip_prefix(GOOGLE_APIS_DOMAIN)
-        .or_else(|| any.type_url.strip_prefix(GOOGLE_PROD_DOMAIN))?;
-
-    let desc = message
-        .desc
-        .parent_pool()
-        .get_message_by_name(message_name)?;
+    let desc = resolve_type_url(
+        options.type_resolver.as_ref(),
+        message.desc.parent_pool(),
+        &any.type_url,
+    )?;
     let body = DynamicMessage::decode(desc, any.value.as_slice()).ok()?;
     Some((any.type_url, body))
 }
@@ -319,6 +447,8 @@ fn as_any(message: &DynamicMessage) -> Option<(String, DynamicMessage)> {
 #[cfg(test)]
 #[cfg(feature = "text-format")]
 mod tests {
+    use prost::Message;
+
     use super::*;
     use crate::ReflectMessage;
 
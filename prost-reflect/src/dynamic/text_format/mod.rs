@@ -4,8 +4,9 @@ mod parse;
 
 #[cfg(feature = "text-format")]
 pub use self::parse::ParseError;
+use crate::{any::SharedTypeResolver, RedactOptions};
 #[cfg(feature = "text-format")]
-use crate::{DynamicMessage, MessageDescriptor};
+use crate::{any::TypeResolver, DynamicMessage, MessageDescriptor};
 
 pub(super) use self::format::Writer;
 
@@ -20,6 +21,22 @@ pub struct FormatOptions {
     expand_any: bool,
     skip_default_fields: bool,
     print_message_fields_in_index_order: bool,
+    redact: Option<RedactOptions>,
+    max_string_len: usize,
+    max_collection_len: usize,
+    max_depth: usize,
+    max_output_len: usize,
+    type_resolver: Option<SharedTypeResolver>,
+}
+
+/// Options to control parsing of the protobuf text format.
+///
+/// Used by [`DynamicMessage::parse_text_format_with_options()`].
+#[derive(Debug, Clone, Default)]
+#[cfg(feature = "text-format")]
+#[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+pub struct ParseOptions {
+    type_resolver: Option<SharedTypeResolver>,
 }
 
 #[cfg(feature = "text-format")]
@@ -58,7 +75,33 @@ impl DynamicMessage {
     /// ```
     #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
     pub fn merge_text_format(&mut self, input: &str) -> Result<(), ParseError> {
-        parse::Parser::new(input)
+        self.merge_text_format_with_options(input, &ParseOptions::new())
+    }
+
+    /// Parse a [`DynamicMessage`] from the given message encoded using the [text format](https://developers.google.com/protocol-buffers/docs/text-format-spec),
+    /// with custom options.
+    ///
+    /// See [`ParseOptions::type_resolver()`] for an example.
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn parse_text_format_with_options(
+        desc: MessageDescriptor,
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let mut message = DynamicMessage::new(desc);
+        message.merge_text_format_with_options(input, options)?;
+        Ok(message)
+    }
+
+    /// Merges the given message encoded using the [text format](https://developers.google.com/protocol-buffers/docs/text-format-spec) into this message,
+    /// with custom options.
+    #[cfg_attr(docsrs, doc(cfg(feature = "text-format")))]
+    pub fn merge_text_format_with_options(
+        &mut self,
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<(), ParseError> {
+        parse::Parser::new(input, options)
             .parse_message(self)
             .map_err(|kind| ParseError::new(kind, input))
     }
@@ -211,6 +254,157 @@ impl FormatOptions {
         self.expand_any = yes;
         self
     }
+
+    /// Replaces the values of sensitive fields with a placeholder, as determined by `options`.
+    ///
+    /// By default, no fields are redacted. The [`Display`](std::fmt::Display) implementation of
+    /// [`DynamicMessage`](crate::DynamicMessage) redacts fields using the default [`RedactOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, RedactOptions, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// let options = FormatOptions::new().redact(RedactOptions::new().placeholder("***"));
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo:150");
+    /// ```
+    pub fn redact(mut self, options: RedactOptions) -> Self {
+        self.redact = Some(options);
+        self
+    }
+
+    /// The maximum number of bytes of a `string` or `bytes` value to print.
+    ///
+    /// Longer values are cut off, and followed by the length of the original value, for example
+    /// `"abc"...(10 bytes)`. The output is then no longer valid text format.
+    ///
+    /// The default value is `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, Value, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let mut dynamic_message = DynamicMessage::new(message_descriptor);
+    /// dynamic_message.set_field_by_name("type_url", Value::String("type.googleapis.com/package.MyMessage".to_owned()));
+    /// let options = FormatOptions::new().expand_any(false).max_string_len(8);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), r#"type_url:"type.goo"...(37 bytes)"#);
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    /// The maximum number of elements of a repeated or map field to print.
+    ///
+    /// Further elements are replaced by a count of the elements omitted, for example `[1,2,...(3 more)]`.
+    ///
+    /// The default value is `usize::MAX`.
+    #[cfg(feature = "text-format")]
+    pub fn max_collection_len(mut self, len: usize) -> Self {
+        self.max_collection_len = len;
+        self
+    }
+
+    /// The maximum depth of nested messages to print.
+    ///
+    /// Messages nested more deeply are printed as `{...}`. A depth of `0` prints only the fields of the top-level
+    /// message.
+    ///
+    /// The default value is `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let options = FormatOptions::new().max_depth(0);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo:150,nested{...}");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// The maximum number of bytes to print in total.
+    ///
+    /// If the output would be longer, it is cut off at this length and followed by `...(truncated)`.
+    ///
+    /// The default value is `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, text_format::FormatOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let options = FormatOptions::new().max_output_len(10);
+    /// assert_eq!(dynamic_message.to_text_format_with_options(&options), "foo:150,ne...(truncated)");
+    /// ```
+    #[cfg(feature = "text-format")]
+    pub fn max_output_len(mut self, len: usize) -> Self {
+        self.max_output_len = len;
+        self
+    }
+
+    /// The resolver used to find the message types of `google.protobuf.Any` values when expanding them.
+    ///
+    /// By default, types are looked up in the descriptor pool of the `Any` message, and only the
+    /// `type.googleapis.com` and `type.googleprod.com` domains are supported. Values whose type cannot be
+    /// resolved are printed without expansion.
+    #[cfg(feature = "text-format")]
+    pub fn type_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        self.type_resolver = Some(SharedTypeResolver::new(resolver));
+        self
+    }
+}
+
+#[cfg(feature = "text-format")]
+impl ParseOptions {
+    /// Creates a new instance of [`ParseOptions`], with the default options.
+    pub const fn new() -> Self {
+        ParseOptions {
+            type_resolver: None,
+        }
+    }
+
+    /// The resolver used to find the message types of `google.protobuf.Any` values written using the
+    /// expanded `[type_url] { ... }` syntax.
+    ///
+    /// By default, types are looked up in the descriptor pool of the `Any` message, and only the
+    /// `type.googleapis.com` and `type.googleprod.com` domains are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DynamicMessage, DescriptorPool, text_format::ParseOptions};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../../file_descriptor_set.bin").as_ref()).unwrap();
+    /// let message_descriptor = pool.get_message_by_name("google.protobuf.Any").unwrap();
+    /// let input = "[example.com/package.MyMessage] { foo: 150 }";
+    /// assert!(DynamicMessage::parse_text_format(message_descriptor.clone(), input).is_err());
+    ///
+    /// let options = ParseOptions::new().type_resolver(pool.clone());
+    /// let any = DynamicMessage::parse_text_format_with_options(message_descriptor, input, &options).unwrap();
+    /// assert_eq!(any.unpack_any_with_resolver(&pool).unwrap().to_text_format(), "foo:150");
+    /// ```
+    pub fn type_resolver<R>(mut self, resolver: R) -> Self
+    where
+        R: TypeResolver + 'static,
+    {
+        self.type_resolver = Some(SharedTypeResolver::new(resolver));
+        self
+    }
 }
 
 impl Default for FormatOptions {
@@ -221,6 +415,12 @@ impl Default for FormatOptions {
             expand_any: true,
             skip_default_fields: true,
             print_message_fields_in_index_order: false,
+            redact: None,
+            max_string_len: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_depth: usize::MAX,
+            max_output_len: usize::MAX,
+            type_resolver: None,
         }
     }
 }
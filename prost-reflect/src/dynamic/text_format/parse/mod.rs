@@ -13,16 +13,18 @@ use self::{
     lex::{Int, Token},
 };
 use crate::{
+    any::resolve_type_url,
     descriptor::{
         GOOGLE_APIS_DOMAIN, GOOGLE_PROD_DOMAIN, MAP_ENTRY_KEY_NUMBER, MAP_ENTRY_VALUE_NUMBER,
     },
-    dynamic::fields::FieldDescriptorLike,
+    dynamic::{fields::FieldDescriptorLike, text_format::ParseOptions},
     DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor, Value,
 };
 
 pub(in crate::dynamic::text_format) struct Parser<'a> {
     lexer: Lexer<'a, Token<'a>>,
     peek: Option<Result<(Token<'a>, Span), ParseErrorKind>>,
+    options: &'a ParseOptions,
 }
 
 enum FieldName {
@@ -32,10 +34,11 @@ enum FieldName {
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(input: &'a str) -> Self {
+    pub fn new(input: &'a str, options: &'a ParseOptions) -> Self {
         Parser {
             lexer: Token::lexer(input),
             peek: None,
+            options,
         }
     }
 
@@ -104,14 +107,16 @@ impl<'a> Parser<'a> {
                 self.parse_field_value(message, &extension)?;
             }
             FieldName::Any(domain, message_name)
-                if domain == GOOGLE_APIS_DOMAIN.trim_end_matches('/')
+                if self.options.type_resolver.is_some()
+                    || domain == GOOGLE_APIS_DOMAIN.trim_end_matches('/')
                     || domain == GOOGLE_PROD_DOMAIN.trim_end_matches('/') =>
             {
-                let value_message = match message
-                    .desc
-                    .parent_pool()
-                    .get_message_by_name(&message_name)
-                {
+                let type_url = format!("{}/{}", domain, message_name);
+                let value_message = match resolve_type_url(
+                    self.options.type_resolver.as_ref(),
+                    message.desc.parent_pool(),
+                    &type_url,
+                ) {
                     Some(msg) => msg,
                     None => return Err(ParseErrorKind::MessageNotFound { message_name, span }),
                 };
@@ -119,7 +124,6 @@ impl<'a> Parser<'a> {
                 let mut value = DynamicMessage::new(value_message);
                 self.parse_message_value(&mut value)?;
 
-                let type_url = format!("{}/{}", domain, message_name);
                 let value = value.encode_to_vec();
 
                 if !(message.desc.full_name() == "google.protobuf.Any"
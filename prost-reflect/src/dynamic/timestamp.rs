@@ -0,0 +1,428 @@
+//! Conversions between the `google.protobuf.Timestamp` and `google.protobuf.Duration` well-known types and
+//! native time types.
+
+use std::{
+    convert::TryFrom,
+    error::Error,
+    fmt,
+    time::{self, SystemTime},
+};
+
+use crate::{
+    dynamic::wkt::{check_duration, check_timestamp},
+    DynamicMessage, Kind, MessageDescriptor, ReflectMessage, Value,
+};
+
+const TIMESTAMP: &str = "google.protobuf.Timestamp";
+const DURATION: &str = "google.protobuf.Duration";
+const NANOS_PER_SECOND: i32 = 1_000_000_000;
+
+/// An error returned when converting between a `google.protobuf.Timestamp` or `google.protobuf.Duration`
+/// message and a native time type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeError {
+    /// The message is not of the expected well-known type.
+    InvalidType {
+        /// The full name of the expected message type.
+        expected: &'static str,
+        /// The full name of the actual message type.
+        actual: String,
+    },
+    /// The value is outside the range of the target type.
+    ///
+    /// Timestamps must be between `0001-01-01T00:00:00Z` and `9999-12-31T23:59:59.999999999Z`, and durations
+    /// must be at most 10,000 years in either direction. Some native types have a smaller range, for example
+    /// [`std::time::Duration`] cannot be negative.
+    OutOfRange {
+        /// The full name of the well-known type.
+        message_name: &'static str,
+    },
+}
+
+impl DynamicMessage {
+    /// Returns the time represented by this message, if it is a valid `google.protobuf.Timestamp`.
+    ///
+    /// Returns `None` if this message is a different type, or if its value is out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::{Duration, UNIX_EPOCH};
+    /// # use prost_reflect::{ReflectMessage, Value};
+    /// let time = UNIX_EPOCH + Duration::from_millis(1_500);
+    /// let value = Value::try_from(time).unwrap();
+    /// assert_eq!(value.as_message().unwrap().as_timestamp(), Some(time));
+    /// ```
+    pub fn as_timestamp(&self) -> Option<SystemTime> {
+        SystemTime::try_from(self).ok()
+    }
+
+    /// Returns the duration represented by this message, if it is a valid non-negative
+    /// `google.protobuf.Duration`.
+    ///
+    /// Returns `None` if this message is a different type, or if its value is negative or out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use prost_reflect::{ReflectMessage, Value};
+    /// let duration = Duration::from_millis(1_500);
+    /// let value = Value::try_from(duration).unwrap();
+    /// assert_eq!(value.as_message().unwrap().as_duration(), Some(duration));
+    /// ```
+    pub fn as_duration(&self) -> Option<time::Duration> {
+        time::Duration::try_from(self).ok()
+    }
+}
+
+impl Value {
+    /// Creates a `google.protobuf.Timestamp` message of the type given by `kind`, such as the
+    /// [`kind()`](crate::FieldDescriptor::kind) of a field.
+    ///
+    /// Unlike the [`TryFrom`] conversions to [`Value`], which use the type from
+    /// [`DescriptorPool::global()`](crate::DescriptorPool::global), this uses the message descriptor of `kind`, so
+    /// the value matches fields from any pool. `time` may be any type which can be converted to a timestamp
+    /// [`Value`], such as [`SystemTime`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeError::InvalidType`] if `kind` is not the `google.protobuf.Timestamp` message type, or
+    /// [`TimeError::OutOfRange`] if the time cannot be represented.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::time::{Duration, UNIX_EPOCH};
+    /// # use prost_reflect::{DescriptorPool, Kind, ReflectMessage, Value};
+    /// # let timestamp_file = DescriptorPool::global().get_file_by_name("google/protobuf/timestamp.proto").unwrap();
+    /// # let mut pool = DescriptorPool::new();
+    /// # pool.add_file_descriptor_proto(timestamp_file.file_descriptor_proto().clone()).unwrap();
+    /// let kind = Kind::Message(pool.get_message_by_name("google.protobuf.Timestamp").unwrap());
+    /// let time = UNIX_EPOCH + Duration::from_millis(1_500);
+    /// let value = Value::timestamp_for(&kind, time).unwrap();
+    /// assert!(value.is_valid(&kind));
+    /// assert_eq!(value.as_message().unwrap().descriptor().parent_pool(), &pool);
+    /// assert_eq!(value.as_message().unwrap().as_timestamp(), Some(time));
+    /// ```
+    pub fn timestamp_for<T>(kind: &Kind, time: T) -> Result<Value, TimeError>
+    where
+        Value: TryFrom<T, Error = TimeError>,
+    {
+        let desc = message_kind(kind, TIMESTAMP)?;
+        retype(Value::try_from(time)?, desc, TIMESTAMP)
+    }
+
+    /// Creates a `google.protobuf.Duration` message of the type given by `kind`, such as the
+    /// [`kind()`](crate::FieldDescriptor::kind) of a field.
+    ///
+    /// Unlike the [`TryFrom`] conversions to [`Value`], which use the type from
+    /// [`DescriptorPool::global()`](crate::DescriptorPool::global), this uses the message descriptor of `kind`, so
+    /// the value matches fields from any pool. `duration` may be any type which can be converted to a duration
+    /// [`Value`], such as [`std::time::Duration`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimeError::InvalidType`] if `kind` is not the `google.protobuf.Duration` message type, or
+    /// [`TimeError::OutOfRange`] if the duration cannot be represented.
+    pub fn duration_for<T>(kind: &Kind, duration: T) -> Result<Value, TimeError>
+    where
+        Value: TryFrom<T, Error = TimeError>,
+    {
+        let desc = message_kind(kind, DURATION)?;
+        retype(Value::try_from(duration)?, desc, DURATION)
+    }
+}
+
+fn message_kind<'a>(
+    kind: &'a Kind,
+    expected: &'static str,
+) -> Result<&'a MessageDescriptor, TimeError> {
+    match kind.as_message() {
+        Some(desc) if desc.full_name() == expected => Ok(desc),
+        Some(desc) => Err(TimeError::InvalidType {
+            expected,
+            actual: desc.full_name().to_owned(),
+        }),
+        None => Err(TimeError::InvalidType {
+            expected,
+            actual: format!("{:?}", kind),
+        }),
+    }
+}
+
+/// Converts a message created with the type from the global pool to the equivalent type `desc`.
+fn retype(
+    value: Value,
+    desc: &MessageDescriptor,
+    expected: &'static str,
+) -> Result<Value, TimeError> {
+    let message = match value {
+        Value::Message(message) => message,
+        _ => unreachable!("time conversions always produce messages"),
+    };
+    if message.descriptor().full_name() != expected {
+        return Err(TimeError::InvalidType {
+            expected,
+            actual: message.descriptor().full_name().to_owned(),
+        });
+    }
+
+    let mut retyped = DynamicMessage::new(desc.clone());
+    retyped
+        .transcode_from(&message)
+        .map_err(|_| TimeError::InvalidType {
+            expected,
+            actual: desc.full_name().to_owned(),
+        })?;
+    Ok(Value::Message(retyped))
+}
+
+/// Returns the value of a `google.protobuf.Timestamp` message, with its nanoseconds in the range `0..1e9`.
+fn get_timestamp(message: &DynamicMessage) -> Result<prost_types::Timestamp, TimeError> {
+    let timestamp: prost_types::Timestamp = transcode(message, TIMESTAMP)?;
+    if check_timestamp(&timestamp).is_err() || !(0..NANOS_PER_SECOND).contains(&timestamp.nanos) {
+        return Err(TimeError::OutOfRange {
+            message_name: TIMESTAMP,
+        });
+    }
+    Ok(timestamp)
+}
+
+/// Returns the value of a `google.protobuf.Duration` message, whose seconds and nanoseconds have the same sign.
+fn get_duration(message: &DynamicMessage) -> Result<prost_types::Duration, TimeError> {
+    let duration: prost_types::Duration = transcode(message, DURATION)?;
+    if check_duration(&duration).is_err()
+        || (duration.seconds < 0 && duration.nanos > 0)
+        || (duration.seconds > 0 && duration.nanos < 0)
+    {
+        return Err(TimeError::OutOfRange {
+            message_name: DURATION,
+        });
+    }
+    Ok(duration)
+}
+
+fn transcode<T>(message: &DynamicMessage, expected: &'static str) -> Result<T, TimeError>
+where
+    T: ReflectMessage + Default,
+{
+    if message.descriptor().full_name() != expected {
+        return Err(TimeError::InvalidType {
+            expected,
+            actual: message.descriptor().full_name().to_owned(),
+        });
+    }
+
+    message.transcode_to().map_err(|_| TimeError::InvalidType {
+        expected,
+        actual: message.descriptor().full_name().to_owned(),
+    })
+}
+
+fn timestamp_value(mut timestamp: prost_types::Timestamp) -> Result<Value, TimeError> {
+    timestamp.normalize();
+    check_timestamp(&timestamp).map_err(|_| TimeError::OutOfRange {
+        message_name: TIMESTAMP,
+    })?;
+    Ok(Value::Message(timestamp.transcode_to_dynamic()))
+}
+
+fn duration_value(mut duration: prost_types::Duration) -> Result<Value, TimeError> {
+    duration.normalize();
+    check_duration(&duration).map_err(|_| TimeError::OutOfRange {
+        message_name: DURATION,
+    })?;
+    Ok(Value::Message(duration.transcode_to_dynamic()))
+}
+
+impl TryFrom<&DynamicMessage> for SystemTime {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        SystemTime::try_from(get_timestamp(message)?).map_err(|_| TimeError::OutOfRange {
+            message_name: TIMESTAMP,
+        })
+    }
+}
+
+impl TryFrom<&DynamicMessage> for time::Duration {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        time::Duration::try_from(get_duration(message)?).map_err(|_| TimeError::OutOfRange {
+            message_name: DURATION,
+        })
+    }
+}
+
+/// Creates a `google.protobuf.Timestamp` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::timestamp_for()`].
+impl TryFrom<SystemTime> for Value {
+    type Error = TimeError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        timestamp_value(prost_types::Timestamp::from(time))
+    }
+}
+
+/// Creates a `google.protobuf.Duration` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::duration_for()`].
+impl TryFrom<time::Duration> for Value {
+    type Error = TimeError;
+
+    fn try_from(duration: time::Duration) -> Result<Self, Self::Error> {
+        let duration =
+            prost_types::Duration::try_from(duration).map_err(|_| TimeError::OutOfRange {
+                message_name: DURATION,
+            })?;
+        duration_value(duration)
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl TryFrom<&DynamicMessage> for chrono::DateTime<chrono::Utc> {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        let timestamp = get_timestamp(message)?;
+        chrono::DateTime::from_timestamp(timestamp.seconds, timestamp.nanos as u32).ok_or(
+            TimeError::OutOfRange {
+                message_name: TIMESTAMP,
+            },
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl TryFrom<&DynamicMessage> for chrono::TimeDelta {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        let duration = get_duration(message)?;
+        chrono::TimeDelta::try_seconds(duration.seconds)
+            .and_then(|seconds| {
+                seconds.checked_add(&chrono::TimeDelta::nanoseconds(duration.nanos.into()))
+            })
+            .ok_or(TimeError::OutOfRange {
+                message_name: DURATION,
+            })
+    }
+}
+
+/// Creates a `google.protobuf.Timestamp` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::timestamp_for()`].
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl<Tz> TryFrom<chrono::DateTime<Tz>> for Value
+where
+    Tz: chrono::TimeZone,
+{
+    type Error = TimeError;
+
+    fn try_from(time: chrono::DateTime<Tz>) -> Result<Self, Self::Error> {
+        // Leap seconds are represented with nanoseconds above one second, which `normalize` carries over.
+        timestamp_value(prost_types::Timestamp {
+            seconds: time.timestamp(),
+            nanos: time.timestamp_subsec_nanos() as i32,
+        })
+    }
+}
+
+/// Creates a `google.protobuf.Duration` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::duration_for()`].
+#[cfg(feature = "chrono")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+impl TryFrom<chrono::TimeDelta> for Value {
+    type Error = TimeError;
+
+    fn try_from(duration: chrono::TimeDelta) -> Result<Self, Self::Error> {
+        duration_value(prost_types::Duration {
+            seconds: duration.num_seconds(),
+            nanos: duration.subsec_nanos(),
+        })
+    }
+}
+
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl TryFrom<&DynamicMessage> for ::time::OffsetDateTime {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        let timestamp = get_timestamp(message)?;
+        let nanos = i128::from(timestamp.seconds) * i128::from(NANOS_PER_SECOND)
+            + i128::from(timestamp.nanos);
+        ::time::OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| {
+            TimeError::OutOfRange {
+                message_name: TIMESTAMP,
+            }
+        })
+    }
+}
+
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl TryFrom<&DynamicMessage> for ::time::Duration {
+    type Error = TimeError;
+
+    fn try_from(message: &DynamicMessage) -> Result<Self, Self::Error> {
+        let duration = get_duration(message)?;
+        Ok(::time::Duration::new(duration.seconds, duration.nanos))
+    }
+}
+
+/// Creates a `google.protobuf.Timestamp` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::timestamp_for()`].
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl TryFrom<::time::OffsetDateTime> for Value {
+    type Error = TimeError;
+
+    fn try_from(time: ::time::OffsetDateTime) -> Result<Self, Self::Error> {
+        timestamp_value(prost_types::Timestamp {
+            seconds: time.unix_timestamp(),
+            nanos: time.nanosecond() as i32,
+        })
+    }
+}
+
+/// Creates a `google.protobuf.Duration` message, using the type from [`DescriptorPool::global()`](crate::DescriptorPool::global).
+///
+/// To create a value for a field whose type comes from a different pool, use [`Value::duration_for()`].
+#[cfg(feature = "time")]
+#[cfg_attr(docsrs, doc(cfg(feature = "time")))]
+impl TryFrom<::time::Duration> for Value {
+    type Error = TimeError;
+
+    fn try_from(duration: ::time::Duration) -> Result<Self, Self::Error> {
+        duration_value(prost_types::Duration {
+            seconds: duration.whole_seconds(),
+            nanos: duration.subsec_nanoseconds(),
+        })
+    }
+}
+
+impl fmt::Display for TimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeError::InvalidType { expected, actual } => write!(
+                f,
+                "expected a message of type '{}', but found '{}'",
+                expected, actual
+            ),
+            TimeError::OutOfRange { message_name } => {
+                write!(f, "{} value out of range", message_name)
+            }
+        }
+    }
+}
+
+impl Error for TimeError {}
@@ -21,27 +21,87 @@ pub struct UnknownField {
     value: UnknownFieldValue,
 }
 
-/// The vaalue of an unknown field in a protobuf message.
+/// The value of an unknown field in a protobuf message.
+///
+/// Since the message descriptor does not describe the field, only its wire format is known. Use the
+/// `as_*` methods to interpret the value as a particular type.
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) enum UnknownFieldValue {
+pub enum UnknownFieldValue {
     /// An unknown field with the `Varint` wire type.
     Varint(u64),
-    /// An unknown field with the `SixtyFourBit` wire type.
+    /// An unknown field with the `SixtyFourBit` wire type, stored in little-endian byte order.
     SixtyFourBit([u8; 8]),
     /// An unknown field with the `LengthDelimited` wire type.
     LengthDelimited(Bytes),
     /// An unknown field with the group wire type.
     Group(UnknownFieldSet),
-    /// An unknown field with the `ThirtyTwoBit` wire type.
+    /// An unknown field with the `ThirtyTwoBit` wire type, stored in little-endian byte order.
     ThirtyTwoBit([u8; 4]),
 }
 
+/// A list of unknown fields, such as the contents of a group.
+///
+/// The fields are stored in the order they were decoded or inserted, and encoding the set writes them in
+/// the same order.
 #[derive(Debug, Default, Clone, PartialEq)]
-pub(crate) struct UnknownFieldSet {
+pub struct UnknownFieldSet {
     fields: Vec<UnknownField>,
 }
 
 impl UnknownField {
+    /// Creates a new unknown field with the given number and value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `number` is not a valid field number (between 1 and 2<sup>29</sup> - 1 inclusive).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{UnknownField, UnknownFieldValue};
+    /// let unknown_field = UnknownField::new(1, UnknownFieldValue::Varint(150));
+    ///
+    /// let mut buf = Vec::new();
+    /// unknown_field.encode(&mut buf);
+    /// assert_eq!(buf, b"\x08\x96\x01");
+    /// ```
+    pub fn new(number: u32, value: UnknownFieldValue) -> Self {
+        assert!(
+            (encoding::MIN_TAG..=encoding::MAX_TAG).contains(&number),
+            "invalid field number {}",
+            number
+        );
+        UnknownField { number, value }
+    }
+
+    /// Creates a new unknown field with the `Varint` wire type.
+    pub fn varint(number: u32, value: u64) -> Self {
+        Self::new(number, UnknownFieldValue::Varint(value))
+    }
+
+    /// Creates a new unknown field with the `ThirtyTwoBit` wire type.
+    pub fn fixed32(number: u32, value: u32) -> Self {
+        Self::new(number, UnknownFieldValue::ThirtyTwoBit(value.to_le_bytes()))
+    }
+
+    /// Creates a new unknown field with the `SixtyFourBit` wire type.
+    pub fn fixed64(number: u32, value: u64) -> Self {
+        Self::new(number, UnknownFieldValue::SixtyFourBit(value.to_le_bytes()))
+    }
+
+    /// Creates a new unknown field with the `LengthDelimited` wire type.
+    pub fn length_delimited(number: u32, value: impl Into<Bytes>) -> Self {
+        Self::new(number, UnknownFieldValue::LengthDelimited(value.into()))
+    }
+
+    /// Creates a new unknown field with the group wire type, containing the given fields.
+    pub fn group(number: u32, fields: impl IntoIterator<Item = UnknownField>) -> Self {
+        Self::new(
+            number,
+            UnknownFieldValue::Group(fields.into_iter().collect()),
+        )
+    }
+
     /// The number of this field as found during decoding.
     pub fn number(&self) -> u32 {
         self.number
@@ -58,10 +118,21 @@ impl UnknownField {
         }
     }
 
-    pub(crate) fn value(&self) -> &UnknownFieldValue {
+    /// Gets a reference to the value of this field.
+    pub fn value(&self) -> &UnknownFieldValue {
         &self.value
     }
 
+    /// Gets a mutable reference to the value of this field.
+    pub fn value_mut(&mut self) -> &mut UnknownFieldValue {
+        &mut self.value
+    }
+
+    /// Consumes this field, returning its value.
+    pub fn into_value(self) -> UnknownFieldValue {
+        self.value
+    }
+
     /// Encodes this field into its byte representation.
     pub fn encode<B>(&self, buf: &mut B)
     where
@@ -196,6 +267,91 @@ impl UnknownField {
     }
 }
 
+impl UnknownFieldValue {
+    /// Returns the value of a `Varint` field, or `None` if the value has a different wire type.
+    ///
+    /// Depending on the type of the field, the value may need to be reinterpreted, for example as a
+    /// negative `int32` or a zigzag-encoded `sint64`.
+    pub fn as_varint(&self) -> Option<u64> {
+        match *self {
+            UnknownFieldValue::Varint(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `ThirtyTwoBit` field as an integer, or `None` if the value has a different
+    /// wire type.
+    ///
+    /// Use [`f32::from_bits`] to interpret the value as a `float`.
+    pub fn as_fixed32(&self) -> Option<u32> {
+        match *self {
+            UnknownFieldValue::ThirtyTwoBit(value) => Some(u32::from_le_bytes(value)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `SixtyFourBit` field as an integer, or `None` if the value has a different
+    /// wire type.
+    ///
+    /// Use [`f64::from_bits`] to interpret the value as a `double`.
+    pub fn as_fixed64(&self) -> Option<u64> {
+        match *self {
+            UnknownFieldValue::SixtyFourBit(value) => Some(u64::from_le_bytes(value)),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a `LengthDelimited` field, or `None` if the value has a different
+    /// wire type.
+    pub fn as_bytes(&self) -> Option<&Bytes> {
+        match self {
+            UnknownFieldValue::LengthDelimited(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields contained in a group, or `None` if the value has a different wire type.
+    pub fn as_group(&self) -> Option<&UnknownFieldSet> {
+        match self {
+            UnknownFieldValue::Group(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Attempts to interpret the contents of a `LengthDelimited` field as a UTF-8 string.
+    ///
+    /// Returns `None` if the value has a different wire type, or is not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// Attempts to interpret the contents of a `LengthDelimited` field as an encoded message.
+    ///
+    /// Returns `None` if the value has a different wire type, or is not a valid protobuf message.
+    /// Note that many short strings and byte sequences are also valid messages, so this is only a heuristic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::UnknownField;
+    /// let unknown_field = UnknownField::length_delimited(3, b"\x10\x42".as_ref());
+    /// let message = unknown_field.value().decode_message().unwrap();
+    /// let nested: Vec<_> = message.iter().collect();
+    /// assert_eq!(nested.len(), 1);
+    /// assert_eq!(nested[0].number(), 2);
+    /// assert_eq!(nested[0].value().as_varint(), Some(66));
+    ///
+    /// let unknown_field = UnknownField::length_delimited(3, "hello\u{1f600}");
+    /// assert_eq!(unknown_field.value().decode_message(), None);
+    /// assert_eq!(unknown_field.value().as_str(), Some("hello\u{1f600}"));
+    /// ```
+    pub fn decode_message(&self) -> Option<UnknownFieldSet> {
+        self.as_bytes()
+            .and_then(|bytes| UnknownFieldSet::decode(bytes.clone()).ok())
+    }
+}
+
 impl fmt::Display for UnknownField {
     /// Formats this unknown field using the protobuf text format.
     ///
@@ -220,20 +376,51 @@ impl fmt::Display for UnknownField {
 }
 
 impl UnknownFieldSet {
-    pub(crate) fn is_empty(&self) -> bool {
+    /// Creates a new, empty set of unknown fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the set contains no fields.
+    pub fn is_empty(&self) -> bool {
         self.fields.is_empty()
     }
 
-    pub(crate) fn iter(&self) -> slice::Iter<'_, UnknownField> {
+    /// Returns the number of fields in the set.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Gets an iterator over the fields in the set.
+    pub fn iter(&self) -> slice::Iter<'_, UnknownField> {
         self.fields.iter()
     }
 
-    pub(crate) fn into_iter(self) -> vec::IntoIter<UnknownField> {
+    /// Adds a field to the end of the set.
+    pub fn insert(&mut self, unknown: UnknownField) {
+        self.fields.push(unknown);
+    }
+
+    pub(super) fn retain_mut(&mut self, f: impl FnMut(&mut UnknownField) -> bool) {
+        self.fields.retain_mut(f);
+    }
+}
+
+impl IntoIterator for UnknownFieldSet {
+    type Item = UnknownField;
+    type IntoIter = vec::IntoIter<UnknownField>;
+
+    fn into_iter(self) -> Self::IntoIter {
         self.fields.into_iter()
     }
+}
 
-    pub(crate) fn insert(&mut self, unknown: UnknownField) {
-        self.fields.push(unknown);
+impl<'a> IntoIterator for &'a UnknownFieldSet {
+    type Item = &'a UnknownField;
+    type IntoIter = slice::Iter<'a, UnknownField>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.iter()
     }
 }
 
@@ -0,0 +1,1524 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::SystemTime,
+};
+
+use regex::Regex;
+
+use crate::{
+    dynamic::diff::{index_path, join_path, key_path},
+    DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MapKey, MessageDescriptor,
+    OneofDescriptor, ReflectMessage, Value,
+};
+
+/// The full name of the extension to `google.protobuf.FieldOptions` containing field constraints.
+const FIELD_CONSTRAINTS: &str = "buf.validate.field";
+/// The full name of the extension to `google.protobuf.MessageOptions` containing message constraints.
+const MESSAGE_CONSTRAINTS: &str = "buf.validate.message";
+/// The full name of the extension to `google.protobuf.OneofOptions` containing oneof constraints.
+const ONEOF_CONSTRAINTS: &str = "buf.validate.oneof";
+
+const IGNORE_IF_UNPOPULATED: i32 = 1;
+const IGNORE_IF_DEFAULT_VALUE: i32 = 2;
+const IGNORE_ALWAYS: i32 = 3;
+
+/// Options to control how [`DynamicMessage::validate_with_options()`] and [`Validator`] check messages.
+#[derive(Debug, Clone)]
+pub struct ValidateOptions {
+    fail_fast: bool,
+    now: Option<SystemTime>,
+}
+
+/// Checks messages of a given type against their [protovalidate](https://github.com/bufbuild/protovalidate)
+/// constraints, reusing the parsed constraints between calls.
+///
+/// Creating a validator reads the constraints of the message type, and of every message type reachable from
+/// its fields, from the descriptor options, and compiles the regular expressions they use. Holding on to a
+/// validator avoids repeating this work each time a message is checked, whereas
+/// [`DynamicMessage::validate()`] creates a new validator on every call.
+///
+/// See [`DynamicMessage::validate_with_options()`] for the supported constraints.
+///
+/// # Examples
+///
+/// ```
+/// # use prost_reflect::{DescriptorPool, DynamicMessage, validate::Validator};
+/// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+/// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+/// let validator = Validator::new(&message_descriptor).unwrap();
+/// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+/// assert!(validator.validate(&dynamic_message).is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Validator {
+    options: ValidateOptions,
+    messages: HashMap<String, MessageConstraints>,
+    regexes: HashMap<String, Regex>,
+}
+
+/// The constraints of a single message type, read from its descriptor options.
+#[derive(Debug, Clone)]
+struct MessageConstraints {
+    desc: MessageDescriptor,
+    disabled: bool,
+    required_oneofs: Vec<OneofDescriptor>,
+    fields: Vec<(FieldDescriptor, Option<DynamicMessage>)>,
+}
+
+/// A single constraint which is not satisfied by a message, as reported by [`DynamicMessage::validate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    field_path: String,
+    rule_id: String,
+    message: String,
+}
+
+/// The error returned by [`DynamicMessage::validate()`] when a message does not satisfy its constraints, or
+/// its constraints are invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    kind: ValidationErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ValidationErrorKind {
+    Violations(Vec<Violation>),
+    InvalidConstraint(ConstraintError),
+}
+
+/// The error returned by [`Validator::new()`] when the constraints of a message type cannot be used, such as
+/// a `pattern` rule which is not a valid regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintError {
+    field: String,
+    message: String,
+}
+
+impl ValidateOptions {
+    /// Creates a new instance of [`ValidateOptions`], with the default options.
+    pub const fn new() -> Self {
+        ValidateOptions {
+            fail_fast: false,
+            now: None,
+        }
+    }
+
+    /// Whether to stop validation at the first violation.
+    ///
+    /// The default value is `false`, which reports all violations.
+    pub const fn fail_fast(mut self, yes: bool) -> Self {
+        self.fail_fast = yes;
+        self
+    }
+
+    /// The current time, used by the `lt_now`, `gt_now` and `within` timestamp rules.
+    ///
+    /// By default, the system clock is used.
+    pub fn now(mut self, now: SystemTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Violation {
+    /// The path to the value which violates the constraint, such as `items[3].name`.
+    ///
+    /// The path uses the same format as [`Difference`](crate::diff::Difference). Violations of a
+    /// `required` oneof constraint use the name of the oneof.
+    pub fn field_path(&self) -> &str {
+        &self.field_path
+    }
+
+    /// The identifier of the constraint, such as `string.min_len` or `required`.
+    pub fn rule_id(&self) -> &str {
+        &self.rule_id
+    }
+
+    /// A description of the violation.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl ValidationError {
+    /// The violations found in the message, in the order they were found.
+    ///
+    /// This is empty if the constraints of the message are invalid.
+    pub fn violations(&self) -> &[Violation] {
+        match &self.kind {
+            ValidationErrorKind::Violations(violations) => violations,
+            ValidationErrorKind::InvalidConstraint(_) => &[],
+        }
+    }
+
+    /// Returns the violations found in the message.
+    pub fn into_violations(self) -> Vec<Violation> {
+        match self.kind {
+            ValidationErrorKind::Violations(violations) => violations,
+            ValidationErrorKind::InvalidConstraint(_) => Vec::new(),
+        }
+    }
+
+    /// The reason the constraints of the message could not be used, if the message was not checked.
+    pub fn constraint_error(&self) -> Option<&ConstraintError> {
+        match &self.kind {
+            ValidationErrorKind::Violations(_) => None,
+            ValidationErrorKind::InvalidConstraint(err) => Some(err),
+        }
+    }
+}
+
+impl ConstraintError {
+    /// The full name of the field whose constraints are invalid.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// A description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl DynamicMessage {
+    /// Checks this message against the [protovalidate](https://github.com/bufbuild/protovalidate) constraints
+    /// defined in the options of its fields, messages and oneofs, with default options.
+    ///
+    /// See [`validate_with_options()`](DynamicMessage::validate_with_options) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// let dynamic_message = DynamicMessage::decode(message_descriptor, b"\x08\x96\x01".as_ref()).unwrap();
+    /// // `package.MyMessage` does not define any constraints.
+    /// assert!(dynamic_message.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_with_options(&ValidateOptions::new())
+    }
+
+    /// Checks this message against the [protovalidate](https://github.com/bufbuild/protovalidate) constraints
+    /// defined in the options of its fields, messages and oneofs.
+    ///
+    /// Constraints are read from the `buf.validate.field`, `buf.validate.message` and `buf.validate.oneof`
+    /// options, so the descriptor pool of this message must include `buf/validate/validate.proto`. Nested
+    /// messages, including the elements of repeated fields and values of map fields, are checked recursively.
+    ///
+    /// The standard rules for scalar types, enums, repeated fields, maps and the `Any`, `Duration` and
+    /// `Timestamp` well-known types are supported, along with the `required` and `ignore` field constraints and
+    /// `required` oneof constraints. Rules for scalar types also apply to the value of wrapper types such as
+    /// `google.protobuf.StringValue`. Custom constraints written as CEL expressions are not evaluated, and
+    /// rules which do not match the type of the field are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing each violated constraint if the message is not valid, or describing the
+    /// problem if the constraints themselves are invalid.
+    ///
+    /// To check many messages of the same type, create a [`Validator`] once and reuse it.
+    pub fn validate_with_options(&self, options: &ValidateOptions) -> Result<(), ValidationError> {
+        Validator::with_options(&self.descriptor(), options)?.validate(self)
+    }
+}
+
+impl Validator {
+    /// Creates a validator for messages of type `desc`, with default options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constraints of `desc`, or of a message type reachable from its fields, are
+    /// invalid.
+    pub fn new(desc: &MessageDescriptor) -> Result<Self, ConstraintError> {
+        Self::with_options(desc, &ValidateOptions::new())
+    }
+
+    /// Creates a validator for messages of type `desc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constraints of `desc`, or of a message type reachable from its fields, are
+    /// invalid.
+    pub fn with_options(
+        desc: &MessageDescriptor,
+        options: &ValidateOptions,
+    ) -> Result<Self, ConstraintError> {
+        let mut validator = Validator {
+            options: options.clone(),
+            messages: HashMap::new(),
+            regexes: HashMap::new(),
+        };
+
+        let mut pending = vec![desc.clone()];
+        while let Some(desc) = pending.pop() {
+            if validator.messages.contains_key(desc.full_name()) {
+                continue;
+            }
+
+            let constraints = MessageConstraints::new(&desc);
+            for (field, field_constraints) in &constraints.fields {
+                if let Some(field_constraints) = field_constraints {
+                    compile_patterns(field, field_constraints, &mut validator.regexes)?;
+                }
+                if let Kind::Message(field_desc) = field.kind() {
+                    pending.push(field_desc);
+                }
+            }
+            validator
+                .messages
+                .insert(desc.full_name().to_owned(), constraints);
+        }
+
+        Ok(validator)
+    }
+
+    /// Checks `message` against its constraints.
+    ///
+    /// Messages of a type other than the one this validator was created for are also checked, but their
+    /// constraints are read again on each call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing each violated constraint if the message is not valid. For messages of another
+    /// type, an error is also returned if their constraints are invalid.
+    pub fn validate(&self, message: &DynamicMessage) -> Result<(), ValidationError> {
+        let desc = message.descriptor();
+        if !self
+            .messages
+            .get(desc.full_name())
+            .is_some_and(|constraints| constraints.desc == desc)
+        {
+            return Validator::with_options(&desc, &self.options)?.validate(message);
+        }
+
+        let mut checker = Checker {
+            validator: self,
+            now: self.options.now.unwrap_or_else(SystemTime::now).into(),
+            violations: Vec::new(),
+        };
+        checker.validate_message("", message);
+
+        if checker.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                kind: ValidationErrorKind::Violations(checker.violations),
+            })
+        }
+    }
+}
+
+impl MessageConstraints {
+    fn new(desc: &MessageDescriptor) -> Self {
+        let disabled = get_constraints(&desc.options(), MESSAGE_CONSTRAINTS)
+            .is_some_and(|constraints| get_bool(&constraints, "disabled"));
+        let required_oneofs = desc
+            .oneofs()
+            .filter(|oneof| {
+                get_constraints(&oneof.options(), ONEOF_CONSTRAINTS)
+                    .is_some_and(|constraints| get_bool(&constraints, "required"))
+            })
+            .collect();
+        let fields = desc
+            .fields()
+            .map(|field| {
+                let constraints = get_constraints(&field.options(), FIELD_CONSTRAINTS);
+                (field, constraints)
+            })
+            .collect();
+
+        MessageConstraints {
+            desc: desc.clone(),
+            disabled,
+            required_oneofs,
+            fields,
+        }
+    }
+}
+
+/// The state of a single call to [`Validator::validate()`].
+struct Checker<'a> {
+    validator: &'a Validator,
+    now: prost_types::Timestamp,
+    violations: Vec<Violation>,
+}
+
+impl Checker<'_> {
+    fn is_done(&self) -> bool {
+        self.validator.options.fail_fast && !self.violations.is_empty()
+    }
+
+    fn violation(&mut self, path: &str, rule_id: impl Into<String>, message: impl Into<String>) {
+        if !self.is_done() {
+            self.violations.push(Violation {
+                field_path: path.to_owned(),
+                rule_id: rule_id.into(),
+                message: message.into(),
+            });
+        }
+    }
+
+    fn validate_message(&mut self, path: &str, message: &DynamicMessage) {
+        // The validator contains every message type reachable from the type of the top-level message.
+        let validator = self.validator;
+        let constraints = &validator.messages[message.descriptor().full_name()];
+        if constraints.disabled {
+            return;
+        }
+
+        for oneof in &constraints.required_oneofs {
+            if !oneof.fields().any(|field| message.has_field(&field)) {
+                self.violation(
+                    &join_path(path, oneof.name()),
+                    "required",
+                    "exactly one field is required in oneof",
+                );
+            }
+        }
+
+        for (field, field_constraints) in &constraints.fields {
+            if self.is_done() {
+                return;
+            }
+            self.validate_field(path, message, field, field_constraints.as_ref());
+        }
+    }
+
+    fn validate_field(
+        &mut self,
+        path: &str,
+        message: &DynamicMessage,
+        field: &FieldDescriptor,
+        constraints: Option<&DynamicMessage>,
+    ) {
+        let path = join_path(path, field.name());
+        let populated = message.has_field(field);
+        let value = message.get_field(field);
+
+        if let Some(constraints) = constraints {
+            let ignore = get_enum(constraints, "ignore");
+            if ignore == IGNORE_ALWAYS {
+                return;
+            }
+
+            if get_bool(constraints, "required") && !populated {
+                self.violation(&path, "required", "value is required");
+                return;
+            }
+
+            match ignore {
+                IGNORE_IF_UNPOPULATED | IGNORE_IF_DEFAULT_VALUE if !populated => return,
+                IGNORE_IF_DEFAULT_VALUE if *value == Value::default_value_for_field(field) => {
+                    return
+                }
+                _ => (),
+            }
+        }
+
+        if !populated && field.supports_presence() {
+            return;
+        }
+
+        if field.is_list() {
+            let list = value.as_list().unwrap_or_default();
+            self.validate_list(&path, field, list, constraints);
+        } else if field.is_map() {
+            if let Some(map) = value.as_map() {
+                self.validate_map(&path, field, map, constraints);
+            }
+        } else {
+            if let Some(constraints) = constraints {
+                self.validate_value(&path, &field.kind(), &value, constraints);
+            }
+            if let Value::Message(nested) = &*value {
+                self.validate_message(&path, nested);
+            }
+        }
+    }
+
+    fn validate_list(
+        &mut self,
+        path: &str,
+        field: &FieldDescriptor,
+        list: &[Value],
+        constraints: Option<&DynamicMessage>,
+    ) {
+        let rules = constraints.and_then(|constraints| get_message(constraints, "repeated"));
+        if let Some(rules) = &rules {
+            let len = list.len() as u64;
+            if let Some(min) = get(rules, "min_items", Value::as_u64) {
+                if len < min {
+                    self.violation(
+                        path,
+                        "repeated.min_items",
+                        format!("value must contain at least {} item(s)", min),
+                    );
+                }
+            }
+            if let Some(max) = get(rules, "max_items", Value::as_u64) {
+                if len > max {
+                    self.violation(
+                        path,
+                        "repeated.max_items",
+                        format!("value must contain no more than {} item(s)", max),
+                    );
+                }
+            }
+            if get_bool(rules, "unique")
+                && !list
+                    .iter()
+                    .enumerate()
+                    .all(|(index, value)| !list[..index].contains(value))
+            {
+                self.violation(
+                    path,
+                    "repeated.unique",
+                    "repeated value must contain unique items",
+                );
+            }
+        }
+
+        let kind = field.kind();
+        let items = rules.and_then(|rules| get_message(&rules, "items"));
+        for (index, value) in list.iter().enumerate() {
+            if self.is_done() {
+                return;
+            }
+            self.validate_element(&index_path(path, index), &kind, value, items.as_ref());
+        }
+    }
+
+    fn validate_map(
+        &mut self,
+        path: &str,
+        field: &FieldDescriptor,
+        map: &HashMap<MapKey, Value>,
+        constraints: Option<&DynamicMessage>,
+    ) {
+        let rules = constraints.and_then(|constraints| get_message(constraints, "map"));
+        if let Some(rules) = &rules {
+            let len = map.len() as u64;
+            if let Some(min) = get(rules, "min_pairs", Value::as_u64) {
+                if len < min {
+                    self.violation(
+                        path,
+                        "map.min_pairs",
+                        format!("map must be at least {} entries", min),
+                    );
+                }
+            }
+            if let Some(max) = get(rules, "max_pairs", Value::as_u64) {
+                if len > max {
+                    self.violation(
+                        path,
+                        "map.max_pairs",
+                        format!("map must be at most {} entries", max),
+                    );
+                }
+            }
+        }
+
+        let entry_desc = field.kind();
+        let entry_desc = entry_desc
+            .as_message()
+            .expect("map entry must be a message");
+        let key_kind = entry_desc.map_entry_key_field().kind();
+        let value_kind = entry_desc.map_entry_value_field().kind();
+        let keys = rules.as_ref().and_then(|rules| get_message(rules, "keys"));
+        let values = rules
+            .as_ref()
+            .and_then(|rules| get_message(rules, "values"));
+
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        for (key, value) in entries {
+            if self.is_done() {
+                return;
+            }
+            let entry_path = key_path(path, key);
+            if let Some(keys) = &keys {
+                self.validate_value(&entry_path, &key_kind, &Value::from(key.clone()), keys);
+            }
+            self.validate_element(&entry_path, &value_kind, value, values.as_ref());
+        }
+    }
+
+    /// Checks an element of a repeated field or a value of a map field, and any nested message it contains.
+    fn validate_element(
+        &mut self,
+        path: &str,
+        kind: &Kind,
+        value: &Value,
+        constraints: Option<&DynamicMessage>,
+    ) {
+        if let Some(constraints) = constraints {
+            match get_enum(constraints, "ignore") {
+                IGNORE_ALWAYS => return,
+                IGNORE_IF_UNPOPULATED | IGNORE_IF_DEFAULT_VALUE
+                    if *value == Value::default_value(kind) =>
+                {
+                    return
+                }
+                _ => (),
+            }
+
+            self.validate_value(path, kind, value, constraints);
+        }
+
+        if let Value::Message(nested) = value {
+            self.validate_message(path, nested);
+        }
+    }
+
+    /// Applies the type-specific rules of `constraints` to a singular value.
+    fn validate_value(
+        &mut self,
+        path: &str,
+        kind: &Kind,
+        value: &Value,
+        constraints: &DynamicMessage,
+    ) {
+        let (rules_field, rules) = match get_type_rules(constraints) {
+            Some(rules) => rules,
+            None => return,
+        };
+        let name = rules_field.name();
+
+        match (name, value) {
+            ("float", &Value::F32(value)) => {
+                self.validate_float(path, name, value, value.is_finite(), &rules, Value::as_f32)
+            }
+            ("double", &Value::F64(value)) => {
+                self.validate_float(path, name, value, value.is_finite(), &rules, Value::as_f64)
+            }
+            ("int32" | "sint32" | "sfixed32", Value::I32(value)) => {
+                self.validate_ordered(path, name, value, &rules, Value::as_i32)
+            }
+            ("int64" | "sint64" | "sfixed64", Value::I64(value)) => {
+                self.validate_ordered(path, name, value, &rules, Value::as_i64)
+            }
+            ("uint32" | "fixed32", Value::U32(value)) => {
+                self.validate_ordered(path, name, value, &rules, Value::as_u32)
+            }
+            ("uint64" | "fixed64", Value::U64(value)) => {
+                self.validate_ordered(path, name, value, &rules, Value::as_u64)
+            }
+            ("bool", &Value::Bool(value)) => {
+                if let Some(expected) = get(&rules, "const", Value::as_bool) {
+                    if value != expected {
+                        self.violation(
+                            path,
+                            "bool.const",
+                            format!("value must equal {}", expected),
+                        );
+                    }
+                }
+            }
+            ("string", Value::String(value)) => self.validate_string(path, value, &rules),
+            ("bytes", Value::Bytes(value)) => self.validate_bytes(path, value, &rules),
+            ("enum", &Value::EnumNumber(value)) => {
+                if let Some(enum_desc) = kind.as_enum() {
+                    self.validate_enum(path, enum_desc, value, &rules)
+                }
+            }
+            ("duration", Value::Message(message))
+                if message.descriptor().full_name() == "google.protobuf.Duration" =>
+            {
+                if let Some(value) = get_duration(value) {
+                    self.validate_ordered(path, name, &value, &rules, get_duration);
+                }
+            }
+            ("timestamp", Value::Message(message))
+                if message.descriptor().full_name() == "google.protobuf.Timestamp" =>
+            {
+                if let Some(value) = get_timestamp(value) {
+                    self.validate_timestamp(path, &value, &rules);
+                }
+            }
+            ("any", Value::Message(message))
+                if message.descriptor().full_name() == "google.protobuf.Any" =>
+            {
+                if let Ok(any) = message.transcode_to::<prost_types::Any>() {
+                    self.validate_any(path, &any.type_url, &rules);
+                }
+            }
+            (_, Value::Message(message)) if is_wrapper_type(message.descriptor().full_name()) => {
+                if let Some(value_field) = message.descriptor().get_field_by_name("value") {
+                    let inner = message.get_field(&value_field);
+                    self.validate_value(path, &value_field.kind(), &inner, constraints);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn validate_float<T>(
+        &mut self,
+        path: &str,
+        prefix: &str,
+        value: T,
+        is_finite: bool,
+        rules: &DynamicMessage,
+        get_value: impl Fn(&Value) -> Option<T>,
+    ) where
+        T: PartialOrd + fmt::Display,
+    {
+        if get_bool(rules, "finite") && !is_finite {
+            self.violation(path, format!("{}.finite", prefix), "value must be finite");
+        }
+        self.validate_ordered(path, prefix, &value, rules, get_value);
+    }
+
+    /// Applies the `const`, `lt`, `lte`, `gt`, `gte`, `in` and `not_in` rules.
+    fn validate_ordered<T>(
+        &mut self,
+        path: &str,
+        prefix: &str,
+        value: &T,
+        rules: &DynamicMessage,
+        get_value: impl Fn(&Value) -> Option<T>,
+    ) where
+        T: PartialOrd + fmt::Display,
+    {
+        if let Some(expected) = get(rules, "const", &get_value) {
+            if *value != expected {
+                self.violation(
+                    path,
+                    format!("{}.const", prefix),
+                    format!("value must equal {}", expected),
+                );
+            }
+        }
+
+        let (upper_name, upper) = match get(rules, "lt", &get_value) {
+            Some(lt) => ("lt", Some(lt)),
+            None => ("lte", get(rules, "lte", &get_value)),
+        };
+        let (lower_name, lower) = match get(rules, "gt", &get_value) {
+            Some(gt) => ("gt", Some(gt)),
+            None => ("gte", get(rules, "gte", &get_value)),
+        };
+
+        let check_lower = |lower: &T| {
+            if lower_name == "gt" {
+                value > lower
+            } else {
+                value >= lower
+            }
+        };
+        let check_upper = |upper: &T| {
+            if upper_name == "lt" {
+                value < upper
+            } else {
+                value <= upper
+            }
+        };
+        let describe_lower = |lower: &T| {
+            if lower_name == "gt" {
+                format!("greater than {}", lower)
+            } else {
+                format!("greater than or equal to {}", lower)
+            }
+        };
+        let describe_upper = |upper: &T| {
+            if upper_name == "lt" {
+                format!("less than {}", upper)
+            } else {
+                format!("less than or equal to {}", upper)
+            }
+        };
+
+        match (&lower, &upper) {
+            (Some(lower), Some(upper)) => {
+                // If the upper bound is below the lower bound, the value must lie outside the range instead.
+                let exclusive = upper < lower;
+                let valid = if exclusive {
+                    check_lower(lower) || check_upper(upper)
+                } else {
+                    check_lower(lower) && check_upper(upper)
+                };
+                if !valid {
+                    self.violation(
+                        path,
+                        format!(
+                            "{}.{}_{}{}",
+                            prefix,
+                            lower_name,
+                            upper_name,
+                            if exclusive { "_exclusive" } else { "" }
+                        ),
+                        format!(
+                            "value must be {} {} {}",
+                            describe_lower(lower),
+                            if exclusive { "or" } else { "and" },
+                            describe_upper(upper)
+                        ),
+                    );
+                }
+            }
+            (Some(lower), None) => {
+                if !check_lower(lower) {
+                    self.violation(
+                        path,
+                        format!("{}.{}", prefix, lower_name),
+                        format!("value must be {}", describe_lower(lower)),
+                    );
+                }
+            }
+            (None, Some(upper)) => {
+                if !check_upper(upper) {
+                    self.violation(
+                        path,
+                        format!("{}.{}", prefix, upper_name),
+                        format!("value must be {}", describe_upper(upper)),
+                    );
+                }
+            }
+            (None, None) => (),
+        }
+
+        self.validate_in(path, prefix, value, rules, &get_value);
+    }
+
+    /// Applies the `in` and `not_in` rules.
+    fn validate_in<T>(
+        &mut self,
+        path: &str,
+        prefix: &str,
+        value: &T,
+        rules: &DynamicMessage,
+        get_value: impl Fn(&Value) -> Option<T>,
+    ) where
+        T: PartialEq + fmt::Display,
+    {
+        let allowed = get_list(rules, "in", &get_value);
+        if !allowed.is_empty() && !allowed.contains(value) {
+            self.violation(
+                path,
+                format!("{}.in", prefix),
+                format!("value must be in list {}", fmt_list(&allowed)),
+            );
+        }
+
+        let disallowed = get_list(rules, "not_in", &get_value);
+        if disallowed.contains(value) {
+            self.violation(
+                path,
+                format!("{}.not_in", prefix),
+                format!("value must not be in list {}", fmt_list(&disallowed)),
+            );
+        }
+    }
+
+    fn validate_string(&mut self, path: &str, value: &str, rules: &DynamicMessage) {
+        let get_str = |value: &Value| value.as_str().map(str::to_owned);
+
+        if let Some(expected) = get(rules, "const", get_str) {
+            if value != expected {
+                self.violation(
+                    path,
+                    "string.const",
+                    format!("value must equal `{}`", expected),
+                );
+            }
+        }
+
+        let chars = value.chars().count() as u64;
+        let bytes = value.len() as u64;
+        self.validate_len(
+            path,
+            "string.len",
+            chars,
+            get(rules, "len", Value::as_u64),
+            |len| format!("value length must be {} characters", len),
+        );
+        self.validate_len(
+            path,
+            "string.min_len",
+            chars,
+            get(rules, "min_len", Value::as_u64).map(|min| min..),
+            |len| format!("value length must be at least {} characters", len.start),
+        );
+        self.validate_len(
+            path,
+            "string.max_len",
+            chars,
+            get(rules, "max_len", Value::as_u64).map(|max| ..=max),
+            |len| format!("value length must be at most {} characters", len.end),
+        );
+        self.validate_len(
+            path,
+            "string.len_bytes",
+            bytes,
+            get(rules, "len_bytes", Value::as_u64),
+            |len| format!("value length must be {} bytes", len),
+        );
+        self.validate_len(
+            path,
+            "string.min_bytes",
+            bytes,
+            get(rules, "min_bytes", Value::as_u64).map(|min| min..),
+            |len| format!("value length must be at least {} bytes", len.start),
+        );
+        self.validate_len(
+            path,
+            "string.max_bytes",
+            bytes,
+            get(rules, "max_bytes", Value::as_u64).map(|max| ..=max),
+            |len| format!("value length must be at most {} bytes", len.end),
+        );
+
+        if let Some(pattern) = get(rules, "pattern", get_str) {
+            self.validate_pattern(path, "string.pattern", value, &pattern);
+        }
+        if let Some(prefix) = get(rules, "prefix", get_str) {
+            if !value.starts_with(&prefix) {
+                self.violation(
+                    path,
+                    "string.prefix",
+                    format!("value does not have prefix `{}`", prefix),
+                );
+            }
+        }
+        if let Some(suffix) = get(rules, "suffix", get_str) {
+            if !value.ends_with(&suffix) {
+                self.violation(
+                    path,
+                    "string.suffix",
+                    format!("value does not have suffix `{}`", suffix),
+                );
+            }
+        }
+        if let Some(substring) = get(rules, "contains", get_str) {
+            if !value.contains(&substring) {
+                self.violation(
+                    path,
+                    "string.contains",
+                    format!("value does not contain substring `{}`", substring),
+                );
+            }
+        }
+        if let Some(substring) = get(rules, "not_contains", get_str) {
+            if value.contains(&substring) {
+                self.violation(
+                    path,
+                    "string.not_contains",
+                    format!("value contains substring `{}`", substring),
+                );
+            }
+        }
+        self.validate_in(path, "string", &value.to_owned(), rules, get_str);
+
+        if let Some(name) = get_well_known(rules) {
+            let (valid, description) = match name.as_str() {
+                "email" => (is_email(value), "email address"),
+                "hostname" => (is_hostname(value), "hostname"),
+                "ip" => (value.parse::<IpAddr>().is_ok(), "IP address"),
+                "ipv4" => (value.parse::<Ipv4Addr>().is_ok(), "IPv4 address"),
+                "ipv6" => (value.parse::<Ipv6Addr>().is_ok(), "IPv6 address"),
+                "uri" => (is_uri(value), "URI"),
+                "uri_ref" => (is_uri_ref(value), "URI Reference"),
+                "address" => (
+                    is_hostname(value) || value.parse::<IpAddr>().is_ok(),
+                    "hostname, or ip address",
+                ),
+                "uuid" => (is_uuid(value), "UUID"),
+                "tuuid" => (is_trimmed_uuid(value), "trimmed UUID"),
+                _ => return,
+            };
+
+            if value.is_empty() && name != "uri_ref" {
+                self.violation(
+                    path,
+                    format!("string.{}_empty", name),
+                    format!("value is empty, which is not a valid {}", description),
+                );
+            } else if !valid {
+                self.violation(
+                    path,
+                    format!("string.{}", name),
+                    format!("value must be a valid {}", description),
+                );
+            }
+        }
+    }
+
+    fn validate_bytes(&mut self, path: &str, value: &[u8], rules: &DynamicMessage) {
+        let get_bytes = |value: &Value| value.as_bytes().cloned();
+
+        if let Some(expected) = get(rules, "const", get_bytes) {
+            if value != expected {
+                self.violation(
+                    path,
+                    "bytes.const",
+                    format!("value must be {}", fmt_hex(&expected)),
+                );
+            }
+        }
+
+        let len = value.len() as u64;
+        self.validate_len(
+            path,
+            "bytes.len",
+            len,
+            get(rules, "len", Value::as_u64),
+            |len| format!("value length must be {} bytes", len),
+        );
+        self.validate_len(
+            path,
+            "bytes.min_len",
+            len,
+            get(rules, "min_len", Value::as_u64).map(|min| min..),
+            |len| format!("value length must be at least {} bytes", len.start),
+        );
+        self.validate_len(
+            path,
+            "bytes.max_len",
+            len,
+            get(rules, "max_len", Value::as_u64).map(|max| ..=max),
+            |len| format!("value must be at most {} bytes", len.end),
+        );
+
+        if let Some(pattern) = get(rules, "pattern", |value| value.as_str().map(str::to_owned)) {
+            match std::str::from_utf8(value) {
+                Ok(value) => self.validate_pattern(path, "bytes.pattern", value, &pattern),
+                Err(_) => self.violation(
+                    path,
+                    "bytes.pattern",
+                    "value must be valid UTF-8 to apply regexp",
+                ),
+            }
+        }
+        if let Some(prefix) = get(rules, "prefix", get_bytes) {
+            if !value.starts_with(&prefix) {
+                self.violation(
+                    path,
+                    "bytes.prefix",
+                    format!("value does not have prefix {}", fmt_hex(&prefix)),
+                );
+            }
+        }
+        if let Some(suffix) = get(rules, "suffix", get_bytes) {
+            if !value.ends_with(&suffix) {
+                self.violation(
+                    path,
+                    "bytes.suffix",
+                    format!("value does not have suffix {}", fmt_hex(&suffix)),
+                );
+            }
+        }
+        if let Some(substring) = get(rules, "contains", get_bytes) {
+            if !substring.is_empty()
+                && !value
+                    .windows(substring.len())
+                    .any(|window| window == substring)
+            {
+                self.violation(
+                    path,
+                    "bytes.contains",
+                    format!("value does not contain {}", fmt_hex(&substring)),
+                );
+            }
+        }
+
+        let allowed = get_list(rules, "in", get_bytes);
+        if !allowed.is_empty() && !allowed.iter().any(|allowed| allowed == value) {
+            self.violation(path, "bytes.in", "value must be in list");
+        }
+        let disallowed = get_list(rules, "not_in", get_bytes);
+        if disallowed.iter().any(|disallowed| disallowed == value) {
+            self.violation(path, "bytes.not_in", "value must not be in list");
+        }
+
+        if let Some(name) = get_well_known(rules) {
+            let (valid, description) = match name.as_str() {
+                "ip" => (value.len() == 4 || value.len() == 16, "IP address"),
+                "ipv4" => (value.len() == 4, "IPv4 address"),
+                "ipv6" => (value.len() == 16, "IPv6 address"),
+                _ => return,
+            };
+
+            if value.is_empty() {
+                self.violation(
+                    path,
+                    format!("bytes.{}_empty", name),
+                    format!("value is empty, which is not a valid {}", description),
+                );
+            } else if !valid {
+                self.violation(
+                    path,
+                    format!("bytes.{}", name),
+                    format!("value must be a valid {}", description),
+                );
+            }
+        }
+    }
+
+    fn validate_enum(
+        &mut self,
+        path: &str,
+        enum_desc: &EnumDescriptor,
+        value: i32,
+        rules: &DynamicMessage,
+    ) {
+        if let Some(expected) = get(rules, "const", Value::as_i32) {
+            if value != expected {
+                self.violation(path, "enum.const", format!("value must equal {}", expected));
+            }
+        }
+        if get_bool(rules, "defined_only") && enum_desc.get_value(value).is_none() {
+            self.violation(
+                path,
+                "enum.defined_only",
+                "value must be one of the defined enum values",
+            );
+        }
+        self.validate_in(path, "enum", &value, rules, Value::as_i32);
+    }
+
+    fn validate_timestamp(
+        &mut self,
+        path: &str,
+        value: &TimeValue<prost_types::Timestamp>,
+        rules: &DynamicMessage,
+    ) {
+        self.validate_ordered(path, "timestamp", value, rules, get_timestamp);
+
+        let now = TimeValue::<prost_types::Timestamp>::new(self.now);
+        if get_bool(rules, "lt_now") && *value >= now {
+            self.violation(path, "timestamp.lt_now", "value must be less than now");
+        }
+        if get_bool(rules, "gt_now") && *value <= now {
+            self.violation(path, "timestamp.gt_now", "value must be greater than now");
+        }
+        if let Some(within) = get(rules, "within", get_duration) {
+            if (value.nanos() - now.nanos()).abs() > within.nanos() {
+                self.violation(
+                    path,
+                    "timestamp.within",
+                    format!("value must be within {} of now", within),
+                );
+            }
+        }
+    }
+
+    fn validate_any(&mut self, path: &str, type_url: &str, rules: &DynamicMessage) {
+        let get_str = |value: &Value| value.as_str().map(str::to_owned);
+
+        let allowed = get_list(rules, "in", get_str);
+        if !allowed.is_empty() && !allowed.iter().any(|allowed| allowed == type_url) {
+            self.violation(path, "any.in", "type URL must be in the allow list");
+        }
+        let disallowed = get_list(rules, "not_in", get_str);
+        if disallowed.iter().any(|disallowed| disallowed == type_url) {
+            self.violation(path, "any.not_in", "type URL must not be in the block list");
+        }
+    }
+
+    fn validate_len<R>(
+        &mut self,
+        path: &str,
+        rule_id: &str,
+        len: u64,
+        range: Option<R>,
+        message: impl FnOnce(&R) -> String,
+    ) where
+        R: LenRange,
+    {
+        if let Some(range) = range {
+            if !range.contains_len(len) {
+                self.violation(path, rule_id, message(&range));
+            }
+        }
+    }
+
+    fn validate_pattern(&mut self, path: &str, rule_id: &str, value: &str, pattern: &str) {
+        // All patterns are compiled when the validator is created.
+        if !self.validator.regexes[pattern].is_match(value) {
+            self.violation(
+                path,
+                rule_id,
+                format!("value does not match regex pattern `{}`", pattern),
+            );
+        }
+    }
+}
+
+/// A bound on the length of a value: an exact length, a minimum or a maximum.
+trait LenRange {
+    fn contains_len(&self, len: u64) -> bool;
+}
+
+impl LenRange for u64 {
+    fn contains_len(&self, len: u64) -> bool {
+        *self == len
+    }
+}
+
+impl LenRange for std::ops::RangeFrom<u64> {
+    fn contains_len(&self, len: u64) -> bool {
+        self.contains(&len)
+    }
+}
+
+impl LenRange for std::ops::RangeToInclusive<u64> {
+    fn contains_len(&self, len: u64) -> bool {
+        self.contains(&len)
+    }
+}
+
+/// A `google.protobuf.Duration` or `google.protobuf.Timestamp` value, ordered by its seconds and nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+struct TimeValue<T> {
+    seconds: i64,
+    subsec_nanos: i32,
+    value: T,
+}
+
+impl TimeValue<prost_types::Duration> {
+    fn new(value: prost_types::Duration) -> Self {
+        TimeValue {
+            seconds: value.seconds,
+            subsec_nanos: value.nanos,
+            value,
+        }
+    }
+}
+
+impl TimeValue<prost_types::Timestamp> {
+    fn new(value: prost_types::Timestamp) -> Self {
+        TimeValue {
+            seconds: value.seconds,
+            subsec_nanos: value.nanos,
+            value,
+        }
+    }
+}
+
+impl<T> TimeValue<T> {
+    fn nanos(&self) -> i128 {
+        i128::from(self.seconds) * 1_000_000_000 + i128::from(self.subsec_nanos)
+    }
+}
+
+impl<T: PartialEq> PartialOrd for TimeValue<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.nanos().cmp(&other.nanos()))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for TimeValue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+fn get_duration(value: &Value) -> Option<TimeValue<prost_types::Duration>> {
+    let message = value.as_message()?;
+    if message.descriptor().full_name() != "google.protobuf.Duration" {
+        return None;
+    }
+    message
+        .transcode_to()
+        .ok()
+        .map(TimeValue::<prost_types::Duration>::new)
+}
+
+fn get_timestamp(value: &Value) -> Option<TimeValue<prost_types::Timestamp>> {
+    let message = value.as_message()?;
+    if message.descriptor().full_name() != "google.protobuf.Timestamp" {
+        return None;
+    }
+    message
+        .transcode_to()
+        .ok()
+        .map(TimeValue::<prost_types::Timestamp>::new)
+}
+
+/// Compiles the regular expressions used by `pattern` rules in `constraints`, including the rules for the
+/// elements of repeated fields and the keys and values of map fields.
+fn compile_patterns(
+    field: &FieldDescriptor,
+    constraints: &DynamicMessage,
+    regexes: &mut HashMap<String, Regex>,
+) -> Result<(), ConstraintError> {
+    for (rule, value) in constraints.fields() {
+        match value {
+            Value::String(pattern) if rule.name() == "pattern" => {
+                if !regexes.contains_key(pattern) {
+                    let regex = Regex::new(pattern).map_err(|err| ConstraintError {
+                        field: field.full_name().to_owned(),
+                        message: format!("invalid regex pattern `{}`: {}", pattern, err),
+                    })?;
+                    regexes.insert(pattern.clone(), regex);
+                }
+            }
+            Value::Message(rules) => compile_patterns(field, rules, regexes)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the value of the extension option `name`, if it is set.
+fn get_constraints(options: &DynamicMessage, name: &str) -> Option<DynamicMessage> {
+    let extension = options.descriptor().get_extension_by_full_name(name)?;
+    if !options.has_extension(&extension) {
+        return None;
+    }
+    options.get_extension(&extension).as_message().cloned()
+}
+
+/// Returns the field set in the `type` oneof of a `buf.validate.FieldConstraints` message, and its value.
+fn get_type_rules(constraints: &DynamicMessage) -> Option<(FieldDescriptor, DynamicMessage)> {
+    let oneof = constraints
+        .descriptor()
+        .oneofs()
+        .find(|oneof| oneof.name() == "type")?;
+    let field = oneof.fields().find(|field| constraints.has_field(field))?;
+    let rules = constraints.get_field(&field).as_message()?.clone();
+    Some((field, rules))
+}
+
+/// Returns the name of the field set to `true` in the `well_known` oneof of a rules message.
+fn get_well_known(rules: &DynamicMessage) -> Option<String> {
+    let oneof = rules
+        .descriptor()
+        .oneofs()
+        .find(|oneof| oneof.name() == "well_known")?;
+    let field = oneof
+        .fields()
+        .find(|field| rules.has_field(field) && rules.get_field(field).as_bool() == Some(true))?;
+    Some(field.name().to_owned())
+}
+
+/// Returns the value of the field `name` of `rules` converted by `get_value`, if the field is set.
+fn get<T>(
+    rules: &DynamicMessage,
+    name: &str,
+    get_value: impl FnOnce(&Value) -> Option<T>,
+) -> Option<T> {
+    let field = rules.descriptor().get_field_by_name(name)?;
+    if !rules.has_field(&field) {
+        return None;
+    }
+    get_value(&rules.get_field(&field))
+}
+
+fn get_list<T>(
+    rules: &DynamicMessage,
+    name: &str,
+    get_value: impl Fn(&Value) -> Option<T>,
+) -> Vec<T> {
+    get(rules, name, |value| {
+        Some(value.as_list()?.iter().filter_map(get_value).collect())
+    })
+    .unwrap_or_default()
+}
+
+fn get_bool(rules: &DynamicMessage, name: &str) -> bool {
+    get(rules, name, Value::as_bool).unwrap_or(false)
+}
+
+fn get_enum(rules: &DynamicMessage, name: &str) -> i32 {
+    get(rules, name, Value::as_enum_number).unwrap_or(0)
+}
+
+fn get_message(rules: &DynamicMessage, name: &str) -> Option<DynamicMessage> {
+    get(rules, name, |value| value.as_message().cloned())
+}
+
+fn is_wrapper_type(full_name: &str) -> bool {
+    matches!(
+        full_name,
+        "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue"
+    )
+}
+
+fn fmt_list<T: fmt::Display>(values: &[T]) -> String {
+    let mut result = String::from("[");
+    for (index, value) in values.iter().enumerate() {
+        if index != 0 {
+            result.push_str(", ");
+        }
+        write!(result, "{}", value).expect("writing to string cannot fail");
+    }
+    result.push(']');
+    result
+}
+
+fn fmt_hex(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(result, "{:02x}", byte).expect("writing to string cannot fail");
+    }
+    result
+}
+
+/// Returns `true` if `value` is an email address, as defined by the HTML specification.
+fn is_email(value: &str) -> bool {
+    let (local, domain) = match value.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(ch))
+        && is_hostname(domain)
+}
+
+/// Returns `true` if `value` is a hostname, as defined by RFC 1034.
+fn is_hostname(value: &str) -> bool {
+    let value = value.strip_suffix('.').unwrap_or(value);
+    if value.is_empty() || value.len() > 253 {
+        return false;
+    }
+
+    let mut labels = value.split('.').peekable();
+    while let Some(label) = labels.next() {
+        if label.is_empty()
+            || label.len() > 63
+            || label.starts_with('-')
+            || label.ends_with('-')
+            || !label
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        {
+            return false;
+        }
+        // The top-level domain must not be entirely numeric.
+        if labels.peek().is_none() && label.chars().all(|ch| ch.is_ascii_digit()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns `true` if `value` is an absolute URI, as defined by RFC 3986.
+fn is_uri(value: &str) -> bool {
+    match value.split_once(':') {
+        Some((scheme, rest)) => is_uri_scheme(scheme) && is_uri_chars(rest),
+        None => false,
+    }
+}
+
+/// Returns `true` if `value` is a URI or a relative reference, as defined by RFC 3986.
+fn is_uri_ref(value: &str) -> bool {
+    match value.split_once(':') {
+        // A colon in the first segment of a relative reference is not allowed.
+        Some((scheme, rest)) if !scheme.contains(['/', '?', '#']) => {
+            is_uri_scheme(scheme) && is_uri_chars(rest)
+        }
+        _ => is_uri_chars(value),
+    }
+}
+
+fn is_uri_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+}
+
+fn is_uri_chars(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' => {
+                if !bytes
+                    .get(index + 1..index + 3)
+                    .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                {
+                    return false;
+                }
+                index += 3;
+            }
+            ch if ch.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'()*+,;=".contains(&ch) => {
+                index += 1;
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(index, ch)| match index {
+            8 | 13 | 18 | 23 => ch == '-',
+            _ => ch.is_ascii_hexdigit(),
+        })
+}
+
+fn is_trimmed_uuid(value: &str) -> bool {
+    value.len() == 32 && value.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field_path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field_path, self.message)
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ValidationErrorKind::Violations(violations) => {
+                write!(f, "validation failed")?;
+                for (index, violation) in violations.iter().enumerate() {
+                    let separator = if index == 0 { ": " } else { "; " };
+                    write!(f, "{}{}", separator, violation)?;
+                }
+                Ok(())
+            }
+            ValidationErrorKind::InvalidConstraint(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+impl From<ConstraintError> for ValidationError {
+    fn from(err: ConstraintError) -> Self {
+        ValidationError {
+            kind: ValidationErrorKind::InvalidConstraint(err),
+        }
+    }
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid constraints for field '{}': {}",
+            self.field, self.message
+        )
+    }
+}
+
+impl Error for ConstraintError {}
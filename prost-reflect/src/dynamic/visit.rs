@@ -0,0 +1,329 @@
+use crate::{
+    DynamicMessage, ExtensionDescriptor, FieldDescriptor, FieldPathSegment, MapKey, UnknownField,
+    Value,
+};
+
+/// Controls how [`DynamicMessage::visit()`] continues after visiting a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Visit the contents of the value, if it is a message, list or map.
+    Continue,
+    /// Do not visit the contents of the value.
+    Skip,
+}
+
+/// Controls how [`DynamicMessage::visit_mut()`] continues after visiting a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlMut {
+    /// Visit the contents of the value, if it is a message, list or map. If the visitor replaced the
+    /// value, the contents of the new value are visited.
+    Continue,
+    /// Do not visit the contents of the value.
+    Skip,
+    /// Remove the value from its containing message, list or map.
+    ///
+    /// Removing a field clears it, as if by [`DynamicMessage::clear_field()`].
+    Remove,
+}
+
+/// A visitor for the contents of a [`DynamicMessage`], used by [`DynamicMessage::visit()`].
+///
+/// Each method receives the path to the visited value from the root message, and returns whether to visit
+/// the contents of the value. For list elements and map entries, the field containing the value is the
+/// second-to-last segment of the path. All methods have a default implementation which does nothing and
+/// returns [`Control::Continue`].
+pub trait Visitor {
+    /// Visits a field which is set in a message.
+    fn visit_field(
+        &mut self,
+        path: &[FieldPathSegment],
+        field: &FieldDescriptor,
+        value: &Value,
+    ) -> Control {
+        let _ = (path, field, value);
+        Control::Continue
+    }
+
+    /// Visits an extension field which is set in a message.
+    fn visit_extension(
+        &mut self,
+        path: &[FieldPathSegment],
+        extension: &ExtensionDescriptor,
+        value: &Value,
+    ) -> Control {
+        let _ = (path, extension, value);
+        Control::Continue
+    }
+
+    /// Visits an element of a repeated field.
+    fn visit_list_element(
+        &mut self,
+        path: &[FieldPathSegment],
+        index: usize,
+        value: &Value,
+    ) -> Control {
+        let _ = (path, index, value);
+        Control::Continue
+    }
+
+    /// Visits an entry of a map field.
+    fn visit_map_entry(
+        &mut self,
+        path: &[FieldPathSegment],
+        key: &MapKey,
+        value: &Value,
+    ) -> Control {
+        let _ = (path, key, value);
+        Control::Continue
+    }
+
+    /// Visits an unknown field of a message. The path is the path to the containing message.
+    fn visit_unknown_field(&mut self, path: &[FieldPathSegment], field: &UnknownField) {
+        let _ = (path, field);
+    }
+}
+
+/// A visitor which may modify the contents of a [`DynamicMessage`], used by [`DynamicMessage::visit_mut()`].
+///
+/// Each method receives the path to the visited value from the root message, and may replace the value in
+/// place or return [`ControlMut::Remove`] to remove it. For list elements and map entries, the field
+/// containing the value is the second-to-last segment of the path. Paths refer to the message as it was before
+/// any values were removed. All methods have a default implementation which does nothing and returns
+/// [`ControlMut::Continue`].
+///
+/// Values must remain valid for their field (see [`Value::is_valid_for_field()`]). Replacing a value with
+/// one of a different type may cause later operations on the message to panic.
+pub trait VisitorMut {
+    /// Visits a field which is set in a message.
+    fn visit_field_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        field: &FieldDescriptor,
+        value: &mut Value,
+    ) -> ControlMut {
+        let _ = (path, field, value);
+        ControlMut::Continue
+    }
+
+    /// Visits an extension field which is set in a message.
+    fn visit_extension_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        extension: &ExtensionDescriptor,
+        value: &mut Value,
+    ) -> ControlMut {
+        let _ = (path, extension, value);
+        ControlMut::Continue
+    }
+
+    /// Visits an element of a repeated field.
+    fn visit_list_element_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        index: usize,
+        value: &mut Value,
+    ) -> ControlMut {
+        let _ = (path, index, value);
+        ControlMut::Continue
+    }
+
+    /// Visits an entry of a map field.
+    fn visit_map_entry_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        key: &MapKey,
+        value: &mut Value,
+    ) -> ControlMut {
+        let _ = (path, key, value);
+        ControlMut::Continue
+    }
+
+    /// Visits an unknown field of a message. The path is the path to the containing message.
+    ///
+    /// Returning [`ControlMut::Skip`] has the same effect as [`ControlMut::Continue`].
+    fn visit_unknown_field_mut(
+        &mut self,
+        path: &[FieldPathSegment],
+        field: &mut UnknownField,
+    ) -> ControlMut {
+        let _ = (path, field);
+        ControlMut::Continue
+    }
+}
+
+impl DynamicMessage {
+    /// Visits every field, extension, list element, map entry and unknown field in this message, depth-first.
+    ///
+    /// Within each message, fields are visited in field number order, followed by extensions and then unknown
+    /// fields. Map entries are visited in an unspecified order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use prost_reflect::{DescriptorPool, DynamicMessage, FieldDescriptor, FieldPathSegment, Value, visit::{Control, Visitor}};
+    /// # let pool = DescriptorPool::decode(include_bytes!("../file_descriptor_set.bin").as_ref()).unwrap();
+    /// # let message_descriptor = pool.get_message_by_name("package.MyMessage").unwrap();
+    /// struct FieldNames(Vec<String>);
+    ///
+    /// impl Visitor for FieldNames {
+    ///     fn visit_field(&mut self, path: &[FieldPathSegment], field: &FieldDescriptor, _: &Value) -> Control {
+    ///         self.0.push(format!("{}:{}", path.len(), field.name()));
+    ///         Control::Continue
+    ///     }
+    /// }
+    ///
+    /// let message = DynamicMessage::decode(message_descriptor, b"\x08\x01\x1a\x02\x10\x42".as_ref()).unwrap();
+    /// let mut visitor = FieldNames(Vec::new());
+    /// message.visit(&mut visitor);
+    /// assert_eq!(visitor.0, ["1:foo", "1:nested", "2:bar"]);
+    /// ```
+    pub fn visit<V>(&self, visitor: &mut V)
+    where
+        V: Visitor + ?Sized,
+    {
+        visit_message(self, visitor, &mut Vec::new());
+    }
+
+    /// Visits every field, extension, list element, map entry and unknown field in this message, depth-first,
+    /// allowing the visitor to modify or remove them.
+    ///
+    /// Values are visited in the same order as [`visit()`](DynamicMessage::visit).
+    pub fn visit_mut<V>(&mut self, visitor: &mut V)
+    where
+        V: VisitorMut + ?Sized,
+    {
+        visit_message_mut(self, visitor, &mut Vec::new());
+    }
+}
+
+fn visit_message<V>(message: &DynamicMessage, visitor: &mut V, path: &mut Vec<FieldPathSegment>)
+where
+    V: Visitor + ?Sized,
+{
+    for (field_desc, value) in message.fields.iter_fields(&message.desc) {
+        path.push(FieldPathSegment::Field(field_desc.clone()));
+        if visitor.visit_field(path, &field_desc, value) == Control::Continue {
+            visit_value(value, visitor, path);
+        }
+        path.pop();
+    }
+
+    for (extension_desc, value) in message.fields.iter_extensions(&message.desc) {
+        path.push(FieldPathSegment::Extension(extension_desc.clone()));
+        if visitor.visit_extension(path, &extension_desc, value) == Control::Continue {
+            visit_value(value, visitor, path);
+        }
+        path.pop();
+    }
+
+    for field in message.fields.iter_unknown() {
+        visitor.visit_unknown_field(path, field);
+    }
+}
+
+fn visit_value<V>(value: &Value, visitor: &mut V, path: &mut Vec<FieldPathSegment>)
+where
+    V: Visitor + ?Sized,
+{
+    match value {
+        Value::Message(message) => visit_message(message, visitor, path),
+        Value::List(values) => {
+            for (index, value) in values.iter().enumerate() {
+                path.push(FieldPathSegment::Index(index));
+                if visitor.visit_list_element(path, index, value) == Control::Continue {
+                    visit_value(value, visitor, path);
+                }
+                path.pop();
+            }
+        }
+        Value::Map(values) => {
+            for (key, value) in values {
+                path.push(FieldPathSegment::Key(key.clone()));
+                if visitor.visit_map_entry(path, key, value) == Control::Continue {
+                    visit_value(value, visitor, path);
+                }
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn visit_message_mut<V>(
+    message: &mut DynamicMessage,
+    visitor: &mut V,
+    path: &mut Vec<FieldPathSegment>,
+) where
+    V: VisitorMut + ?Sized,
+{
+    let mut removed_fields = Vec::new();
+    for (field_desc, value) in message.fields.iter_fields_mut(&message.desc) {
+        path.push(FieldPathSegment::Field(field_desc.clone()));
+        match visitor.visit_field_mut(path, &field_desc, value) {
+            ControlMut::Continue => visit_value_mut(value, visitor, path),
+            ControlMut::Skip => (),
+            ControlMut::Remove => removed_fields.push(field_desc),
+        }
+        path.pop();
+    }
+    for field_desc in removed_fields {
+        message.fields.clear(&field_desc);
+    }
+
+    let mut removed_extensions = Vec::new();
+    for (extension_desc, value) in message.fields.iter_extensions_mut(&message.desc) {
+        path.push(FieldPathSegment::Extension(extension_desc.clone()));
+        match visitor.visit_extension_mut(path, &extension_desc, value) {
+            ControlMut::Continue => visit_value_mut(value, visitor, path),
+            ControlMut::Skip => (),
+            ControlMut::Remove => removed_extensions.push(extension_desc),
+        }
+        path.pop();
+    }
+    for extension_desc in removed_extensions {
+        message.fields.clear(&extension_desc);
+    }
+
+    message
+        .fields
+        .retain_unknown(|field| visitor.visit_unknown_field_mut(path, field) != ControlMut::Remove);
+}
+
+fn visit_value_mut<V>(value: &mut Value, visitor: &mut V, path: &mut Vec<FieldPathSegment>)
+where
+    V: VisitorMut + ?Sized,
+{
+    match value {
+        Value::Message(message) => visit_message_mut(message, visitor, path),
+        Value::List(values) => {
+            let mut keep = Vec::with_capacity(values.len());
+            for (index, value) in values.iter_mut().enumerate() {
+                path.push(FieldPathSegment::Index(index));
+                let control = visitor.visit_list_element_mut(path, index, value);
+                if control == ControlMut::Continue {
+                    visit_value_mut(value, visitor, path);
+                }
+                keep.push(control != ControlMut::Remove);
+                path.pop();
+            }
+            let mut keep = keep.into_iter();
+            values.retain(|_| keep.next().unwrap());
+        }
+        Value::Map(values) => {
+            let mut removed_keys = Vec::new();
+            for (key, value) in values.iter_mut() {
+                path.push(FieldPathSegment::Key(key.clone()));
+                match visitor.visit_map_entry_mut(path, key, value) {
+                    ControlMut::Continue => visit_value_mut(value, visitor, path),
+                    ControlMut::Skip => (),
+                    ControlMut::Remove => removed_keys.push(key.clone()),
+                }
+                path.pop();
+            }
+            for key in removed_keys {
+                values.remove(&key);
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,29 @@
+//! Validation of the well-known types which have restricted ranges.
+
+pub(in crate::dynamic) const MAX_DURATION_SECONDS: u64 = 315_576_000_000;
+pub(in crate::dynamic) const MAX_DURATION_NANOS: u32 = 999_999_999;
+
+pub(in crate::dynamic) const MIN_TIMESTAMP_SECONDS: i64 = -62135596800;
+pub(in crate::dynamic) const MAX_TIMESTAMP_SECONDS: i64 = 253402300799;
+
+pub(in crate::dynamic) fn check_duration(
+    duration: &prost_types::Duration,
+) -> Result<(), &'static str> {
+    if duration.seconds.unsigned_abs() > MAX_DURATION_SECONDS
+        || duration.nanos.unsigned_abs() > MAX_DURATION_NANOS
+    {
+        Err("duration out of range")
+    } else {
+        Ok(())
+    }
+}
+
+pub(in crate::dynamic) fn check_timestamp(
+    timestamp: &prost_types::Timestamp,
+) -> Result<(), &'static str> {
+    if timestamp.seconds < MIN_TIMESTAMP_SECONDS || MAX_TIMESTAMP_SECONDS < timestamp.seconds {
+        Err("timestamp out of range")
+    } else {
+        Ok(())
+    }
+}
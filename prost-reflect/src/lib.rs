@@ -24,11 +24,19 @@ pub use self::descriptor::{
     ExtensionDescriptor, FieldDescriptor, FileDescriptor, Kind, MessageDescriptor,
     MethodDescriptor, OneofDescriptor, ServiceDescriptor, Syntax,
 };
-pub use self::dynamic::{DynamicMessage, MapKey, SetFieldError, UnknownField, Value};
+pub use self::dynamic::{
+    any, convert, delimited, diff, example, field_mask, raw, visit, CanonicalDynamicMessage,
+    CanonicalOptions, CanonicalValue, DecodeOptions, DynamicMessage, DynamicMessageRef, FieldPath,
+    FieldPathError, FieldPathSegment, MapKey, RedactOptions, SetFieldError, TimeError,
+    UnknownField, UnknownFieldSet, UnknownFieldValue, Value,
+};
 pub use self::reflect::ReflectMessage;
 
 #[cfg(feature = "serde")]
-pub use self::dynamic::{DeserializeOptions, SerializeOptions};
+pub use self::dynamic::{
+    DeserializeError, DeserializeOptions, DeserializePathSegment, MessageDeserializer,
+    MessageSerializer, SerializeError, SerializeOptions,
+};
 
 #[cfg(feature = "derive")]
 #[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
@@ -36,3 +44,12 @@ pub use prost_reflect_derive::ReflectMessage;
 
 #[cfg(feature = "text-format")]
 pub use self::dynamic::text_format;
+
+#[cfg(feature = "proptest")]
+pub use self::dynamic::arbitrary;
+
+#[cfg(feature = "validate")]
+pub use self::dynamic::validate;
+
+#[cfg(feature = "cel")]
+pub use self::dynamic::cel;